@@ -36,6 +36,7 @@ use crate::{
         job_status::JobStatus,
         user::{self, Column as UserColumn},
     },
+    job_queue::JobQueue,
 };
 
 // ── Screen state ─────────────────────────────────────────────────────────────
@@ -126,6 +127,9 @@ enum Screen {
         email_input: String,
     },
     Jobs,
+    DeleteJobConfirm {
+        job: job::Model,
+    },
 }
 
 struct SubInfo {
@@ -513,6 +517,32 @@ impl<'a> AdminApp<'a> {
         }
     }
 
+    fn do_delete_job(&mut self, job_id: Uuid) {
+        let db = self.db;
+        let result = self
+            .handle
+            .block_on(async { JobQueue::database().delete(db, job_id).await });
+
+        match result {
+            Ok(true) => {
+                self.message = Some(("Job deleted.".to_string(), false));
+                self.jobs.bottom_state.select(None);
+                self.load_jobs();
+            }
+            Ok(false) => {
+                self.message = Some((
+                    "Job not deleted: it is running or no longer exists.".to_string(),
+                    true,
+                ));
+                self.load_jobs();
+            }
+            Err(e) => {
+                self.message = Some((format!("Failed to delete job: {e}"), true));
+            }
+        }
+        self.screen = Screen::Jobs;
+    }
+
     // ── Key handling ──────────────────────────────────────────────────────────
 
     /// Returns true if the app should quit.
@@ -529,6 +559,7 @@ impl<'a> AdminApp<'a> {
             Screen::GiftSubscription { .. } => self.handle_key_gift(key),
             Screen::DeleteConfirm { .. } => self.handle_key_delete_confirm(key),
             Screen::Jobs => self.handle_key_jobs(key),
+            Screen::DeleteJobConfirm { .. } => self.handle_key_delete_job_confirm(key),
         }
     }
 
@@ -718,6 +749,24 @@ impl<'a> AdminApp<'a> {
         false
     }
 
+    fn handle_key_delete_job_confirm(&mut self, key: KeyEvent) -> bool {
+        let Screen::DeleteJobConfirm { ref job } = self.screen else {
+            return false;
+        };
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                let id = job.id;
+                self.do_delete_job(id);
+            }
+            _ => {
+                self.screen = Screen::Jobs;
+            }
+        }
+        false
+    }
+
     fn handle_key_jobs(&mut self, key: KeyEvent) -> bool {
         match key.code {
             KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
@@ -769,7 +818,8 @@ impl<'a> AdminApp<'a> {
                     None => Some(JobStatus::Failed),
                     Some(JobStatus::Failed) => Some(JobStatus::Pending),
                     Some(JobStatus::Pending) => Some(JobStatus::Running),
-                    Some(JobStatus::Running) => None,
+                    Some(JobStatus::Running) => Some(JobStatus::Cancelled),
+                    Some(JobStatus::Cancelled) => None,
                     _ => None,
                 };
                 self.load_jobs();
@@ -807,6 +857,18 @@ impl<'a> AdminApp<'a> {
                     self.load_jobs();
                 }
             }
+            KeyCode::Char('d') | KeyCode::Char('D') if self.jobs.panel == JobPanel::Bottom => {
+                if let Some(idx) = self.jobs.bottom_state.selected() {
+                    if let Some(j) = self.jobs.jobs.get(idx) {
+                        if j.status == JobStatus::Running {
+                            self.message =
+                                Some(("Running jobs cannot be deleted.".to_string(), true));
+                        } else {
+                            self.screen = Screen::DeleteJobConfirm { job: j.clone() };
+                        }
+                    }
+                }
+            }
             _ => {}
         }
         false
@@ -842,6 +904,7 @@ impl<'a> AdminApp<'a> {
             Screen::GiftSubscription { .. } => self.render_gift(f, chunks[0]),
             Screen::DeleteConfirm { .. } => self.render_delete_confirm(f, chunks[0]),
             Screen::Jobs => self.render_jobs(f, chunks[0]),
+            Screen::DeleteJobConfirm { .. } => self.render_delete_job_confirm(f, chunks[0]),
         }
     }
 
@@ -1268,6 +1331,52 @@ impl<'a> AdminApp<'a> {
         f.render_widget(help, chunks[3]);
     }
 
+    fn render_delete_job_confirm(&self, f: &mut Frame, area: Rect) {
+        let Screen::DeleteJobConfirm { job: j } = &self.screen else {
+            return;
+        };
+
+        let block = Block::default()
+            .title(" Confirm Job Deletion ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(5),
+                Constraint::Min(0),
+                Constraint::Length(2),
+            ])
+            .split(inner);
+
+        let warning = Paragraph::new(vec![
+            Line::from(Span::styled(
+                "  ⚠  This will permanently delete the job and its execution history.",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(format!("  Job: {}({})", j.r#type, j.id)),
+            Line::from(format!(
+                "  Status: {}   Retries: {}",
+                j.status, j.retry_count
+            )),
+            Line::from(format!(
+                "  Arguments: {}",
+                j.decoded_arguments()
+                    .map_or_else(|e| format!("<unreadable: {e}>"), |a| a.to_string())
+            )),
+        ]);
+        f.render_widget(warning, chunks[0]);
+
+        let help = Paragraph::new(Line::from(Span::styled(
+            "  [y] Delete   Any other key Cancel",
+            Style::default().fg(Color::DarkGray),
+        )));
+        f.render_widget(help, chunks[2]);
+    }
+
     fn render_jobs(&mut self, f: &mut Frame, area: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -1345,6 +1454,7 @@ impl<'a> AdminApp<'a> {
                         JobStatus::Failed => Style::default().fg(Color::Red),
                         JobStatus::Running => Style::default().fg(Color::Yellow),
                         JobStatus::Completed => Style::default().fg(Color::Green),
+                        JobStatus::Cancelled => Style::default().fg(Color::DarkGray),
                         _ => Style::default(),
                     },
                 ));
@@ -1364,7 +1474,7 @@ impl<'a> AdminApp<'a> {
 
         let bottom_title = if self.jobs.panel == JobPanel::Bottom {
             format!(
-                " Jobs ({}) status={filter_label} type={type_label} — R retry, F filter ",
+                " Jobs ({}) status={filter_label} type={type_label} — R retry, D delete, F filter ",
                 self.jobs.jobs.len()
             )
         } else {
@@ -1392,7 +1502,7 @@ impl<'a> AdminApp<'a> {
         f.render_stateful_widget(job_table, chunks[1], &mut self.jobs.bottom_state);
 
         let help = Paragraph::new(Line::from(Span::styled(
-            "  Tab Panel   ↑↓ Navigate   [r] Retry/Refresh   [f] Filter status   [t] Filter type   Esc Back",
+            "  Tab Panel   ↑↓ Navigate   [r] Retry/Refresh   [d] Delete   [f] Filter status   [t] Filter type   Esc Back",
             Style::default().fg(Color::DarkGray),
         )));
         f.render_widget(help, chunks[2]);
@@ -1,7 +1,15 @@
 #[cfg(feature = "test-utils")]
 pub mod assertions;
+pub mod client_ip;
+pub mod find_or_404;
 pub mod health_checks;
 pub mod json_error;
+pub mod locale;
+pub mod pagination;
+pub mod render_cache;
+pub mod request_result;
+pub mod strict_json;
+pub mod timestamp;
 pub mod unique_constraint;
 pub mod validated_json;
 pub mod view_param;
@@ -0,0 +1,270 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts, Extensions, HeaderMap},
+};
+use ipnetwork::IpNetwork;
+use tracing::warn;
+
+use crate::{api::request_result::RequestError, app::App};
+
+/// The resolved client IP address of the request.
+///
+/// Uses the same resolution as the rate limiter: proxy headers are honoured
+/// only when `rate_limiting.trust_proxy` is enabled or the peer is listed in
+/// `rate_limiting.trusted_proxies`, otherwise the socket address is used. Rejects with 500 if no address is available, which means
+/// the server was not started with connect info.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// async fn sign_in(ClientIp(ip): ClientIp, ...) -> RequestResult {
+///     info!(%ip, "Sign-in attempt");
+///     ...
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl<ExtraConfig> FromRequestParts<App<ExtraConfig>> for ClientIp
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    type Rejection = RequestError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &App<ExtraConfig>,
+    ) -> Result<Self, Self::Rejection> {
+        resolve_client_ip(
+            &parts.headers,
+            &parts.extensions,
+            state.rate_limit_state.trust_proxy(),
+            state.rate_limit_state.trusted_proxies(),
+        )
+        .map(ClientIp)
+        .ok_or_else(|| {
+            warn!("No client IP found in request");
+            RequestError::internal()
+        })
+    }
+}
+
+/// Resolve the client IP from proxy headers, falling back to the socket address.
+///
+/// Only reads proxy headers when `trust_proxy` is enabled or the peer is one of
+/// `trusted_proxies` — otherwise an attacker could spoof `X-Forwarded-For` to
+/// bypass rate limiting entirely.
+pub fn resolve_client_ip(
+    headers: &HeaderMap,
+    extensions: &Extensions,
+    trust_proxy: bool,
+    trusted_proxies: &[IpNetwork],
+) -> Option<IpAddr> {
+    if trust_proxy {
+        // X-Forwarded-For: client, proxy1, proxy2 — leftmost is the real client
+        if let Some(ip) = headers
+            .get("X-Forwarded-For")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.split(',').next())
+            .and_then(|s| s.trim().parse::<IpAddr>().ok())
+        {
+            return Some(ip);
+        }
+
+        if let Some(ip) = headers
+            .get("X-Real-IP")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.trim().parse::<IpAddr>().ok())
+        {
+            return Some(ip);
+        }
+    }
+
+    let peer = extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ci| ci.0.ip())?;
+
+    let is_trusted = |ip: &IpAddr| trusted_proxies.iter().any(|net| net.contains(*ip));
+    if !is_trusted(&peer) {
+        return Some(peer);
+    }
+
+    match forwarded_chain(headers) {
+        // Each trusted proxy appends the address it received from, so walking
+        // right to left the first untrusted hop is the client.
+        Ok(chain) => Some(
+            chain
+                .iter()
+                .rev()
+                .find(|ip| !is_trusted(ip))
+                .or(chain.first())
+                .copied()
+                .unwrap_or(peer),
+        ),
+        Err(value) => {
+            warn!(%peer, value, "Malformed forwarding header from trusted proxy, using socket address");
+            Some(peer)
+        }
+    }
+}
+
+/// Addresses listed by `Forwarded` (preferred) or `X-Forwarded-For`, in order.
+///
+/// Returns the offending header value if any entry isn't a plain IP address,
+/// including obfuscated `Forwarded` identifiers like `unknown` or `_hidden`.
+fn forwarded_chain(headers: &HeaderMap) -> Result<Vec<IpAddr>, String> {
+    let forwarded: Vec<&str> = headers
+        .get_all(header::FORWARDED)
+        .iter()
+        .map(|v| v.to_str().unwrap_or(""))
+        .collect();
+    if !forwarded.is_empty() {
+        let mut chain = Vec::new();
+        for value in &forwarded {
+            for element in value.split(',') {
+                let node = element
+                    .split(';')
+                    .filter_map(|pair| pair.trim().split_once('='))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("for"))
+                    .map(|(_, node)| node.trim_matches('"'));
+                let ip = node
+                    .and_then(parse_forwarded_node)
+                    .ok_or_else(|| value.to_string())?;
+                chain.push(ip);
+            }
+        }
+        return Ok(chain);
+    }
+
+    let mut chain = Vec::new();
+    for value in headers.get_all("X-Forwarded-For") {
+        let value = value.to_str().map_err(|_| "<non-ASCII>".to_string())?;
+        for entry in value.split(',') {
+            let ip = entry.trim().parse().map_err(|_| value.to_string())?;
+            chain.push(ip);
+        }
+    }
+    Ok(chain)
+}
+
+/// Parse a `Forwarded` node: `192.0.2.60`, `192.0.2.60:4711` or `[2001:db8::1]:4711`.
+fn parse_forwarded_node(node: &str) -> Option<IpAddr> {
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    node.parse()
+        .ok()
+        .or_else(|| node.rsplit_once(':')?.0.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use axum::{extract::ConnectInfo, extract::Request, routing::get, Router};
+
+    use super::ClientIp;
+    use crate::{
+        app::App,
+        database::migrations::Migrator,
+        tests::setup_test::{no_fixtures, setup_test_with_config},
+    };
+
+    async fn show_ip(ClientIp(ip): ClientIp) -> String {
+        ip.to_string()
+    }
+
+    // The test server has no socket, so pretend the peer is 192.0.2.10
+    fn test_router(app: App) -> Router {
+        Router::new()
+            .route("/ip", get(show_ip))
+            .layer(axum::middleware::map_request(
+                |mut req: Request| async move {
+                    let peer: SocketAddr = "192.0.2.10:4000".parse().unwrap();
+                    req.extensions_mut().insert(ConnectInfo(peer));
+                    req
+                },
+            ))
+            .with_state(app)
+    }
+
+    #[tokio::test]
+    async fn test_direct_connection_uses_socket_address() {
+        let test = setup_test_with_config::<Migrator>(test_router, no_fixtures, |config| {
+            config.rate_limiting.trust_proxy = false;
+        })
+        .await;
+
+        // Forwarded headers are ignored without a trusted proxy
+        let response = test
+            .server
+            .get("/api/ip")
+            .add_header("X-Forwarded-For", "203.0.113.7")
+            .await;
+        response.assert_status_ok();
+        response.assert_text("192.0.2.10");
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_ip_with_trusted_proxy() {
+        let test = setup_test_with_config::<Migrator>(test_router, no_fixtures, |config| {
+            config.rate_limiting.trust_proxy = true;
+        })
+        .await;
+
+        let response = test
+            .server
+            .get("/api/ip")
+            .add_header("X-Forwarded-For", "203.0.113.7, 10.0.0.1")
+            .await;
+        response.assert_status_ok();
+        response.assert_text("203.0.113.7");
+    }
+
+    fn trusting_proxies(config: &mut crate::config::Config) {
+        config.rate_limiting.trusted_proxies = vec![
+            "192.0.2.0/24".parse().unwrap(),
+            "10.0.0.0/8".parse().unwrap(),
+        ];
+    }
+
+    #[tokio::test]
+    async fn test_rightmost_untrusted_forwarded_address_is_used() {
+        let test =
+            setup_test_with_config::<Migrator>(test_router, no_fixtures, trusting_proxies).await;
+
+        // The leftmost entry is whatever the client claimed and can't be trusted
+        let response = test
+            .server
+            .get("/api/ip")
+            .add_header("X-Forwarded-For", "198.51.100.1, 203.0.113.7, 10.0.0.5")
+            .await;
+        response.assert_text("203.0.113.7");
+
+        let response = test
+            .server
+            .get("/api/ip")
+            .add_header(
+                "Forwarded",
+                r#"for=198.51.100.1, for="[2001:db8::7]:4711";proto=https"#,
+            )
+            .await;
+        response.assert_text("2001:db8::7");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_forwarded_header_falls_back_to_socket() {
+        let test =
+            setup_test_with_config::<Migrator>(test_router, no_fixtures, trusting_proxies).await;
+
+        let response = test
+            .server
+            .get("/api/ip")
+            .add_header("X-Forwarded-For", "203.0.113.7, not-an-ip")
+            .await;
+        response.assert_status_ok();
+        response.assert_text("192.0.2.10");
+    }
+}
@@ -0,0 +1,87 @@
+use sea_orm::{ConnectionTrait, EntityTrait, PrimaryKeyTrait};
+use uuid::Uuid;
+
+use crate::api::request_result::RequestError;
+
+/// Load an entity by its UUID primary key, or fail with a 404 `RequestError`.
+///
+/// # Example
+/// ```rust,ignore
+/// let post = find_or_404::<post::Entity>(&app.db, id).await?;
+/// ```
+pub async fn find_or_404<E>(db: &impl ConnectionTrait, id: Uuid) -> Result<E::Model, RequestError>
+where
+    E: EntityTrait,
+    <E::PrimaryKey as PrimaryKeyTrait>::ValueType: From<Uuid>,
+{
+    E::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or_else(RequestError::not_found)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        extract::{Path, State},
+        routing::get,
+        Router,
+    };
+    use sea_orm::{ActiveModelTrait, Set};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::find_or_404;
+    use crate::{
+        api::request_result::{RequestResult, RequestSuccess},
+        app::App,
+        database::{migrations::Migrator, models::user},
+        tests::setup_test::{no_fixtures, setup_test},
+    };
+
+    async fn show_user(State(app): State<App>, Path(id): Path<Uuid>) -> RequestResult {
+        let user = find_or_404::<user::Entity>(&app.db, id).await?;
+        Ok(RequestSuccess::Ok(json!({ "email": user.email })))
+    }
+
+    fn test_router(app: App) -> Router {
+        Router::new()
+            .route("/users/{id}", get(show_user))
+            .with_state(app)
+    }
+
+    #[tokio::test]
+    async fn test_find_or_404_returns_existing_entity() {
+        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let user = user::ActiveModel {
+            email: Set("found@example.com".to_string()),
+            password_hash: Set("hash".to_string()),
+            ..Default::default()
+        }
+        .insert(&t.db)
+        .await
+        .unwrap();
+
+        let response = t.server.get(&format!("/api/users/{}", user.id)).await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(
+            response.json::<serde_json::Value>()["email"],
+            "found@example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_or_404_returns_not_found() {
+        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+
+        let response = t
+            .server
+            .get(&format!("/api/users/{}", Uuid::new_v4()))
+            .await;
+        assert_eq!(response.status_code(), 404);
+        assert_eq!(
+            response.json::<serde_json::Value>(),
+            json!({ "error": "not_found" })
+        );
+    }
+}
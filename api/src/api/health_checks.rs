@@ -1,6 +1,21 @@
+use axum::extract::State;
 use axum_macros::debug_handler;
 
+use crate::{app::App, app::ReadinessError, database::DatabaseSetupStatus};
+
 #[debug_handler]
 pub async fn ok() -> &'static str {
     "OK"
 }
+
+/// Readiness probe: the database must be reachable and fully migrated.
+pub async fn readiness<ExtraConfig>(
+    State(app): State<App<ExtraConfig>>,
+) -> Result<&'static str, ReadinessError> {
+    app.db.ping().await?;
+
+    match app.database_status.get() {
+        DatabaseSetupStatus::Completed => Ok("OK"),
+        status => Err(ReadinessError::DatabaseSetupError(status)),
+    }
+}
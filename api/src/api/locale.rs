@@ -0,0 +1,251 @@
+use std::{collections::HashMap, convert::Infallible, fmt};
+
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{header, request::Parts, Extensions, HeaderMap, Uri},
+};
+
+use crate::{
+    app::App,
+    config::{LocaleConfig, LocaleSource},
+};
+
+/// The locale of the request, resolved from the sources in `[locale]`.
+///
+/// The first extraction stores it in the request extensions, so later
+/// extractors and anything handed the request parts see the same value. Pass
+/// it on to emails sent from the handler so they match the response.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// async fn show_invoice(locale: Locale, ...) -> RequestResult {
+///     let title = translate(locale.as_str(), "invoice.title");
+///     ...
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale(pub String);
+
+impl Locale {
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A user's saved locale, read by [`LocaleSource::User`].
+///
+/// Erno doesn't store one; apps that do insert this into the request
+/// extensions, e.g. from a middleware that loads the user's settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreferredLocale(pub String);
+
+impl<ExtraConfig> FromRequestParts<App<ExtraConfig>> for Locale
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &App<ExtraConfig>,
+    ) -> Result<Self, Self::Rejection> {
+        if let Some(locale) = parts.extensions.get::<Self>() {
+            return Ok(locale.clone());
+        }
+
+        let locale = resolve_locale(
+            &state.config.locale,
+            &parts.uri,
+            &parts.headers,
+            &parts.extensions,
+        );
+        parts.extensions.insert(locale.clone());
+        Ok(locale)
+    }
+}
+
+/// Resolve the locale from `config.sources` in order, falling back to
+/// `config.default`.
+///
+/// A candidate is accepted when it is one of `config.supported` (ignoring
+/// case) or when its language is, so `de-AT` resolves to `de`. The result
+/// uses the spelling from `config.supported`.
+pub fn resolve_locale(
+    config: &LocaleConfig,
+    uri: &Uri,
+    headers: &HeaderMap,
+    extensions: &Extensions,
+) -> Locale {
+    for source in &config.sources {
+        let found = match source {
+            LocaleSource::Query => Query::<HashMap<String, String>>::try_from_uri(uri)
+                .ok()
+                .and_then(|Query(params)| params.get(&config.query_param).cloned())
+                .and_then(|tag| supported_locale(config, &tag)),
+            LocaleSource::User => extensions
+                .get::<PreferredLocale>()
+                .and_then(|preferred| supported_locale(config, &preferred.0)),
+            LocaleSource::AcceptLanguage => accept_language(headers)
+                .into_iter()
+                .find_map(|tag| supported_locale(config, tag)),
+        };
+        if let Some(locale) = found {
+            return Locale(locale);
+        }
+    }
+
+    Locale(config.default.clone())
+}
+
+fn supported_locale(config: &LocaleConfig, tag: &str) -> Option<String> {
+    let tag = tag.trim();
+    let well_formed = !tag.is_empty()
+        && tag.len() <= 35
+        && tag.split('-').all(|subtag| {
+            !subtag.is_empty()
+                && subtag.len() <= 8
+                && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    if !well_formed {
+        return None;
+    }
+    if config.supported.is_empty() {
+        return Some(tag.to_string());
+    }
+
+    let language = tag.split('-').next().unwrap_or(tag);
+    config
+        .supported
+        .iter()
+        .find(|supported| supported.eq_ignore_ascii_case(tag))
+        .or_else(|| {
+            config
+                .supported
+                .iter()
+                .find(|supported| supported.eq_ignore_ascii_case(language))
+        })
+        .cloned()
+}
+
+/// Language tags from `Accept-Language`, most preferred first. Tags with
+/// `q=0` and the `*` wildcard are left out.
+fn accept_language(headers: &HeaderMap) -> Vec<&str> {
+    let mut tags: Vec<(&str, f32)> = headers
+        .get_all(header::ACCEPT_LANGUAGE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (tag != "*" && quality > 0.0).then_some((tag, quality))
+        })
+        .collect();
+    // Stable, so equal weights keep the client's order
+    tags.sort_by(|a, b| b.1.total_cmp(&a.1));
+    tags.into_iter().map(|(tag, _)| tag).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, Extensions, HeaderMap, HeaderValue, Uri};
+
+    use super::{resolve_locale, PreferredLocale};
+    use crate::config::{LocaleConfig, LocaleSource};
+
+    fn config() -> LocaleConfig {
+        LocaleConfig {
+            supported: vec!["en".to_string(), "de".to_string(), "pt-BR".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn accepting(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT_LANGUAGE, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn resolve(
+        config: &LocaleConfig,
+        uri: &str,
+        headers: &HeaderMap,
+        extensions: &Extensions,
+    ) -> String {
+        resolve_locale(config, &uri.parse::<Uri>().unwrap(), headers, extensions).0
+    }
+
+    #[test]
+    fn test_resolves_from_accept_language_by_weight() {
+        let config = config();
+        let none = Extensions::new();
+
+        let headers = accepting("fr;q=0.9, en;q=0.5, de-AT;q=0.8");
+        assert_eq!(resolve(&config, "/", &headers, &none), "de");
+
+        let headers = accepting("pt-br, en;q=0.1");
+        assert_eq!(resolve(&config, "/", &headers, &none), "pt-BR");
+
+        // Refused and wildcard entries are never picked
+        let headers = accepting("de;q=0, *, en;q=0.2");
+        assert_eq!(resolve(&config, "/", &headers, &none), "en");
+    }
+
+    #[test]
+    fn test_query_overrides_user_and_header() {
+        let config = config();
+        let mut extensions = Extensions::new();
+        extensions.insert(PreferredLocale("pt-BR".to_string()));
+        let headers = accepting("en");
+
+        assert_eq!(resolve(&config, "/?locale=DE", &headers, &extensions), "de");
+        // The user's preference beats the header
+        assert_eq!(resolve(&config, "/", &headers, &extensions), "pt-BR");
+        // Unsupported values are skipped rather than trusted
+        assert_eq!(
+            resolve(&config, "/?locale=xx", &headers, &extensions),
+            "pt-BR"
+        );
+
+        let config = LocaleConfig {
+            sources: vec![LocaleSource::AcceptLanguage, LocaleSource::Query],
+            ..config
+        };
+        assert_eq!(resolve(&config, "/?locale=de", &headers, &extensions), "en");
+    }
+
+    #[test]
+    fn test_falls_back_to_default() {
+        let config = config();
+        let none = Extensions::new();
+
+        assert_eq!(resolve(&config, "/", &HeaderMap::new(), &none), "en");
+        assert_eq!(
+            resolve(&config, "/?locale=fr", &accepting("fr, it"), &none),
+            "en"
+        );
+
+        // Without a supported list any well-formed tag goes, but not garbage
+        let open = LocaleConfig {
+            default: "de".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(resolve(&open, "/", &accepting("fr-CA"), &none), "fr-CA");
+        assert_eq!(
+            resolve(&open, "/?locale=%3Cscript%3E", &HeaderMap::new(), &none),
+            "de"
+        );
+    }
+}
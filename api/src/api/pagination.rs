@@ -0,0 +1,276 @@
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, NaiveDateTime};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Select,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::request_result::RequestError;
+
+/// Page size used when the request doesn't give a `limit`.
+pub const DEFAULT_PAGE_LIMIT: u64 = 20;
+
+/// Largest `limit` a client may ask for; bigger values are clamped.
+pub const MAX_PAGE_LIMIT: u64 = 100;
+
+/// Position after the last row of a page: its sort timestamp and id.
+///
+/// Clients only ever see it as an opaque URL-safe base64 token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub sort: NaiveDateTime,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}|{}",
+            self.sort.and_utc().timestamp_micros(),
+            self.id
+        ))
+    }
+
+    /// Parse a token produced by [`Cursor::encode`]. `None` if it's malformed.
+    pub fn decode(token: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(token).ok()?;
+        let (micros, id) = std::str::from_utf8(&bytes).ok()?.split_once('|')?;
+        Some(Self {
+            sort: DateTime::from_timestamp_micros(micros.parse().ok()?)?.naive_utc(),
+            id: id.parse().ok()?,
+        })
+    }
+}
+
+/// One page of results and the cursor for the next, `None` on the last page.
+#[derive(Debug, Clone, Serialize)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> CursorPage<T> {
+    /// Convert the items, e.g. to render them with a view.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> CursorPage<U> {
+        CursorPage {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CursorQuery {
+    after: Option<String>,
+    limit: Option<u64>,
+}
+
+/// Query parameter extractor for keyset pagination: `?after=<cursor>&limit=<n>`.
+///
+/// Pages are ordered by a timestamp column with the id as a tie-breaker, and
+/// each page starts strictly after the previous page's last row. Unlike
+/// offset pagination, rows inserted or deleted between requests never make a
+/// page skip or repeat rows. Rejects a malformed cursor with 400 `invalid_cursor`.
+///
+/// # Example
+/// ```rust,ignore
+/// pub async fn index(State(app): State<App>, page: CursorParams) -> RequestResult {
+///     let posts = page
+///         .fetch(
+///             &app.db,
+///             post::Entity::find(),
+///             (post::Column::CreatedAt, post::Column::Id),
+///             |post| (post.created_at, post.id),
+///         )
+///         .await?;
+///     Ok(RequestSuccess::Ok(json!(posts.map(|post| PostView::Default.render(post)))))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorParams {
+    pub after: Option<Cursor>,
+    pub limit: u64,
+}
+
+impl<S> FromRequestParts<S> for CursorParams
+where
+    S: Send + Sync,
+{
+    type Rejection = RequestError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<CursorQuery>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| RequestError::bad_request("invalid_pagination"))?;
+
+        let after = match query.after {
+            Some(token) => Some(
+                Cursor::decode(&token)
+                    .ok_or_else(|| RequestError::bad_request("invalid_cursor"))?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            after,
+            limit: query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_LIMIT)
+                .clamp(1, MAX_PAGE_LIMIT),
+        })
+    }
+}
+
+impl CursorParams {
+    /// Load the page of `query` after the cursor, ascending by `sort_column`
+    /// then `id_column`. `key` reads the same two values from a row and must
+    /// match the columns, or the next cursor points at the wrong place.
+    pub async fn fetch<E, C>(
+        &self,
+        db: &C,
+        query: Select<E>,
+        (sort_column, id_column): (E::Column, E::Column),
+        key: impl Fn(&E::Model) -> (NaiveDateTime, Uuid),
+    ) -> Result<CursorPage<E::Model>, DbErr>
+    where
+        E: EntityTrait,
+        C: ConnectionTrait,
+    {
+        let mut query = query.order_by_asc(sort_column).order_by_asc(id_column);
+        if let Some(after) = self.after {
+            query = query.filter(
+                Condition::any().add(sort_column.gt(after.sort)).add(
+                    Condition::all()
+                        .add(sort_column.eq(after.sort))
+                        .add(id_column.gt(after.id)),
+                ),
+            );
+        }
+
+        // One extra row tells whether there is a next page
+        let mut items = query.limit(self.limit + 1).all(db).await?;
+        let next_cursor = if items.len() as u64 > self.limit {
+            items.truncate(self.limit as usize);
+            items.last().map(|last| {
+                let (sort, id) = key(last);
+                Cursor { sort, id }.encode()
+            })
+        } else {
+            None
+        };
+
+        Ok(CursorPage { items, next_cursor })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{extract::State, routing::get, Router};
+    use chrono::{Duration, Utc};
+    use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+    use serde_json::{json, Value};
+
+    use super::CursorParams;
+    use crate::{
+        api::request_result::{RequestResult, RequestSuccess},
+        app::App,
+        database::{migrations::Migrator, models::user},
+        tests::setup_test::{no_fixtures, setup_test},
+    };
+
+    async fn list_users(State(app): State<App>, page: CursorParams) -> RequestResult {
+        let users = page
+            .fetch(
+                &app.db,
+                user::Entity::find(),
+                (user::Column::CreatedAt, user::Column::Id),
+                |user| (user.created_at, user.id),
+            )
+            .await?;
+        Ok(RequestSuccess::Ok(json!(users.map(|user| user.email))))
+    }
+
+    fn test_router(app: App) -> Router {
+        Router::new()
+            .route("/users", get(list_users))
+            .with_state(app)
+    }
+
+    async fn insert_user(db: &sea_orm::DatabaseConnection, email: &str, minutes_ago: i64) {
+        user::ActiveModel {
+            email: Set(email.to_string()),
+            password_hash: Set("hash".to_string()),
+            created_at: Set((Utc::now() - Duration::minutes(minutes_ago)).naive_utc()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap();
+    }
+
+    fn emails(page: &Value) -> Vec<&str> {
+        page["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e.as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_insert_between_pages_does_not_skip_or_duplicate() {
+        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        for (i, email) in [
+            "a@example.com",
+            "b@example.com",
+            "c@example.com",
+            "d@example.com",
+        ]
+        .iter()
+        .enumerate()
+        {
+            insert_user(&t.db, email, 40 - i as i64 * 10).await;
+        }
+
+        let first: Value = t
+            .server
+            .get("/api/users")
+            .add_query_param("limit", 2)
+            .await
+            .json();
+        assert_eq!(emails(&first), ["a@example.com", "b@example.com"]);
+
+        // An offset-based second page would now start at b again
+        insert_user(&t.db, "early@example.com", 60).await;
+
+        let cursor = first["next_cursor"].as_str().unwrap();
+        let second: Value = t
+            .server
+            .get("/api/users")
+            .add_query_param("limit", 2)
+            .add_query_param("after", cursor)
+            .await
+            .json();
+        assert_eq!(emails(&second), ["c@example.com", "d@example.com"]);
+        assert!(second["next_cursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_malformed_cursor_is_rejected() {
+        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+
+        let response = t
+            .server
+            .get("/api/users")
+            .add_query_param("after", "not-a-cursor")
+            .await;
+        response.assert_status_bad_request();
+        assert_eq!(response.json::<Value>()["error"], "invalid_cursor");
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::NaiveDateTime;
+
+use super::view_param::{Renderer, ViewEnum};
+
+/// Bounded LRU cache for rendered entity views.
+///
+/// Entries are keyed by entity id, `updated_at` and view name. When an entity
+/// changes its `updated_at` moves forward, so the old entry is simply never
+/// looked up again and eventually falls out of the cache — no explicit
+/// invalidation is needed.
+///
+/// The cache is cheap to clone; clones share the same underlying storage.
+///
+/// # Example
+/// ```rust,ignore
+/// pub async fn show(
+///     State(cache): State<RenderCache>,
+///     view: ViewParam<UserView>,
+/// ) -> RequestResult {
+///     let user = find_user(&db, id).await?;
+///     Ok(Json(cache.render(view.inner(), user.id, user.updated_at, user)))
+/// }
+/// ```
+#[derive(Clone)]
+pub struct RenderCache {
+    inner: Arc<Mutex<LruState>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    id: String,
+    updated_at: NaiveDateTime,
+    view: String,
+}
+
+struct CacheEntry {
+    value: serde_json::Value,
+    last_used: u64,
+}
+
+struct LruState {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<CacheKey, CacheEntry>,
+}
+
+impl RenderCache {
+    /// Create a cache holding at most `capacity` rendered entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(LruState {
+                capacity,
+                tick: 0,
+                entries: HashMap::with_capacity(capacity),
+            })),
+        }
+    }
+
+    /// Render `entity` with `view`, reusing a cached result when the same
+    /// entity version has already been rendered with the same view.
+    pub fn render<V, E>(
+        &self,
+        view: &V,
+        id: impl ToString,
+        updated_at: NaiveDateTime,
+        entity: E,
+    ) -> serde_json::Value
+    where
+        V: ViewEnum + Renderer<E>,
+    {
+        self.render_with(view, id, updated_at, || entity)
+    }
+
+    /// Like [`render`](Self::render), but only calls `load` on a cache miss.
+    ///
+    /// Useful when the id and `updated_at` can be fetched cheaply and loading
+    /// the full entity (joins, related records) is the expensive part.
+    pub fn render_with<V, E, F>(
+        &self,
+        view: &V,
+        id: impl ToString,
+        updated_at: NaiveDateTime,
+        load: F,
+    ) -> serde_json::Value
+    where
+        V: ViewEnum + Renderer<E>,
+        F: FnOnce() -> E,
+    {
+        let key = CacheKey {
+            id: id.to_string(),
+            updated_at,
+            view: view.name().to_string(),
+        };
+
+        if let Some(value) = self.inner.lock().unwrap().get(&key) {
+            return value;
+        }
+
+        let value = view.render(load());
+        self.inner.lock().unwrap().insert(key, value.clone());
+        value
+    }
+
+    /// Number of rendered entries currently cached.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Returns `true` if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop all cached entries.
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().entries.clear();
+    }
+}
+
+impl LruState {
+    fn get(&mut self, key: &CacheKey) -> Option<serde_json::Value> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(key).map(|entry| {
+            entry.last_used = tick;
+            entry.value.clone()
+        })
+    }
+
+    fn insert(&mut self, key: CacheKey, value: serde_json::Value) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            if let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.tick += 1;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                last_used: self.tick,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use chrono::{NaiveDateTime, TimeDelta};
+    use serde_json::json;
+
+    use super::RenderCache;
+    use crate::api::view_param::{Renderer, ViewEnum};
+
+    static RENDER_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Entity {
+        id: u32,
+        name: &'static str,
+    }
+
+    enum EntityView {
+        Default,
+    }
+
+    impl ViewEnum for EntityView {
+        fn from_name(name: &str) -> Option<Self> {
+            (name == "default").then_some(Self::Default)
+        }
+
+        fn name(&self) -> &str {
+            "default"
+        }
+
+        fn default_view() -> Self {
+            Self::Default
+        }
+    }
+
+    impl Renderer<Entity> for EntityView {
+        fn render(&self, entity: Entity) -> serde_json::Value {
+            RENDER_CALLS.fetch_add(1, Ordering::SeqCst);
+            json!({ "id": entity.id, "name": entity.name })
+        }
+    }
+
+    fn timestamp() -> NaiveDateTime {
+        chrono::DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .naive_utc()
+    }
+
+    #[test]
+    fn test_render_cache() {
+        let cache = RenderCache::new(2);
+        let view = EntityView::Default;
+        let updated_at = timestamp();
+        let calls = || RENDER_CALLS.load(Ordering::SeqCst);
+
+        // Second render of an unchanged entity is served from the cache
+        let first = cache.render(&view, 1, updated_at, Entity { id: 1, name: "a" });
+        let second = cache.render(&view, 1, updated_at, Entity { id: 1, name: "a" });
+        assert_eq!(first, second);
+        assert_eq!(calls(), 1);
+
+        // A newer updated_at misses the cache and renders again
+        let updated = cache.render(
+            &view,
+            1,
+            updated_at + TimeDelta::seconds(1),
+            Entity { id: 1, name: "b" },
+        );
+        assert_eq!(updated["name"], "b");
+        assert_eq!(calls(), 2);
+
+        // Capacity is bounded; the least recently used entry is evicted
+        cache.render(&view, 2, updated_at, Entity { id: 2, name: "c" });
+        assert_eq!(cache.len(), 2);
+        assert_eq!(calls(), 3);
+        cache.render(&view, 1, updated_at, Entity { id: 1, name: "a" });
+        assert_eq!(calls(), 4);
+    }
+}
@@ -0,0 +1,164 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use tracing::error;
+
+/// Result type for handlers built on `RequestSuccess` / `RequestError`.
+///
+/// # Example
+/// ```rust,ignore
+/// pub async fn show(
+///     State(app): State<App>,
+///     Path(id): Path<Uuid>,
+///     policy: PostPolicy,
+/// ) -> RequestResult {
+///     let post = find_or_404::<post::Entity>(&app.db, id).await?;
+///     authorize!(policy, read, &post)?;
+///     Ok(RequestSuccess::Ok(json!(post)))
+/// }
+/// ```
+pub type RequestResult = Result<RequestSuccess, RequestError>;
+
+/// Successful handler response.
+#[derive(Debug, Clone)]
+pub enum RequestSuccess {
+    /// 200 with a JSON body
+    Ok(serde_json::Value),
+    /// 201 with a JSON body and a `Location` header pointing at the new resource
+    Created {
+        location: String,
+        body: serde_json::Value,
+    },
+    /// 204 without a body
+    NoContent,
+}
+
+impl IntoResponse for RequestSuccess {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Ok(body) => (StatusCode::OK, Json(body)).into_response(),
+            Self::Created { location, body } => (
+                StatusCode::CREATED,
+                [(header::LOCATION, location)],
+                Json(body),
+            )
+                .into_response(),
+            Self::NoContent => StatusCode::NO_CONTENT.into_response(),
+        }
+    }
+}
+
+/// Handler error rendered as `{ "error": "<code>" }` with the matching status.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{status}: {error}")]
+pub struct RequestError {
+    status: StatusCode,
+    error: String,
+}
+
+impl RequestError {
+    pub fn new(status: StatusCode, error: impl Into<String>) -> Self {
+        Self {
+            status,
+            error: error.into(),
+        }
+    }
+
+    pub fn bad_request(error: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, error)
+    }
+
+    pub fn unauthorized() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "unauthorized")
+    }
+
+    pub fn forbidden() -> Self {
+        Self::new(StatusCode::FORBIDDEN, "forbidden")
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found")
+    }
+
+    pub fn internal() -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_server_error")
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+}
+
+impl IntoResponse for RequestError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(serde_json::json!({ "error": self.error })),
+        )
+            .into_response()
+    }
+}
+
+impl From<sea_orm::DbErr> for RequestError {
+    fn from(err: sea_orm::DbErr) -> Self {
+        error!("❌ Database error while handling request: {}", err);
+        Self::internal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{extract::State, http::header, routing::post, Router};
+    use sea_orm::{ActiveModelTrait, Set};
+    use serde_json::{json, Value};
+
+    use super::{RequestResult, RequestSuccess};
+    use crate::{
+        app::App,
+        database::{migrations::Migrator, models::user},
+        tests::setup_test::{no_fixtures, setup_test},
+    };
+
+    async fn create_user(State(app): State<App>) -> RequestResult {
+        let user = user::ActiveModel {
+            email: Set("created@example.com".to_string()),
+            password_hash: Set("hash".to_string()),
+            ..Default::default()
+        }
+        .insert(&app.db)
+        .await?;
+
+        Ok(RequestSuccess::Created {
+            location: format!("/api/users/{}", user.id),
+            body: json!({ "id": user.id, "email": user.email }),
+        })
+    }
+
+    fn test_router(app: App) -> Router {
+        Router::new()
+            .route("/users", post(create_user))
+            .with_state(app)
+    }
+
+    #[tokio::test]
+    async fn test_created_sets_status_and_location() {
+        let test = setup_test::<Migrator>(test_router, no_fixtures).await;
+
+        let response = test.server.post("/api/users").await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+
+        let body: Value = response.json();
+        assert_eq!(body["email"], "created@example.com");
+        let id = body["id"].as_str().unwrap();
+        assert_eq!(
+            response.header(header::LOCATION),
+            format!("/api/users/{id}").as_str()
+        );
+    }
+}
@@ -0,0 +1,408 @@
+use std::cell::RefCell;
+
+use axum::{
+    body::to_bytes,
+    extract::{FromRequest, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{
+    de::{
+        self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserializer,
+};
+use serde_json::Value;
+
+use crate::{app::App, config::JsonConfig};
+
+/// A JSON body extractor with the limits from `server.json`.
+///
+/// Unlike `axum::Json`, it rejects bodies over `max_bytes` or nested deeper
+/// than `max_depth` before deserializing them, and with `deny_unknown_fields`
+/// (the default) it rejects fields `T` doesn't have, as if `T` were marked
+/// `#[serde(deny_unknown_fields)]`. Fields inside externally tagged enums
+/// and `#[serde(flatten)]`ed structs are not checked.
+///
+/// # Example
+/// ```rust,ignore
+/// async fn update_profile(user: CurrentUser, StrictJson(body): StrictJson<UpdateProfile>) -> RequestResult {
+///     // A typo like `"dispaly_name"` is a 400 instead of a silent no-op
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StrictJson<T>(pub T);
+
+impl<T, ExtraConfig> FromRequest<App<ExtraConfig>> for StrictJson<T>
+where
+    T: DeserializeOwned,
+    ExtraConfig: Send + Sync,
+{
+    type Rejection = StrictJsonRejection;
+
+    async fn from_request(req: Request, app: &App<ExtraConfig>) -> Result<Self, Self::Rejection> {
+        let config = &app.config.server.json;
+        if !has_json_content_type(&req) {
+            return Err(StrictJsonRejection::UnsupportedMediaType);
+        }
+        let body = to_bytes(req.into_body(), config.max_bytes)
+            .await
+            .map_err(|_| StrictJsonRejection::TooLarge)?;
+
+        parse(&body, config).map(Self)
+    }
+}
+
+/// Why [`StrictJson`] rejected a body. Rendered as `{ "error": "<code>" }`,
+/// plus the offending `fields` for `unknown_fields`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StrictJsonRejection {
+    #[error("Expected an application/json body")]
+    UnsupportedMediaType,
+    #[error("Body is too large")]
+    TooLarge,
+    #[error("Body is nested too deeply")]
+    TooDeep,
+    #[error("Invalid JSON: {0}")]
+    InvalidJson(String),
+    #[error("Unknown fields: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
+}
+
+impl IntoResponse for StrictJsonRejection {
+    fn into_response(self) -> Response {
+        let (status, body) = match self {
+            Self::UnsupportedMediaType => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                serde_json::json!({ "error": "unsupported_media_type" }),
+            ),
+            Self::TooLarge => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                serde_json::json!({ "error": "payload_too_large" }),
+            ),
+            Self::TooDeep => (
+                StatusCode::BAD_REQUEST,
+                serde_json::json!({ "error": "json_too_deep" }),
+            ),
+            Self::InvalidJson(message) => (
+                StatusCode::BAD_REQUEST,
+                serde_json::json!({ "error": "invalid_json", "message": message }),
+            ),
+            Self::UnknownFields(fields) => (
+                StatusCode::BAD_REQUEST,
+                serde_json::json!({ "error": "unknown_fields", "fields": fields }),
+            ),
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+fn has_json_content_type(req: &Request) -> bool {
+    let Some(content_type) = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    essence == "application/json"
+        || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+fn parse<T: DeserializeOwned>(body: &[u8], config: &JsonConfig) -> Result<T, StrictJsonRejection> {
+    if nesting_depth(body) > config.max_depth {
+        return Err(StrictJsonRejection::TooDeep);
+    }
+    let value: Value = serde_json::from_slice(body)
+        .map_err(|e| StrictJsonRejection::InvalidJson(e.to_string()))?;
+
+    if !config.deny_unknown_fields {
+        return T::deserialize(value).map_err(|e| StrictJsonRejection::InvalidJson(e.to_string()));
+    }
+
+    let unknown = RefCell::new(Vec::new());
+    let parsed = T::deserialize(Tracked {
+        value,
+        path: String::new(),
+        unknown: &unknown,
+    })
+    .map_err(|e| StrictJsonRejection::InvalidJson(e.to_string()))?;
+
+    let unknown = unknown.into_inner();
+    if unknown.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(StrictJsonRejection::UnknownFields(unknown))
+    }
+}
+
+/// Deepest nesting of arrays and objects in `body`, without parsing it.
+fn nesting_depth(body: &[u8]) -> usize {
+    let (mut depth, mut deepest) = (0usize, 0usize);
+    let (mut in_string, mut escaped) = (false, false);
+    for &byte in body {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    deepest
+}
+
+/// Deserializes from a parsed [`Value`], recording the path of every field
+/// the target type skips. Serde deserializes fields it doesn't know as
+/// `IgnoredAny`, which is what `deserialize_ignored_any` catches.
+struct Tracked<'a> {
+    value: Value,
+    path: String,
+    unknown: &'a RefCell<Vec<String>>,
+}
+
+impl<'de> Deserializer<'de> for Tracked<'_> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Object(map) => visitor.visit_map(TrackedMap {
+                entries: map.into_iter(),
+                pending: None,
+                path: self.path,
+                unknown: self.unknown,
+            }),
+            Value::Array(items) => visitor.visit_seq(TrackedSeq {
+                items: items.into_iter().enumerate(),
+                path: self.path,
+                unknown: self.unknown,
+            }),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.unknown.borrow_mut().push(self.path);
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct TrackedMap<'a> {
+    entries: serde_json::map::IntoIter,
+    pending: Option<(String, Value)>,
+    path: String,
+    unknown: &'a RefCell<Vec<String>>,
+}
+
+impl<'de> MapAccess<'de> for TrackedMap<'_> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        let parsed = seed.deserialize(key.clone().into_deserializer())?;
+        self.pending = Some((key, value));
+        Ok(Some(parsed))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (key, value) = self
+            .pending
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        let path = if self.path.is_empty() {
+            key
+        } else {
+            format!("{}.{key}", self.path)
+        };
+        seed.deserialize(Tracked {
+            value,
+            path,
+            unknown: self.unknown,
+        })
+    }
+}
+
+struct TrackedSeq<'a> {
+    items: std::iter::Enumerate<std::vec::IntoIter<Value>>,
+    path: String,
+    unknown: &'a RefCell<Vec<String>>,
+}
+
+impl<'de> SeqAccess<'de> for TrackedSeq<'_> {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        let Some((index, value)) = self.items.next() else {
+            return Ok(None);
+        };
+        seed.deserialize(Tracked {
+            value,
+            path: format!("{}[{index}]", self.path),
+            unknown: self.unknown,
+        })
+        .map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+
+    use super::{nesting_depth, StrictJson};
+    use crate::{
+        api::request_result::{RequestResult, RequestSuccess},
+        app::App,
+        database::migrations::Migrator,
+        tests::setup_test::{no_fixtures, setup_test, setup_test_with_config},
+    };
+
+    #[derive(Deserialize)]
+    struct Address {
+        city: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Signup {
+        email: String,
+        nickname: Option<String>,
+        addresses: Vec<Address>,
+    }
+
+    async fn signup(StrictJson(body): StrictJson<Signup>) -> RequestResult {
+        let cities: Vec<_> = body
+            .addresses
+            .into_iter()
+            .map(|address| address.city)
+            .collect();
+        Ok(RequestSuccess::Ok(
+            json!({ "email": body.email, "nickname": body.nickname, "cities": cities }),
+        ))
+    }
+
+    fn test_router(app: App) -> Router {
+        Router::new().route("/signup", post(signup)).with_state(app)
+    }
+
+    fn body_with_typos() -> Value {
+        json!({
+            "email": "a@example.com",
+            "nick_name": "al",
+            "addresses": [{ "city": "Kraków" }, { "city": "Gdańsk", "zip": "80-001" }],
+        })
+    }
+
+    #[tokio::test]
+    async fn test_unknown_fields_are_rejected_in_strict_mode() {
+        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+
+        let response = t.server.post("/api/signup").json(&body_with_typos()).await;
+        response.assert_status_bad_request();
+        assert_eq!(
+            response.json::<Value>(),
+            json!({ "error": "unknown_fields", "fields": ["addresses[1].zip", "nick_name"] })
+        );
+
+        let known = json!({ "email": "a@example.com", "nickname": null, "addresses": [] });
+        t.server
+            .post("/api/signup")
+            .json(&known)
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_unknown_fields_are_ignored_in_lenient_mode() {
+        let t = setup_test_with_config::<Migrator>(test_router, no_fixtures, |config| {
+            config.server.json.deny_unknown_fields = false;
+        })
+        .await;
+
+        let response = t.server.post("/api/signup").json(&body_with_typos()).await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<Value>()["cities"],
+            json!(["Kraków", "Gdańsk"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deeply_nested_body_is_rejected() {
+        let t = setup_test_with_config::<Migrator>(test_router, no_fixtures, |config| {
+            config.server.json.max_depth = 3;
+        })
+        .await;
+
+        let nested =
+            json!({ "email": "a@example.com", "addresses": [{ "city": "x", "extra": [[1]] }] });
+        let response = t.server.post("/api/signup").json(&nested).await;
+        response.assert_status_bad_request();
+        assert_eq!(response.json::<Value>()["error"], "json_too_deep");
+    }
+
+    #[test]
+    fn test_nesting_depth_ignores_brackets_in_strings() {
+        assert_eq!(nesting_depth(br#"{"a": [1, {"b": "[[{{"}]}"#), 3);
+        assert_eq!(nesting_depth(br#""\"[""#), 0);
+        assert_eq!(nesting_depth(b"42"), 0);
+    }
+}
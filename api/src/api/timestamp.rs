@@ -0,0 +1,155 @@
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A point in time that serializes as RFC 3339 in UTC with a `Z` suffix and
+/// microsecond precision, e.g. `2026-10-17T08:30:00.000000Z`.
+///
+/// Database columns are `NaiveDateTime` holding UTC, and serde's defaults
+/// render those without any offset. Use this in view DTOs so every entity
+/// exposes timestamps in the same format.
+///
+/// # Example
+/// ```rust,ignore
+/// #[derive(Serialize)]
+/// struct PostDto {
+///     id: Uuid,
+///     created_at: Timestamp,
+/// }
+///
+/// impl Renderer<post::Model> for PostView {
+///     fn render(&self, post: post::Model) -> serde_json::Value {
+///         json!(PostDto { id: post.id, created_at: post.created_at.into() })
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub DateTime<Utc>);
+
+impl Timestamp {
+    /// Format as RFC 3339 UTC, the same string the serializer produces.
+    pub fn to_rfc3339(&self) -> String {
+        self.0.to_rfc3339_opts(SecondsFormat::Micros, true)
+    }
+}
+
+impl From<NaiveDateTime> for Timestamp {
+    /// Interprets the naive value as UTC, which is how the database stores it.
+    fn from(value: NaiveDateTime) -> Self {
+        Self(value.and_utc())
+    }
+}
+
+impl<Tz: TimeZone> From<DateTime<Tz>> for Timestamp {
+    fn from(value: DateTime<Tz>) -> Self {
+        Self(value.with_timezone(&Utc))
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_rfc3339())
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    /// Accepts any RFC 3339 timestamp and converts it to UTC.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&value)
+            .map(Self::from)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// `serialize_with` helper for `NaiveDateTime` fields that should render like
+/// [`Timestamp`] without changing the field's type.
+///
+/// ```rust,ignore
+/// #[derive(Serialize)]
+/// struct UserDto {
+///     #[serde(serialize_with = "erno::api::timestamp::serialize_rfc3339")]
+///     created_at: NaiveDateTime,
+/// }
+/// ```
+pub fn serialize_rfc3339<S: Serializer>(
+    value: &NaiveDateTime,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    Timestamp::from(*value).serialize(serializer)
+}
+
+/// Like [`serialize_rfc3339`], for optional fields. `None` renders as `null`.
+pub fn serialize_rfc3339_option<S: Serializer>(
+    value: &Option<NaiveDateTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    value.map(Timestamp::from).serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDateTime};
+    use serde::Serialize;
+    use serde_json::json;
+
+    use super::{serialize_rfc3339_option, Timestamp};
+    use crate::api::view_param::{Renderer, ViewEnum};
+
+    struct Post {
+        created_at: NaiveDateTime,
+        published_at: Option<NaiveDateTime>,
+    }
+
+    #[derive(Serialize)]
+    struct PostDto {
+        created_at: Timestamp,
+        #[serde(serialize_with = "serialize_rfc3339_option")]
+        published_at: Option<NaiveDateTime>,
+    }
+
+    enum PostView {
+        Default,
+    }
+
+    impl ViewEnum for PostView {
+        fn from_name(name: &str) -> Option<Self> {
+            (name == "default").then_some(Self::Default)
+        }
+
+        fn name(&self) -> &str {
+            "default"
+        }
+
+        fn default_view() -> Self {
+            Self::Default
+        }
+    }
+
+    impl Renderer<Post> for PostView {
+        fn render(&self, post: Post) -> serde_json::Value {
+            json!(PostDto {
+                created_at: post.created_at.into(),
+                published_at: post.published_at,
+            })
+        }
+    }
+
+    #[test]
+    fn test_rendered_timestamp_is_rfc3339_utc() {
+        let created_at = DateTime::from_timestamp(1_700_000_000, 123_456_000)
+            .unwrap()
+            .naive_utc();
+        let post = Post {
+            created_at,
+            published_at: None,
+        };
+
+        let rendered = PostView::Default.render(post);
+
+        assert_eq!(rendered["created_at"], "2023-11-14T22:13:20.123456Z");
+        assert!(rendered["published_at"].is_null());
+
+        let parsed: Timestamp = serde_json::from_value(rendered["created_at"].clone()).unwrap();
+        assert_eq!(parsed, Timestamp::from(created_at));
+    }
+}
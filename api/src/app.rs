@@ -3,13 +3,23 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use sea_orm::DatabaseConnection;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    config::Config, database::DatabaseSetupStatus, environment::Environment, job_queue::JobQueue,
-    jobs::Job, mailer::Mailer, metrics::{collector::CollectorRegistry, PrometheusHandle},
-    rate_limiting::RateLimitState, storage::FileStorage,
-    sync::queue::SyncQueue, sync::registry::SyncRegistry, websocket::connections::Connections,
+    auth::UserLoader,
+    config::Config,
+    database::{DatabaseSetupStatus, DatabaseStatus},
+    environment::Environment,
+    events::EventBus,
+    job_queue::JobQueue,
+    jobs::{job_registry::JobRegistry, job_result::JobResult, Job, JobError},
+    mailer::Mailer,
+    metrics::{collector::CollectorRegistry, PrometheusHandle},
+    rate_limiting::RateLimitState,
+    storage::FileStorage,
+    sync::queue::SyncQueue,
+    sync::registry::SyncRegistry,
+    websocket::connections::Connections,
 };
 
 #[derive(Clone)]
@@ -17,19 +27,28 @@ pub struct App<ExtraConfig = ()> {
     pub config: Config<ExtraConfig>,
     pub environment: Environment,
     pub db: DatabaseConnection,
+    pub database_status: DatabaseStatus,
     pub mailer: Mailer,
+    /// Transports from `[email_transports]`, by name; see [`App::email_transport`]
+    pub email_transports: Arc<HashMap<String, Mailer>>,
     pub job_queue: JobQueue,
+    /// Jobs registered at boot; used by [`App::run_job_now`]
+    pub job_registry: Arc<JobRegistry<ExtraConfig>>,
     pub sync_queue: SyncQueue,
     pub sync_registry: Arc<SyncRegistry>,
+    pub user_loader: Arc<dyn UserLoader>,
     pub rate_limit_state: RateLimitState,
     pub websocket_connections: Connections,
+    /// Domain event subscribers, see [`EventBus`]
+    pub event_bus: EventBus,
     pub storage: FileStorage,
     pub metrics_collectors: Arc<CollectorRegistry>,
     pub prometheus_handle: PrometheusHandle,
 }
 
 impl<ExtraConfig> App<ExtraConfig> {
-    pub async fn run_job<J>(&self, arguments: J::Arguments) -> Result<(), sea_orm::DbErr>
+    /// Enqueue `J` and return the new job's id.
+    pub async fn run_job<J>(&self, arguments: J::Arguments) -> Result<uuid::Uuid, sea_orm::DbErr>
     where
         J: Job<ExtraConfig>,
         J::Arguments: serde::Serialize,
@@ -39,6 +58,40 @@ impl<ExtraConfig> App<ExtraConfig> {
             .await
     }
 
+    /// The transport configured as `[email_transports.<name>]`.
+    pub fn email_transport(&self, name: &str) -> Option<&Mailer> {
+        self.email_transports.get(name)
+    }
+}
+
+impl<ExtraConfig> App<ExtraConfig>
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    /// Execute a registered job inline and return its result, without
+    /// inserting a job row.
+    ///
+    /// The job runs on the caller's task, so a request waits for it. There is
+    /// no timeout, retry or execution record: use this only for short,
+    /// idempotent work the user is waiting on, and [`App::run_job`] for
+    /// anything slow or that must survive a failure.
+    ///
+    /// Returns `JobResult::Failed` if `J` isn't registered.
+    pub async fn run_job_now<J>(&self, arguments: J::Arguments) -> JobResult
+    where
+        J: Job<ExtraConfig>,
+        J::Arguments: serde::Serialize,
+    {
+        let arguments = match serde_json::to_value(arguments) {
+            Ok(arguments) => arguments,
+            Err(e) => {
+                return JobResult::Failed(JobError::FailPermanently(format!(
+                    "Failed to serialize job arguments: {e}"
+                )))
+            }
+        };
+        self.job_registry.execute(self, J::name(), &arguments).await
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -54,3 +107,62 @@ impl IntoResponse for ReadinessError {
         (StatusCode::SERVICE_UNAVAILABLE, self.to_string()).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use sea_orm::{EntityTrait, PaginatorTrait};
+
+    use super::App;
+    use crate::{
+        database::{migrations::Migrator, models::job},
+        jobs::{job_registry::JobRegistry, job_result::JobResult, Job, JobError},
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+    };
+
+    struct CheckEvenJob;
+
+    impl Job for CheckEvenJob {
+        type Arguments = u32;
+
+        fn name() -> &'static str {
+            "check_even"
+        }
+
+        async fn execute(_app: &App, n: u32) -> Result<(), JobError> {
+            if n.is_multiple_of(2) {
+                Ok(())
+            } else {
+                Err(JobError::FailPermanently(format!("{n} is odd")))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_job_now_returns_result_without_row() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let mut registry = JobRegistry::new();
+        registry.register_job::<CheckEvenJob>();
+        let app = App {
+            job_registry: Arc::new(registry),
+            ..test.app()
+        };
+        let jobs_before = job::Entity::find().count(&test.db).await.unwrap();
+
+        assert!(matches!(
+            app.run_job_now::<CheckEvenJob>(2).await,
+            JobResult::Completed
+        ));
+        match app.run_job_now::<CheckEvenJob>(3).await {
+            JobResult::Failed(JobError::FailPermanently(reason)) => assert_eq!(reason, "3 is odd"),
+            other => panic!("expected a permanent failure, got {other:?}"),
+        }
+
+        assert_eq!(
+            job::Entity::find().count(&test.db).await.unwrap(),
+            jobs_before
+        );
+        assert!(test.enqueued_jobs().is_empty());
+    }
+}
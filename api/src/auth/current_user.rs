@@ -4,12 +4,13 @@ use axum::{
     http::{request::Parts, StatusCode},
     response::{IntoResponse, Response},
 };
-use sea_orm::{DatabaseConnection, EntityTrait};
+use sea_orm::{DatabaseConnection, DbErr, EntityTrait};
 use uuid::Uuid;
 
 use crate::app::App;
 use crate::auth::jwt;
 use crate::database::models::user;
+use crate::log_context::LogContext;
 
 /// Trait for loading app-specific profile data alongside the authenticated user.
 ///
@@ -27,6 +28,38 @@ impl LoadForUser for () {
     }
 }
 
+/// Loads the user row for an authenticated request.
+///
+/// `CurrentUser` calls `app.user_loader` once per authenticated request after
+/// the JWT has been verified. Implement this to add a short-lived cache, touch
+/// a `last_seen_at` column, and so on, then pass it to
+/// `BootConfig::with_user_loader`. The token version check still runs on the
+/// returned user, so a cache must expire quickly for logout to take effect.
+#[async_trait]
+pub trait UserLoader: Send + Sync {
+    /// Return the user with `user_id`, or `None` if it doesn't exist.
+    async fn load_user(
+        &self,
+        user_id: Uuid,
+        db: &DatabaseConnection,
+    ) -> Result<Option<user::Model>, DbErr>;
+}
+
+/// The default [`UserLoader`]: one `find_by_id` query per request.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DatabaseUserLoader;
+
+#[async_trait]
+impl UserLoader for DatabaseUserLoader {
+    async fn load_user(
+        &self,
+        user_id: Uuid,
+        db: &DatabaseConnection,
+    ) -> Result<Option<user::Model>, DbErr> {
+        user::Entity::find_by_id(user_id).one(db).await
+    }
+}
+
 /// Authenticated user extracted from the JWT Bearer token.
 ///
 /// `P` is an optional app-defined profile type loaded from the database
@@ -98,10 +131,15 @@ where
         let claims =
             jwt::verify_token(&state.config, token).map_err(|_| AuthError::Unauthorized)?;
 
+        if !jwt::matches_client(&state.config, &claims, &parts.headers) {
+            return Err(AuthError::Unauthorized);
+        }
+
         let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AuthError::Unauthorized)?;
 
-        let user = user::Entity::find_by_id(user_id)
-            .one(&state.db)
+        let user = state
+            .user_loader
+            .load_user(user_id, &state.db)
             .await
             .map_err(|_| AuthError::DatabaseError)?
             .ok_or(AuthError::Unauthorized)?;
@@ -112,8 +150,188 @@ where
             return Err(AuthError::Unauthorized);
         }
 
+        LogContext::set_user_id(user_id);
+
         let profile = P::load_for_user(user_id, &state.db).await?;
 
         Ok(CurrentUser { user, profile })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use async_trait::async_trait;
+    use axum::{http::HeaderMap, routing::get, Router};
+    use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, Set};
+    use uuid::Uuid;
+
+    use super::{CurrentUser, UserLoader};
+    use crate::{
+        app::App,
+        auth::jwt::generate_token,
+        database::migrations::Migrator,
+        database::models::user,
+        password::hash_password,
+        tests::setup_test::{no_fixtures, setup_test, setup_test_with_config, TestUtils},
+    };
+
+    // Not stored in the database, so only the cache can resolve it
+    const CACHED_USER_ID: Uuid = Uuid::from_u128(0x0199_0000_0000_7000_8000_0000_0000_1740);
+
+    static LOADS: AtomicUsize = AtomicUsize::new(0);
+
+    struct CachedUserLoader {
+        user: user::Model,
+    }
+
+    #[async_trait]
+    impl UserLoader for CachedUserLoader {
+        async fn load_user(
+            &self,
+            user_id: Uuid,
+            _db: &DatabaseConnection,
+        ) -> Result<Option<user::Model>, DbErr> {
+            LOADS.fetch_add(1, Ordering::SeqCst);
+            Ok((user_id == self.user.id).then(|| self.user.clone()))
+        }
+    }
+
+    async fn whoami(CurrentUser { user, .. }: CurrentUser) -> String {
+        user.email
+    }
+
+    fn test_router(app: App) -> Router {
+        let now = chrono::Utc::now().naive_utc();
+        let user = user::Model {
+            id: CACHED_USER_ID,
+            email: "cached@example.com".to_string(),
+            password_hash: String::new(),
+            email_verified_at: Some(now),
+            token_version: 0,
+            subscription_id: None,
+            subscription_type: None,
+            subscription_plan: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let app = App {
+            user_loader: Arc::new(CachedUserLoader { user }),
+            ..app
+        };
+        Router::new().route("/whoami", get(whoami)).with_state(app)
+    }
+
+    #[tokio::test]
+    async fn test_custom_loader_serves_cached_user() {
+        let test = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let token = generate_token(&test.config, CACHED_USER_ID, 0, &HeaderMap::new()).unwrap();
+
+        let response = test
+            .server
+            .get("/api/whoami")
+            .add_header("Authorization", format!("Bearer {token}"))
+            .await;
+
+        response.assert_status_ok();
+        response.assert_text("cached@example.com");
+        assert_eq!(LOADS.load(Ordering::SeqCst), 1);
+    }
+
+    fn bound_router(app: App) -> Router {
+        Router::new().route("/whoami", get(whoami)).with_state(app)
+    }
+
+    fn client_headers(user_agent: &str, secret: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("User-Agent", user_agent.parse().unwrap());
+        headers.insert("X-Client-Secret", secret.parse().unwrap());
+        headers
+    }
+
+    async fn setup_bound_test() -> (TestUtils, String) {
+        let test = setup_test_with_config::<Migrator>(bound_router, no_fixtures, |config| {
+            config.auth.bind_tokens_to_client = true;
+        })
+        .await;
+        let u = user::ActiveModel {
+            email: Set("bound@example.com".to_string()),
+            password_hash: Set(String::new()),
+            email_verified_at: Set(Some(chrono::Utc::now().naive_utc())),
+            ..Default::default()
+        }
+        .insert(&test.db)
+        .await
+        .unwrap();
+        let headers = client_headers("ExampleApp/1.0", "device-secret");
+        let token = generate_token(&test.config, u.id, u.token_version, &headers).unwrap();
+        (test, token)
+    }
+
+    #[tokio::test]
+    async fn test_bound_token_accepted_from_same_client() {
+        let (test, token) = setup_bound_test().await;
+
+        let response = test
+            .server
+            .get("/api/whoami")
+            .add_header("Authorization", format!("Bearer {token}"))
+            .add_header("User-Agent", "ExampleApp/1.0")
+            .add_header("X-Client-Secret", "device-secret")
+            .await;
+
+        response.assert_status_ok();
+        response.assert_text("bound@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_bound_token_rejected_from_other_client() {
+        let (test, token) = setup_bound_test().await;
+
+        let response = test
+            .server
+            .get("/api/whoami")
+            .add_header("Authorization", format!("Bearer {token}"))
+            .add_header("User-Agent", "ExampleApp/1.0")
+            .add_header("X-Client-Secret", "stolen-elsewhere")
+            .await;
+
+        response.assert_status_unauthorized();
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_is_not_accepted_as_bearer() {
+        let test = setup_test::<Migrator>(bound_router, no_fixtures).await;
+        user::ActiveModel {
+            email: Set("pair@example.com".to_string()),
+            password_hash: Set(hash_password("password123").unwrap()),
+            email_verified_at: Set(Some(chrono::Utc::now().naive_utc())),
+            ..Default::default()
+        }
+        .insert(&test.db)
+        .await
+        .unwrap();
+        let pair: serde_json::Value = test
+            .server
+            .post("/api/auth/login")
+            .json(&serde_json::json!({ "email": "pair@example.com", "password": "password123" }))
+            .await
+            .json();
+
+        let whoami = |token: &str| {
+            test.server
+                .get("/api/whoami")
+                .add_header("Authorization", format!("Bearer {token}"))
+        };
+        whoami(pair["access_token"].as_str().unwrap())
+            .await
+            .assert_status_ok();
+        whoami(pair["refresh_token"].as_str().unwrap())
+            .await
+            .assert_status_unauthorized();
+    }
+}
@@ -1,4 +1,9 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use serde::Deserialize;
 use validator::Validate;
@@ -18,9 +23,9 @@ pub struct LoginRequest {
     pub password: String,
 }
 
-
 pub async fn login<ExtraConfig>(
     State(app): State<App<ExtraConfig>>,
+    headers: HeaderMap,
     ValidatedJson(body): ValidatedJson<LoginRequest>,
 ) -> impl IntoResponse
 where
@@ -63,7 +68,7 @@ where
             .into_response();
     }
 
-    match issue_token_pair(&app, &user).await {
+    match issue_token_pair(&app, &user, &headers).await {
         Ok(pair) => (StatusCode::OK, Json(pair)).into_response(),
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     }
@@ -71,31 +76,18 @@ where
 
 #[cfg(test)]
 mod tests {
-    use axum::Router;
     use sea_orm::{ActiveModelTrait, Set};
     use serde_json::json;
 
     use crate::{
-        app::App,
         database::{migrations::Migrator, models::user},
         password::hash_password,
-        tests::setup_test::setup_test,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
     };
 
-    fn test_router(_app: App) -> Router {
-        Router::new()
-    }
-    fn no_fixtures(
-        db: &sea_orm::DatabaseConnection,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
-        Box::pin(async move {
-            let _ = db;
-        })
-    }
-
     #[tokio::test]
     async fn test_login_with_valid_credentials_returns_token() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         user::ActiveModel {
             email: Set("verified@example.com".to_string()),
             password_hash: Set(hash_password("password123").unwrap()),
@@ -119,7 +111,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_login_with_wrong_password_returns_401() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         user::ActiveModel {
             email: Set("verified2@example.com".to_string()),
             password_hash: Set(hash_password("password123").unwrap()),
@@ -140,7 +132,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_login_unverified_user_returns_403() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         t.server
             .post("/api/auth/register")
             .json(&json!({ "email": "unverified@example.com", "password": "password123" }))
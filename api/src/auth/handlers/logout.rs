@@ -52,37 +52,25 @@ where
 
 #[cfg(test)]
 mod tests {
-    use axum::Router;
+    use axum::http::HeaderMap;
     use chrono::Utc;
     use sea_orm::{ActiveModelTrait, Set};
     use serde_json::json;
 
     use crate::{
-        app::App,
         auth::jwt::generate_token,
         database::{
             migrations::Migrator,
             models::{user, user_token, user_token_type::UserTokenType},
         },
         password::hash_password,
-        tests::setup_test::setup_test,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
         token::hash_token,
     };
 
-    fn test_router(_app: App) -> Router {
-        Router::new()
-    }
-    fn no_fixtures(
-        db: &sea_orm::DatabaseConnection,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
-        Box::pin(async move {
-            let _ = db;
-        })
-    }
-
     #[tokio::test]
     async fn test_logout_invalidates_token() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         let u = user::ActiveModel {
             email: Set("logout@example.com".to_string()),
@@ -94,7 +82,7 @@ mod tests {
         .await
         .unwrap();
 
-        let token = generate_token(&t.config, u.id, u.token_version).unwrap();
+        let token = generate_token(&t.config, u.id, u.token_version, &HeaderMap::new()).unwrap();
 
         let response = t
             .server
@@ -113,7 +101,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_logout_deletes_refresh_token() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         let u = user::ActiveModel {
             email: Set("logout_refresh@example.com".to_string()),
@@ -136,7 +124,7 @@ mod tests {
         .await
         .unwrap();
 
-        let token = generate_token(&t.config, u.id, u.token_version).unwrap();
+        let token = generate_token(&t.config, u.id, u.token_version, &HeaderMap::new()).unwrap();
         let response = t
             .server
             .post("/api/auth/logout")
@@ -156,14 +144,14 @@ mod tests {
 
     #[tokio::test]
     async fn test_logout_requires_auth() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         let response = t.server.post("/api/auth/logout").await;
         assert_eq!(response.status_code(), 401);
     }
 
     #[tokio::test]
     async fn test_password_reset_invalidates_previous_token() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         let u = user::ActiveModel {
             email: Set("reset_invalidates@example.com".to_string()),
@@ -175,7 +163,8 @@ mod tests {
         .await
         .unwrap();
 
-        let old_token = generate_token(&t.config, u.id, u.token_version).unwrap();
+        let old_token =
+            generate_token(&t.config, u.id, u.token_version, &HeaderMap::new()).unwrap();
 
         let login_response = t
             .server
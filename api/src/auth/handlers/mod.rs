@@ -6,6 +6,7 @@ pub mod register;
 pub mod resend_verification;
 pub mod verify_email;
 
+use axum::http::HeaderMap;
 use chrono::Utc;
 use sea_orm::ActiveModelTrait;
 use sea_orm::Set;
@@ -31,14 +32,19 @@ pub struct TokenPair {
 }
 
 /// Generate an access JWT and a fresh refresh token, persisting the refresh token to the DB.
+///
+/// `headers` are those of the current request; the access token is bound to
+/// that client when `auth.bind_tokens_to_client` is enabled.
 pub async fn issue_token_pair<ExtraConfig>(
     app: &App<ExtraConfig>,
     user: &user::Model,
+    headers: &HeaderMap,
 ) -> Result<TokenPair, ()>
 where
     ExtraConfig: Clone + Send + Sync + 'static,
 {
-    let access_token = generate_token(&app.config, user.id, user.token_version).map_err(|_| ())?;
+    let access_token =
+        generate_token(&app.config, user.id, user.token_version, headers).map_err(|_| ())?;
 
     let raw_refresh = generate_secure_token(64);
     let expires_at = Utc::now().naive_utc()
@@ -1,4 +1,9 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
 use chrono::Utc;
 use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
 use sea_orm::sea_query::Expr;
@@ -67,6 +72,7 @@ pub struct PasswordResetConfirmBody {
 
 pub async fn password_reset_confirm<ExtraConfig>(
     State(app): State<App<ExtraConfig>>,
+    headers: HeaderMap,
     ValidatedJson(body): ValidatedJson<PasswordResetConfirmBody>,
 ) -> impl IntoResponse
 where
@@ -150,7 +156,7 @@ where
         _ => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     };
 
-    match issue_token_pair(&app, &updated_user).await {
+    match issue_token_pair(&app, &updated_user, &headers).await {
         Ok(pair) => (StatusCode::OK, Json(pair)).into_response(),
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     }
@@ -158,36 +164,23 @@ where
 
 #[cfg(test)]
 mod tests {
-    use axum::Router;
     use chrono::Utc;
     use sea_orm::{ActiveModelTrait, Set};
     use serde_json::json;
 
     use crate::{
-        app::App,
         database::{
             migrations::Migrator,
             models::{user, user_token, user_token_type::UserTokenType},
         },
         password::hash_password,
-        tests::setup_test::setup_test,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
         token::hash_token,
     };
 
-    fn test_router(_app: App) -> Router {
-        Router::new()
-    }
-    fn no_fixtures(
-        db: &sea_orm::DatabaseConnection,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
-        Box::pin(async move {
-            let _ = db;
-        })
-    }
-
     #[tokio::test]
     async fn test_password_reset_request_always_returns_200() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         let response = t
             .server
             .post("/api/auth/password-reset/request")
@@ -198,7 +191,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_password_reset_request_enqueues_job_for_existing_user() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         user::ActiveModel {
             email: Set("reset_request@example.com".to_string()),
@@ -224,7 +217,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_password_reset_confirm_with_valid_token_logs_in() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         let u = user::ActiveModel {
             email: Set("reset_confirm@example.com".to_string()),
@@ -260,7 +253,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_password_reset_confirm_with_expired_token_returns_422() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         let response = t
             .server
             .post("/api/auth/password-reset/confirm")
@@ -1,4 +1,9 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
 use chrono::Utc;
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use serde::Deserialize;
@@ -17,6 +22,7 @@ pub struct RefreshRequest {
 
 pub async fn refresh<ExtraConfig>(
     State(app): State<App<ExtraConfig>>,
+    headers: HeaderMap,
     Json(body): Json<RefreshRequest>,
 ) -> impl IntoResponse
 where
@@ -70,7 +76,7 @@ where
         _ => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     };
 
-    match issue_token_pair(&app, &user).await {
+    match issue_token_pair(&app, &user, &headers).await {
         Ok(pair) => (StatusCode::OK, Json(pair)).into_response(),
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     }
@@ -78,36 +84,23 @@ where
 
 #[cfg(test)]
 mod tests {
-    use axum::Router;
     use chrono::Utc;
     use sea_orm::{ActiveModelTrait, Set};
     use serde_json::json;
 
     use crate::{
-        app::App,
         database::{
             migrations::Migrator,
             models::{user, user_token, user_token_type::UserTokenType},
         },
         password::hash_password,
-        tests::setup_test::setup_test,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
         token::hash_token,
     };
 
-    fn test_router(_app: App) -> Router {
-        Router::new()
-    }
-    fn no_fixtures(
-        db: &sea_orm::DatabaseConnection,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
-        Box::pin(async move {
-            let _ = db;
-        })
-    }
-
     #[tokio::test]
     async fn test_refresh_returns_new_token_pair() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         let u = user::ActiveModel {
             email: Set("refresh@example.com".to_string()),
@@ -144,7 +137,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_refresh_token_rotation_prevents_replay() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         let u = user::ActiveModel {
             email: Set("refresh_replay@example.com".to_string()),
@@ -185,7 +178,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_refresh_with_invalid_token_returns_401() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         let response = t
             .server
             .post("/api/auth/refresh")
@@ -116,29 +116,16 @@ where
 
 #[cfg(test)]
 mod tests {
-    use axum::Router;
     use serde_json::json;
 
     use crate::{
-        app::App,
         database::migrations::Migrator,
-        tests::setup_test::setup_test,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
     };
 
-    fn test_router(_app: App) -> Router {
-        Router::new()
-    }
-    fn no_fixtures(
-        db: &sea_orm::DatabaseConnection,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
-        Box::pin(async move {
-            let _ = db;
-        })
-    }
-
     #[tokio::test]
     async fn test_register_creates_user_and_enqueues_email() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         let response = t
             .server
@@ -152,7 +139,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_register_duplicate_unverified_email_resends_verification() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         t.server
             .post("/api/auth/register")
@@ -182,7 +169,7 @@ mod tests {
 
         use crate::{database::models::user, password::hash_password};
 
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         user::ActiveModel {
             email: Set("verified@example.com".to_string()),
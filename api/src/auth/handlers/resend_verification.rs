@@ -54,31 +54,18 @@ where
 
 #[cfg(test)]
 mod tests {
-    use axum::Router;
     use sea_orm::{ActiveModelTrait, Set};
     use serde_json::json;
 
     use crate::{
-        app::App,
         database::{migrations::Migrator, models::user},
         password::hash_password,
-        tests::setup_test::setup_test,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
     };
 
-    fn test_router(_app: App) -> Router {
-        Router::new()
-    }
-    fn no_fixtures(
-        db: &sea_orm::DatabaseConnection,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
-        Box::pin(async move {
-            let _ = db;
-        })
-    }
-
     #[tokio::test]
     async fn test_resend_always_returns_200() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         let response = t
             .server
             .post("/api/auth/email/resend-verification")
@@ -89,7 +76,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_resend_for_unverified_user_enqueues_job() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         user::ActiveModel {
             email: Set("resend_unverified@example.com".to_string()),
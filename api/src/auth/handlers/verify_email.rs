@@ -1,4 +1,9 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
 use chrono::Utc;
 use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
 use serde::Deserialize;
@@ -17,6 +22,7 @@ pub struct VerifyEmailRequest {
 
 pub async fn verify_email<ExtraConfig>(
     State(app): State<App<ExtraConfig>>,
+    headers: HeaderMap,
     Json(body): Json<VerifyEmailRequest>,
 ) -> impl IntoResponse
 where
@@ -81,7 +87,7 @@ where
         _ => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     };
 
-    match issue_token_pair(&app, &verified_user).await {
+    match issue_token_pair(&app, &verified_user, &headers).await {
         Ok(pair) => (StatusCode::OK, Json(pair)).into_response(),
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     }
@@ -89,36 +95,23 @@ where
 
 #[cfg(test)]
 mod tests {
-    use axum::Router;
     use chrono::Utc;
     use sea_orm::{ActiveModelTrait, Set};
     use serde_json::json;
 
     use crate::{
-        app::App,
         database::{
             migrations::Migrator,
             models::{user, user_token, user_token_type::UserTokenType},
         },
         password::hash_password,
-        tests::setup_test::setup_test,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
         token::hash_token,
     };
 
-    fn test_router(_app: App) -> Router {
-        Router::new()
-    }
-    fn no_fixtures(
-        db: &sea_orm::DatabaseConnection,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
-        Box::pin(async move {
-            let _ = db;
-        })
-    }
-
     #[tokio::test]
     async fn test_verify_email_with_valid_token_returns_jwt() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
 
         let u = user::ActiveModel {
             email: Set("verify_valid@example.com".to_string()),
@@ -153,7 +146,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_verify_email_with_invalid_token_returns_422() {
-        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         let response = t
             .server
             .post("/api/auth/email/verify")
@@ -1,9 +1,21 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use axum::http::{header, HeaderMap};
 use chrono::Utc;
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{
+    decode, encode, errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::config::Config;
+use crate::{
+    config::{AuthConfig, Config, JwtAlgorithm},
+    token::hash_token,
+};
+
+/// Header carrying the client-provided secret mixed into the token binding.
+pub const CLIENT_SECRET_HEADER: &str = "X-Client-Secret";
 
 /// JWT claims structure containing user information and token metadata.
 ///
@@ -21,17 +33,25 @@ pub struct Claims {
     pub exp: usize,
     /// Issued at (Unix timestamp)
     pub iat: usize,
+    /// Confirmation — the fingerprint of the client the token was issued to.
+    /// Only set when `auth.bind_tokens_to_client` is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cnf: Option<String>,
 }
 
 /// Generate a JWT token for the specified user.
 ///
 /// Creates a signed JWT token with the user's ID as the subject and expiration
-/// set according to the configuration. The token is signed using the HS256 algorithm
-/// with the secret from the application configuration.
+/// set according to the configuration. The token is signed with `auth.algorithm`,
+/// using `auth.secret` or the private key at `auth.private_key_path`.
+///
+/// When `auth.bind_tokens_to_client` is enabled, the fingerprint of the
+/// requesting client (see [`client_fingerprint`]) is embedded as the `cnf` claim.
 ///
 /// # Arguments
 /// * `config` - Application configuration containing JWT secret and expiration settings
 /// * `user_id` - UUID of the user to create the token for
+/// * `headers` - Headers of the request the token is issued for
 ///
 /// # Returns
 /// A signed JWT token string, or an error if token generation fails
@@ -42,6 +62,7 @@ pub fn generate_token<ExtraConfig>(
     config: &Config<ExtraConfig>,
     user_id: Uuid,
     token_version: i32,
+    headers: &HeaderMap,
 ) -> Result<String, jsonwebtoken::errors::Error> {
     let now = Utc::now().timestamp() as usize;
     let exp = now + (config.auth.access_token_minutes * 60) as usize;
@@ -51,28 +72,19 @@ pub fn generate_token<ExtraConfig>(
         ver: token_version,
         exp,
         iat: now,
+        cnf: config
+            .auth
+            .bind_tokens_to_client
+            .then(|| client_fingerprint(headers)),
     };
 
     encode(
-        &Header::default(),
+        &Header::new(algorithm(&config.auth)),
         &claims,
-        &EncodingKey::from_secret(config.auth.secret.as_bytes()),
+        &encoding_key(&config.auth)?,
     )
 }
 
-/// Verify and decode a JWT token.
-///
-/// Validates the token signature and expiration, then returns the decoded claims.
-///
-/// # Arguments
-/// * `config` - Application configuration containing JWT secret
-/// * `token` - The JWT token string to verify
-///
-/// # Returns
-/// The decoded claims if the token is valid, or an error if verification fails
-///
-/// # Errors
-/// Returns `jsonwebtoken::errors::Error` if token is invalid, expired, or malformed
 /// Minimum acceptable byte length for an HS256 JWT secret (256 bits).
 const MIN_JWT_SECRET_LEN: usize = 32;
 
@@ -80,7 +92,11 @@ const MIN_JWT_SECRET_LEN: usize = 32;
 ///
 /// Returns `Err` with a descriptive message when the secret is too short.
 /// Call this at startup so misconfiguration is caught before serving traffic.
+/// Always passes for algorithms that sign with a key pair.
 pub fn validate_jwt_secret<ExtraConfig>(config: &Config<ExtraConfig>) -> Result<(), String> {
+    if !is_hmac(config.auth.algorithm) {
+        return Ok(());
+    }
     if config.auth.secret.len() < MIN_JWT_SECRET_LEN {
         return Err(format!(
             "JWT secret is too short ({} bytes). Minimum is {} bytes (256 bits).",
@@ -91,15 +107,248 @@ pub fn validate_jwt_secret<ExtraConfig>(config: &Config<ExtraConfig>) -> Result<
     Ok(())
 }
 
+/// Check that the key files for `auth.algorithm` exist, parse and belong
+/// together, by signing and verifying a token.
+///
+/// A no-op for the `HS*` algorithms. Call this at startup, since every login
+/// would fail otherwise.
+pub fn validate_jwt_keys<ExtraConfig>(config: &Config<ExtraConfig>) -> Result<(), String> {
+    let auth = &config.auth;
+    if is_hmac(auth.algorithm) {
+        return Ok(());
+    }
+    for (name, path) in [
+        ("private_key_path", &auth.private_key_path),
+        ("public_key_path", &auth.public_key_path),
+    ] {
+        let path = path
+            .as_deref()
+            .ok_or_else(|| format!("auth.{name} is required for {:?}.", auth.algorithm))?;
+        std::fs::metadata(path).map_err(|e| format!("Can't read auth.{name} {path}: {e}."))?;
+    }
+
+    let token = generate_token(config, Uuid::nil(), 0, &HeaderMap::new())
+        .map_err(|e| format!("Can't sign with auth.private_key_path: {e}."))?;
+    verify_token(config, &token).map_err(|e| {
+        format!("Can't verify with auth.public_key_path, is it the private key's public half? {e}.")
+    })?;
+    Ok(())
+}
+
+/// Verify and decode a JWT token.
+///
+/// Validates the token signature and expiration, then returns the decoded claims.
+/// Tokens signed with any algorithm other than `auth.algorithm` are rejected,
+/// and `exp` is checked with `auth.leeway_seconds` of tolerance for clock skew.
+///
+/// # Arguments
+/// * `config` - Application configuration containing JWT secret
+/// * `token` - The JWT token string to verify
+///
+/// # Returns
+/// The decoded claims if the token is valid, or an error if verification fails
+///
+/// # Errors
+/// Returns `jsonwebtoken::errors::Error` if token is invalid, expired, or malformed
 pub fn verify_token<ExtraConfig>(
     config: &Config<ExtraConfig>,
     token: &str,
 ) -> Result<Claims, jsonwebtoken::errors::Error> {
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(config.auth.secret.as_bytes()),
-        &Validation::default(),
-    )?;
+    let mut validation = Validation::new(algorithm(&config.auth));
+    validation.leeway = config.auth.leeway_seconds;
+    let token_data = decode::<Claims>(token, &decoding_key(&config.auth)?, &validation)?;
 
     Ok(token_data.claims)
 }
+
+const fn algorithm(auth: &AuthConfig) -> Algorithm {
+    match auth.algorithm {
+        JwtAlgorithm::Hs256 => Algorithm::HS256,
+        JwtAlgorithm::Hs384 => Algorithm::HS384,
+        JwtAlgorithm::Hs512 => Algorithm::HS512,
+        JwtAlgorithm::Rs256 => Algorithm::RS256,
+        JwtAlgorithm::Rs384 => Algorithm::RS384,
+        JwtAlgorithm::Rs512 => Algorithm::RS512,
+        JwtAlgorithm::Es256 => Algorithm::ES256,
+        JwtAlgorithm::Es384 => Algorithm::ES384,
+    }
+}
+
+const fn is_hmac(algorithm: JwtAlgorithm) -> bool {
+    matches!(
+        algorithm,
+        JwtAlgorithm::Hs256 | JwtAlgorithm::Hs384 | JwtAlgorithm::Hs512
+    )
+}
+
+fn encoding_key(auth: &AuthConfig) -> Result<EncodingKey, jsonwebtoken::errors::Error> {
+    match auth.algorithm {
+        JwtAlgorithm::Hs256 | JwtAlgorithm::Hs384 | JwtAlgorithm::Hs512 => {
+            Ok(EncodingKey::from_secret(auth.secret.as_bytes()))
+        }
+        JwtAlgorithm::Rs256 | JwtAlgorithm::Rs384 | JwtAlgorithm::Rs512 => {
+            EncodingKey::from_rsa_pem(&key_file(auth.private_key_path.as_deref())?)
+        }
+        JwtAlgorithm::Es256 | JwtAlgorithm::Es384 => {
+            EncodingKey::from_ec_pem(&key_file(auth.private_key_path.as_deref())?)
+        }
+    }
+}
+
+fn decoding_key(auth: &AuthConfig) -> Result<DecodingKey, jsonwebtoken::errors::Error> {
+    match auth.algorithm {
+        JwtAlgorithm::Hs256 | JwtAlgorithm::Hs384 | JwtAlgorithm::Hs512 => {
+            Ok(DecodingKey::from_secret(auth.secret.as_bytes()))
+        }
+        JwtAlgorithm::Rs256 | JwtAlgorithm::Rs384 | JwtAlgorithm::Rs512 => {
+            DecodingKey::from_rsa_pem(&key_file(auth.public_key_path.as_deref())?)
+        }
+        JwtAlgorithm::Es256 | JwtAlgorithm::Es384 => {
+            DecodingKey::from_ec_pem(&key_file(auth.public_key_path.as_deref())?)
+        }
+    }
+}
+
+/// Contents of a PEM key file, read on first use. Keys change with a
+/// restart, like the rest of `[auth]`.
+fn key_file(path: Option<&str>) -> Result<Arc<Vec<u8>>, jsonwebtoken::errors::Error> {
+    static KEY_FILES: LazyLock<Mutex<HashMap<String, Arc<Vec<u8>>>>> =
+        LazyLock::new(Mutex::default);
+
+    let path = path.ok_or(ErrorKind::InvalidKeyFormat)?;
+    let mut key_files = KEY_FILES.lock().unwrap();
+    if let Some(contents) = key_files.get(path) {
+        return Ok(contents.clone());
+    }
+    let contents = Arc::new(std::fs::read(path).map_err(|_| ErrorKind::InvalidKeyFormat)?);
+    key_files.insert(path.to_string(), contents.clone());
+    Ok(contents)
+}
+
+/// SHA-256 of the request's User-Agent and `X-Client-Secret` headers.
+///
+/// Missing headers count as empty, so a client that sends neither still gets
+/// a stable fingerprint.
+pub fn client_fingerprint(headers: &HeaderMap) -> String {
+    let header_value = |name| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+    };
+    hash_token(&format!(
+        "{}\n{}",
+        header_value(header::USER_AGENT.as_str()),
+        header_value(CLIENT_SECRET_HEADER)
+    ))
+}
+
+/// Whether `claims` may be used by the client that sent `headers`.
+///
+/// Always true when binding is disabled. When enabled, tokens without a `cnf`
+/// claim are rejected too, so binding can't be bypassed with an older token.
+pub fn matches_client<ExtraConfig>(
+    config: &Config<ExtraConfig>,
+    claims: &Claims,
+    headers: &HeaderMap,
+) -> bool {
+    !config.auth.bind_tokens_to_client
+        || claims.cnf.as_deref() == Some(client_fingerprint(headers).as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderMap;
+    use uuid::Uuid;
+
+    use jsonwebtoken::{
+        decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+    };
+
+    use super::{generate_token, validate_jwt_keys, verify_token, Claims};
+    use crate::{boot::read_config, config::JwtAlgorithm, environment::Environment};
+
+    const PRIVATE_KEY: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/auth/testdata/rs256_private.pem"
+    );
+    const PUBLIC_KEY: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/auth/testdata/rs256_public.pem"
+    );
+
+    #[test]
+    fn test_access_token_expires_after_configured_minutes() {
+        let mut config = read_config::<()>(&Environment::Test);
+        config.auth.access_token_minutes = 15;
+
+        let token = generate_token(&config, Uuid::new_v4(), 0, &HeaderMap::new()).unwrap();
+        let claims = verify_token(&config, &token).unwrap();
+
+        assert_eq!(claims.exp - claims.iat, 15 * 60);
+    }
+
+    #[test]
+    fn test_recently_expired_token_passes_within_leeway() {
+        let mut config = read_config::<()>(&Environment::Test);
+        let now = chrono::Utc::now().timestamp() as usize;
+        let claims = Claims {
+            sub: Uuid::new_v4().to_string(),
+            ver: 0,
+            exp: now - 30,
+            iat: now - 15 * 60,
+            cnf: None,
+        };
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(config.auth.secret.as_bytes()),
+        )
+        .unwrap();
+
+        assert_eq!(config.auth.leeway_seconds, 60);
+        assert!(verify_token(&config, &token).is_ok());
+
+        config.auth.leeway_seconds = 0;
+        assert!(verify_token(&config, &token).is_err());
+    }
+
+    #[test]
+    fn test_rs256_tokens_verify_with_the_public_key_alone() {
+        let mut config = read_config::<()>(&Environment::Test);
+        config.auth.algorithm = JwtAlgorithm::Rs256;
+        config.auth.private_key_path = Some(PRIVATE_KEY.to_string());
+        config.auth.public_key_path = Some(PUBLIC_KEY.to_string());
+        validate_jwt_keys(&config).unwrap();
+
+        let user_id = Uuid::new_v4();
+        let token = generate_token(&config, user_id, 0, &HeaderMap::new()).unwrap();
+        assert_eq!(decode_header(&token).unwrap().alg, Algorithm::RS256);
+        assert_eq!(
+            verify_token(&config, &token).unwrap().sub,
+            user_id.to_string()
+        );
+
+        // What another service holding only the public key does
+        let public_key = DecodingKey::from_rsa_pem(&std::fs::read(PUBLIC_KEY).unwrap()).unwrap();
+        let claims = decode::<Claims>(&token, &public_key, &Validation::new(Algorithm::RS256))
+            .unwrap()
+            .claims;
+        assert_eq!(claims.sub, user_id.to_string());
+
+        // Tokens signed with the other algorithm aren't accepted either way
+        let mut hs256 = read_config::<()>(&Environment::Test);
+        assert_eq!(hs256.auth.algorithm, JwtAlgorithm::Hs256);
+        assert!(verify_token(&hs256, &token).is_err());
+        let hs256_token = generate_token(&hs256, user_id, 0, &HeaderMap::new()).unwrap();
+        assert!(verify_token(&config, &hs256_token).is_err());
+
+        // Missing or mismatched keys are caught at startup
+        hs256.auth.algorithm = JwtAlgorithm::Rs256;
+        assert!(validate_jwt_keys(&hs256)
+            .unwrap_err()
+            .contains("private_key_path"));
+        config.auth.public_key_path = Some(PRIVATE_KEY.to_string());
+        assert!(validate_jwt_keys(&config).is_err());
+    }
+}
@@ -5,6 +5,6 @@ pub mod jwt;
 pub mod prelude;
 pub mod router;
 
-pub use current_user::{AuthError, CurrentUser, LoadForUser};
+pub use current_user::{AuthError, CurrentUser, DatabaseUserLoader, LoadForUser, UserLoader};
 pub use jwt::{generate_token, verify_token, Claims};
 pub use router::auth_router;
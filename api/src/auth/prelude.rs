@@ -20,10 +20,17 @@
 pub use crate::auth::CurrentUser;
 
 // Re-export policy traits
+pub use crate::policy::abilities::{abilities_handler, Abilities, UserAbilities};
 pub use crate::policy::Policy;
 
+// Re-export request helpers
+pub use crate::api::client_ip::ClientIp;
+pub use crate::api::find_or_404::find_or_404;
+pub use crate::api::request_result::{RequestError, RequestResult, RequestSuccess};
+pub use crate::api::timestamp::Timestamp;
+
 // Re-export view types
 pub use crate::api::view_param::{Renderer, ViewEnum, ViewParam};
 
 // Re-export authorization macros
-pub use crate::{authorize, authorize_view};
+pub use crate::{abilities, authorize, authorize_view};
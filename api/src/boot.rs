@@ -1,4 +1,4 @@
-use std::{env, str::FromStr as _};
+use std::{env, str::FromStr as _, sync::Arc};
 
 use axum::Router;
 use clap::Parser as _;
@@ -10,13 +10,14 @@ use tracing::{debug, trace};
 use crate::{
     app::App,
     app_info::AppInfo,
-    cli::{Cli, Commands},
+    auth::{DatabaseUserLoader, UserLoader},
+    cli::{Cli, Commands, MigrateAction},
     commands::{db, db_reset, migrate, routes, serve, version},
     config::Config,
     environment::Environment,
     jobs::{
-        job_registry::JobRegistry,
-        scheduled_job::ScheduledJob,
+        deliver_job_callback_job::DeliverJobCallbackJob, job_registry::JobRegistry,
+        ping_job::PingJob, scheduled_job::ScheduledJob,
         send_already_registered_email_job::SendAlreadyRegisteredEmailJob,
         send_password_reset_email_job::SendPasswordResetEmailJob,
         send_verification_email_job::SendVerificationEmailJob,
@@ -37,6 +38,10 @@ pub struct BootConfig<ExtraConfig = ()> {
     pub job_registry: JobRegistry<ExtraConfig>,
     pub job_schedule: Vec<ScheduledJob>,
     pub sync_registry: SyncRegistry,
+    pub user_loader: Arc<dyn UserLoader>,
+    /// Subscribes to `app.event_bus` before workers, listeners and the
+    /// server start
+    pub event_subscribers: Option<fn(&App<ExtraConfig>)>,
 }
 
 impl<ExtraConfig> BootConfig<ExtraConfig> {
@@ -53,9 +58,39 @@ impl<ExtraConfig> BootConfig<ExtraConfig> {
             job_registry,
             job_schedule,
             sync_registry: SyncRegistry::new(),
+            user_loader: Arc::new(DatabaseUserLoader),
+            event_subscribers: None,
         }
     }
 
+    /// Call `subscribe` with the app once it is built, before any job,
+    /// listener or request can raise an event, so no event is missed.
+    ///
+    /// ```rust,ignore
+    /// fn event_subscribers(app: &App) {
+    ///     let db = app.db.clone();
+    ///     app.event_bus.subscribe("user.created", move |payload| {
+    ///         let db = db.clone();
+    ///         async move { /* ... */ }
+    ///     });
+    /// }
+    ///
+    /// BootConfig::new(app_info, router, job_registry(), job_schedule())
+    ///     .with_event_subscribers(event_subscribers)
+    /// ```
+    #[must_use]
+    pub fn with_event_subscribers(mut self, subscribe: fn(&App<ExtraConfig>)) -> Self {
+        self.event_subscribers = Some(subscribe);
+        self
+    }
+
+    /// Replace the default [`DatabaseUserLoader`] used by `CurrentUser`.
+    #[must_use]
+    pub fn with_user_loader(mut self, user_loader: impl UserLoader + 'static) -> Self {
+        self.user_loader = Arc::new(user_loader);
+        self
+    }
+
     /// Register a syncable entity in the sync registry.
     #[must_use]
     pub fn with_sync<E>(mut self) -> Self
@@ -78,31 +113,30 @@ where
         version::print_version_info(config.app_info);
         return;
     }
+    if let Some(Commands::Cron {
+        expression,
+        count,
+        timezone,
+    }) = &cli.command
+    {
+        crate::commands::cron::handle_cron_command(expression, *count, timezone);
+        return;
+    }
 
     let environment = set_environment();
 
     let app_config = read_config::<ExtraConfig>(&environment);
 
     // Set up tracing with appropriate level based on command
-    setup_tracing_for_command(&cli.command, &app_config.tracing.log_level);
+    setup_tracing_for_command(&cli.command, app_config.tracing.log_level(environment));
 
     debug!("Environment set to: {:?}", environment);
     trace!("Configuration loaded");
 
-    let mut job_registry = config.job_registry;
-    register_builtin_jobs::<ExtraConfig>(&mut job_registry);
+    let mut config = config;
+    register_builtin_jobs::<ExtraConfig>(&mut config.job_registry);
 
-    handle_command::<AppMigrator, ExtraConfig>(
-        environment,
-        app_config,
-        cli,
-        config.app_router,
-        job_registry,
-        config.job_schedule,
-        config.sync_registry,
-        config.app_info,
-    )
-    .await;
+    handle_command::<AppMigrator, ExtraConfig>(environment, app_config, cli, config).await;
 }
 
 fn register_builtin_jobs<ExtraConfig>(job_registry: &mut JobRegistry<ExtraConfig>)
@@ -112,6 +146,8 @@ where
     job_registry.register_job::<SendVerificationEmailJob<ExtraConfig>>();
     job_registry.register_job::<SendPasswordResetEmailJob<ExtraConfig>>();
     job_registry.register_job::<SendAlreadyRegisteredEmailJob<ExtraConfig>>();
+    job_registry.register_optional_job::<DeliverJobCallbackJob<ExtraConfig>>();
+    job_registry.register_optional_job::<PingJob<ExtraConfig>>();
 }
 
 #[must_use]
@@ -123,6 +159,17 @@ pub fn set_environment() -> Environment {
 }
 
 pub fn read_config<ExtraConfig>(environment: &Environment) -> Config<ExtraConfig>
+where
+    ExtraConfig: Default + DeserializeOwned,
+{
+    try_read_config(environment).expect("Failed to deserialize configuration")
+}
+
+/// Like [`read_config`], but returns an error instead of panicking. Used when
+/// re-reading the config of a running server.
+pub fn try_read_config<ExtraConfig>(
+    environment: &Environment,
+) -> Result<Config<ExtraConfig>, config_rs::ConfigError>
 where
     ExtraConfig: Default + DeserializeOwned,
 {
@@ -133,26 +180,46 @@ where
     ConfigRs::builder()
         .add_source(config_rs::File::with_name(&config_file_name))
         .add_source(config_rs::Environment::with_prefix("APP"))
-        .build()
-        .unwrap()
+        .build()?
         .try_deserialize()
-        .expect("Failed to deserialize configuration")
+        .and_then(|config: Config<ExtraConfig>| {
+            config.validate().map_err(config_rs::ConfigError::Message)?;
+            Ok(config)
+        })
+}
+
+/// Exit instead of running a command that wipes all data where the
+/// environment doesn't allow it.
+fn ensure_destructive_ops_allowed(environment: Environment, command: &str) {
+    if !environment.allows_destructive_ops() {
+        eprintln!("❌ `{command}` deletes all data and is disabled in {environment}");
+        std::process::exit(1);
+    }
 }
 
 pub async fn handle_command<AppMigrator: MigratorTrait, ExtraConfig>(
     environment: Environment,
     config: Config<ExtraConfig>,
     cli: Cli,
-    app_router: fn(App<ExtraConfig>) -> Router,
-    job_registry: JobRegistry<ExtraConfig>,
-    job_schedule: Vec<ScheduledJob>,
-    sync_registry: SyncRegistry,
-    app_info: AppInfo,
+    boot_config: BootConfig<ExtraConfig>,
 ) where
     ExtraConfig: Clone + Default + DeserializeOwned + Send + Sync + 'static,
 {
+    let BootConfig {
+        app_info,
+        app_router,
+        job_registry,
+        job_schedule,
+        sync_registry,
+        user_loader,
+        event_subscribers,
+    } = boot_config;
+
     match cli.command {
         Some(Commands::Migrate { action }) => {
+            if matches!(action, MigrateAction::Reset) {
+                ensure_destructive_ops_allowed(environment, "migrate reset");
+            }
             migrate::handle_migrate_command::<AppMigrator, ExtraConfig>(&config, action).await;
         }
         Some(Commands::Db { action }) => match action {
@@ -160,9 +227,13 @@ pub async fn handle_command<AppMigrator: MigratorTrait, ExtraConfig>(
                 db::handle_db_console_command(&config);
             }
             Some(crate::cli::DbAction::Reset) => {
+                ensure_destructive_ops_allowed(environment, "db reset");
                 db_reset::handle_db_reset_command::<AppMigrator, ExtraConfig>(&config).await;
             }
         },
+        Some(Commands::Generate { action }) => {
+            crate::commands::generate::handle_generate_command(action);
+        }
         Some(Commands::GenerateJwtSecret) => {
             crate::commands::generate_secret::handle_generate_secret_command();
         }
@@ -172,6 +243,16 @@ pub async fn handle_command<AppMigrator: MigratorTrait, ExtraConfig>(
         Some(Commands::Routes) => {
             routes::handle_routes_command::<ExtraConfig>(config, app_router).await;
         }
+        Some(Commands::Cron {
+            expression,
+            count,
+            timezone,
+        }) => {
+            crate::commands::cron::handle_cron_command(&expression, count, &timezone);
+        }
+        Some(Commands::Ping { timeout }) => {
+            crate::commands::ping::handle_ping_command(&config, timeout).await;
+        }
         #[cfg(feature = "admin")]
         Some(Commands::Admin) => {
             let db = crate::database::setup_database_connection(&config.database).await;
@@ -185,6 +266,8 @@ pub async fn handle_command<AppMigrator: MigratorTrait, ExtraConfig>(
                 job_registry,
                 job_schedule,
                 sync_registry,
+                user_loader,
+                event_subscribers,
             )
             .await;
         }
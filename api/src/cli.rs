@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
@@ -22,12 +24,34 @@ pub enum Commands {
         #[command(subcommand)]
         action: Option<DbAction>,
     },
+    /// Generate code skeletons
+    Generate {
+        #[command(subcommand)]
+        action: GenerateAction,
+    },
     /// Generate a JWT secret for configuration
     GenerateJwtSecret,
     /// Show version information
     Version,
     /// List all application routes
     Routes,
+    /// Enqueue a ping job and wait for a worker to complete it
+    Ping {
+        /// Seconds to wait before giving up
+        #[arg(long, default_value = "30")]
+        timeout: u64,
+    },
+    /// Preview the next runs of a cron expression
+    Cron {
+        /// 6-field cron expression (seconds first), e.g. "0 0 2 * * *"
+        expression: String,
+        /// Number of runs to show
+        #[arg(short = 'n', long, default_value = "5")]
+        count: usize,
+        /// Zone shown next to UTC: "local", "UTC" or an offset like "+02:00"
+        #[arg(long, default_value = "local")]
+        timezone: String,
+    },
     /// Open the admin text user interface
     #[cfg(feature = "admin")]
     Admin,
@@ -41,6 +65,18 @@ pub enum DbAction {
     Reset,
 }
 
+#[derive(Subcommand)]
+pub enum GenerateAction {
+    /// Create a timestamped migration file
+    Migration {
+        /// Snake-case migration name, e.g. create_posts
+        name: String,
+        /// Directory to write the migration into
+        #[arg(long, default_value = "src/migrations")]
+        dir: PathBuf,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum MigrateAction {
     /// Run migrations up
@@ -1,7 +1,10 @@
+pub mod cron;
 pub mod db;
 pub mod db_reset;
+pub mod generate;
 pub mod generate_secret;
 pub mod migrate;
+pub mod ping;
 pub mod routes;
 pub mod serve;
 pub mod version;
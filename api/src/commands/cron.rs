@@ -0,0 +1,28 @@
+use crate::jobs::cron_preview::{upcoming_runs, PreviewZone};
+
+/// Handle the `cron` command - prints the next runs of a cron expression.
+pub fn handle_cron_command(expression: &str, count: usize, timezone: &str) {
+    let zone = match timezone.parse::<PreviewZone>() {
+        Ok(zone) => zone,
+        Err(e) => {
+            eprintln!("❌ {e}");
+            std::process::exit(1);
+        }
+    };
+    let runs = match upcoming_runs(expression, chrono::Utc::now(), count) {
+        Ok(runs) => runs,
+        Err(e) => {
+            eprintln!("❌ Invalid cron expression '{expression}': {e}");
+            std::process::exit(1);
+        }
+    };
+
+    println!("📅 Next {} runs of '{expression}'\n", runs.len());
+    for run in runs {
+        println!(
+            "  {}    {}",
+            run.format("%Y-%m-%d %H:%M:%S UTC"),
+            zone.format(run)
+        );
+    }
+}
@@ -20,7 +20,13 @@ pub fn handle_db_command(db_config: &DatabaseConfig) -> Result<(), Box<dyn Error
     println!();
 
     // Execute psql with the database URL directly
-    let status = Command::new("psql").arg(&db_config.url).status()?;
+    let mut command = Command::new("psql");
+    command.arg(&db_config.url);
+    if let Some(schema) = &db_config.schema {
+        // Unqualified names resolve to the app's tables, as they do for the app
+        command.env("PGOPTIONS", format!("-c search_path={schema}"));
+    }
+    let status = command.status()?;
 
     if !status.success() {
         return Err(format!("psql exited with code: {:?}", status.code()).into());
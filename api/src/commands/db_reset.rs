@@ -99,6 +99,7 @@ async fn reset_database<AppMigrator: sea_orm_migration::MigratorTrait, ExtraConf
     // Now connect to the new database and run migrations
     info!("Running migrations...");
     let db = crate::database::setup_database_connection(&config.database).await;
+    crate::database::create_schema(&db, config.database.schema.as_deref()).await?;
 
     // Run all migrations up
     crate::commands::migrate::handle_migration_command::<AppMigrator>(
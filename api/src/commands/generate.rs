@@ -0,0 +1,96 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::cli::GenerateAction;
+
+#[cfg(test)]
+mod migration_template;
+
+/// Skeleton written for new migrations. `DeriveMigrationName` takes the
+/// migration's name from the module, so the file name is all that varies.
+const MIGRATION_TEMPLATE: &str = include_str!("generate/migration_template.rs");
+
+pub fn handle_generate_command(action: GenerateAction) {
+    match action {
+        GenerateAction::Migration { name, dir } => match generate_migration(&dir, &name) {
+            Ok(path) => {
+                let module = path.file_stem().unwrap_or_default().to_string_lossy();
+                println!("✅ Created {}", path.display());
+                println!();
+                println!("Register it in your Migrator:");
+                println!("  mod {module};");
+                println!("  Box::new({module}::Migration),");
+            }
+            Err(e) => {
+                eprintln!("❌ Failed to generate migration: {e}");
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+/// Write a migration skeleton named `mYYYYMMDD_HHMMSS_<name>.rs` into `dir`.
+///
+/// `name` must be snake_case (lowercase letters, digits and underscores).
+/// Returns the path of the new file.
+pub fn generate_migration(dir: &Path, name: &str) -> io::Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("migration name '{name}' must be snake_case, e.g. create_posts"),
+        ));
+    }
+
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let path = dir.join(format!("m{timestamp}_{name}.rs"));
+
+    fs::create_dir_all(dir)?;
+    // create_new refuses to overwrite an existing migration
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut file| io::Write::write_all(&mut file, MIGRATION_TEMPLATE.as_bytes()))?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm_migration::MigrationName;
+
+    use super::{generate_migration, migration_template, MIGRATION_TEMPLATE};
+
+    #[test]
+    fn test_generate_migration_writes_timestamped_skeleton() {
+        let dir = std::env::temp_dir().join(format!("erno-generate-{}", uuid::Uuid::new_v4()));
+
+        let path = generate_migration(&dir, "create_posts").unwrap();
+
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        let (timestamp, rest) = file_name[1..].split_at(15);
+        assert!(file_name.starts_with('m'));
+        assert!(timestamp[..8].chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(&timestamp[8..9], "_");
+        assert!(timestamp[9..].chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(rest, "_create_posts.rs");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), MIGRATION_TEMPLATE);
+
+        assert!(generate_migration(&dir, "Create Posts").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_migration_template_compiles_and_is_named_after_its_file() {
+        // The template is compiled as a module in tests; its name comes from the file
+        assert_eq!(migration_template::Migration.name(), "migration_template");
+    }
+}
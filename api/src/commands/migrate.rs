@@ -3,7 +3,7 @@ use std::{cmp, error::Error, process};
 use sea_orm::DatabaseConnection;
 
 use crate::{
-    database::setup_database_connection,
+    database::{create_schema, setup_database_connection},
     {cli::MigrateAction, config::Config},
 };
 
@@ -16,6 +16,11 @@ pub async fn handle_migrate_command<AppMigrator: sea_orm_migration::MigratorTrai
     // Create a simple connection just for migrations (no background setup)
     let db = setup_database_connection(&config.database).await;
 
+    if let Err(e) = create_schema(&db, config.database.schema.as_deref()).await {
+        eprintln!("❌ Failed to create schema: {e}");
+        process::exit(1);
+    }
+
     if let Err(e) = handle_migration_command::<AppMigrator>(&db, action).await {
         eprintln!("❌ Migration failed: {e}");
         process::exit(1);
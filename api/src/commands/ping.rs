@@ -0,0 +1,65 @@
+use std::{
+    process,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::Config,
+    database::setup_database_connection,
+    job_queue::JobQueue,
+    jobs::{
+        ping_job::{wait_for_job, PingJob},
+        status::JobStatus,
+        Job,
+    },
+};
+
+/// Handle the `ping` command - enqueues a [`PingJob`] and waits for a worker
+/// to complete it.
+pub async fn handle_ping_command<ExtraConfig>(config: &Config<ExtraConfig>, timeout_secs: u64)
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    let db = setup_database_connection(&config.database).await;
+    let queue = JobQueue::database();
+
+    let id = match queue
+        .add::<PingJob<ExtraConfig>, ExtraConfig>(&db, ())
+        .await
+    {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("❌ Failed to enqueue ping job: {e}");
+            process::exit(1);
+        }
+    };
+    println!("🏓 Enqueued ping job {id}, waiting up to {timeout_secs}s for a worker...");
+
+    let start = Instant::now();
+    match wait_for_job(&db, id, Duration::from_secs(timeout_secs)).await {
+        Ok(Some(JobStatus::Completed)) => {
+            println!("✅ Completed in {:.1}s", start.elapsed().as_secs_f64());
+        }
+        Ok(Some(status)) if status.is_terminal() => {
+            eprintln!("❌ Ping job ended as {status:?}");
+            process::exit(1);
+        }
+        Ok(Some(status)) => {
+            // Don't leave it behind for a worker that comes up later
+            let _ = queue.cancel(&db, id).await;
+            eprintln!(
+                "❌ Ping job still {status:?} after {timeout_secs}s. Is a worker running with '{}' in its jobs?",
+                PingJob::<ExtraConfig>::name()
+            );
+            process::exit(1);
+        }
+        Ok(None) => {
+            eprintln!("❌ Ping job {id} disappeared before completing");
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to check on ping job: {e}");
+            process::exit(1);
+        }
+    }
+}
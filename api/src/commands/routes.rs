@@ -6,7 +6,14 @@ use std::time::Duration;
 use std::sync::Arc;
 
 use crate::{
-    app::App, config::Config, environment::Environment, job_queue::JobQueue, mailer::Mailer,
+    app::App,
+    auth::DatabaseUserLoader,
+    config::{Config, ServerConfig},
+    database::{DatabaseSetupStatus, DatabaseStatus},
+    environment::Environment,
+    job_queue::JobQueue,
+    jobs::job_registry::JobRegistry,
+    mailer::Mailer,
     metrics::{self, collector::CollectorRegistry},
     rate_limiting::RateLimitState, sync::queue::SyncQueue, sync::registry::SyncRegistry,
     websocket::connections::Connections,
@@ -21,16 +28,23 @@ pub async fn handle_routes_command<ExtraConfig>(
 {
     println!("📍 Application Routes\n");
 
+    let server_config = config.server.clone();
     let app = create_app_for_routes(config).await;
     let router = crate::router::router(app, app_router);
-    extract_and_print_routes(router);
+    extract_and_print_routes(router, &server_config);
 }
 
-async fn create_app_for_routes<ExtraConfig>(config: Config<ExtraConfig>) -> App<ExtraConfig> {
+async fn create_app_for_routes<ExtraConfig>(config: Config<ExtraConfig>) -> App<ExtraConfig>
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
     let mut opt = ConnectOptions::new(config.database.url.clone());
     opt.max_connections(1)
         .connect_timeout(Duration::from_secs(5))
         .acquire_timeout(Duration::from_secs(5));
+    if let Some(schema) = &config.database.schema {
+        opt.set_schema_search_path(schema);
+    }
 
     let db = Database::connect(opt)
         .await
@@ -44,15 +58,20 @@ async fn create_app_for_routes<ExtraConfig>(config: Config<ExtraConfig>) -> App<
         config,
         environment: Environment::Development,
         db,
+        database_status: DatabaseStatus::new(DatabaseSetupStatus::Completed),
         mailer: Mailer::mock(),
+        email_transports: std::sync::Arc::default(),
         job_queue: JobQueue::mock(),
+        job_registry: Arc::new(JobRegistry::new()),
         sync_queue: SyncQueue::mock(),
         sync_registry: Arc::new(SyncRegistry::new()),
+        user_loader: Arc::new(DatabaseUserLoader),
         websocket_connections: Connections::new(),
+        event_bus: crate::events::EventBus::new(),
     }
 }
 
-fn extract_and_print_routes(router: Router) {
+fn extract_and_print_routes(router: Router, server_config: &ServerConfig) {
     // Use debug output to extract routes
     let debug_output = format!("{:?}", router);
 
@@ -78,8 +97,8 @@ fn extract_and_print_routes(router: Router) {
 
     for (path, methods) in routes_vec {
         let description = match path.as_str() {
-            "/liveness" => "Health check (liveness probe)",
-            "/readiness" => "Health check (readiness probe)",
+            p if p == server_config.liveness_path => "Health check (liveness probe)",
+            p if p == server_config.readiness_path => "Health check (readiness probe)",
             "/ws" => "WebSocket endpoint",
             p if p.starts_with("/api/") => "Application endpoint",
             _ => "",
@@ -1,28 +1,41 @@
 use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
 use axum::{routing::get, Router};
-use lettre::transport::smtp::authentication::Credentials;
-use lettre::{AsyncSmtpTransport, Tokio1Executor};
 use sea_orm_migration::MigratorTrait;
+use serde::de::DeserializeOwned;
 use tokio::net::TcpListener;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
     api::health_checks::ok,
     app::App,
-    auth::jwt::validate_jwt_secret,
+    auth::{
+        jwt::{validate_jwt_keys, validate_jwt_secret},
+        UserLoader,
+    },
     config::Config,
-    database::setup_database,
+    database::connect_for_serve,
     environment::Environment,
+    events::{spawn_event_listener, EventBus},
     jobs::{
         job_registry::JobRegistry, job_supervisor::job_supervisor, scheduled_job::ScheduledJob,
     },
+    mailer::Mailer,
     metrics::{self, collector::CollectorRegistry},
+    rate_limiting::RateLimitSnapshot,
     router::router,
+    shutdown::{shutdown_signal, ShutdownReport},
     sync::registry::SyncRegistry,
-    websocket::connections::Connections,
+    websocket::{
+        connections::{ConnectionLimit, Connections, Heartbeat, ReliableDelivery},
+        ip_limit::IpConnectionLimit,
+        listener::spawn_listener,
+        outbox::SendBuffer,
+        retention::OfflineRetention,
+    },
 };
 
 pub async fn handle_serve_command<AppMigrator: MigratorTrait, ExtraConfig>(
@@ -32,75 +45,68 @@ pub async fn handle_serve_command<AppMigrator: MigratorTrait, ExtraConfig>(
     job_registry: JobRegistry<ExtraConfig>,
     job_schedule: Vec<ScheduledJob>,
     sync_registry: SyncRegistry,
+    user_loader: Arc<dyn UserLoader>,
+    event_subscribers: Option<fn(&App<ExtraConfig>)>,
 ) where
-    ExtraConfig: Clone + Send + Sync + 'static,
+    ExtraConfig: Clone + Default + DeserializeOwned + Send + Sync + 'static,
 {
     let port = config.server.port;
 
     // Validate JWT secret strength before starting.
     if let Err(msg) = validate_jwt_secret(&config) {
-        if environment == Environment::Production {
+        if environment.rejects_weak_secrets() {
             panic!("🔐 {msg} Refusing to start in production with a weak JWT secret.");
         } else {
             tracing::warn!("🔐 {msg}");
         }
     }
+    if let Err(msg) = validate_jwt_keys(&config) {
+        panic!("🔐 {msg}");
+    }
 
     // We start a temporary liveness server for Kubernetes to know that the application is alive
-    let liveness_server_task = tokio::spawn(start_liveness_server(port));
-
-    let (db, migration_receiver) = setup_database::<AppMigrator>(&config.database).await;
+    let liveness_server_task = tokio::spawn(start_liveness_server(
+        port,
+        config.server.liveness_path.clone(),
+    ));
 
-    // Wait for migrations to complete
-    match migration_receiver.await {
-        Ok(Ok(())) => {
-            info!("✅ Database is ready!");
-        }
-        Ok(Err(e)) => {
+    let (db, database_status) = match connect_for_serve::<AppMigrator>(&config.database).await {
+        Ok(connected) => connected,
+        Err(e) => {
             error!("❌ Database setup failed: {}", e);
             liveness_server_task.abort();
             return;
         }
-        Err(_) => {
-            error!("❌ Database setup channel closed unexpectedly");
-            liveness_server_task.abort();
-            return;
-        }
-    }
-
-    let mailer = match &config.email {
-        crate::config::EmailConfig::Mock => crate::mailer::Mailer::mock(),
-        crate::config::EmailConfig::Smtp {
-            host,
-            port,
-            username,
-            password,
-            use_tls,
-            ..
-        } => {
-            let mut mailer_builder = if *use_tls {
-                AsyncSmtpTransport::<Tokio1Executor>::relay(host)
-                    .expect("Failed to create mailer transport")
-                    .port(*port)
-            } else {
-                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host).port(*port)
-            };
-
-            if let (Some(username), Some(password)) = (username, password) {
-                mailer_builder = mailer_builder
-                    .credentials(Credentials::new(username.clone(), password.clone()));
-            }
-
-            crate::mailer::Mailer::smtp(mailer_builder.build())
-        }
     };
 
+    let mailer = Mailer::for_environment(&config.email, environment);
+    let email_transports = Arc::new(
+        config
+            .email_transports
+            .iter()
+            .map(|(name, email_config)| {
+                (
+                    name.clone(),
+                    Mailer::for_environment(email_config, environment),
+                )
+            })
+            .collect(),
+    );
+
     let job_queue = crate::job_queue::JobQueue::database();
     let sync_queue = crate::sync::queue::SyncQueue::database();
     let sync_registry = Arc::new(sync_registry);
 
     // Initialize rate limiting state
-    let rate_limit_state = crate::rate_limiting::RateLimitState::new(config.rate_limiting.clone());
+    let rate_limit_state =
+        crate::rate_limiting::RateLimitState::from_config(config.rate_limiting.clone())
+            .expect("Failed to create rate limit backend");
+
+    // Pick up the penalties saved by the previous process
+    if let Some(path) = &config.rate_limiting.snapshot_path {
+        restore_rate_limits(&rate_limit_state, path).await;
+    }
+    let snapshot_state = rate_limit_state.clone();
 
     // Periodically clean up stale IP entries to prevent unbounded memory growth
     {
@@ -114,10 +120,20 @@ pub async fn handle_serve_command<AppMigrator: MigratorTrait, ExtraConfig>(
         });
     }
 
+    // Re-read the config file on SIGHUP and apply what can change live
+    #[cfg(unix)]
+    spawn_config_reloader::<ExtraConfig>(environment, config.clone(), rate_limit_state.clone());
+
     // Initialize WebSocket connections manager
-    let websocket_connections = Connections::new();
+    let websocket_connections = Connections::new()
+        .with_heartbeat(Heartbeat::from_config(&config.websocket))
+        .with_send_buffer(SendBuffer::from_config(&config.websocket))
+        .with_connection_limit(ConnectionLimit::from_config(&config.websocket))
+        .with_ip_connection_limit(IpConnectionLimit::from_config(&config.websocket))
+        .with_offline_retention(OfflineRetention::from_config(&config.websocket, db.clone()));
 
     let storage = crate::storage::FileStorage::from_config(&config.storage);
+    let event_bus = EventBus::new();
 
     // Set up Prometheus metrics recorder
     let prometheus_handle = metrics::setup_metrics();
@@ -127,36 +143,115 @@ pub async fn handle_serve_command<AppMigrator: MigratorTrait, ExtraConfig>(
         config: config.clone(),
         environment,
         db: db.clone(),
+        database_status,
         mailer,
+        email_transports,
         job_queue,
+        job_registry: Arc::new(job_registry.clone()),
         sync_queue,
         sync_registry: sync_registry.clone(),
+        user_loader,
         rate_limit_state,
         websocket_connections: websocket_connections.clone(),
+        event_bus: event_bus.clone(),
         storage,
         metrics_collectors: metrics_collectors.clone(),
         prometheus_handle,
     };
 
-    // Spawn workers in the background
-    tokio::spawn(job_supervisor(
-        config.jobs,
-        app.clone(),
-        job_registry,
-        job_schedule,
-    ));
+    // Subscribe before anything that can raise an event is running
+    if let Some(subscribe) = event_subscribers {
+        subscribe(&app);
+    }
+
+    // Background work expects the current schema, so while migrations are
+    // pending (`auto_migrate_on_serve = false`) it waits for them
+    {
+        let app = app.clone();
+        tokio::spawn(async move {
+            app.database_status.completed().await;
+            spawn_background_tasks(app, job_registry, job_schedule);
+        });
+    }
+
+    // Stop the temporary liveness server
+    liveness_server_task.abort();
+    let _ = liveness_server_task.await;
+
+    // Report unfinished work once a shutdown signal arrives, then let the
+    // server drain in-flight HTTP requests
+    let shutdown = async move {
+        shutdown_signal().await;
+        info!("🛑 Shutdown signal received");
+        ShutdownReport::collect(&db, &websocket_connections)
+            .await
+            .log();
+    };
+
+    // Start the full server
+    let router = router(app, app_router);
+    start_server(router, port, shutdown).await;
+
+    // Save penalties once in-flight requests have been counted
+    if let Some(path) = &config.rate_limiting.snapshot_path {
+        save_rate_limits(&snapshot_state, path).await;
+    }
+}
+
+/// Spawn the job workers, the listeners and the periodic tasks that read and
+/// write the database.
+fn spawn_background_tasks<ExtraConfig>(
+    app: App<ExtraConfig>,
+    job_registry: JobRegistry<ExtraConfig>,
+    job_schedule: Vec<ScheduledJob>,
+) where
+    ExtraConfig: Clone + Default + DeserializeOwned + Send + Sync + 'static,
+{
+    let config = &app.config;
+
+    // Periodically resend reliable WebSocket messages that were not
+    // acknowledged, and give up on those of users who don't come back
+    if config.websocket.enabled {
+        let redelivery_connections = app.websocket_connections.clone();
+        let delivery = ReliableDelivery::from_config(&config.websocket);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(delivery.ack_timeout);
+            loop {
+                interval.tick().await;
+                redelivery_connections
+                    .redeliver_unacked(delivery.ack_timeout)
+                    .await;
+                redelivery_connections
+                    .expire_unacked(delivery.unacked_ttl)
+                    .await;
+            }
+        });
+    }
+
+    // Spawn DB stats + custom metrics collector task
+    if config.metrics.enabled {
+        let stats_db = app.db.clone();
+        let stats_config = config.metrics.clone();
+        let stats_collectors = (*app.metrics_collectors).clone();
+        tokio::spawn(async move {
+            metrics::db_stats::db_stats_task(stats_db, stats_config, stats_collectors).await;
+        });
+    }
 
     // Spawn WebSocket listener in the background
-    let listener_db = db.clone();
-    let listener_connections = websocket_connections.clone();
-    tokio::spawn(async move {
-        crate::websocket::listener::start_listener(listener_db, listener_connections).await;
-    });
+    spawn_listener(
+        &config.websocket,
+        app.db.clone(),
+        app.websocket_connections.clone(),
+    );
+
+    // Receive events published by this and other instances
+    spawn_event_listener(&config.events, app.db.clone(), app.event_bus.clone());
 
     // Spawn sync push listener in the background
-    let sync_listener_db = db.clone();
-    let sync_listener_connections = websocket_connections.clone();
-    let sync_listener_registry = sync_registry.clone();
+    let sync_listener_db = app.db.clone();
+    let sync_listener_connections = app.websocket_connections.clone();
+    let sync_listener_registry = app.sync_registry.clone();
     tokio::spawn(async move {
         crate::sync::listener::start_sync_listener(
             sync_listener_db,
@@ -166,36 +261,155 @@ pub async fn handle_serve_command<AppMigrator: MigratorTrait, ExtraConfig>(
         .await;
     });
 
-    // Spawn DB stats + custom metrics collector task
-    if config.metrics.enabled {
-        let stats_db = db.clone();
-        let stats_config = config.metrics.clone();
-        let stats_collectors = (*metrics_collectors).clone();
-        tokio::spawn(async move {
-            metrics::db_stats::db_stats_task(stats_db, stats_config, stats_collectors).await;
-        });
+    // Spawn workers in the background
+    tokio::spawn(job_supervisor(
+        config.jobs.clone(),
+        app,
+        job_registry,
+        job_schedule,
+    ));
+}
+
+async fn restore_rate_limits(rate_limit_state: &crate::rate_limiting::RateLimitState, path: &Path) {
+    match RateLimitSnapshot::read(path).await {
+        Ok(Some(snapshot)) => {
+            if rate_limit_state.restore(&snapshot) {
+                info!(
+                    "🚦 Restored rate limits for {} clients from {}",
+                    snapshot.clients.len(),
+                    path.display()
+                );
+            } else {
+                warn!("🚦 rate_limiting.snapshot_path only applies to the memory backend, ignoring it");
+            }
+        }
+        Ok(None) => {}
+        Err(e) => error!(
+            "🚦 Failed to read rate limit snapshot {}: {e}",
+            path.display()
+        ),
     }
+}
 
-    // Stop the temporary liveness server
-    liveness_server_task.abort();
-    let _ = liveness_server_task.await;
+async fn save_rate_limits(rate_limit_state: &crate::rate_limiting::RateLimitState, path: &Path) {
+    let Some(snapshot) = rate_limit_state.snapshot() else {
+        return;
+    };
+    match snapshot.write(path).await {
+        Ok(()) => info!(
+            "🚦 Saved rate limits for {} clients to {}",
+            snapshot.clients.len(),
+            path.display()
+        ),
+        Err(e) => error!(
+            "🚦 Failed to write rate limit snapshot {}: {e}",
+            path.display()
+        ),
+    }
+}
 
-    // Start the full server
-    let router = router(app, app_router);
-    start_server(router, port).await;
+/// Reload the log level and rate limits whenever the process gets a SIGHUP.
+/// Other sections are only read at startup, changes to them are logged and
+/// need a restart.
+#[cfg(unix)]
+fn spawn_config_reloader<ExtraConfig>(
+    environment: Environment,
+    mut current: Config<ExtraConfig>,
+    rate_limit_state: crate::rate_limiting::RateLimitState,
+) where
+    ExtraConfig: Default + DeserializeOwned + Send + 'static,
+{
+    use tokio::signal::unix::{signal, SignalKind};
+
+    use crate::boot::try_read_config;
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            tracing::warn!("🔄 Failed to install SIGHUP handler, config reload is disabled: {e}");
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            info!("🔄 SIGHUP received, reloading config");
+            match try_read_config::<ExtraConfig>(&environment) {
+                Ok(config) => {
+                    current = reload_config(environment, current, config, &rate_limit_state);
+                }
+                Err(e) => error!("🔄 Failed to read config, keeping the current one: {e}"),
+            }
+        }
+    });
+}
+
+/// Apply the hot-reloadable parts of `new` and return the config now in effect
+#[cfg(unix)]
+fn reload_config<ExtraConfig>(
+    environment: Environment,
+    mut current: Config<ExtraConfig>,
+    new: Config<ExtraConfig>,
+    rate_limit_state: &crate::rate_limiting::RateLimitState,
+) -> Config<ExtraConfig> {
+    use crate::setup_tracing::reload_log_level;
+
+    let (current_level, new_level) = (
+        current.tracing.log_level(environment),
+        new.tracing.log_level(environment),
+    );
+    if new_level != current_level {
+        match reload_log_level(new_level) {
+            Ok(()) => {
+                info!("🔄 Log level changed from {current_level} to {new_level}");
+                current.tracing.log_level = new.tracing.log_level;
+            }
+            Err(e) => error!("🔄 Failed to change log level: {e}"),
+        }
+    }
+
+    if rate_limit_state.reload_config(new.rate_limiting.clone()) {
+        current.rate_limiting = new.rate_limiting;
+    }
+
+    let fixed = [
+        ("database", changed(&current.database, &new.database)),
+        ("jobs", changed(&current.jobs, &new.jobs)),
+        ("server", changed(&current.server, &new.server)),
+        ("email", changed(&current.email, &new.email)),
+        ("auth", changed(&current.auth, &new.auth)),
+        ("storage", changed(&current.storage, &new.storage)),
+        ("metrics", changed(&current.metrics, &new.metrics)),
+        ("cors", changed(&current.cors, &new.cors)),
+        ("websocket", changed(&current.websocket, &new.websocket)),
+    ];
+    for (section, _) in fixed.iter().filter(|(_, changed)| *changed) {
+        tracing::warn!("🔄 [{section}] changed but can't be reloaded, restart to apply it");
+    }
+
+    current
+}
+
+#[cfg(unix)]
+fn changed<T: serde::Serialize>(current: &T, new: &T) -> bool {
+    serde_json::to_value(current).ok() != serde_json::to_value(new).ok()
 }
 
 // Minimal server that only serves liveness endpoint during migrations
-async fn start_liveness_server(port: u16) {
+async fn start_liveness_server(port: u16, liveness_path: String) {
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     let listener = TcpListener::bind(addr).await.unwrap();
 
-    let migration_router = Router::new().route("/liveness", get(ok));
+    let migration_router = Router::new().route(&liveness_path, get(ok));
     axum::serve(listener, migration_router).await.unwrap();
 }
 
 // Full server with all endpoints
-async fn start_server(router: Router, port: u16) {
+async fn start_server(
+    router: Router,
+    port: u16,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) {
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     let listener = TcpListener::bind(addr).await.unwrap();
 
@@ -204,6 +418,8 @@ async fn start_server(router: Router, port: u16) {
         listener,
         router.into_make_service_with_connect_info::<SocketAddr>(),
     )
+    .with_graceful_shutdown(shutdown)
     .await
     .unwrap();
+    info!("👋 Server stopped");
 }
@@ -2,6 +2,8 @@ use lettre::message::Mailbox;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 
+use crate::environment::Environment;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum StorageBackend {
@@ -91,6 +93,10 @@ pub struct Config<ExtraConfig = ()> {
     pub jobs: JobsConfig,
     pub server: ServerConfig,
     pub email: EmailConfig,
+    /// Extra transports by name, e.g. `bulk` for newsletters; `email` stays
+    /// the default
+    #[serde(default)]
+    pub email_transports: HashMap<String, EmailConfig>,
     /// API server base URL (used for CORS, self-referencing API links).
     pub api_url: String,
     /// Frontend app URL used in email links (verify-email, password-reset, etc.).
@@ -106,6 +112,12 @@ pub struct Config<ExtraConfig = ()> {
     pub metrics: MetricsConfig,
     #[serde(default)]
     pub cors: CorsConfig,
+    #[serde(default)]
+    pub websocket: WebSocketConfig,
+    #[serde(default)]
+    pub events: EventsConfig,
+    #[serde(default)]
+    pub locale: LocaleConfig,
     #[serde(flatten, default)]
     pub extra: ExtraConfig,
 }
@@ -115,11 +127,29 @@ impl<ExtraConfig> Config<ExtraConfig> {
     pub fn app_url(&self) -> &str {
         self.app_url.as_deref().unwrap_or(&self.api_url)
     }
+
+    /// Reject combinations of values that deserialize fine but can't work.
+    pub fn validate(&self) -> Result<(), String> {
+        self.jobs.validate()?;
+        self.websocket.validate()?;
+        self.events.validate()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthConfig {
+    /// HMAC key for the `HS*` algorithms; unused with key files
+    #[serde(default)]
     pub secret: String,
+    /// Algorithm access tokens are signed with. Default: HS256.
+    #[serde(default)]
+    pub algorithm: JwtAlgorithm,
+    /// PEM private key used to sign tokens with an `RS*` or `ES*` algorithm
+    #[serde(default)]
+    pub private_key_path: Option<String>,
+    /// PEM public key used to verify tokens with an `RS*` or `ES*` algorithm
+    #[serde(default)]
+    pub public_key_path: Option<String>,
     /// Access token TTL in minutes. Default: 15.
     #[serde(default = "default_access_token_minutes")]
     pub access_token_minutes: u64,
@@ -127,6 +157,30 @@ pub struct AuthConfig {
     /// Refresh token TTL in days. Default: 30.
     #[serde(default = "default_refresh_token_days")]
     pub refresh_token_days: u64,
+    /// Bind access tokens to the client that requested them (`cnf` claim).
+    /// Requests from a different User-Agent or `X-Client-Secret` are rejected.
+    #[serde(default)]
+    pub bind_tokens_to_client: bool,
+    /// Clock skew tolerated when checking a token's `exp`, in seconds. Default: 60.
+    #[serde(default = "default_leeway_seconds")]
+    pub leeway_seconds: u64,
+}
+
+/// Signing algorithm for access tokens. The `HS*` algorithms use
+/// `auth.secret`; the others a key pair, so other services can verify tokens
+/// with just the public key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum JwtAlgorithm {
+    #[default]
+    Hs256,
+    Hs384,
+    Hs512,
+    Rs256,
+    Rs384,
+    Rs512,
+    Es256,
+    Es384,
 }
 
 const fn default_access_token_minutes() -> u64 {
@@ -137,6 +191,10 @@ const fn default_refresh_token_days() -> u64 {
     30
 }
 
+const fn default_leeway_seconds() -> u64 {
+    60
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum EmailConfig {
@@ -167,6 +225,255 @@ fn default_use_tls() -> bool {
     true
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketConfig {
+    /// Mount `/ws` and run the notification listener. Turn off for apps that
+    /// don't use WebSockets to save the listener's database connection.
+    #[serde(default = "default_websocket_enabled")]
+    pub enabled: bool,
+    /// Delay before the listener's first reconnect attempt, doubled after
+    /// each failed attempt; at least 1 (default: 1)
+    #[serde(default = "default_listener_retry_base_seconds")]
+    pub listener_retry_base_seconds: u64,
+    /// Upper bound for the listener's reconnect delay (default: 60)
+    #[serde(default = "default_listener_retry_max_seconds")]
+    pub listener_retry_max_seconds: u64,
+    /// How often each connection is sent a ping; 0 turns heartbeats off
+    /// (default: 30)
+    #[serde(default = "default_heartbeat_interval_seconds")]
+    pub heartbeat_interval_seconds: u64,
+    /// A connection that hasn't answered a ping for this long is closed;
+    /// must be longer than the interval (default: 60)
+    #[serde(default = "default_heartbeat_timeout_seconds")]
+    pub heartbeat_timeout_seconds: u64,
+    /// Messages queued per connection while the client reads slower than
+    /// they are sent (default: 1024)
+    #[serde(default = "default_send_buffer_size")]
+    pub send_buffer_size: usize,
+    /// What to do when a connection's queue is full (default: drop_oldest)
+    #[serde(default)]
+    pub overflow_policy: OverflowPolicy,
+    /// Open connections allowed per user; 0 means no limit (default: 20)
+    #[serde(default = "default_max_connections_per_user")]
+    pub max_connections_per_user: usize,
+    /// What to do with a connection over the limit (default: reject_new)
+    #[serde(default)]
+    pub connection_limit_policy: ConnectionLimitPolicy,
+    /// Open connections allowed per client IP, counted before
+    /// authentication; 0 means no limit (default: 0)
+    #[serde(default)]
+    pub max_connections_per_ip: usize,
+    /// Keep `user`-targeted outbox messages for a user with no open
+    /// connection this long, and deliver them when they connect; 0 drops
+    /// them. Single-instance deployments only (default: 0)
+    #[serde(default)]
+    pub offline_retention_seconds: u64,
+    /// How long a reliable message may go unacknowledged before it is sent
+    /// again; must be greater than 0 (default: 30)
+    #[serde(default = "default_ack_timeout_seconds")]
+    pub ack_timeout_seconds: u64,
+    /// Drop reliable messages still unacknowledged after this long while
+    /// their user has no open connection (default: 86400)
+    #[serde(default = "default_unacked_ttl_seconds")]
+    pub unacked_ttl_seconds: u64,
+}
+
+/// What happens when a user opens more connections than allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionLimitPolicy {
+    /// Close the new connection with a policy violation close frame
+    #[default]
+    RejectNew,
+    /// Accept the new connection and close the user's oldest one
+    EvictOldest,
+}
+
+/// What happens to a message sent to a connection whose queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued message to make room
+    #[default]
+    DropOldest,
+    /// Drop the message being sent
+    DropNewest,
+    /// Close the connection; the client is expected to reconnect and catch up
+    Disconnect,
+}
+
+impl WebSocketConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.listener_retry_base_seconds == 0 {
+            return Err("websocket.listener_retry_base_seconds must be at least 1, or the listener retries in a busy loop".to_string());
+        }
+        if self.heartbeat_interval_seconds > 0
+            && self.heartbeat_timeout_seconds <= self.heartbeat_interval_seconds
+        {
+            return Err(format!(
+                "websocket.heartbeat_timeout_seconds ({}) must be longer than heartbeat_interval_seconds ({}), or healthy connections get closed",
+                self.heartbeat_timeout_seconds, self.heartbeat_interval_seconds
+            ));
+        }
+        if self.ack_timeout_seconds == 0 {
+            return Err("websocket.ack_timeout_seconds must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_websocket_enabled(),
+            listener_retry_base_seconds: default_listener_retry_base_seconds(),
+            listener_retry_max_seconds: default_listener_retry_max_seconds(),
+            heartbeat_interval_seconds: default_heartbeat_interval_seconds(),
+            heartbeat_timeout_seconds: default_heartbeat_timeout_seconds(),
+            send_buffer_size: default_send_buffer_size(),
+            overflow_policy: OverflowPolicy::default(),
+            max_connections_per_user: default_max_connections_per_user(),
+            connection_limit_policy: ConnectionLimitPolicy::default(),
+            max_connections_per_ip: 0,
+            offline_retention_seconds: 0,
+            ack_timeout_seconds: default_ack_timeout_seconds(),
+            unacked_ttl_seconds: default_unacked_ttl_seconds(),
+        }
+    }
+}
+
+const fn default_websocket_enabled() -> bool {
+    true
+}
+
+const fn default_listener_retry_base_seconds() -> u64 {
+    1
+}
+
+const fn default_listener_retry_max_seconds() -> u64 {
+    60
+}
+
+const fn default_heartbeat_interval_seconds() -> u64 {
+    30
+}
+
+const fn default_heartbeat_timeout_seconds() -> u64 {
+    60
+}
+
+const fn default_ack_timeout_seconds() -> u64 {
+    30
+}
+
+const fn default_unacked_ttl_seconds() -> u64 {
+    86_400 // 1 day
+}
+
+const fn default_send_buffer_size() -> usize {
+    1024
+}
+
+const fn default_max_connections_per_user() -> usize {
+    20
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventsConfig {
+    /// Receive events published by any instance. Turn off for apps that only
+    /// dispatch events in-process to save the listener's database connection.
+    #[serde(default = "default_events_enabled")]
+    pub enabled: bool,
+    /// How long published events are kept, so a listener that reconnects
+    /// within this time still dispatches them; must be greater than 0
+    /// (default: 3600)
+    #[serde(default = "default_events_retention_seconds")]
+    pub retention_seconds: u64,
+}
+
+impl EventsConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.retention_seconds == 0 {
+            return Err("events.retention_seconds must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_events_enabled(),
+            retention_seconds: default_events_retention_seconds(),
+        }
+    }
+}
+
+const fn default_events_enabled() -> bool {
+    true
+}
+
+const fn default_events_retention_seconds() -> u64 {
+    3600
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocaleConfig {
+    /// Used when no source yields a supported locale
+    #[serde(default = "default_locale")]
+    pub default: String,
+    /// Locales the app has translations for, e.g. ["en", "de", "pt-BR"].
+    /// Empty accepts any well-formed language tag.
+    #[serde(default)]
+    pub supported: Vec<String>,
+    /// Where to look for the locale, first match wins
+    #[serde(default = "default_locale_sources")]
+    pub sources: Vec<LocaleSource>,
+    /// Query parameter read by [`LocaleSource::Query`]
+    #[serde(default = "default_locale_query_param")]
+    pub query_param: String,
+}
+
+/// A place [`crate::api::locale::Locale`] is resolved from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocaleSource {
+    /// `?locale=de`, e.g. for a language switcher
+    Query,
+    /// A [`crate::api::locale::PreferredLocale`] the app put in the request
+    /// extensions, e.g. from the user's settings
+    User,
+    /// The `Accept-Language` header
+    AcceptLanguage,
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self {
+        Self {
+            default: default_locale(),
+            supported: Vec::new(),
+            sources: default_locale_sources(),
+            query_param: default_locale_query_param(),
+        }
+    }
+}
+
+fn default_locale() -> String {
+    "en".to_string()
+}
+
+fn default_locale_sources() -> Vec<LocaleSource> {
+    vec![
+        LocaleSource::Query,
+        LocaleSource::User,
+        LocaleSource::AcceptLanguage,
+    ]
+}
+
+fn default_locale_query_param() -> String {
+    "locale".to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CorsConfig {
     /// Origins allowed to call the API, e.g. ["http://localhost:4200"].
@@ -177,24 +484,182 @@ pub struct CorsConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TracingConfig {
-    pub log_level: String,
+    /// Defaults to [`Environment::default_log_level`] when unset
+    #[serde(default)]
+    pub log_level: Option<String>,
+}
+
+impl TracingConfig {
+    /// The configured log level, or the environment's default.
+    pub fn log_level(&self, environment: Environment) -> &str {
+        self.log_level
+            .as_deref()
+            .unwrap_or_else(|| environment.default_log_level())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseConfig {
     pub url: String,
     pub pool_size: u32,
+    /// Run pending migrations when `serve` starts (default: true). When false,
+    /// migrations must be applied separately and readiness fails until they are.
+    #[serde(default = "default_auto_migrate_on_serve")]
+    pub auto_migrate_on_serve: bool,
+    /// Log statements that take longer than this many milliseconds at WARN
+    /// level. Unset (the default) keeps all statement logging off.
+    #[serde(default)]
+    pub slow_query_threshold_ms: Option<u64>,
+    /// Postgres schema holding the app's tables. Set as the `search_path` of
+    /// every connection and created before migrations run. Unset (the
+    /// default) uses the database's own `search_path`, normally `public`.
+    #[serde(default)]
+    pub schema: Option<String>,
+}
+
+const fn default_auto_migrate_on_serve() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub port: u16,
+    /// Path of the liveness probe endpoint (default: "/liveness")
+    #[serde(default = "default_liveness_path")]
+    pub liveness_path: String,
+    /// Path of the readiness probe endpoint (default: "/readiness")
+    #[serde(default = "default_readiness_path")]
+    pub readiness_path: String,
+    /// Requests handled at once before further ones are shed with 503.
+    /// Health checks and metrics are never shed. Unlimited when unset.
+    #[serde(default)]
+    pub max_concurrent_requests: Option<usize>,
+    /// Limits applied by the `StrictJson` extractor
+    #[serde(default)]
+    pub json: JsonConfig,
+}
+
+/// Limits for request bodies read with `StrictJson`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonConfig {
+    /// Reject fields the target type doesn't declare (default: true)
+    #[serde(default = "default_deny_unknown_fields")]
+    pub deny_unknown_fields: bool,
+    /// Deepest nesting of arrays and objects accepted (default: 32)
+    #[serde(default = "default_json_max_depth")]
+    pub max_depth: usize,
+    /// Largest body accepted in bytes (default: 1048576 = 1 MiB)
+    #[serde(default = "default_json_max_bytes")]
+    pub max_bytes: usize,
+}
+
+impl Default for JsonConfig {
+    fn default() -> Self {
+        Self {
+            deny_unknown_fields: default_deny_unknown_fields(),
+            max_depth: default_json_max_depth(),
+            max_bytes: default_json_max_bytes(),
+        }
+    }
+}
+
+const fn default_deny_unknown_fields() -> bool {
+    true
+}
+
+const fn default_json_max_depth() -> usize {
+    32
+}
+
+const fn default_json_max_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_liveness_path() -> String {
+    "/liveness".to_string()
+}
+
+fn default_readiness_path() -> String {
+    "/readiness".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobsConfig {
+    /// Run the worker pools in this process. Turn off for web-only
+    /// instances; enqueued jobs then wait for a process that runs workers.
+    #[serde(default = "default_workers_enabled")]
+    pub workers_enabled: bool,
     pub cleanup: CleanupConfig,
+    #[serde(default)]
+    pub recovery: RecoveryConfig,
     pub workers: WorkersConfig,
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+}
+
+impl JobsConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.recovery.interval_seconds == 0 {
+            return Err("jobs.recovery.interval_seconds must be greater than 0".to_string());
+        }
+        for (name, pool) in &self.workers.workers {
+            // Below 2, a job still inside its timeout can be taken for stuck
+            // and run a second time
+            if pool.stuck_multiplier < 2 {
+                return Err(format!(
+                    "jobs.workers.{name}.stuck_multiplier ({}) must be at least 2",
+                    pool.stuck_multiplier
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+const fn default_workers_enabled() -> bool {
+    true
+}
+
+/// Selects which scheduled jobs run in the current environment, by `ScheduledJob::name`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    /// Scheduled jobs to run; when unset, every scheduled job runs
+    #[serde(default)]
+    pub enabled: Option<Vec<String>>,
+    /// Scheduled jobs to skip, applied after `enabled`
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    /// Periods during which no scheduled job is enqueued
+    #[serde(default)]
+    pub maintenance: Vec<MaintenanceWindow>,
+}
+
+/// A period, in UTC, during which scheduled runs are skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    /// Start of the window (inclusive), RFC 3339
+    pub start: chrono::DateTime<chrono::Utc>,
+    /// End of the window (exclusive), RFC 3339
+    pub end: chrono::DateTime<chrono::Utc>,
+}
+
+impl MaintenanceWindow {
+    /// Whether `at` falls inside this window.
+    pub fn contains(&self, at: chrono::DateTime<chrono::Utc>) -> bool {
+        self.start <= at && at < self.end
+    }
+}
+
+impl ScheduleConfig {
+    /// Whether the scheduled job called `name` should run.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        let enabled = self
+            .enabled
+            .as_ref()
+            .is_none_or(|enabled| enabled.iter().any(|n| n == name));
+
+        enabled && !self.disabled.iter().any(|n| n == name)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -224,6 +689,23 @@ impl Default for CleanupConfig {
     }
 }
 
+/// Settings for the task that resets jobs left `running` by a crashed worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryConfig {
+    /// Interval between checks for stuck jobs in seconds, greater than 0
+    /// (default: 300 = 5 minutes)
+    #[serde(default = "default_recovery_interval")]
+    pub interval_seconds: u64,
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            interval_seconds: default_recovery_interval(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkersConfig {
     #[serde(flatten)]
@@ -234,6 +716,9 @@ pub struct WorkersConfig {
 pub struct WorkerQueueConfig {
     pub jobs: Vec<String>,
     pub count: u32,
+    /// Jobs each worker runs at once (default: 1)
+    #[serde(default = "default_worker_concurrency")]
+    pub concurrency: usize,
     /// Job execution timeout in seconds (default: 300)
     #[serde(default = "default_job_timeout")]
     pub job_timeout: u32,
@@ -246,6 +731,30 @@ pub struct WorkerQueueConfig {
     /// Exponential backoff multiplier (default: 5.0)
     #[serde(default = "default_retry_multiplier")]
     pub retry_backoff_multiplier: u64,
+    /// Upper bound in seconds for the computed retry delay (default: 86400)
+    #[serde(default = "default_max_retry_delay")]
+    pub max_retry_delay_seconds: u64,
+    /// Share of the retry delay that is randomized, from 0.0 (exact delay)
+    /// to 1.0 (anywhere between zero and the delay) (default: 0.0)
+    #[serde(default)]
+    pub retry_jitter_fraction: f64,
+    /// Move permanently failed jobs to the `dead_letter_job` table instead of
+    /// leaving them in the queue for cleanup (default: false)
+    #[serde(default)]
+    pub dead_letter: bool,
+    /// A running job counts as stuck after this many times its timeout; at
+    /// least 2 (default: 2)
+    #[serde(default = "default_stuck_multiplier")]
+    pub stuck_multiplier: u32,
+    /// Seconds each retry adds to a job's age when ordering the queue, so a
+    /// job that keeps failing lets fresher jobs go first; 0 claims strictly
+    /// oldest first (default: 0)
+    #[serde(default)]
+    pub retry_age_penalty_seconds: u64,
+}
+
+const fn default_worker_concurrency() -> usize {
+    1
 }
 
 const fn default_max_retries() -> i32 {
@@ -264,6 +773,18 @@ const fn default_retry_multiplier() -> u64 {
     5
 }
 
+const fn default_max_retry_delay() -> u64 {
+    86_400 // 1 day
+}
+
+const fn default_stuck_multiplier() -> u32 {
+    2
+}
+
+const fn default_recovery_interval() -> u64 {
+    300 // 5 minutes
+}
+
 const fn default_cleanup_interval() -> u64 {
     3600 // 1 hour
 }
@@ -279,3 +800,59 @@ const fn default_failed_retention() -> u64 {
 const fn default_cleanup_batch_size() -> usize {
     1000
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{JobsConfig, WebSocketConfig};
+
+    #[test]
+    fn test_heartbeat_timeout_must_exceed_interval() {
+        let config = |interval, timeout| WebSocketConfig {
+            heartbeat_interval_seconds: interval,
+            heartbeat_timeout_seconds: timeout,
+            ..Default::default()
+        };
+
+        assert!(config(30, 60).validate().is_ok());
+        assert!(config(30, 30).validate().is_err());
+        assert!(config(30, 0).validate().is_err());
+        // Heartbeats off, so the timeout is unused
+        assert!(config(0, 0).validate().is_ok());
+
+        let no_ack_timeout = WebSocketConfig {
+            ack_timeout_seconds: 0,
+            ..Default::default()
+        };
+        assert!(no_ack_timeout.validate().is_err());
+    }
+
+    #[test]
+    fn test_listener_retry_base_must_be_at_least_one_second() {
+        let config = |base| WebSocketConfig {
+            listener_retry_base_seconds: base,
+            ..Default::default()
+        };
+
+        assert!(config(1).validate().is_ok());
+        assert!(config(0).validate().is_err());
+    }
+
+    #[test]
+    fn test_stuck_job_recovery_settings_are_checked() {
+        let config = |recovery_interval: u64, stuck_multiplier: u32| -> JobsConfig {
+            serde_json::from_value(serde_json::json!({
+                "cleanup": {},
+                "recovery": { "interval_seconds": recovery_interval },
+                "workers": {
+                    "default": { "jobs": [], "count": 1, "stuck_multiplier": stuck_multiplier },
+                },
+            }))
+            .unwrap()
+        };
+
+        assert!(config(300, 2).validate().is_ok());
+        assert!(config(300, 1).validate().is_err());
+        assert!(config(300, 0).validate().is_err());
+        assert!(config(0, 2).validate().is_err());
+    }
+}
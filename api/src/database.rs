@@ -1,9 +1,13 @@
-use std::fmt::{self, Display, Formatter};
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::Arc,
+    time::Duration,
+};
 
-use sea_orm::{ConnectOptions, DbErr};
+use sea_orm::{ConnectOptions, ConnectionTrait, DatabaseConnection, DbErr};
 use sea_orm_migration::MigratorTrait;
-use tokio::sync::oneshot;
-use tracing::debug;
+use tokio::sync::{oneshot, watch};
+use tracing::{debug, info, warn};
 
 use crate::config::DatabaseConfig;
 
@@ -13,6 +17,7 @@ pub(crate) mod models;
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum DatabaseSetupStatus {
     MigrationsInProgress,
+    MigrationsPending(usize),
     MigrationsFailed(String),
     Completed,
 }
@@ -21,12 +26,116 @@ impl Display for DatabaseSetupStatus {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Self::MigrationsInProgress => write!(f, "Migrations in progress"),
+            Self::MigrationsPending(count) => write!(f, "{count} migration(s) pending"),
             Self::MigrationsFailed(e) => write!(f, "Migrations failed: {e}"),
             Self::Completed => write!(f, "Database setup completed"),
         }
     }
 }
 
+/// Shared database setup status, consulted by the readiness probe.
+#[derive(Debug, Clone)]
+pub struct DatabaseStatus(Arc<watch::Sender<DatabaseSetupStatus>>);
+
+impl DatabaseStatus {
+    pub fn new(status: DatabaseSetupStatus) -> Self {
+        Self(Arc::new(watch::Sender::new(status)))
+    }
+
+    pub fn get(&self) -> DatabaseSetupStatus {
+        self.0.borrow().clone()
+    }
+
+    pub fn set(&self, status: DatabaseSetupStatus) {
+        self.0.send_replace(status);
+    }
+
+    /// Wait until every migration has been applied.
+    pub async fn completed(&self) {
+        let mut receiver = self.0.subscribe();
+        // The sender is held by `self`, so the channel can't close
+        let _ = receiver
+            .wait_for(|status| *status == DatabaseSetupStatus::Completed)
+            .await;
+    }
+}
+
+const PENDING_MIGRATIONS_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Returns `Completed` when every migration of `AppMigrator` has been applied,
+/// `MigrationsPending` otherwise.
+pub async fn check_pending_migrations<AppMigrator: MigratorTrait>(
+    db: &DatabaseConnection,
+) -> Result<DatabaseSetupStatus, DbErr> {
+    let pending = AppMigrator::get_pending_migrations(db).await?.len();
+
+    if pending == 0 {
+        Ok(DatabaseSetupStatus::Completed)
+    } else {
+        Ok(DatabaseSetupStatus::MigrationsPending(pending))
+    }
+}
+
+/// Spawns a task polling for pending migrations until all of them have been
+/// applied externally, keeping `status` up to date for the readiness probe.
+pub fn watch_pending_migrations<AppMigrator: MigratorTrait>(
+    db: DatabaseConnection,
+    status: DatabaseStatus,
+) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(PENDING_MIGRATIONS_POLL_INTERVAL).await;
+
+            match AppMigrator::get_pending_migrations(&db).await {
+                Ok(pending) if pending.is_empty() => {
+                    info!("✅ Pending migrations have been applied, database is ready");
+                    status.set(DatabaseSetupStatus::Completed);
+                    return;
+                }
+                Ok(pending) => status.set(DatabaseSetupStatus::MigrationsPending(pending.len())),
+                Err(e) => warn!("Failed to check pending migrations: {e}"),
+            }
+        }
+    });
+}
+
+/// Connect to the database for `serve`. Migrations are run first when
+/// `auto_migrate_on_serve` is set. Otherwise pending ones are only reported,
+/// and the returned status is kept up to date until they have been applied
+/// externally.
+pub async fn connect_for_serve<AppMigrator: MigratorTrait>(
+    db_config: &DatabaseConfig,
+) -> Result<(DatabaseConnection, DatabaseStatus), DbErr> {
+    if db_config.auto_migrate_on_serve {
+        let (db, migration_receiver) = setup_database::<AppMigrator>(db_config).await;
+
+        // Wait for migrations to complete
+        migration_receiver.await.map_err(|_| {
+            DbErr::Custom("Database setup channel closed unexpectedly".to_string())
+        })??;
+        info!("✅ Database is ready!");
+
+        Ok((db, DatabaseStatus::new(DatabaseSetupStatus::Completed)))
+    } else {
+        info!("⏭️ Automatic migrations disabled, checking for pending migrations");
+        let db = setup_database_connection(db_config).await;
+
+        let status = check_pending_migrations::<AppMigrator>(&db)
+            .await
+            .map_err(|e| DbErr::Custom(format!("Failed to check pending migrations: {e}")))?;
+
+        let database_status = DatabaseStatus::new(status.clone());
+        if status == DatabaseSetupStatus::Completed {
+            info!("✅ Database is ready!");
+        } else {
+            warn!("⏳ {status}, readiness will fail until they are applied");
+            watch_pending_migrations::<AppMigrator>(db.clone(), database_status.clone());
+        }
+
+        Ok((db, database_status))
+    }
+}
+
 pub async fn setup_database<AppMigrator: MigratorTrait>(
     db_config: &DatabaseConfig,
 ) -> (
@@ -38,8 +147,13 @@ pub async fn setup_database<AppMigrator: MigratorTrait>(
 
     let (sender, receiver) = oneshot::channel();
 
+    let schema = db_config.schema.clone();
     tokio::spawn(async move {
-        let migration_result = AppMigrator::up(&migrations_connection, None).await;
+        let migration_result = match create_schema(&migrations_connection, schema.as_deref()).await
+        {
+            Ok(()) => AppMigrator::up(&migrations_connection, None).await,
+            Err(e) => Err(e),
+        };
         let _ = sender.send(migration_result);
     });
 
@@ -47,14 +161,218 @@ pub async fn setup_database<AppMigrator: MigratorTrait>(
 }
 
 pub async fn setup_database_connection(db_config: &DatabaseConfig) -> sea_orm::DatabaseConnection {
-    let mut options = ConnectOptions::new(db_config.url.clone());
+    debug!("Connecting to database at: {}", &db_config.url);
+
+    sea_orm::Database::connect(connect_options(db_config))
+        .await
+        .expect("Failed to connect to the database")
+}
+
+/// Create `schema` if it doesn't exist yet, so migrations have somewhere to
+/// put their tables. Does nothing when no schema is configured.
+pub async fn create_schema(db: &DatabaseConnection, schema: Option<&str>) -> Result<(), DbErr> {
+    let Some(schema) = schema else {
+        return Ok(());
+    };
 
-    options.sqlx_logging(false); // Disable SQL query logging to reduce noise
+    let schema = schema.replace('"', "\"\"");
+    db.execute_unprepared(&format!("CREATE SCHEMA IF NOT EXISTS \"{schema}\""))
+        .await?;
+    Ok(())
+}
+
+/// Build the connection options for `db_config`.
+///
+/// Statement logging stays off unless `slow_query_threshold_ms` is set, in
+/// which case only statements slower than the threshold are logged (at WARN,
+/// with their duration).
+fn connect_options(db_config: &DatabaseConfig) -> ConnectOptions {
+    let mut options = ConnectOptions::new(db_config.url.clone());
     options.max_connections(db_config.pool_size);
+    if let Some(schema) = &db_config.schema {
+        options.set_schema_search_path(schema);
+    }
 
-    debug!("Connecting to database at: {}", &db_config.url);
+    match db_config.slow_query_threshold_ms {
+        Some(threshold_ms) => {
+            options
+                .sqlx_logging(true)
+                .sqlx_logging_level(log::LevelFilter::Off)
+                .sqlx_slow_statements_logging_settings(
+                    log::LevelFilter::Warn,
+                    Duration::from_millis(threshold_ms),
+                );
+        }
+        None => {
+            options.sqlx_logging(false); // Disable SQL query logging to reduce noise
+        }
+    }
+
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm_migration::prelude::*;
+
+    use sea_orm::{ActiveModelTrait, ConnectionTrait, EntityTrait, Set, Statement};
+
+    use super::{
+        check_pending_migrations, connect_for_serve, connect_options, create_schema,
+        setup_database_connection, DatabaseSetupStatus,
+    };
+    use crate::{
+        boot::read_config,
+        config::DatabaseConfig,
+        database::{migrations::Migrator, models::user},
+        environment::Environment,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+    };
+
+    struct PendingMigration;
+
+    impl MigrationName for PendingMigration {
+        fn name(&self) -> &str {
+            "m99991231_000000_not_yet_applied"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for PendingMigration {
+        async fn up(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+            Ok(())
+        }
+    }
+
+    /// The framework migrations plus one that is never applied.
+    struct MigratorWithPending;
+
+    impl MigratorTrait for MigratorWithPending {
+        fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+            let mut migrations = Migrator::migrations();
+            migrations.push(Box::new(PendingMigration));
+            migrations
+        }
+    }
 
-    sea_orm::Database::connect(options)
+    #[tokio::test]
+    async fn test_readiness_fails_with_pending_migrations() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        test.server.get("/readiness").await.assert_status_ok();
+
+        let status = check_pending_migrations::<Migrator>(&test.db)
+            .await
+            .unwrap();
+        assert_eq!(status, DatabaseSetupStatus::Completed);
+
+        let status = check_pending_migrations::<MigratorWithPending>(&test.db)
+            .await
+            .unwrap();
+        assert_eq!(status, DatabaseSetupStatus::MigrationsPending(1));
+
+        test.database_status.set(status);
+        test.server
+            .get("/readiness")
+            .await
+            .assert_status_service_unavailable();
+    }
+
+    #[tokio::test]
+    async fn test_serve_without_auto_migrate_waits_for_pending_migrations() {
+        // Makes sure the framework migrations have been applied
+        let _test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let mut db_config = read_config::<()>(&Environment::Test).database;
+        db_config.auto_migrate_on_serve = false;
+        db_config.pool_size = 1;
+
+        let (db, status) = connect_for_serve::<MigratorWithPending>(&db_config)
+            .await
+            .unwrap();
+        assert_eq!(status.get(), DatabaseSetupStatus::MigrationsPending(1));
+        let pending = check_pending_migrations::<MigratorWithPending>(&db)
+            .await
+            .unwrap();
+        assert_eq!(pending, DatabaseSetupStatus::MigrationsPending(1));
+
+        // Background tasks are held back until the migrations are applied
+        let waiting = status.clone();
+        let completed = tokio::spawn(async move { waiting.completed().await });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!completed.is_finished());
+
+        status.set(DatabaseSetupStatus::Completed);
+        tokio::time::timeout(std::time::Duration::from_secs(1), completed)
+            .await
+            .expect("still waiting after the migrations were applied")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_slow_query_threshold_enables_slow_statement_logging() {
+        let mut db_config = DatabaseConfig {
+            url: "postgres://localhost/erno".to_string(),
+            pool_size: 5,
+            auto_migrate_on_serve: true,
+            slow_query_threshold_ms: None,
+            schema: None,
+        };
+
+        let options = connect_options(&db_config);
+        assert!(!options.get_sqlx_logging());
+
+        db_config.slow_query_threshold_ms = Some(250);
+        let options = connect_options(&db_config);
+        assert!(options.get_sqlx_logging());
+        assert_eq!(options.get_sqlx_logging_level(), log::LevelFilter::Off);
+        assert_eq!(
+            options.get_sqlx_slow_statements_logging_settings(),
+            (
+                log::LevelFilter::Warn,
+                std::time::Duration::from_millis(250)
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_migrations_and_queries_use_configured_schema() {
+        let mut db_config = read_config::<()>(&Environment::Test).database;
+        db_config.schema = Some("erno_schema_test".to_string());
+        db_config.pool_size = 1;
+
+        let db = setup_database_connection(&db_config).await;
+        db.execute_unprepared("DROP SCHEMA IF EXISTS erno_schema_test CASCADE")
+            .await
+            .unwrap();
+        create_schema(&db, db_config.schema.as_deref())
+            .await
+            .unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        user::ActiveModel {
+            email: Set("schema@example.com".to_string()),
+            password_hash: Set("hash".to_string()),
+            ..Default::default()
+        }
+        .insert(&db)
         .await
-        .expect("Failed to connect to the database")
+        .unwrap();
+        let users = user::Entity::find().all(&db).await.unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].email, "schema@example.com");
+
+        let row = db
+            .query_one(Statement::from_string(
+                db.get_database_backend(),
+                "SELECT table_schema FROM information_schema.tables WHERE table_name = 'seaql_migrations' AND table_schema = current_schema()",
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        let schema: String = row.try_get("", "table_schema").unwrap();
+        assert_eq!(schema, "erno_schema_test");
+
+        db.execute_unprepared("DROP SCHEMA erno_schema_test CASCADE")
+            .await
+            .unwrap();
+    }
 }
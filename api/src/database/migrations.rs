@@ -1,3 +1,5 @@
+use std::{collections::HashSet, marker::PhantomData};
+
 pub use sea_orm_migration::prelude::*;
 
 mod m20250805_180000_create_update_at_trigger;
@@ -15,6 +17,21 @@ mod m20260514_100003_add_subscription_cache_to_users;
 mod m20260514_200000_create_files;
 mod m20260514_200001_create_file_attachments;
 mod m20260515_000001_add_refresh_token_type;
+mod m20261017_000001_add_requires_ack_to_websocket_message;
+mod m20261017_000002_add_dedup_key_to_job;
+mod m20261017_000003_add_callback_url_to_job;
+mod m20261017_000004_add_log_context_to_job;
+mod m20261017_000005_add_compressed_arguments_to_job;
+mod m20261017_000006_add_priority_to_job;
+mod m20261017_000007_add_unique_key_to_job;
+mod m20261017_000008_add_cancel_requested_to_job;
+mod m20261017_000009_create_dead_letter_job;
+mod m20261017_000010_notify_job_insert_per_statement;
+mod m20261017_000011_add_claimable_job_index;
+mod m20261017_000012_add_output_to_job_execution;
+mod m20261017_000013_add_retained_until_to_websocket_message;
+mod m20261017_000014_create_scheduled_run;
+mod m20261017_000015_create_event;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
@@ -35,6 +52,21 @@ impl MigratorTrait for Migrator {
             Box::new(m20260514_200000_create_files::Migration),
             Box::new(m20260514_200001_create_file_attachments::Migration),
             Box::new(m20260515_000001_add_refresh_token_type::Migration),
+            Box::new(m20261017_000001_add_requires_ack_to_websocket_message::Migration),
+            Box::new(m20261017_000002_add_dedup_key_to_job::Migration),
+            Box::new(m20261017_000003_add_callback_url_to_job::Migration),
+            Box::new(m20261017_000004_add_log_context_to_job::Migration),
+            Box::new(m20261017_000005_add_compressed_arguments_to_job::Migration),
+            Box::new(m20261017_000006_add_priority_to_job::Migration),
+            Box::new(m20261017_000007_add_unique_key_to_job::Migration),
+            Box::new(m20261017_000008_add_cancel_requested_to_job::Migration),
+            Box::new(m20261017_000009_create_dead_letter_job::Migration),
+            Box::new(m20261017_000010_notify_job_insert_per_statement::Migration),
+            Box::new(m20261017_000011_add_claimable_job_index::Migration),
+            Box::new(m20261017_000012_add_output_to_job_execution::Migration),
+            Box::new(m20261017_000013_add_retained_until_to_websocket_message::Migration),
+            Box::new(m20261017_000014_create_scheduled_run::Migration),
+            Box::new(m20261017_000015_create_event::Migration),
         ]
     }
 }
@@ -46,3 +78,140 @@ pub struct Migrator;
 pub fn erno_migrations() -> Vec<Box<dyn MigrationTrait>> {
     Migrator::migrations()
 }
+
+/// A migrator that runs all of `First`'s migrations, then all of `Second`'s.
+///
+/// Each set keeps its own order, and the combined set is recorded in
+/// `First`'s migration table. Migration names must be unique across both
+/// sets; a duplicate panics when the migrations are listed, before anything
+/// is applied.
+///
+/// # Example
+/// ```rust,ignore
+/// // Erno's migrations first, then the app's own
+/// boot::<WithErnoMigrations<migration::Migrator>, _>(boot_config()).await;
+/// ```
+pub struct CombinedMigrator<First, Second>(PhantomData<(First, Second)>);
+
+/// The framework's migrations followed by the app's `AppMigrator`.
+pub type WithErnoMigrations<AppMigrator> = CombinedMigrator<Migrator, AppMigrator>;
+
+#[async_trait::async_trait]
+impl<First, Second> MigratorTrait for CombinedMigrator<First, Second>
+where
+    First: MigratorTrait,
+    Second: MigratorTrait,
+{
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        let mut migrations = First::migrations();
+        migrations.extend(Second::migrations());
+
+        let mut names = HashSet::new();
+        for migration in &migrations {
+            let name = migration.name();
+            assert!(
+                names.insert(name.to_string()),
+                "Migration '{name}' is defined by both combined migrators"
+            );
+        }
+
+        migrations
+    }
+
+    fn migration_table_name() -> DynIden {
+        First::migration_table_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use sea_orm::ConnectionTrait;
+
+    use super::*;
+    use crate::tests::setup_test::{empty_router, no_fixtures, setup_test};
+
+    static APPLIED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+    struct Recording(&'static str);
+
+    impl MigrationName for Recording {
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for Recording {
+        async fn up(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+            APPLIED.lock().unwrap().push(self.0);
+            Ok(())
+        }
+    }
+
+    struct FrameworkMigrator;
+
+    #[async_trait::async_trait]
+    impl MigratorTrait for FrameworkMigrator {
+        fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+            vec![
+                Box::new(Recording("m20990101_000001_framework_b")),
+                Box::new(Recording("m20990101_000002_framework_a")),
+            ]
+        }
+
+        fn migration_table_name() -> DynIden {
+            Alias::new("seaql_migrations_combined_test").into_iden()
+        }
+    }
+
+    struct AppMigrator;
+
+    #[async_trait::async_trait]
+    impl MigratorTrait for AppMigrator {
+        fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+            // Sorts before the framework's migrations, but must still run after them
+            vec![Box::new(Recording("m20000101_000001_app"))]
+        }
+    }
+
+    struct CollidingMigrator;
+
+    #[async_trait::async_trait]
+    impl MigratorTrait for CollidingMigrator {
+        fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+            vec![Box::new(Recording("m20990101_000001_framework_b"))]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_combined_migrator_applies_both_sets_in_order() {
+        type Combined = CombinedMigrator<FrameworkMigrator, AppMigrator>;
+
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let drop_table = "DROP TABLE IF EXISTS seaql_migrations_combined_test";
+        test.db.execute_unprepared(drop_table).await.unwrap();
+
+        Combined::up(&test.db, None).await.unwrap();
+
+        let expected = [
+            "m20990101_000001_framework_b",
+            "m20990101_000002_framework_a",
+            "m20000101_000001_app",
+        ];
+        assert_eq!(*APPLIED.lock().unwrap(), expected);
+        assert!(Combined::get_pending_migrations(&test.db)
+            .await
+            .unwrap()
+            .is_empty());
+
+        test.db.execute_unprepared(drop_table).await.unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "defined by both combined migrators")]
+    fn test_combined_migrator_rejects_duplicate_names() {
+        let _ = CombinedMigrator::<FrameworkMigrator, CollidingMigrator>::migrations();
+    }
+}
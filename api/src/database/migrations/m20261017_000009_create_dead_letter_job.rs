@@ -0,0 +1,74 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{integer, json_binary, string, timestamp, uuid},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Copies of permanently failed jobs, kept for inspection and replay
+        manager
+            .create_table(
+                Table::create()
+                    .table(DeadLetterJob::Table)
+                    .if_not_exists()
+                    .col(uuid(DeadLetterJob::Id).primary_key())
+                    .col(string(DeadLetterJob::Type).not_null())
+                    .col(json_binary(DeadLetterJob::Arguments).not_null())
+                    .col(
+                        ColumnDef::new(DeadLetterJob::CompressedArguments)
+                            .binary()
+                            .null(),
+                    )
+                    .col(integer(DeadLetterJob::RetryCount).not_null())
+                    .col(ColumnDef::new(DeadLetterJob::FailureReason).text().null())
+                    .col(
+                        ColumnDef::new(DeadLetterJob::LogContext)
+                            .json_binary()
+                            .null(),
+                    )
+                    .col(timestamp(DeadLetterJob::CreatedAt).not_null())
+                    .col(
+                        timestamp(DeadLetterJob::FailedAt)
+                            .not_null()
+                            .default(Expr::cust("CURRENT_TIMESTAMP")),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_dead_letter_job_type_failed_at")
+                    .table(DeadLetterJob::Table)
+                    .col(DeadLetterJob::Type)
+                    .col(DeadLetterJob::FailedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DeadLetterJob::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum DeadLetterJob {
+    Table,
+    Id,
+    Type,
+    Arguments,
+    CompressedArguments,
+    RetryCount,
+    FailureReason,
+    LogContext,
+    CreatedAt,
+    FailedAt,
+}
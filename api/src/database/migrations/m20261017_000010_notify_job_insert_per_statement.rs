@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One notification per INSERT statement, so a batch of jobs wakes the
+        // workers once instead of once per row. Workers ignore the payload.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r"
+                DROP TRIGGER IF EXISTS job_insert_notify ON job;
+
+                CREATE OR REPLACE FUNCTION notify_job_insert()
+                RETURNS trigger AS $$
+                BEGIN
+                    PERFORM pg_notify('job_new', '');
+                    RETURN NULL;
+                END;
+                $$ LANGUAGE plpgsql;
+
+                CREATE TRIGGER job_insert_notify
+                    AFTER INSERT ON job
+                    FOR EACH STATEMENT
+                    EXECUTE FUNCTION notify_job_insert();
+                ",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r"
+                DROP TRIGGER IF EXISTS job_insert_notify ON job;
+
+                CREATE OR REPLACE FUNCTION notify_job_insert()
+                RETURNS trigger AS $$
+                BEGIN
+                    PERFORM pg_notify('job_new', NEW.id::text);
+                    RETURN NEW;
+                END;
+                $$ LANGUAGE plpgsql;
+
+                CREATE TRIGGER job_insert_notify
+                    AFTER INSERT ON job
+                    FOR EACH ROW
+                    EXECUTE FUNCTION notify_job_insert();
+                ",
+            )
+            .await?;
+
+        Ok(())
+    }
+}
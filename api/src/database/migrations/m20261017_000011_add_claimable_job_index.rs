@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Only waiting jobs are indexed, so the claim query stays fast however
+        // many completed and failed jobs the table holds. Columns follow the
+        // claim's ordering: most urgent first, then oldest.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                CREATE INDEX "idx-job-claimable"
+                    ON job (type, priority DESC, created_at)
+                    WHERE status IN ('pending', 'pending_retry');
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-job-claimable")
+                    .table(Job::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Job {
+    Table,
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{string, timestamp},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Last slot handled per scheduled job, so catch-up doesn't depend on
+        // job rows that cleanup may already have deleted
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduledRun::Table)
+                    .if_not_exists()
+                    .col(string(ScheduledRun::Name).primary_key())
+                    .col(timestamp(ScheduledRun::LastSlotAt).not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ScheduledRun::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ScheduledRun {
+    Table,
+    Name,
+    LastSlotAt,
+}
@@ -0,0 +1,56 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, json_binary, string, timestamp},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Published events, kept for a while so a listener that reconnects
+        // can dispatch the ones it missed
+        manager
+            .create_table(
+                Table::create()
+                    .table(Event::Table)
+                    .if_not_exists()
+                    .col(big_integer(Event::Id).auto_increment().primary_key())
+                    .col(string(Event::Name).not_null())
+                    .col(json_binary(Event::Payload).not_null())
+                    .col(
+                        timestamp(Event::CreatedAt)
+                            .not_null()
+                            .default(Expr::cust("CURRENT_TIMESTAMP")),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_event_created_at")
+                    .table(Event::Table)
+                    .col(Event::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Event::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Event {
+    Table,
+    Id,
+    Name,
+    Payload,
+    CreatedAt,
+}
@@ -2,10 +2,13 @@
 
 pub mod prelude;
 
+pub mod dead_letter_job;
+pub mod event;
 pub mod job;
 pub mod job_execution;
 pub mod job_result;
 pub mod job_status;
+pub mod scheduled_run;
 pub mod sync_push_queue;
 pub mod user;
 pub mod user_token;
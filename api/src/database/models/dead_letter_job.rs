@@ -0,0 +1,33 @@
+//! `SeaORM` Entity for permanently failed jobs moved out of the queue
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// A job that failed for good in a pool with `dead_letter` enabled. Keeps
+/// everything needed to inspect or re-enqueue it; its executions are not
+/// kept.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "dead_letter_job")]
+pub struct Model {
+    /// Id the job had in the queue
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub r#type: String,
+    /// JSON `null` when the arguments are stored in `compressed_arguments`
+    #[sea_orm(column_type = "JsonBinary")]
+    pub arguments: Json,
+    pub compressed_arguments: Option<Vec<u8>>,
+    pub retry_count: i32,
+    /// Reason of the last failed execution
+    pub failure_reason: Option<String>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub log_context: Option<Json>,
+    /// When the job was originally enqueued
+    pub created_at: DateTime,
+    pub failed_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+//! `SeaORM` Entity for events sent with `EventBus::publish`
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// A published event. Its id is what goes out through `NOTIFY`; rows are
+/// deleted once they are older than `events.retention_seconds`.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "event")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub name: String,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub payload: Json,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
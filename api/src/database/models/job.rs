@@ -13,11 +13,31 @@ pub struct Model {
     pub created_at: DateTime,
     pub updated_at: DateTime,
     pub r#type: String,
+    /// JSON `null` when the arguments are stored in `compressed_arguments`
     #[sea_orm(column_type = "JsonBinary")]
     pub arguments: Json,
     pub status: JobStatus,
     pub retry_count: i32,
     pub next_execution_at: Option<DateTime>,
+    pub dedup_key: Option<String>,
+    /// URL to POST the outcome to once the job reaches a terminal status
+    pub callback_url: Option<String>,
+    /// [`LogContext`](crate::log_context::LogContext) captured at enqueue
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub log_context: Option<Json>,
+    /// Gzipped JSON arguments of jobs that opt into
+    /// [`Job::compress_arguments`](crate::jobs::Job::compress_arguments)
+    pub compressed_arguments: Option<Vec<u8>>,
+    /// Higher values are claimed first; 0 unless set with
+    /// [`JobQueue::add_with_priority`](crate::job_queue::JobQueue::add_with_priority)
+    pub priority: i32,
+    /// Key passed to [`JobQueue::add_unique`](crate::job_queue::JobQueue::add_unique);
+    /// unique per type among pending and running jobs
+    pub unique_key: Option<String>,
+    /// Set by [`JobQueue::cancel`](crate::job_queue::JobQueue::cancel) on a
+    /// running job; the worker passes it on through
+    /// [`cancellation`](crate::jobs::cancellation)
+    pub cancel_requested: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -42,7 +62,7 @@ impl ActiveModelBehavior for ActiveModel {
 
         if insert {
             if matches!(self.id, ActiveValue::NotSet) {
-                self.id = ActiveValue::Set(uuid::Uuid::new_v4());
+                self.id = ActiveValue::Set(uuid::Uuid::now_v7());
             }
 
             if matches!(self.created_at, ActiveValue::NotSet) {
@@ -58,6 +78,14 @@ impl ActiveModelBehavior for ActiveModel {
 
 #[allow(dead_code)]
 impl Model {
+    /// The job's arguments, decompressed if they were stored compressed.
+    pub fn decoded_arguments(&self) -> std::io::Result<Json> {
+        match &self.compressed_arguments {
+            Some(bytes) => crate::jobs::compression::decompress(bytes),
+            None => Ok(self.arguments.clone()),
+        }
+    }
+
     /// Mark the job as running
     pub const fn start(&mut self) {
         self.status = JobStatus::Running;
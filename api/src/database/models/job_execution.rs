@@ -15,6 +15,8 @@ pub struct Model {
     pub finished_at: DateTime,
     pub execution_time_ms: i64,
     pub failure_reason: Option<String>,
+    /// Set with [`set_output`](crate::jobs::output::set_output) by a run that completed
+    pub output: Option<Json>,
     pub created_at: DateTime,
 }
 
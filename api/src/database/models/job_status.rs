@@ -17,6 +17,8 @@ use strum::{Display, EnumIter, EnumString};
 /// - `Pending` → `Running` → `Failed` (permanent failure or timeout)
 /// - `Pending` → `Running` → `PendingRetry` (retry after transient failure)
 /// - `PendingRetry` → `Running` → `Completed`/`Failed`/`PendingRetry` (subsequent attempts)
+/// - `Pending`/`PendingRetry` → `Cancelled` (cancelled before a worker claimed it)
+/// - `Running` → `Cancelled` (stopped early after cancellation was requested)
 #[derive(
     Debug,
     Clone,
@@ -78,6 +80,14 @@ pub enum JobStatus {
     /// permanent failure, timeout, or after exceeding the maximum retry count.
     #[sea_orm(string_value = "failed")]
     Failed,
+
+    /// Job was cancelled before it started or while it was running.
+    ///
+    /// This is a terminal state set by `JobQueue::cancel`. A pending job is
+    /// cancelled at once; a running job only once it notices the request
+    /// through [`cancellation`](crate::jobs::cancellation) and stops early.
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
 }
 
 #[allow(dead_code)]
@@ -85,9 +95,9 @@ impl JobStatus {
     /// Checks if this status represents a terminal state.
     ///
     /// Terminal states are final - jobs in these states will not be processed again.
-    /// This includes `Completed` and `Failed` (which covers timeouts as well).
+    /// This includes `Completed`, `Failed` (which covers timeouts as well) and `Cancelled`.
     pub const fn is_terminal(&self) -> bool {
-        matches!(self, Self::Completed | Self::Failed)
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
     }
 
     /// Checks if this job is currently being executed by a worker.
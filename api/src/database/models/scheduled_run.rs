@@ -0,0 +1,21 @@
+//! `SeaORM` Entity for the last handled slot of each scheduled job
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// The most recent slot of a [`ScheduledJob`](crate::jobs::scheduled_job::ScheduledJob)
+/// that was enqueued or skipped for a maintenance window. Catch-up compares
+/// it with the schedule at startup.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "scheduled_run")]
+pub struct Model {
+    /// `ScheduledJob::name`
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub name: String,
+    pub last_slot_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
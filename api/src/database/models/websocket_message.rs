@@ -1,4 +1,5 @@
 use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveValue, ConnectionTrait};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
@@ -8,10 +9,27 @@ pub struct Model {
     pub id: Uuid,
     pub recipient_criteria: serde_json::Value,
     pub payload: serde_json::Value,
+    pub requires_ack: bool,
     pub created_at: chrono::NaiveDateTime,
+    /// Set on a `user`-targeted message kept for a user who wasn't
+    /// connected; it is delivered when they connect before this time
+    pub retained_until: Option<chrono::NaiveDateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        // Generate a time-ordered id app-side instead of the column's random default
+        if insert && matches!(self.id, ActiveValue::NotSet) {
+            self.id = ActiveValue::Set(uuid::Uuid::now_v7());
+        }
+
+        Ok(self)
+    }
+}
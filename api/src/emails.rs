@@ -1,11 +1,16 @@
 //! Docs: docs/src/content/docs/api/email.md
 use lettre::{
-    message::{header::ContentType, MultiPart},
+    message::{header::ContentType, Mailbox, MultiPart},
     Message,
 };
 use thiserror::Error;
 
-use crate::{app::App, jobs::JobError, mailer::MockEmailRecord};
+use crate::{
+    app::App,
+    config::EmailConfig,
+    jobs::JobError,
+    mailer::{Mailer, MockEmailRecord},
+};
 
 #[derive(Error, Debug)]
 pub enum EmailError {
@@ -19,6 +24,8 @@ pub enum EmailError {
     TemplateError(String),
     #[error("Mailer error: {0}")]
     MailerError(String),
+    #[error("No email transport named {0}")]
+    UnknownTransport(String),
 }
 
 impl From<EmailError> for JobError {
@@ -29,6 +36,9 @@ impl From<EmailError> for JobError {
             EmailError::TransportError(e) => JobError::TryAgainLater(e.to_string()),
             EmailError::TemplateError(e) => JobError::FailPermanently(e),
             EmailError::MailerError(e) => JobError::TryAgainLater(e),
+            EmailError::UnknownTransport(name) => {
+                JobError::FailPermanently(format!("No email transport named {name}"))
+            }
         }
     }
 }
@@ -45,14 +55,85 @@ pub async fn send_html_email<ExtraConfig>(
     subject: &str,
     body: String,
 ) -> Result<(), EmailError> {
-    let sender = match &app.config.email {
-        crate::config::EmailConfig::Smtp { sender, .. } => sender.clone(),
-        crate::config::EmailConfig::Mock => {
-            "noreply@example.com".parse().expect("Invalid mock sender")
-        }
-    };
+    send_html(&app.mailer, &app.config.email, recipient, subject, body).await
+}
+
+/// Like [`send_html_email`], through the transport configured as
+/// `[email_transports.<transport>]`.
+pub async fn send_html_email_via<ExtraConfig>(
+    app: &App<ExtraConfig>,
+    transport: &str,
+    recipient: &str,
+    subject: &str,
+    body: String,
+) -> Result<(), EmailError> {
+    let (mailer, config) = named_transport(app, transport)?;
+    send_html(mailer, config, recipient, subject, body).await
+}
+
+/// Sends a multipart email with both plain text and HTML versions.
+///
+/// This is the preferred method for sending emails as it provides better
+/// accessibility and compatibility. Email clients will automatically choose
+/// the best format for the user.
+pub async fn send_multipart_email<ExtraConfig>(
+    app: &App<ExtraConfig>,
+    recipient: &str,
+    subject: &str,
+    text_body: String,
+    html_body: String,
+) -> Result<(), EmailError> {
+    send_multipart(
+        &app.mailer,
+        &app.config.email,
+        recipient,
+        subject,
+        text_body,
+        html_body,
+    )
+    .await
+}
 
-    app.mailer.store_record(MockEmailRecord {
+/// Like [`send_multipart_email`], through the transport configured as
+/// `[email_transports.<transport>]`, e.g. `bulk` for newsletters.
+pub async fn send_multipart_email_via<ExtraConfig>(
+    app: &App<ExtraConfig>,
+    transport: &str,
+    recipient: &str,
+    subject: &str,
+    text_body: String,
+    html_body: String,
+) -> Result<(), EmailError> {
+    let (mailer, config) = named_transport(app, transport)?;
+    send_multipart(mailer, config, recipient, subject, text_body, html_body).await
+}
+
+fn named_transport<'a, ExtraConfig>(
+    app: &'a App<ExtraConfig>,
+    transport: &str,
+) -> Result<(&'a Mailer, &'a EmailConfig), EmailError> {
+    app.email_transport(transport)
+        .zip(app.config.email_transports.get(transport))
+        .ok_or_else(|| EmailError::UnknownTransport(transport.to_string()))
+}
+
+fn sender(config: &EmailConfig) -> Mailbox {
+    match config {
+        EmailConfig::Smtp { sender, .. } => sender.clone(),
+        EmailConfig::Mock => "noreply@example.com".parse().expect("Invalid mock sender"),
+    }
+}
+
+async fn send_html(
+    mailer: &Mailer,
+    config: &EmailConfig,
+    recipient: &str,
+    subject: &str,
+    body: String,
+) -> Result<(), EmailError> {
+    let sender = sender(config);
+
+    mailer.store_record(MockEmailRecord {
         id: uuid::Uuid::new_v4(),
         to: recipient.to_string(),
         from: sender.to_string(),
@@ -69,7 +150,7 @@ pub async fn send_html_email<ExtraConfig>(
         .header(ContentType::TEXT_HTML)
         .body(body)?;
 
-    app.mailer
+    mailer
         .send(email)
         .await
         .map_err(|e| EmailError::MailerError(e.to_string()))?;
@@ -77,26 +158,17 @@ pub async fn send_html_email<ExtraConfig>(
     Ok(())
 }
 
-/// Sends a multipart email with both plain text and HTML versions.
-///
-/// This is the preferred method for sending emails as it provides better
-/// accessibility and compatibility. Email clients will automatically choose
-/// the best format for the user.
-pub async fn send_multipart_email<ExtraConfig>(
-    app: &App<ExtraConfig>,
+async fn send_multipart(
+    mailer: &Mailer,
+    config: &EmailConfig,
     recipient: &str,
     subject: &str,
     text_body: String,
     html_body: String,
 ) -> Result<(), EmailError> {
-    let sender = match &app.config.email {
-        crate::config::EmailConfig::Smtp { sender, .. } => sender.clone(),
-        crate::config::EmailConfig::Mock => {
-            "noreply@example.com".parse().expect("Invalid mock sender")
-        }
-    };
+    let sender = sender(config);
 
-    app.mailer.store_record(MockEmailRecord {
+    mailer.store_record(MockEmailRecord {
         id: uuid::Uuid::new_v4(),
         to: recipient.to_string(),
         from: sender.to_string(),
@@ -124,10 +196,63 @@ pub async fn send_multipart_email<ExtraConfig>(
                 ),
         )?;
 
-    app.mailer
+    mailer
         .send(email)
         .await
         .map_err(|e| EmailError::MailerError(e.to_string()))?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::Router;
+
+    use super::{send_multipart_email, send_multipart_email_via, EmailError};
+    use crate::{
+        app::App,
+        config::EmailConfig,
+        database::migrations::Migrator,
+        tests::setup_test::{no_fixtures, setup_test_with_config},
+    };
+
+    fn test_router(app: App) -> Router {
+        Router::new().with_state(app)
+    }
+
+    #[tokio::test]
+    async fn test_named_transports_are_isolated() {
+        let test = setup_test_with_config::<Migrator>(test_router, no_fixtures, |config| {
+            for name in ["transactional", "bulk"] {
+                config
+                    .email_transports
+                    .insert(name.to_string(), EmailConfig::Mock);
+            }
+        })
+        .await;
+        let app = test.app();
+        let body = || ("Hi".to_string(), "<p>Hi</p>".to_string());
+
+        let (text, html) = body();
+        send_multipart_email_via(&app, "bulk", "reader@example.com", "Newsletter", text, html)
+            .await
+            .unwrap();
+        let (text, html) = body();
+        send_multipart_email(&app, "user@example.com", "Welcome", text, html)
+            .await
+            .unwrap();
+
+        let bulk = app.email_transport("bulk").unwrap();
+        let transactional = app.email_transport("transactional").unwrap();
+        assert_eq!(bulk.sent_count(), Some(1));
+        assert_eq!(bulk.records().unwrap()[0].subject, "Newsletter");
+        assert_eq!(transactional.sent_count(), Some(0));
+        assert_eq!(test.sent_emails().len(), 1);
+        assert_eq!(test.sent_emails()[0].subject, "Welcome");
+
+        let (text, html) = body();
+        let unknown =
+            send_multipart_email_via(&app, "marketing", "a@example.com", "Hi", text, html).await;
+        assert!(matches!(unknown, Err(EmailError::UnknownTransport(name)) if name == "marketing"));
+    }
+}
@@ -1,5 +1,7 @@
 use strum::{Display, EnumString};
 
+/// Where the app runs. Behavior that differs between environments is decided
+/// by the methods below rather than by comparing variants at each call site.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumString, Display)]
 #[strum(serialize_all = "snake_case")]
 pub enum Environment {
@@ -8,3 +10,64 @@ pub enum Environment {
     Production,
     Test,
 }
+
+impl Environment {
+    /// Emails are captured by the mock mailer whatever `[email]` says, so a
+    /// test run can never reach a real inbox. Applied by
+    /// [`Mailer::for_environment`](crate::mailer::Mailer::for_environment).
+    pub const fn should_use_mock_email(self) -> bool {
+        matches!(self, Self::Test)
+    }
+
+    /// Commands that wipe all data (`db reset`, `migrate reset`) may run.
+    pub const fn allows_destructive_ops(self) -> bool {
+        !matches!(self, Self::Production)
+    }
+
+    /// Log level used when `[tracing] log_level` is not set.
+    pub const fn default_log_level(self) -> &'static str {
+        match self {
+            Self::Development => "debug",
+            Self::Production => "info",
+            Self::Test => "warn",
+        }
+    }
+
+    /// Development helpers such as the mailbox preview are mounted.
+    pub const fn exposes_dev_routes(self) -> bool {
+        matches!(self, Self::Development)
+    }
+
+    /// Weak secrets (e.g. a short JWT secret) stop the server from starting
+    /// instead of only being logged.
+    pub const fn rejects_weak_secrets(self) -> bool {
+        matches!(self, Self::Production)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Environment;
+
+    #[test]
+    fn test_behavior_flags_per_environment() {
+        let flags = |env: Environment| {
+            (
+                env.should_use_mock_email(),
+                env.allows_destructive_ops(),
+                env.exposes_dev_routes(),
+                env.rejects_weak_secrets(),
+            )
+        };
+        assert_eq!(flags(Environment::Development), (false, true, true, false));
+        assert_eq!(flags(Environment::Test), (true, true, false, false));
+        assert_eq!(flags(Environment::Production), (false, false, false, true));
+    }
+
+    #[test]
+    fn test_default_log_level_per_environment() {
+        assert_eq!(Environment::Development.default_log_level(), "debug");
+        assert_eq!(Environment::Test.default_log_level(), "warn");
+        assert_eq!(Environment::Production.default_log_level(), "info");
+    }
+}
@@ -0,0 +1,556 @@
+//! In-process pub/sub for domain events such as `user.created`, so several
+//! parts of an app can react to something without the code raising it
+//! knowing about them.
+//!
+//! [`EventBus::dispatch`] runs this instance's subscribers. [`EventBus::publish`]
+//! stores the event and announces it through Postgres `NOTIFY` to every
+//! instance, this one included, whose listener then dispatches it.
+//!
+//! Docs: docs/src/content/docs/api/events.md
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, RwLock},
+};
+
+use futures_util::{Stream, StreamExt, TryStreamExt};
+
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Statement,
+};
+use serde_json::Value;
+use sqlx::postgres::PgListener;
+use tokio::{
+    sync::Semaphore,
+    task::{JoinHandle, JoinSet},
+    time::{sleep, Duration},
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{config::EventsConfig, database::models::event};
+
+/// Postgres channel events are published on.
+pub const EVENTS_CHANNEL: &str = "erno_events";
+
+/// Published events whose subscribers may run at once on one instance. The
+/// listener stops reading notifications while all are taken.
+const MAX_CONCURRENT_DISPATCHES: usize = 64;
+
+/// How often expired published events are deleted.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How far before a disconnect the catch-up looks back. An event's
+/// `created_at` is when its transaction started, so one that started up to
+/// this long before the disconnect and committed after it is still replayed.
+const REPLAY_MARGIN: Duration = Duration::from_secs(60);
+
+/// How many recently dispatched event ids are remembered, so the catch-up
+/// doesn't dispatch them a second time.
+const REMEMBERED_DISPATCHES: usize = 10_000;
+
+type EventHandler = Arc<dyn Fn(Value) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Subscribers by event name. Cheap to clone; clones share subscribers.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<RwLock<HashMap<String, Vec<EventHandler>>>>,
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let subscribers = self.subscribers.read().unwrap();
+        f.debug_struct("EventBus")
+            .field("events", &subscribers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl EventBus {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `handler` with the payload of every `event` dispatched on this
+    /// instance, including those published from other instances.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// app.event_bus.subscribe("user.created", move |payload| async move {
+    ///     let user_id = payload["user_id"].as_str().unwrap_or_default();
+    ///     info!("Welcome {user_id}");
+    /// });
+    /// ```
+    pub fn subscribe<F, Fut>(&self, event: impl Into<String>, handler: F)
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handler: EventHandler = Arc::new(move |payload| Box::pin(handler(payload)));
+        self.subscribers
+            .write()
+            .unwrap()
+            .entry(event.into())
+            .or_default()
+            .push(handler);
+    }
+
+    /// Run every subscriber of `event` on this instance and wait for them to
+    /// finish. Subscribers run concurrently, each on its own task, so one
+    /// that panics doesn't stop the others. Returns how many were run.
+    pub async fn dispatch(&self, event: &str, payload: Value) -> usize {
+        let handlers = self
+            .subscribers
+            .read()
+            .unwrap()
+            .get(event)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut tasks = JoinSet::new();
+        for handler in &handlers {
+            tasks.spawn(handler(payload.clone()));
+        }
+        while let Some(result) = tasks.join_next().await {
+            if let Err(e) = result {
+                error!(event, "Event subscriber failed: {e}");
+            }
+        }
+        handlers.len()
+    }
+
+    /// Send `event` to the subscribers of every instance, this one included,
+    /// and return the id it was stored under.
+    ///
+    /// The event is stored in the `event` table and its id sent through
+    /// `NOTIFY`, so when `db` is a transaction it goes out only once it
+    /// commits. A listener that reconnects dispatches the stored events it
+    /// missed, for up to `events.retention_seconds`. Instances that start
+    /// later don't receive it.
+    pub async fn publish<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        event: &str,
+        payload: Value,
+    ) -> Result<i64, DbErr> {
+        let row = db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "WITH published AS (INSERT INTO event (name, payload) VALUES ($1, $2) RETURNING id) \
+                 SELECT id, pg_notify($3, id::text) FROM published",
+                [event.into(), payload.into(), EVENTS_CHANNEL.into()],
+            ))
+            .await?
+            .ok_or_else(|| DbErr::Custom("Publishing the event returned no id".to_string()))?;
+        row.try_get("", "id")
+    }
+}
+
+/// Spawn [`start_event_listener`] in the background, unless cross-instance
+/// events are disabled in the config. Expired events are deleted either way,
+/// since this instance may still publish them.
+pub fn spawn_event_listener(
+    config: &EventsConfig,
+    db: DatabaseConnection,
+    bus: EventBus,
+) -> Option<JoinHandle<()>> {
+    tokio::spawn(prune_events(
+        db.clone(),
+        Duration::from_secs(config.retention_seconds),
+    ));
+    if !config.enabled {
+        info!("Events listener disabled, published events won't be received");
+        return None;
+    }
+    Some(tokio::spawn(start_event_listener(db, bus)))
+}
+
+/// Ids of the most recently dispatched events. The oldest is forgotten once
+/// there are more than `REMEMBERED_DISPATCHES`.
+#[derive(Debug, Default)]
+struct DispatchedIds {
+    ids: HashSet<i64>,
+    order: VecDeque<i64>,
+}
+
+impl DispatchedIds {
+    /// Remember `id`; returns false if it was dispatched already.
+    fn insert(&mut self, id: i64) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        self.order.push_back(id);
+        if self.order.len() > REMEMBERED_DISPATCHES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Listen on [`EVENTS_CHANNEL`] and dispatch every published event to the
+/// local subscribers.
+pub async fn start_event_listener(db: DatabaseConnection, bus: EventBus) {
+    // Both survive reconnects, so events published meanwhile can be caught
+    // up on without dispatching any twice
+    let mut dispatched = DispatchedIds::default();
+    let mut disconnected_at = None;
+    loop {
+        if let Err(e) = listen_loop(&db, &bus, &mut dispatched, &mut disconnected_at).await {
+            error!("Events listener error: {}, restarting in 5s...", e);
+        } else {
+            warn!("Events listener exited normally, restarting...");
+        }
+        // Kept from the first failure while reconnecting keeps failing
+        disconnected_at.get_or_insert_with(|| Utc::now().naive_utc());
+        sleep(Duration::from_secs(5)).await;
+    }
+}
+
+async fn listen_loop(
+    db: &DatabaseConnection,
+    bus: &EventBus,
+    dispatched: &mut DispatchedIds,
+    disconnected_at: &mut Option<NaiveDateTime>,
+) -> Result<(), DbErr> {
+    let mut listener = PgListener::connect_with(db.get_postgres_connection_pool())
+        .await
+        .map_err(|e| DbErr::Custom(e.to_string()))?;
+    listener
+        .listen(EVENTS_CHANNEL)
+        .await
+        .map_err(|e| DbErr::Custom(e.to_string()))?;
+    info!("Events listener started, listening on channel '{EVENTS_CHANNEL}'");
+
+    let slots = Arc::new(Semaphore::new(MAX_CONCURRENT_DISPATCHES));
+    // Already listening, so nothing published from here on can slip between
+    // the catch-up and the first notification. On first start there's
+    // nothing to catch up on.
+    if let Some(since) = *disconnected_at {
+        let margin = chrono::Duration::from_std(REPLAY_MARGIN).expect("the margin is in range");
+        let replayed = replay_events(db, bus, &slots, since - margin, dispatched).await?;
+        if replayed > 0 {
+            info!("Dispatched {replayed} event(s) published while the events listener was down");
+        }
+        *disconnected_at = None;
+    }
+
+    let notifications = listener
+        .into_stream()
+        .map_ok(|notification| notification.payload().to_string())
+        .map_err(|e| DbErr::Custom(e.to_string()));
+    dispatch_notifications(db, bus, &slots, notifications, dispatched).await
+}
+
+/// Dispatch the stored events created since `since` that aren't in
+/// `dispatched` yet, oldest first, and return how many there were.
+///
+/// Goes by time rather than id: a transaction that took a lower id can
+/// commit after one with a higher id was seen.
+async fn replay_events(
+    db: &DatabaseConnection,
+    bus: &EventBus,
+    slots: &Arc<Semaphore>,
+    since: NaiveDateTime,
+    dispatched: &mut DispatchedIds,
+) -> Result<usize, DbErr> {
+    let stored = event::Entity::find()
+        .filter(event::Column::CreatedAt.gte(since))
+        .order_by_asc(event::Column::Id)
+        .all(db)
+        .await?;
+    let mut replayed = 0;
+    for missed in stored
+        .into_iter()
+        .filter(|stored| dispatched.insert(stored.id))
+    {
+        spawn_dispatch(bus, slots, move |bus| async move {
+            bus.dispatch(&missed.name, missed.payload).await;
+        })
+        .await;
+        replayed += 1;
+    }
+    Ok(replayed)
+}
+
+/// Dispatch each notified event on its own task, so a slow subscriber
+/// doesn't hold up the events published after it. Events in `dispatched`
+/// were dispatched by the catch-up already and are skipped.
+async fn dispatch_notifications<S>(
+    db: &DatabaseConnection,
+    bus: &EventBus,
+    slots: &Arc<Semaphore>,
+    notifications: S,
+    dispatched: &mut DispatchedIds,
+) -> Result<(), DbErr>
+where
+    S: Stream<Item = Result<String, DbErr>>,
+{
+    let mut notifications = std::pin::pin!(notifications);
+    while let Some(message) = notifications.next().await {
+        let Ok(id) = message?.parse::<i64>() else {
+            warn!("Ignoring malformed event notification");
+            continue;
+        };
+        if !dispatched.insert(id) {
+            continue;
+        }
+
+        let db = db.clone();
+        spawn_dispatch(bus, slots, move |bus| async move {
+            match event::Entity::find_by_id(id).one(&db).await {
+                Ok(Some(stored)) => {
+                    let subscribers = bus.dispatch(&stored.name, stored.payload).await;
+                    debug!(
+                        event = stored.name,
+                        subscribers, "Dispatched published event"
+                    );
+                }
+                Ok(None) => warn!("Published event {id} was deleted before it was dispatched"),
+                Err(e) => error!("Failed to load published event {id}: {e}"),
+            }
+        })
+        .await;
+    }
+    Ok(())
+}
+
+/// Run `dispatch` on its own task once one of `slots` is free. The caller
+/// stops reading notifications while all `MAX_CONCURRENT_DISPATCHES` are
+/// taken.
+async fn spawn_dispatch<F, Fut>(bus: &EventBus, slots: &Arc<Semaphore>, dispatch: F)
+where
+    F: FnOnce(EventBus) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let slot = slots
+        .clone()
+        .acquire_owned()
+        .await
+        .expect("the semaphore is never closed");
+    let task = dispatch(bus.clone());
+    tokio::spawn(async move {
+        let _slot = slot;
+        task.await;
+    });
+}
+
+/// Delete stored events older than `retention`, once a minute.
+async fn prune_events(db: DatabaseConnection, retention: Duration) {
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let cutoff = chrono::Duration::from_std(retention)
+            .ok()
+            .and_then(|retention| chrono::Utc::now().naive_utc().checked_sub_signed(retention));
+        let Some(cutoff) = cutoff else {
+            continue;
+        };
+        match event::Entity::delete_many()
+            .filter(event::Column::CreatedAt.lt(cutoff))
+            .exec(&db)
+            .await
+        {
+            Ok(result) if result.rows_affected > 0 => {
+                debug!(
+                    "Deleted {} expired published event(s)",
+                    result.rows_affected
+                );
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to delete expired published events: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use chrono::Utc;
+    use sea_orm::{sea_query::Expr, ColumnTrait, EntityTrait, QueryFilter};
+    use tokio::sync::Semaphore;
+
+    use super::{
+        dispatch_notifications, replay_events, DispatchedIds, EventBus, MAX_CONCURRENT_DISPATCHES,
+        REMEMBERED_DISPATCHES, REPLAY_MARGIN,
+    };
+    use crate::{
+        database::{migrations::Migrator, models::event},
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+    };
+
+    #[tokio::test]
+    async fn test_dispatch_runs_every_subscriber_of_the_event() {
+        let bus = EventBus::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        for subscriber in ["mailer", "analytics"] {
+            let received = received.clone();
+            bus.subscribe("user.created", move |payload| {
+                let received = received.clone();
+                async move {
+                    received
+                        .lock()
+                        .unwrap()
+                        .push(format!("{subscriber}: {}", payload["id"]));
+                }
+            });
+        }
+        let other = received.clone();
+        bus.subscribe("user.deleted", move |_| {
+            let other = other.clone();
+            async move { other.lock().unwrap().push("deleted".to_string()) }
+        });
+
+        assert_eq!(bus.dispatch("user.created", json!({ "id": 7 })).await, 2);
+
+        let mut received = received.lock().unwrap().clone();
+        received.sort();
+        assert_eq!(received, ["analytics: 7", "mailer: 7"]);
+        assert_eq!(bus.dispatch("user.renamed", json!({})).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_subscriber_does_not_stop_the_others() {
+        let bus = EventBus::new();
+        let ran = Arc::new(Mutex::new(false));
+        bus.subscribe("order.paid", |_| async { panic!("subscriber bug") });
+        let flag = ran.clone();
+        bus.subscribe("order.paid", move |_| {
+            let flag = flag.clone();
+            async move { *flag.lock().unwrap() = true }
+        });
+
+        assert_eq!(bus.dispatch("order.paid", json!({})).await, 2);
+        assert!(*ran.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_does_not_hold_up_later_events() {
+        let bus = EventBus::new();
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+        let released = Arc::new(tokio::sync::Notify::new());
+        let gate = released.clone();
+        // Waits for an event published after it
+        bus.subscribe("report.requested", move |_| {
+            let gate = gate.clone();
+            async move { gate.notified().await }
+        });
+        let done_tx = Arc::new(Mutex::new(Some(done_tx)));
+        bus.subscribe("report.cancelled", move |_| {
+            let released = released.clone();
+            let done_tx = done_tx.clone();
+            async move {
+                released.notify_one();
+                if let Some(done_tx) = done_tx.lock().unwrap().take() {
+                    let _ = done_tx.send(());
+                }
+            }
+        });
+
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let mut messages = Vec::new();
+        for name in ["report.requested", "report.cancelled"] {
+            let id = bus.publish(&test.db, name, json!({})).await.unwrap();
+            messages.push(Ok(id.to_string()));
+        }
+        let slots = Arc::new(Semaphore::new(MAX_CONCURRENT_DISPATCHES));
+        let dispatched = async {
+            let notifications = futures_util::stream::iter(messages);
+            dispatch_notifications(
+                &test.db,
+                &bus,
+                &slots,
+                notifications,
+                &mut DispatchedIds::default(),
+            )
+            .await
+            .unwrap();
+            done_rx.await.unwrap();
+        };
+
+        tokio::time::timeout(std::time::Duration::from_secs(5), dispatched)
+            .await
+            .expect("the second event was held up");
+    }
+
+    #[tokio::test]
+    async fn test_events_missed_while_disconnected_are_replayed_once() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let bus = EventBus::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        bus.subscribe("invoice.sent", move |payload| {
+            let log = log.clone();
+            async move { log.lock().unwrap().push(payload["n"].as_i64().unwrap()) }
+        });
+        let slots = Arc::new(Semaphore::new(MAX_CONCURRENT_DISPATCHES));
+        let mut dispatched = DispatchedIds::default();
+
+        // Received before the listener dropped
+        let seen = bus
+            .publish(&test.db, "invoice.sent", json!({ "n": 1 }))
+            .await
+            .unwrap();
+        let notifications =
+            futures_util::stream::iter([Ok(seen.to_string()), Ok("bogus".to_string())]);
+        dispatch_notifications(&test.db, &bus, &slots, notifications, &mut dispatched)
+            .await
+            .unwrap();
+        let disconnected_at = Utc::now().naive_utc();
+
+        // Committed while disconnected: one whose transaction took a lower
+        // id than the one already seen, and one after it
+        let late = bus
+            .publish(&test.db, "invoice.sent", json!({ "n": 2 }))
+            .await
+            .unwrap();
+        event::Entity::update_many()
+            .col_expr(event::Column::Id, Expr::value(-late))
+            .filter(event::Column::Id.eq(late))
+            .exec(&test.db)
+            .await
+            .unwrap();
+        let next = bus
+            .publish(&test.db, "invoice.sent", json!({ "n": 3 }))
+            .await
+            .unwrap();
+
+        let since = disconnected_at - chrono::Duration::from_std(REPLAY_MARGIN).unwrap();
+        let replayed = replay_events(&test.db, &bus, &slots, since, &mut dispatched)
+            .await
+            .unwrap();
+        assert_eq!(replayed, 2);
+
+        // A notification for a replayed event is skipped
+        let notifications = futures_util::stream::iter([Ok(next.to_string())]);
+        dispatch_notifications(&test.db, &bus, &slots, notifications, &mut dispatched)
+            .await
+            .unwrap();
+
+        // Wait for the spawned dispatches to finish
+        let _ = slots
+            .acquire_many(MAX_CONCURRENT_DISPATCHES as u32)
+            .await
+            .unwrap();
+        let mut received = received.lock().unwrap().clone();
+        received.sort_unstable();
+        assert_eq!(received, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_dispatched_ids_forget_the_oldest_past_the_limit() {
+        let mut dispatched = DispatchedIds::default();
+        for id in 0..=REMEMBERED_DISPATCHES as i64 {
+            assert!(dispatched.insert(id));
+        }
+        assert!(!dispatched.insert(REMEMBERED_DISPATCHES as i64));
+        assert!(dispatched.insert(0));
+    }
+}
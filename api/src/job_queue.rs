@@ -1,6 +1,9 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use crate::jobs::Job;
+use crate::{jobs::Job, log_context::LogContext};
 
 /// Job queue that can be either real (database) or mock (in-memory) for testing
 #[derive(Clone, Debug)]
@@ -14,8 +17,77 @@ pub enum JobQueue {
 /// A job that was added (captured by mock queue)
 #[derive(Debug, Clone)]
 pub struct EnqueuedJob {
+    /// Id returned to the caller; no job row exists with it
+    pub id: uuid::Uuid,
     pub job_type: String,
     pub arguments: serde_json::Value,
+    /// Deduplication key passed to [`JobQueue::add_throttled`]
+    pub dedup_key: Option<String>,
+    /// Key passed to [`JobQueue::add_unique`]
+    pub unique_key: Option<String>,
+    /// Callback URL passed to [`JobQueue::add_with_callback`]
+    pub callback_url: Option<String>,
+    /// Logging context current when the job was added
+    pub log_context: Option<LogContext>,
+    /// Priority passed to [`JobQueue::add_with_priority`], 0 otherwise
+    pub priority: i32,
+    /// Earliest execution time set by [`JobQueue::add_delayed`] or
+    /// [`JobQueue::add_at`], `None` to run immediately
+    pub run_at: Option<chrono::NaiveDateTime>,
+    pub enqueued_at: chrono::NaiveDateTime,
+}
+
+/// Optional columns of a newly inserted job
+#[derive(Default)]
+struct InsertOptions {
+    dedup_key: Option<String>,
+    unique_key: Option<String>,
+    callback_url: Option<String>,
+    priority: i32,
+    run_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Rows per `INSERT` in [`JobQueue::add_many`], well below Postgres' limit of
+/// 65535 bind parameters per statement
+const ADD_MANY_CHUNK_SIZE: usize = 1000;
+
+/// Active model of a new pending job.
+fn new_job_model(
+    job_id: uuid::Uuid,
+    job_type: &str,
+    arguments: serde_json::Value,
+    compress: bool,
+    options: InsertOptions,
+    log_context: Option<LogContext>,
+) -> crate::database::models::job::ActiveModel {
+    use crate::database::models::{job, job_status::JobStatus};
+
+    let (arguments, compressed_arguments) = if compress {
+        let compressed = crate::jobs::compression::compress(&arguments);
+        (serde_json::Value::Null, Some(compressed))
+    } else {
+        (arguments, None)
+    };
+
+    job::ActiveModel {
+        id: sea_orm::Set(job_id),
+        created_at: sea_orm::NotSet,
+        updated_at: sea_orm::NotSet,
+        r#type: sea_orm::Set(job_type.to_string()),
+        arguments: sea_orm::Set(arguments),
+        status: sea_orm::Set(JobStatus::Pending),
+        retry_count: sea_orm::Set(0),
+        next_execution_at: sea_orm::Set(options.run_at),
+        dedup_key: sea_orm::Set(options.dedup_key),
+        callback_url: sea_orm::Set(options.callback_url),
+        log_context: sea_orm::Set(
+            log_context.map(|context| serde_json::to_value(context).unwrap()),
+        ),
+        compressed_arguments: sea_orm::Set(compressed_arguments),
+        priority: sea_orm::Set(options.priority),
+        unique_key: sea_orm::Set(options.unique_key),
+        cancel_requested: sea_orm::Set(false),
+    }
 }
 
 impl JobQueue {
@@ -34,40 +106,487 @@ impl JobQueue {
         &self,
         db: &sea_orm::DatabaseConnection,
         arguments: J::Arguments,
-    ) -> Result<(), sea_orm::DbErr>
+    ) -> Result<uuid::Uuid, sea_orm::DbErr>
     where
         J: Job<ExtraConfig>,
         J::Arguments: serde::Serialize,
     {
+        self.insert(
+            db,
+            J::name(),
+            serde_json::to_value(arguments).unwrap(),
+            J::compress_arguments(),
+            InsertOptions::default(),
+        )
+        .await
+        .map(|id| id.expect("only jobs with a unique key are skipped"))
+    }
+
+    /// Schedule one job of type `J` per element of `arguments`, returning
+    /// their ids in the same order.
+    ///
+    /// The database queue inserts them with one multi-row `INSERT` (split
+    /// into chunks of 1000 rows in a single transaction for larger batches),
+    /// so workers are woken once per statement rather than once per job.
+    pub async fn add_many<J, ExtraConfig>(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        arguments: Vec<J::Arguments>,
+    ) -> Result<Vec<uuid::Uuid>, sea_orm::DbErr>
+    where
+        J: Job<ExtraConfig>,
+        J::Arguments: serde::Serialize,
+    {
+        let log_context = LogContext::current().filter(|context| !context.is_empty());
+        let arguments: Vec<serde_json::Value> = arguments
+            .into_iter()
+            .map(|arguments| serde_json::to_value(arguments).unwrap())
+            .collect();
+        let ids: Vec<uuid::Uuid> = arguments.iter().map(|_| uuid::Uuid::now_v7()).collect();
+
+        match self {
+            Self::Database => {
+                use crate::database::models::job;
+                use sea_orm::{ActiveModelBehavior, EntityTrait, TransactionTrait};
+
+                let mut models = Vec::with_capacity(ids.len());
+                for (&job_id, arguments) in ids.iter().zip(arguments) {
+                    let model = new_job_model(
+                        job_id,
+                        J::name(),
+                        arguments,
+                        J::compress_arguments(),
+                        InsertOptions::default(),
+                        log_context.clone(),
+                    );
+                    models.push(model.before_save(db, true).await?);
+                }
+
+                match models.len() {
+                    0 => {}
+                    len if len <= ADD_MANY_CHUNK_SIZE => {
+                        job::Entity::insert_many(models)
+                            .exec_without_returning(db)
+                            .await?;
+                    }
+                    _ => {
+                        let txn = db.begin().await?;
+                        let mut models = models.into_iter().peekable();
+                        while models.peek().is_some() {
+                            let chunk: Vec<_> = models.by_ref().take(ADD_MANY_CHUNK_SIZE).collect();
+                            job::Entity::insert_many(chunk)
+                                .exec_without_returning(&txn)
+                                .await?;
+                        }
+                        txn.commit().await?;
+                    }
+                }
+            }
+            Self::Mock(scheduled) => {
+                let enqueued_at = chrono::Utc::now().naive_utc();
+                scheduled
+                    .lock()
+                    .unwrap()
+                    .extend(
+                        ids.iter()
+                            .zip(arguments)
+                            .map(|(&id, arguments)| EnqueuedJob {
+                                id,
+                                job_type: J::name().to_string(),
+                                arguments,
+                                dedup_key: None,
+                                unique_key: None,
+                                callback_url: None,
+                                log_context: log_context.clone(),
+                                priority: 0,
+                                run_at: None,
+                                enqueued_at,
+                            }),
+                    );
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Schedule a job that workers claim ahead of lower-priority ones.
+    ///
+    /// Pending jobs are claimed by `priority` descending, then oldest first,
+    /// so urgent work isn't stuck behind a backlog. Jobs added any other way
+    /// have priority 0; negative values go behind them.
+    pub async fn add_with_priority<J, ExtraConfig>(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        arguments: J::Arguments,
+        priority: i32,
+    ) -> Result<uuid::Uuid, sea_orm::DbErr>
+    where
+        J: Job<ExtraConfig>,
+        J::Arguments: serde::Serialize,
+    {
+        self.insert(
+            db,
+            J::name(),
+            serde_json::to_value(arguments).unwrap(),
+            J::compress_arguments(),
+            InsertOptions {
+                priority,
+                ..Default::default()
+            },
+        )
+        .await
+        .map(|id| id.expect("only jobs with a unique key are skipped"))
+    }
+
+    /// Schedule a job that no worker claims until `delay` has passed,
+    /// e.g. "send this reminder in 2 hours".
+    ///
+    /// Fails with [`DbErr::Custom`](sea_orm::DbErr::Custom) if the run time
+    /// would be past the latest representable date.
+    pub async fn add_delayed<J, ExtraConfig>(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        arguments: J::Arguments,
+        delay: Duration,
+    ) -> Result<uuid::Uuid, sea_orm::DbErr>
+    where
+        J: Job<ExtraConfig>,
+        J::Arguments: serde::Serialize,
+    {
+        let run_at = chrono::Duration::from_std(delay)
+            .ok()
+            .and_then(|delay| chrono::Utc::now().naive_utc().checked_add_signed(delay))
+            .ok_or_else(|| sea_orm::DbErr::Custom(format!("Job delay of {delay:?} is too long")))?;
+        self.add_at::<J, ExtraConfig>(db, arguments, run_at).await
+    }
+
+    /// Schedule a job that no worker claims before `run_at` (UTC). A time in
+    /// the past makes it due immediately.
+    pub async fn add_at<J, ExtraConfig>(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        arguments: J::Arguments,
+        run_at: chrono::NaiveDateTime,
+    ) -> Result<uuid::Uuid, sea_orm::DbErr>
+    where
+        J: Job<ExtraConfig>,
+        J::Arguments: serde::Serialize,
+    {
+        self.insert(
+            db,
+            J::name(),
+            serde_json::to_value(arguments).unwrap(),
+            J::compress_arguments(),
+            InsertOptions {
+                run_at: Some(run_at),
+                ..Default::default()
+            },
+        )
+        .await
+        .map(|id| id.expect("only jobs with a unique key are skipped"))
+    }
+
+    /// Schedule a job and have its outcome POSTed to `callback_url` once it
+    /// completes or fails permanently.
+    ///
+    /// Delivery runs as a [`DeliverJobCallbackJob`](crate::jobs::deliver_job_callback_job::DeliverJobCallbackJob),
+    /// so an unreachable endpoint is retried like any other job.
+    pub async fn add_with_callback<J, ExtraConfig>(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        arguments: J::Arguments,
+        callback_url: impl Into<String>,
+    ) -> Result<uuid::Uuid, sea_orm::DbErr>
+    where
+        J: Job<ExtraConfig>,
+        J::Arguments: serde::Serialize,
+    {
+        self.insert(
+            db,
+            J::name(),
+            serde_json::to_value(arguments).unwrap(),
+            J::compress_arguments(),
+            InsertOptions {
+                callback_url: Some(callback_url.into()),
+                ..Default::default()
+            },
+        )
+        .await
+        .map(|id| id.expect("only jobs with a unique key are skipped"))
+    }
+
+    /// Schedule a job unless one of the same type holding the same
+    /// `unique_key` is still pending, waiting for a retry or running.
+    ///
+    /// Enforced by a partial unique index, so two racing callers can't both
+    /// enqueue. Once the job finishes, the key is free again.
+    ///
+    /// Returns `true` if the job was enqueued, `false` if it was deduplicated.
+    pub async fn add_unique<J, ExtraConfig>(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        arguments: J::Arguments,
+        unique_key: impl Into<String>,
+    ) -> Result<bool, sea_orm::DbErr>
+    where
+        J: Job<ExtraConfig>,
+        J::Arguments: serde::Serialize,
+    {
+        let unique_key = unique_key.into();
+        let job_id = self
+            .insert(
+                db,
+                J::name(),
+                serde_json::to_value(arguments).unwrap(),
+                J::compress_arguments(),
+                InsertOptions {
+                    unique_key: Some(unique_key.clone()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        if job_id.is_none() {
+            tracing::debug!(
+                job_type = J::name(),
+                unique_key,
+                "⏭️ Skipping duplicate unique job"
+            );
+        }
+        Ok(job_id.is_some())
+    }
+
+    /// Schedule a job unless one of the same type with the same `key` was
+    /// created within the last `within`.
+    ///
+    /// Unlike uniqueness, this looks at every job created in the window
+    /// regardless of status, so a completed job still suppresses new ones
+    /// (e.g. "rebuild this cache at most once per hour"). The check and the
+    /// insert are not atomic; concurrent callers may both enqueue.
+    ///
+    /// Only jobs still in the table count, so a window longer than the
+    /// cleanup retention of finished jobs throttles no further back than that
+    /// retention. A window reaching before the earliest representable time
+    /// covers every job.
+    ///
+    /// Returns `true` if the job was enqueued, `false` if it was skipped.
+    pub async fn add_throttled<J, ExtraConfig>(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        arguments: J::Arguments,
+        key: impl Into<String>,
+        within: Duration,
+    ) -> Result<bool, sea_orm::DbErr>
+    where
+        J: Job<ExtraConfig>,
+        J::Arguments: serde::Serialize,
+    {
+        let key = key.into();
+        let since = chrono::Duration::from_std(within)
+            .ok()
+            .and_then(|within| chrono::Utc::now().naive_utc().checked_sub_signed(within))
+            .unwrap_or(chrono::NaiveDateTime::MIN);
+
+        let recent = match self {
+            Self::Database => {
+                use crate::database::models::job;
+                use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+
+                job::Entity::find()
+                    .filter(job::Column::Type.eq(J::name()))
+                    .filter(job::Column::DedupKey.eq(key.as_str()))
+                    .filter(job::Column::CreatedAt.gte(since))
+                    .count(db)
+                    .await?
+                    > 0
+            }
+            Self::Mock(scheduled) => scheduled.lock().unwrap().iter().any(|job| {
+                job.job_type == J::name()
+                    && job.dedup_key.as_deref() == Some(key.as_str())
+                    && job.enqueued_at >= since
+            }),
+        };
+
+        if recent {
+            tracing::debug!(job_type = J::name(), key, "⏭️ Skipping throttled job");
+            return Ok(false);
+        }
+
+        self.insert(
+            db,
+            J::name(),
+            serde_json::to_value(arguments).unwrap(),
+            J::compress_arguments(),
+            InsertOptions {
+                dedup_key: Some(key),
+                ..Default::default()
+            },
+        )
+        .await?;
+        Ok(true)
+    }
+
+    /// Cancel a job.
+    ///
+    /// Marks a `Pending` or `PendingRetry` job as `Cancelled` so no worker
+    /// claims it. For a `Running` job this only requests cancellation: the job
+    /// sees it through [`cancellation`](crate::jobs::cancellation) and, if it
+    /// stops early, ends up `Cancelled`. Returns `false` if the job doesn't
+    /// exist or is already finished. The mock queue drops the job from its
+    /// recorded jobs.
+    pub async fn cancel(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        job_id: uuid::Uuid,
+    ) -> Result<bool, sea_orm::DbErr> {
         match self {
             Self::Database => {
-                // Real implementation - insert into database
                 use crate::database::models::{job, job_status::JobStatus};
+                use sea_orm::{sea_query::Expr, ColumnTrait, EntityTrait, QueryFilter};
+
+                // The status filter makes this atomic with respect to a worker claiming the job
+                let result = job::Entity::update_many()
+                    .col_expr(job::Column::Status, Expr::value(JobStatus::Cancelled))
+                    .filter(job::Column::Id.eq(job_id))
+                    .filter(
+                        job::Column::Status.is_in([JobStatus::Pending, JobStatus::PendingRetry]),
+                    )
+                    .exec(db)
+                    .await?;
+                if result.rows_affected == 1 {
+                    return Ok(true);
+                }
+
+                let result = job::Entity::update_many()
+                    .col_expr(job::Column::CancelRequested, Expr::value(true))
+                    .filter(job::Column::Id.eq(job_id))
+                    .filter(job::Column::Status.eq(JobStatus::Running))
+                    .exec(db)
+                    .await?;
+                Ok(result.rows_affected == 1)
+            }
+            Self::Mock(scheduled) => {
+                let mut scheduled = scheduled.lock().unwrap();
+                let recorded = scheduled.len();
+                scheduled.retain(|job| job.id != job_id);
+                Ok(scheduled.len() < recorded)
+            }
+        }
+    }
+
+    /// Permanently remove a job that is not currently running, whatever its
+    /// status, along with its execution history.
+    ///
+    /// Meant for incident response, e.g. dropping a poison-pill job that keeps
+    /// failing. Returns `false` if the job doesn't exist or is running; a
+    /// running job can't be interrupted, so wait for it to finish first. The
+    /// mock queue drops the job from its recorded jobs.
+    pub async fn delete(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        job_id: uuid::Uuid,
+    ) -> Result<bool, sea_orm::DbErr> {
+        match self {
+            Self::Database => {
+                use crate::database::models::{job, job_status::JobStatus};
+                use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+                // The status filter keeps the row of a job a worker is executing
+                let result = job::Entity::delete_many()
+                    .filter(job::Column::Id.eq(job_id))
+                    .filter(job::Column::Status.ne(JobStatus::Running))
+                    .exec(db)
+                    .await?;
+                Ok(result.rows_affected == 1)
+            }
+            Self::Mock(scheduled) => {
+                let mut scheduled = scheduled.lock().unwrap();
+                let recorded = scheduled.len();
+                scheduled.retain(|job| job.id != job_id);
+                Ok(scheduled.len() < recorded)
+            }
+        }
+    }
+
+    async fn insert(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        job_type: &str,
+        arguments: serde_json::Value,
+        compress: bool,
+        options: InsertOptions,
+    ) -> Result<Option<uuid::Uuid>, sea_orm::DbErr> {
+        let log_context = LogContext::current().filter(|context| !context.is_empty());
+
+        match self {
+            Self::Database => {
+                // Real implementation - insert into database
+                use crate::database::models::job;
                 use sea_orm::ActiveModelTrait;
 
-                let job_id = uuid::Uuid::new_v4();
-
-                let job_model = job::ActiveModel {
-                    id: sea_orm::Set(job_id),
-                    created_at: sea_orm::NotSet,
-                    updated_at: sea_orm::NotSet,
-                    r#type: sea_orm::Set(J::name().to_string()),
-                    arguments: sea_orm::Set(serde_json::to_value(arguments).unwrap()),
-                    status: sea_orm::Set(JobStatus::Pending),
-                    retry_count: sea_orm::Set(0),
-                    next_execution_at: sea_orm::Set(None),
-                };
+                // Time-ordered ids keep inserts clustered at the end of the primary key index
+                let job_id = uuid::Uuid::now_v7();
+                let has_unique_key = options.unique_key.is_some();
+                let job_model =
+                    new_job_model(job_id, job_type, arguments, compress, options, log_context);
+
+                if !has_unique_key {
+                    job_model.insert(db).await?;
+                    return Ok(Some(job_id));
+                }
 
-                job_model.insert(db).await?;
-                Ok(())
+                // Skip the row if an unfinished job holds the key; the index
+                // predicate must match idx_job_type_unique_key's
+                use sea_orm::{
+                    sea_query::{Expr, OnConflict},
+                    ActiveModelBehavior, EntityTrait,
+                };
+                let job_model = job_model.before_save(db, true).await?;
+                let inserted = job::Entity::insert(job_model)
+                    .on_conflict(
+                        OnConflict::columns([job::Column::Type, job::Column::UniqueKey])
+                            .target_and_where(Expr::cust(
+                                "unique_key IS NOT NULL AND status IN ('pending', 'pending_retry', 'running')",
+                            ))
+                            .do_nothing()
+                            .to_owned(),
+                    )
+                    .exec_without_returning(db)
+                    .await?;
+                Ok((inserted == 1).then_some(job_id))
             }
             Self::Mock(scheduled) => {
                 // Mock implementation - capture the job
-                scheduled.lock().unwrap().push(EnqueuedJob {
-                    job_type: J::name().to_string(),
-                    arguments: serde_json::to_value(arguments).unwrap(),
+                let InsertOptions {
+                    dedup_key,
+                    unique_key,
+                    callback_url,
+                    priority,
+                    run_at,
+                } = options;
+                let mut scheduled = scheduled.lock().unwrap();
+                // The mock never runs jobs, so every recorded one still holds its key
+                if unique_key.is_some()
+                    && scheduled
+                        .iter()
+                        .any(|job| job.job_type == job_type && job.unique_key == unique_key)
+                {
+                    return Ok(None);
+                }
+                let job_id = uuid::Uuid::now_v7();
+                scheduled.push(EnqueuedJob {
+                    id: job_id,
+                    job_type: job_type.to_string(),
+                    arguments,
+                    dedup_key,
+                    unique_key,
+                    callback_url,
+                    log_context,
+                    priority,
+                    run_at,
+                    enqueued_at: chrono::Utc::now().naive_utc(),
                 });
-                Ok(())
+                Ok(Some(job_id))
             }
         }
     }
@@ -103,3 +622,320 @@ impl JobQueue {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+
+    use super::JobQueue;
+    use crate::{
+        app::App,
+        database::{
+            migrations::Migrator,
+            models::{job, job_status::JobStatus},
+        },
+        jobs::{Job, JobError},
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+    };
+
+    struct RebuildCacheJob;
+
+    impl Job for RebuildCacheJob {
+        type Arguments = ();
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            Ok(())
+        }
+
+        fn name() -> &'static str {
+            "rebuild_cache_throttle_test"
+        }
+    }
+
+    struct BatchJob;
+
+    impl Job for BatchJob {
+        type Arguments = usize;
+
+        async fn execute(_app: &App, _arguments: usize) -> Result<(), JobError> {
+            Ok(())
+        }
+
+        fn name() -> &'static str {
+            "uuid_v7_batch_test"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_job_ids_increase_within_batch() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let db = &test.db;
+        let queue = JobQueue::database();
+
+        for n in 0..20 {
+            queue.add::<BatchJob, ()>(db, n).await.unwrap();
+        }
+
+        let jobs = job::Entity::find()
+            .filter(job::Column::Type.eq(BatchJob::name()))
+            .order_by_asc(job::Column::Id)
+            .all(db)
+            .await
+            .unwrap();
+        let batch = &jobs[jobs.len() - 20..];
+
+        // Sorting by id yields the jobs in the order they were enqueued
+        assert!(batch.iter().all(|job| job.id.get_version_num() == 7));
+        let order: Vec<_> = batch.iter().map(|job| job.arguments.clone()).collect();
+        let expected: Vec<_> = (0..20).map(serde_json::Value::from).collect();
+        assert_eq!(order, expected);
+    }
+
+    #[tokio::test]
+    async fn test_add_throttled_skips_within_window() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let db = &test.db;
+        let queue = JobQueue::database();
+        let key = uuid::Uuid::new_v4().to_string();
+        let hour = Duration::from_secs(3600);
+
+        assert!(queue
+            .add_throttled::<RebuildCacheJob, ()>(db, (), &key, hour)
+            .await
+            .unwrap());
+        assert!(!queue
+            .add_throttled::<RebuildCacheJob, ()>(db, (), &key, hour)
+            .await
+            .unwrap());
+
+        // Move the first job outside the window
+        job::Entity::update_many()
+            .col_expr(
+                job::Column::CreatedAt,
+                sea_orm::sea_query::Expr::value(
+                    chrono::Utc::now().naive_utc() - chrono::Duration::hours(2),
+                ),
+            )
+            .filter(job::Column::DedupKey.eq(key.as_str()))
+            .exec(db)
+            .await
+            .unwrap();
+
+        assert!(queue
+            .add_throttled::<RebuildCacheJob, ()>(db, (), &key, hour)
+            .await
+            .unwrap());
+
+        let count = job::Entity::find()
+            .filter(job::Column::DedupKey.eq(key.as_str()))
+            .count(db)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_add_throttled_with_endless_window_does_not_overflow() {
+        let queue = JobQueue::mock();
+        let db = sea_orm::DatabaseConnection::Disconnected;
+
+        assert!(queue
+            .add_throttled::<RebuildCacheJob, ()>(&db, (), "all", Duration::MAX)
+            .await
+            .unwrap());
+        assert!(!queue
+            .add_throttled::<RebuildCacheJob, ()>(&db, (), "all", Duration::MAX)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_add_unique_skips_while_job_is_unfinished() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let db = &test.db;
+        let queue = JobQueue::database();
+        let key = uuid::Uuid::new_v4().to_string();
+
+        assert!(queue
+            .add_unique::<RebuildCacheJob, ()>(db, (), &key)
+            .await
+            .unwrap());
+        assert!(!queue
+            .add_unique::<RebuildCacheJob, ()>(db, (), &key)
+            .await
+            .unwrap());
+
+        // A finished job releases its key
+        job::Entity::update_many()
+            .col_expr(
+                job::Column::Status,
+                sea_orm::sea_query::Expr::value(JobStatus::Completed),
+            )
+            .filter(job::Column::UniqueKey.eq(key.as_str()))
+            .exec(db)
+            .await
+            .unwrap();
+
+        assert!(queue
+            .add_unique::<RebuildCacheJob, ()>(db, (), &key)
+            .await
+            .unwrap());
+
+        let count = job::Entity::find()
+            .filter(job::Column::UniqueKey.eq(key.as_str()))
+            .count(db)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_mock_queue_dedups_unique_jobs() {
+        let queue = JobQueue::mock();
+        let db = sea_orm::DatabaseConnection::Disconnected;
+
+        assert!(queue
+            .add_unique::<RebuildCacheJob, ()>(&db, (), "stats:1")
+            .await
+            .unwrap());
+        assert!(!queue
+            .add_unique::<RebuildCacheJob, ()>(&db, (), "stats:1")
+            .await
+            .unwrap());
+        assert!(queue
+            .add_unique::<RebuildCacheJob, ()>(&db, (), "stats:2")
+            .await
+            .unwrap());
+
+        assert_eq!(queue.enqueued_jobs().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_add_returns_the_job_id() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let db = &test.db;
+
+        let id = JobQueue::database()
+            .add::<BatchJob, ()>(db, 7)
+            .await
+            .unwrap();
+
+        let job = job::Entity::find_by_id(id).one(db).await.unwrap().unwrap();
+        assert_eq!(job.r#type, BatchJob::name());
+        assert_eq!(job.arguments, serde_json::json!(7));
+    }
+
+    #[tokio::test]
+    async fn test_mock_queue_records_job_ids() {
+        let queue = JobQueue::mock();
+        let db = sea_orm::DatabaseConnection::Disconnected;
+
+        let first = queue.add::<BatchJob, ()>(&db, 1).await.unwrap();
+        let second = queue.add::<BatchJob, ()>(&db, 2).await.unwrap();
+        assert_ne!(first, second);
+
+        let ids: Vec<_> = queue
+            .enqueued_jobs()
+            .unwrap()
+            .iter()
+            .map(|job| job.id)
+            .collect();
+        assert_eq!(ids, [first, second]);
+
+        assert!(queue.cancel(&db, first).await.unwrap());
+        assert!(!queue.cancel(&db, first).await.unwrap());
+        assert_eq!(queue.enqueued_jobs().unwrap()[0].id, second);
+    }
+
+    #[tokio::test]
+    async fn test_mock_queue_records_delay() {
+        let queue = JobQueue::mock();
+        let before = chrono::Utc::now().naive_utc();
+
+        let db = sea_orm::DatabaseConnection::Disconnected;
+        queue
+            .add_delayed::<RebuildCacheJob, ()>(&db, (), Duration::from_secs(2 * 60 * 60))
+            .await
+            .unwrap();
+
+        let delay = queue.enqueued_jobs().unwrap()[0].run_at.unwrap() - before;
+        assert!(delay >= chrono::Duration::hours(2));
+        assert!(delay < chrono::Duration::hours(2) + chrono::Duration::seconds(5));
+    }
+
+    #[tokio::test]
+    async fn test_add_delayed_rejects_a_delay_out_of_range() {
+        let queue = JobQueue::mock();
+
+        let db = sea_orm::DatabaseConnection::Disconnected;
+        let result = queue
+            .add_delayed::<RebuildCacheJob, ()>(&db, (), Duration::MAX)
+            .await;
+
+        assert!(matches!(result, Err(sea_orm::DbErr::Custom(_))));
+        assert!(queue.enqueued_jobs().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_add_many_inserts_every_job_in_order() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let db = &test.db;
+
+        let ids = JobQueue::database()
+            .add_many::<BatchJob, ()>(db, vec![10, 11, 12])
+            .await
+            .unwrap();
+        assert_eq!(ids.len(), 3);
+
+        let jobs = job::Entity::find()
+            .filter(job::Column::Id.is_in(ids.clone()))
+            .order_by_asc(job::Column::Id)
+            .all(db)
+            .await
+            .unwrap();
+        let stored: Vec<_> = jobs
+            .iter()
+            .map(|job| (job.id, job.arguments.clone()))
+            .collect();
+        let expected: Vec<_> = ids
+            .iter()
+            .zip(10..)
+            .map(|(&id, n)| (id, serde_json::json!(n)))
+            .collect();
+        assert_eq!(stored, expected);
+        assert!(jobs.iter().all(|job| job.status == JobStatus::Pending));
+
+        assert!(JobQueue::database()
+            .add_many::<BatchJob, ()>(db, vec![])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mock_queue_records_add_many() {
+        let queue = JobQueue::mock();
+        let db = sea_orm::DatabaseConnection::Disconnected;
+
+        let ids = queue
+            .add_many::<BatchJob, ()>(&db, vec![1, 2])
+            .await
+            .unwrap();
+
+        let recorded: Vec<_> = queue
+            .enqueued_jobs()
+            .unwrap()
+            .iter()
+            .map(|job| (job.id, job.arguments.clone()))
+            .collect();
+        assert_eq!(
+            recorded,
+            [
+                (ids[0], serde_json::json!(1)),
+                (ids[1], serde_json::json!(2))
+            ]
+        );
+    }
+}
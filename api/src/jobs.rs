@@ -1,18 +1,29 @@
 //! Docs: docs/src/content/docs/api/jobs.md
 mod advisory_lock;
+pub mod cancellation;
+pub(crate) mod compression;
+pub mod cron_preview;
+pub mod deliver_job_callback_job;
 pub mod job_registry;
 pub mod job_result;
 pub mod job_supervisor;
+pub mod output;
+pub mod ping_job;
 pub mod scheduled_job;
 mod scheduler;
 pub mod send_already_registered_email_job;
 pub mod send_password_reset_email_job;
 pub mod send_verification_email_job;
+pub mod status;
 mod worker;
 
+pub use worker::in_flight_jobs;
+
 use crate::app::App;
+use sea_orm::{DatabaseConnection, DbErr};
 use serde::de::DeserializeOwned;
 use std::future::Future;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -23,6 +34,18 @@ pub enum JobError {
     TryAgainLater(String),
 }
 
+/// Cancel the job with `job_id`. A pending job is marked `Cancelled` and
+/// never claimed; a running one is asked to stop through [`cancellation`].
+/// Returns `false` if the job doesn't exist or is already finished.
+///
+/// Same as [`JobQueue::cancel`](crate::job_queue::JobQueue::cancel) on the
+/// database queue, for code that has a connection but no `App`.
+pub async fn cancel(db: &DatabaseConnection, job_id: uuid::Uuid) -> Result<bool, DbErr> {
+    crate::job_queue::JobQueue::database()
+        .cancel(db, job_id)
+        .await
+}
+
 pub trait Job<ExtraConfig = ()>: Send + Sync {
     type Arguments: DeserializeOwned + Send + Sync;
 
@@ -32,4 +55,72 @@ pub trait Job<ExtraConfig = ()>: Send + Sync {
     ) -> impl Future<Output = Result<(), JobError>> + Send;
 
     fn name() -> &'static str;
+
+    /// Upgrade arguments stored in an older shape before they are
+    /// deserialized, so jobs enqueued before a deploy changed
+    /// [`Arguments`](Job::Arguments) still run. Returns `raw` unchanged by
+    /// default.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// fn migrate_arguments(mut raw: serde_json::Value) -> serde_json::Value {
+    ///     // v1 stored a single `email`; v2 takes a list
+    ///     if let Some(email) = raw.get("email").cloned() {
+    ///         raw["emails"] = serde_json::json!([email]);
+    ///     }
+    ///     raw
+    /// }
+    /// ```
+    fn migrate_arguments(raw: serde_json::Value) -> serde_json::Value {
+        raw
+    }
+
+    /// Run this job on Tokio's blocking thread pool instead of the shared
+    /// runtime, for CPU-heavy work (image processing, hashing) that would
+    /// otherwise stall HTTP requests. A blocking job that outlives its
+    /// timeout is recorded as timed out but keeps its thread until it
+    /// returns, so check [`cancellation`] in long loops.
+    fn is_blocking() -> bool {
+        false
+    }
+
+    /// Store this job's arguments gzipped instead of as JSONB.
+    ///
+    /// Worth enabling for job types with large, repetitive arguments; the
+    /// worker decompresses them before [`execute`](Job::execute). Jobs enqueued
+    /// by the scheduler are always stored uncompressed.
+    fn compress_arguments() -> bool {
+        false
+    }
+
+    /// Most jobs of this type allowed to run at once across all workers and
+    /// replicas, e.g. to respect a provider's concurrency cap. Jobs over the
+    /// limit stay pending until a slot frees up. `None` means no limit.
+    fn max_concurrency() -> Option<usize> {
+        None
+    }
+
+    /// How long one run of this job may take before it is stopped and counts
+    /// as timed out. `None` uses the worker pool's `job_timeout`.
+    fn timeout() -> Option<Duration> {
+        None
+    }
+
+    /// Retries after a failure or timeout before the job is marked failed.
+    /// `None` uses the worker pool's `max_retries`.
+    fn max_retries() -> Option<i32> {
+        None
+    }
+
+    /// Called once a job of this type has failed for good, because it failed
+    /// permanently or ran out of retries, e.g. to alert someone or undo
+    /// partial work. Not called for cancelled jobs. Does nothing by default.
+    fn on_permanent_failure(
+        app: &App<ExtraConfig>,
+        arguments: Self::Arguments,
+        reason: &str,
+    ) -> impl Future<Output = ()> + Send {
+        let _ = (app, arguments, reason);
+        async {}
+    }
 }
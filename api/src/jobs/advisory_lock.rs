@@ -13,6 +13,9 @@ pub mod lock_keys {
 
     /// Lock key for stuck job recovery
     pub const RECOVERY: i64 = 0x5245_434F_5645_5259; // "RECOVERY" in hex
+
+    /// Namespace for per-job-type locks; the second key is `hashtext(type)`
+    pub const JOB_CONCURRENCY: i32 = 0x4A4F_4243; // "JOBC" in hex
 }
 /// Tries to acquire a `PostgreSQL` advisory lock
 pub async fn try_acquire_lock(db: &DatabaseConnection, key: i64) -> Result<bool, DbErr> {
@@ -28,6 +31,22 @@ pub async fn try_acquire_lock(db: &DatabaseConnection, key: i64) -> Result<bool,
         .unwrap_or(false))
 }
 
+/// Waits for the transaction-scoped lock on `job_type`, serializing claims of
+/// that type across workers. Released when the transaction ends.
+pub async fn lock_job_type_for_transaction(
+    txn: &impl ConnectionTrait,
+    job_type: &str,
+) -> Result<(), DbErr> {
+    let stmt = Statement::from_sql_and_values(
+        sea_orm::DatabaseBackend::Postgres,
+        "SELECT pg_advisory_xact_lock($1, hashtext($2))",
+        [lock_keys::JOB_CONCURRENCY.into(), job_type.into()],
+    );
+
+    txn.execute(stmt).await?;
+    Ok(())
+}
+
 /// Explicitly releases a `PostgreSQL` advisory lock
 pub async fn release_lock(db: &DatabaseConnection, key: i64) -> Result<bool, DbErr> {
     let stmt = Statement::from_sql_and_values(
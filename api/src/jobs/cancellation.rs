@@ -0,0 +1,145 @@
+//! Cooperative cancellation of running jobs.
+//!
+//! [`jobs::cancel`](super::cancel) on a running job only records the request.
+//! The worker notices it within [`POLL_INTERVAL`] and signals the job through
+//! the handle below; the job decides where it is safe to stop. A job that
+//! never checks runs to the end as usual.
+//!
+//! The signal is task-local, like [`output`](super::output), rather than a
+//! token passed to [`Job::execute`](super::Job::execute): a new parameter
+//! would break every existing job, including those that never check it.
+//!
+//! ```rust,ignore
+//! async fn execute(app: &App, args: ExportArgs) -> Result<(), JobError> {
+//!     for chunk in rows.chunks(500) {
+//!         if cancellation::is_cancelled() {
+//!             return Err(JobError::FailPermanently("cancelled".into()));
+//!         }
+//!         write_chunk(chunk).await?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// How often the worker checks a running job for a cancellation request.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+tokio::task_local! {
+    static CURRENT: Cancellation;
+}
+
+/// Cancellation signal of the job running on the current task.
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl Cancellation {
+    /// The signal of the current job, if called from inside one.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Resolves once cancellation is requested, e.g. to race it against a
+    /// slow call with `tokio::select!`.
+    pub async fn cancelled(&self) {
+        let notified = self.0.notify.notified();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Release);
+        self.0.notify.notify_waiters();
+    }
+
+    /// Run `future` with `self` as the current signal.
+    pub(crate) async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+}
+
+/// Signals of the jobs one worker is running, so it can check all of them
+/// for cancellation with a single query.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RunningJobs(Arc<Mutex<HashMap<Uuid, Cancellation>>>);
+
+impl RunningJobs {
+    /// Track `job_id` until the returned guard is dropped.
+    pub(crate) fn track(&self, job_id: Uuid) -> TrackedJob {
+        let cancellation = Cancellation::default();
+        self.0.lock().unwrap().insert(job_id, cancellation.clone());
+        TrackedJob {
+            jobs: self.clone(),
+            job_id,
+            cancellation,
+        }
+    }
+
+    pub(crate) fn ids(&self) -> Vec<Uuid> {
+        self.0.lock().unwrap().keys().copied().collect()
+    }
+
+    /// Signal the job, if it is still running. Returns `false` if it isn't or
+    /// was signalled already.
+    pub(crate) fn cancel(&self, job_id: Uuid) -> bool {
+        match self.0.lock().unwrap().get(&job_id) {
+            Some(cancellation) if !cancellation.is_cancelled() => {
+                cancellation.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A job in [`RunningJobs`]; stops tracking it when dropped.
+#[derive(Debug)]
+pub(crate) struct TrackedJob {
+    jobs: RunningJobs,
+    job_id: Uuid,
+    pub(crate) cancellation: Cancellation,
+}
+
+impl Drop for TrackedJob {
+    fn drop(&mut self) {
+        self.jobs.0.lock().unwrap().remove(&self.job_id);
+    }
+}
+
+/// Whether the current job has been asked to stop. Always `false` outside a
+/// job, e.g. when run with `App::run_job_now`.
+pub fn is_cancelled() -> bool {
+    Cancellation::current().is_some_and(|cancellation| cancellation.is_cancelled())
+}
+
+/// Resolves once the current job has been asked to stop. Never resolves
+/// outside a job.
+pub async fn cancelled() {
+    match Cancellation::current() {
+        Some(cancellation) => cancellation.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,22 @@
+//! Gzip encoding for the arguments of jobs that opt into
+//! [`Job::compress_arguments`](super::Job::compress_arguments).
+use std::io::{self, Read, Write};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+/// Serialize `arguments` to JSON and gzip it.
+pub(crate) fn compress(arguments: &serde_json::Value) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    // Writing into a Vec can't fail
+    encoder
+        .write_all(arguments.to_string().as_bytes())
+        .and_then(|()| encoder.finish())
+        .expect("Failed to gzip job arguments")
+}
+
+/// Reverse of [`compress`].
+pub(crate) fn decompress(bytes: &[u8]) -> io::Result<serde_json::Value> {
+    let mut json = Vec::new();
+    GzDecoder::new(bytes).read_to_end(&mut json)?;
+    serde_json::from_slice(&json).map_err(io::Error::from)
+}
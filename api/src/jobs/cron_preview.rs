@@ -0,0 +1,99 @@
+//! Upcoming run times of a cron expression, for checking a schedule before
+//! it goes live. Backs the `cron` CLI command.
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, Local, Utc};
+
+/// Time zone the preview shows next to UTC. The scheduler itself always
+/// evaluates expressions in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewZone {
+    /// The machine's local time zone, including its DST changes
+    Local,
+    /// A fixed offset from UTC, e.g. `+02:00`
+    Fixed(FixedOffset),
+}
+
+impl FromStr for PreviewZone {
+    type Err = String;
+
+    /// Accepts `local`, `UTC` or an offset like `+02:00`.
+    fn from_str(zone: &str) -> Result<Self, Self::Err> {
+        if zone.eq_ignore_ascii_case("local") {
+            return Ok(Self::Local);
+        }
+        if zone.eq_ignore_ascii_case("utc") || zone == "Z" {
+            return Ok(Self::Fixed(
+                FixedOffset::east_opt(0).expect("zero offset is valid"),
+            ));
+        }
+        zone.parse::<FixedOffset>().map(Self::Fixed).map_err(|_| {
+            format!(
+                "unknown time zone '{zone}', expected 'local', 'UTC' or an offset like '+02:00'"
+            )
+        })
+    }
+}
+
+impl PreviewZone {
+    /// `at` in this zone, formatted with its offset.
+    pub fn format(self, at: DateTime<Utc>) -> String {
+        const FORMAT: &str = "%Y-%m-%d %H:%M:%S %:z";
+        match self {
+            Self::Local => at.with_timezone(&Local).format(FORMAT).to_string(),
+            Self::Fixed(offset) => at.with_timezone(&offset).format(FORMAT).to_string(),
+        }
+    }
+}
+
+/// The next `count` times after `after` at which the scheduler would run a
+/// job with `cron_expression` (6-field format, seconds included).
+///
+/// # Errors
+/// Returns the parse error for an invalid expression.
+pub fn upcoming_runs(
+    cron_expression: &str,
+    after: DateTime<Utc>,
+    count: usize,
+) -> Result<Vec<DateTime<Utc>>, cron::error::Error> {
+    let schedule = cron::Schedule::from_str(cron_expression)?;
+    Ok(schedule.after(&after).take(count).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+    use super::{upcoming_runs, PreviewZone};
+
+    fn utc(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_preview_lists_upcoming_weekday_runs() {
+        // Friday afternoon, so the next runs skip the weekend
+        let runs = upcoming_runs("0 30 9 * * Mon-Fri", utc(16, 12, 0), 3).unwrap();
+        assert_eq!(runs, [utc(19, 9, 30), utc(20, 9, 30), utc(21, 9, 30)]);
+
+        let zone: PreviewZone = "+02:00".parse().unwrap();
+        assert_eq!(zone.format(runs[0]), "2026-10-19 11:30:00 +02:00");
+
+        assert!(upcoming_runs("30 9 * * *", utc(16, 12, 0), 3).is_err());
+    }
+
+    #[test]
+    fn test_preview_zone_parsing() {
+        assert_eq!("local".parse::<PreviewZone>(), Ok(PreviewZone::Local));
+        assert_eq!(
+            "UTC".parse::<PreviewZone>(),
+            Ok(PreviewZone::Fixed(FixedOffset::east_opt(0).unwrap()))
+        );
+        assert_eq!(
+            "-05:00".parse::<PreviewZone>(),
+            Ok(PreviewZone::Fixed(FixedOffset::west_opt(5 * 3600).unwrap()))
+        );
+        assert!("Europe/Warsaw".parse::<PreviewZone>().is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use std::{sync::LazyLock, time::Duration};
+
+use axum::http::{header, Method, Request, StatusCode};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper_rustls::HttpsConnector;
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client},
+    rt::TokioExecutor,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    app::App,
+    jobs::{Job, JobError},
+};
+
+/// POSTs a finished job's outcome to the callback URL given to
+/// [`JobQueue::add_with_callback`](crate::job_queue::JobQueue::add_with_callback).
+///
+/// Network errors, 5xx, 408 and 429 responses are retried; any other non-2xx
+/// response fails the delivery permanently.
+pub struct DeliverJobCallbackJob<ExtraConfig = ()>(std::marker::PhantomData<ExtraConfig>);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeliverJobCallbackArgs {
+    pub callback_url: String,
+    pub payload: JobCallbackPayload,
+}
+
+/// JSON body sent to the callback URL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobCallbackPayload {
+    pub job_id: Uuid,
+    pub job_type: String,
+    /// `completed` or `failed`
+    pub status: String,
+    /// Failure reason of the last attempt, if the job failed
+    pub error: Option<String>,
+}
+
+impl<ExtraConfig: Clone + Send + Sync + 'static> Job<ExtraConfig>
+    for DeliverJobCallbackJob<ExtraConfig>
+{
+    type Arguments = DeliverJobCallbackArgs;
+
+    fn name() -> &'static str {
+        "deliver_job_callback"
+    }
+
+    async fn execute(_app: &App<ExtraConfig>, args: Self::Arguments) -> Result<(), JobError> {
+        let body = serde_json::to_vec(&args.payload)
+            .map_err(|e| JobError::FailPermanently(e.to_string()))?;
+
+        let status = post_json(&args.callback_url, body).await?;
+
+        if status.is_success() {
+            Ok(())
+        } else if status.is_server_error()
+            || status == StatusCode::REQUEST_TIMEOUT
+            || status == StatusCode::TOO_MANY_REQUESTS
+        {
+            Err(JobError::TryAgainLater(format!(
+                "Callback to {} returned {status}",
+                args.callback_url
+            )))
+        } else {
+            Err(JobError::FailPermanently(format!(
+                "Callback to {} returned {status}",
+                args.callback_url
+            )))
+        }
+    }
+}
+
+/// How long one delivery may take, connecting included, before it is retried.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+type HttpsClient = Client<HttpsConnector<HttpConnector>, Full<Bytes>>;
+
+/// Shared by all deliveries so connections and TLS roots are reused.
+static CLIENT: LazyLock<Result<HttpsClient, String>> = LazyLock::new(|| {
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .map_err(|e| format!("Failed to load TLS roots: {e}"))?
+        .https_or_http()
+        .enable_http1()
+        .build();
+    Ok(Client::builder(TokioExecutor::new()).build(connector))
+});
+
+async fn post_json(url: &str, body: Vec<u8>) -> Result<StatusCode, JobError> {
+    let client = CLIENT
+        .as_ref()
+        .map_err(|e| JobError::TryAgainLater(e.clone()))?;
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(url)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Full::new(Bytes::from(body)))
+        .map_err(|e| JobError::FailPermanently(format!("Invalid callback URL {url}: {e}")))?;
+
+    let response = tokio::time::timeout(REQUEST_TIMEOUT, client.request(request))
+        .await
+        .map_err(|_| {
+            JobError::TryAgainLater(format!(
+                "Callback to {url} timed out after {}s",
+                REQUEST_TIMEOUT.as_secs()
+            ))
+        })?
+        .map_err(|e| JobError::TryAgainLater(format!("Callback to {url} failed: {e}")))?;
+
+    Ok(response.status())
+}
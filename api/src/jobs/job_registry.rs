@@ -1,10 +1,16 @@
 use std::future::Future;
 use std::pin::Pin;
-use std::{collections::HashMap, sync::Arc};
+use std::time::Duration;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-use crate::app::App;
+use tracing::{warn, Instrument};
 
-use super::{job_result::JobResult, Job, JobError};
+use crate::{app::App, log_context::LogContext};
+
+use super::{cancellation::Cancellation, job_result::JobResult, output::JobOutput, Job, JobError};
 
 /// Type alias for job executor function to reduce type complexity
 type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
@@ -14,9 +20,18 @@ type JobExecutor<ExtraConfig> = Arc<
         + Sync,
 >;
 
+type FailureHook<ExtraConfig> = Arc<
+    dyn Fn(&App<ExtraConfig>, serde_json::Value, String) -> BoxFuture<'static, ()> + Send + Sync,
+>;
+
 #[derive(Clone)]
 pub struct JobRegistry<ExtraConfig = ()> {
     jobs: HashMap<&'static str, JobExecutor<ExtraConfig>>,
+    failure_hooks: HashMap<&'static str, FailureHook<ExtraConfig>>,
+    max_concurrency: HashMap<&'static str, usize>,
+    timeouts: HashMap<&'static str, Duration>,
+    max_retries: HashMap<&'static str, i32>,
+    optional: HashSet<&'static str>,
 }
 
 impl<ExtraConfig> JobRegistry<ExtraConfig>
@@ -26,29 +41,119 @@ where
     pub fn new() -> Self {
         Self {
             jobs: HashMap::new(),
+            failure_hooks: HashMap::new(),
+            max_concurrency: HashMap::new(),
+            timeouts: HashMap::new(),
+            max_retries: HashMap::new(),
+            optional: HashSet::new(),
         }
     }
 
     pub fn register_job<J: Job<ExtraConfig> + 'static>(&mut self) {
+        if let Some(max) = J::max_concurrency() {
+            self.max_concurrency.insert(J::name(), max);
+        }
+        if let Some(timeout) = J::timeout() {
+            self.timeouts.insert(J::name(), timeout);
+        }
+        if let Some(max_retries) = J::max_retries() {
+            self.max_retries.insert(J::name(), max_retries);
+        }
+        let blocking = J::is_blocking();
         self.jobs.insert(
             J::name(),
-            Arc::new(|app: &App<ExtraConfig>, args_json: serde_json::Value| {
-                let app = app.clone();
-                Box::pin(async move {
-                    let arguments: J::Arguments =
-                        serde_json::from_value(args_json).map_err(|e| {
+            Arc::new(
+                move |app: &App<ExtraConfig>, args_json: serde_json::Value| {
+                    let app = app.clone();
+                    Box::pin(async move {
+                        let arguments: J::Arguments = serde_json::from_value(J::migrate_arguments(
+                            args_json,
+                        ))
+                        .map_err(|e| {
                             JobError::FailPermanently(format!("Failed to parse job arguments: {e}"))
                         })?;
-                    J::execute(&app, arguments).await
-                })
-            }),
+                        if blocking {
+                            execute_blocking::<J, ExtraConfig>(app, arguments).await
+                        } else {
+                            J::execute(&app, arguments).await
+                        }
+                    })
+                },
+            ),
+        );
+        self.failure_hooks.insert(
+            J::name(),
+            Arc::new(
+                |app: &App<ExtraConfig>, args_json: serde_json::Value, reason: String| {
+                    let app = app.clone();
+                    Box::pin(async move {
+                        match serde_json::from_value::<J::Arguments>(J::migrate_arguments(
+                            args_json,
+                        )) {
+                            Ok(arguments) => {
+                                J::on_permanent_failure(&app, arguments, &reason).await
+                            }
+                            Err(e) => warn!(
+                                "Skipping failure hook of {}, its arguments don't parse: {e}",
+                                J::name()
+                            ),
+                        }
+                    })
+                },
+            ),
         );
     }
 
+    /// Register a built-in job that apps may leave out of their worker pools.
+    /// Startup warns instead of panicking when no pool lists it, so adding a
+    /// built-in job doesn't break existing configs.
+    pub(crate) fn register_optional_job<J: Job<ExtraConfig> + 'static>(&mut self) {
+        self.register_job::<J>();
+        self.optional.insert(J::name());
+    }
+
+    /// Whether `job_type` was registered with [`Self::register_optional_job`].
+    pub(crate) fn is_optional(&self, job_type: &str) -> bool {
+        self.optional.contains(job_type)
+    }
+
     pub(crate) fn job_names(&self) -> impl Iterator<Item = &&'static str> {
         self.jobs.keys()
     }
 
+    /// Cluster-wide cap declared by [`Job::max_concurrency`] for `job_type`.
+    pub(crate) fn max_concurrency(&self, job_type: &str) -> Option<usize> {
+        self.max_concurrency.get(job_type).copied()
+    }
+
+    /// Timeout declared by [`Job::timeout`] for `job_type`.
+    pub(crate) fn timeout(&self, job_type: &str) -> Option<Duration> {
+        self.timeouts.get(job_type).copied()
+    }
+
+    /// Every timeout declared by [`Job::timeout`], by job type.
+    pub(crate) fn timeouts(&self) -> HashMap<&'static str, Duration> {
+        self.timeouts.clone()
+    }
+
+    /// Retry limit declared by [`Job::max_retries`] for `job_type`.
+    pub(crate) fn max_retries(&self, job_type: &str) -> Option<i32> {
+        self.max_retries.get(job_type).copied()
+    }
+
+    /// Run [`Job::on_permanent_failure`] of `job_type`.
+    pub(crate) async fn on_permanent_failure(
+        &self,
+        app: &App<ExtraConfig>,
+        job_type: &str,
+        arguments: serde_json::Value,
+        reason: String,
+    ) {
+        if let Some(hook) = self.failure_hooks.get(job_type) {
+            hook(app, arguments, reason).await;
+        }
+    }
+
     pub(crate) async fn execute(
         &self,
         app: &App<ExtraConfig>,
@@ -68,6 +173,30 @@ where
     }
 }
 
+/// Run `J` on a blocking thread, carrying over the job's cancellation
+/// signal, logging context and span, which are bound to the current task.
+async fn execute_blocking<J, ExtraConfig>(
+    app: App<ExtraConfig>,
+    arguments: J::Arguments,
+) -> Result<(), JobError>
+where
+    J: Job<ExtraConfig> + 'static,
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    let runtime = tokio::runtime::Handle::current();
+    let cancellation = Cancellation::current().unwrap_or_default();
+    let log_context = LogContext::current().unwrap_or_default();
+    let output = JobOutput::current().unwrap_or_default();
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let execution = output.scope(log_context.scope(J::execute(&app, arguments)));
+        runtime.block_on(cancellation.scope(execution).instrument(span))
+    })
+    .await
+    .map_err(|e| JobError::FailPermanently(format!("Blocking job did not finish: {e}")))?
+}
+
 impl<ExtraConfig> Default for JobRegistry<ExtraConfig>
 where
     ExtraConfig: Clone + Send + Sync + 'static,
@@ -76,3 +205,122 @@ where
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use serde::Deserialize;
+
+    use super::JobRegistry;
+    use crate::{
+        app::App,
+        database::migrations::Migrator,
+        jobs::{job_result::JobResult, Job, JobError},
+        log_context::LogContext,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+    };
+
+    /// Second version of the arguments; the first had a single `email`.
+    #[derive(Deserialize)]
+    struct InviteArguments {
+        emails: Vec<String>,
+    }
+
+    struct InviteJob;
+
+    impl Job for InviteJob {
+        type Arguments = InviteArguments;
+
+        fn name() -> &'static str {
+            "invite_test_job"
+        }
+
+        fn migrate_arguments(mut raw: serde_json::Value) -> serde_json::Value {
+            if let Some(email) = raw.get("email").cloned() {
+                raw["emails"] = serde_json::json!([email]);
+            }
+            raw
+        }
+
+        async fn execute(_app: &App, arguments: InviteArguments) -> Result<(), JobError> {
+            if arguments.emails == ["old@example.com"] {
+                Ok(())
+            } else {
+                Err(JobError::FailPermanently(format!(
+                    "unexpected emails {:?}",
+                    arguments.emails
+                )))
+            }
+        }
+    }
+
+    /// Hogs its thread, like CPU-heavy work would.
+    struct BusyJob;
+
+    impl Job for BusyJob {
+        type Arguments = ();
+
+        fn name() -> &'static str {
+            "busy_test_job"
+        }
+
+        fn is_blocking() -> bool {
+            true
+        }
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            std::thread::sleep(Duration::from_millis(500));
+            match LogContext::current().and_then(|context| context.request_id) {
+                Some(request_id) if request_id == "busy-request" => Ok(()),
+                other => Err(JobError::FailPermanently(format!(
+                    "lost the log context: {other:?}"
+                ))),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_old_arguments_are_migrated_before_execution() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let mut registry = JobRegistry::new();
+        registry.register_job::<InviteJob>();
+
+        let old = serde_json::json!({ "email": "old@example.com" });
+        let result = registry.execute(&test.app(), InviteJob::name(), &old).await;
+        assert!(matches!(result, JobResult::Completed), "{result:?}");
+
+        let current = serde_json::json!({ "emails": ["old@example.com"] });
+        let result = registry
+            .execute(&test.app(), InviteJob::name(), &current)
+            .await;
+        assert!(matches!(result, JobResult::Completed), "{result:?}");
+    }
+
+    #[tokio::test]
+    async fn test_blocking_job_does_not_stall_other_tasks() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let mut registry = JobRegistry::new();
+        registry.register_job::<BusyJob>();
+        let app = test.app();
+
+        let context = LogContext {
+            request_id: Some("busy-request".to_string()),
+            user_id: None,
+        };
+        let job = context.scope(registry.execute(&app, BusyJob::name(), &serde_json::Value::Null));
+        // The test runtime has a single thread, which the job would otherwise hog
+        let started = Instant::now();
+        let ticker = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            started.elapsed()
+        };
+        let (result, ticker_elapsed) = tokio::join!(job, ticker);
+
+        assert!(matches!(result, JobResult::Completed), "{result:?}");
+        assert!(
+            ticker_elapsed < Duration::from_millis(400),
+            "stalled for {ticker_elapsed:?}"
+        );
+    }
+}
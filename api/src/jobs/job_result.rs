@@ -3,6 +3,7 @@ use std::fmt::{Display, Formatter, Result};
 use crate::database::models::job_status::JobStatus;
 use crate::jobs::JobError;
 
+#[derive(Debug)]
 pub enum JobResult {
     Completed,
     Failed(JobError),
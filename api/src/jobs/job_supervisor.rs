@@ -2,13 +2,19 @@ use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
     QueryOrder as _, QuerySelect as _,
 };
-use std::{collections::HashSet, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
 use tokio::{spawn, time::sleep};
 use tracing::{debug, error, info, warn};
 
 use crate::{
     app::App,
-    config::{CleanupConfig, JobsConfig, WorkerQueueConfig, WorkersConfig},
+    config::{
+        CleanupConfig, JobsConfig, MaintenanceWindow, RecoveryConfig, ScheduleConfig,
+        WorkerQueueConfig, WorkersConfig,
+    },
     database::models::{
         job::{self, Entity as JobEntity},
         job_execution,
@@ -52,6 +58,14 @@ fn verify_job_types_have_workers<ExtraConfig>(
     }
 
     for job_type in job_registry.job_names() {
+        if job_registry.is_optional(job_type) {
+            if !covered_job_types.contains(*job_type) {
+                warn!(
+                    "No worker pool configured to handle built-in job type '{job_type}'; these jobs will stay pending. Add it to a pool's jobs to run them."
+                );
+            }
+            continue;
+        }
         assert!(
             covered_job_types.contains(*job_type),
             "No worker pool configured to handle job type '{job_type}'. Please add a worker pool for this job type."
@@ -67,16 +81,23 @@ pub async fn job_supervisor<ExtraConfig>(
 ) where
     ExtraConfig: Clone + Send + Sync + 'static,
 {
-    // Verify that all JobTypes have corresponding worker pools
-    verify_job_types_have_workers(&jobs_config.workers, &job_registry);
-    // Start all worker pools
-    start_worker_pools(&jobs_config.workers, &app, &job_registry);
-
-    // Start the scheduler
-    start_scheduler(&app.db, job_schedule);
+    start_workers(&jobs_config, &app, &job_registry);
+
+    // Start the scheduler with the jobs enabled for this environment
+    let job_schedule = filter_schedule(&jobs_config.schedule, job_schedule);
+    start_scheduler(
+        &app.db,
+        job_schedule,
+        jobs_config.schedule.maintenance.clone(),
+    );
 
     // Start the stuck job recovery task
-    start_recovery_task(&jobs_config.workers, &app.db);
+    start_recovery_task(
+        &jobs_config.workers,
+        &jobs_config.recovery,
+        job_registry.timeouts(),
+        &app.db,
+    );
 
     // Start the job cleanup task
     start_cleanup_task(&jobs_config.cleanup, &app.db);
@@ -85,6 +106,27 @@ pub async fn job_supervisor<ExtraConfig>(
     run_supervisor_loop().await;
 }
 
+/// Check that every job type has a pool and start the pools, unless workers
+/// are disabled for this process.
+///
+/// # Panics
+/// Panics if workers are enabled and a registered job type has no pool.
+fn start_workers<ExtraConfig>(
+    jobs_config: &JobsConfig,
+    app: &App<ExtraConfig>,
+    job_registry: &JobRegistry<ExtraConfig>,
+) where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    if !jobs_config.workers_enabled {
+        info!("⏸️  Job workers disabled, jobs will wait for another process to run them");
+        return;
+    }
+    // Verify that all JobTypes have corresponding worker pools
+    verify_job_types_have_workers(&jobs_config.workers, job_registry);
+    start_worker_pools(&jobs_config.workers, app, job_registry);
+}
+
 /// Start all worker pools based on configuration
 fn start_worker_pools<ExtraConfig>(
     config: &WorkersConfig,
@@ -169,8 +211,29 @@ async fn run_worker_with_restart<ExtraConfig>(
     }
 }
 
+/// Drop scheduled jobs that are disabled by the schedule config
+fn filter_schedule(config: &ScheduleConfig, job_schedule: Vec<ScheduledJob>) -> Vec<ScheduledJob> {
+    job_schedule
+        .into_iter()
+        .filter(|scheduled_job| {
+            let enabled = config.is_enabled(&scheduled_job.name);
+            if !enabled {
+                info!(
+                    "📅 Scheduled job '{}' is disabled in this environment",
+                    scheduled_job.name
+                );
+            }
+            enabled
+        })
+        .collect()
+}
+
 /// Start the job scheduler
-fn start_scheduler(db: &DatabaseConnection, job_schedule: Vec<ScheduledJob>) {
+fn start_scheduler(
+    db: &DatabaseConnection,
+    job_schedule: Vec<ScheduledJob>,
+    maintenance_windows: Vec<MaintenanceWindow>,
+) {
     let scheduler_db = db.clone();
     let job_schedule_for_spawn = job_schedule.clone();
 
@@ -182,9 +245,11 @@ fn start_scheduler(db: &DatabaseConnection, job_schedule: Vec<ScheduledJob>) {
             "scheduler",
             move |db| {
                 let job_schedule_clone = job_schedule_inner.clone();
+                let maintenance_windows = maintenance_windows.clone();
                 async move {
                     info!("📅 Starting job scheduler");
-                    let mut scheduler = Scheduler::new(db, job_schedule_clone);
+                    let mut scheduler = Scheduler::new(db, job_schedule_clone)
+                        .with_maintenance_windows(maintenance_windows);
                     scheduler.run().await;
                 }
             },
@@ -194,8 +259,14 @@ fn start_scheduler(db: &DatabaseConnection, job_schedule: Vec<ScheduledJob>) {
 }
 
 /// Start the stuck job recovery task
-fn start_recovery_task(config: &WorkersConfig, db: &DatabaseConnection) {
+fn start_recovery_task(
+    config: &WorkersConfig,
+    recovery: &RecoveryConfig,
+    job_timeouts: HashMap<&'static str, Duration>,
+    db: &DatabaseConnection,
+) {
     let recovery_config = config.clone();
+    let interval = Duration::from_secs(recovery.interval_seconds);
     let recovery_db = db.clone();
     spawn(async move {
         advisory_lock::run_with_advisory_lock(
@@ -205,8 +276,9 @@ fn start_recovery_task(config: &WorkersConfig, db: &DatabaseConnection) {
             move |db| {
                 info!("🏥 Starting stuck job recovery");
                 let config = recovery_config.clone();
+                let job_timeouts = job_timeouts.clone();
                 async move {
-                    run_recovery_loop(&config, &db).await;
+                    run_recovery_loop(&config, interval, &job_timeouts, &db).await;
                 }
             },
         )
@@ -221,9 +293,14 @@ async fn run_supervisor_loop() {
     }
 }
 
-async fn run_recovery_loop(config: &WorkersConfig, db: &DatabaseConnection) {
+async fn run_recovery_loop(
+    config: &WorkersConfig,
+    interval: Duration,
+    job_timeouts: &HashMap<&'static str, Duration>,
+    db: &DatabaseConnection,
+) {
     loop {
-        match recover_stuck_jobs(config, db).await {
+        match recover_stuck_jobs(config, job_timeouts, db).await {
             Ok(recovered_count) => {
                 if recovered_count > 0 {
                     info!("🏥 Recovered {} stuck jobs", recovered_count);
@@ -235,20 +312,22 @@ async fn run_recovery_loop(config: &WorkersConfig, db: &DatabaseConnection) {
             }
         }
 
-        // Check for stuck jobs every 5 minutes
-        sleep(Duration::from_secs(300)).await;
+        sleep(interval).await;
     }
 }
 
-/// Finds and recovers jobs that have been running longer than 2x their timeout
+/// Finds and recovers jobs that have been running longer than their pool's
+/// `stuck_multiplier` times their timeout
 async fn recover_stuck_jobs(
     config: &WorkersConfig,
+    job_timeouts: &HashMap<&'static str, Duration>,
     db: &DatabaseConnection,
 ) -> Result<usize, DbErr> {
     let mut total_recovered = 0;
 
     for (pool_name, worker_config) in &config.workers {
-        let recovered_count = recover_stuck_jobs_for_pool(pool_name, worker_config, db).await?;
+        let recovered_count =
+            recover_stuck_jobs_for_pool(pool_name, worker_config, job_timeouts, db).await?;
         total_recovered += recovered_count;
     }
 
@@ -258,25 +337,41 @@ async fn recover_stuck_jobs(
 async fn recover_stuck_jobs_for_pool(
     pool_name: &str,
     worker_config: &WorkerQueueConfig,
+    job_timeouts: &HashMap<&'static str, Duration>,
     db: &DatabaseConnection,
 ) -> Result<usize, DbErr> {
-    // Calculate the stuck threshold: 2x the job timeout
-    let stuck_threshold_seconds = worker_config.job_timeout * 2;
-    let stuck_threshold = chrono::Duration::seconds(stuck_threshold_seconds.into());
-    let cutoff_time = chrono::Utc::now().naive_utc() - stuck_threshold;
-
-    // Find jobs in this pool that have been running too long
-    let stuck_jobs = JobEntity::find()
-        .filter(job::Column::Status.eq(JobStatus::Running))
-        .filter(job::Column::Type.is_in(&worker_config.jobs))
-        .filter(job::Column::UpdatedAt.lte(cutoff_time))
-        .all(db)
-        .await?;
+    // Job types that declare their own timeout get their own threshold
+    let mut types_by_timeout: BTreeMap<u32, Vec<&str>> = BTreeMap::new();
+    for job_type in &worker_config.jobs {
+        let timeout =
+            job_timeouts
+                .get(job_type.as_str())
+                .map_or(worker_config.job_timeout, |timeout| {
+                    // Whole seconds, rounded up
+                    let seconds = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+                    u32::try_from(seconds).unwrap_or(u32::MAX)
+                });
+        types_by_timeout.entry(timeout).or_default().push(job_type);
+    }
 
     let mut recovered_count = 0;
-    for stuck_job in stuck_jobs {
-        recover_individual_stuck_job(stuck_job, pool_name, stuck_threshold_seconds, db).await?;
-        recovered_count += 1;
+    for (job_timeout, job_types) in types_by_timeout {
+        let stuck_threshold_seconds = job_timeout.saturating_mul(worker_config.stuck_multiplier);
+        let stuck_threshold = chrono::Duration::seconds(stuck_threshold_seconds.into());
+        let cutoff_time = chrono::Utc::now().naive_utc() - stuck_threshold;
+
+        // Find jobs in this pool that have been running too long
+        let stuck_jobs = JobEntity::find()
+            .filter(job::Column::Status.eq(JobStatus::Running))
+            .filter(job::Column::Type.is_in(job_types))
+            .filter(job::Column::UpdatedAt.lte(cutoff_time))
+            .all(db)
+            .await?;
+
+        for stuck_job in stuck_jobs {
+            recover_individual_stuck_job(stuck_job, pool_name, stuck_threshold_seconds, db).await?;
+            recovered_count += 1;
+        }
     }
 
     Ok(recovered_count)
@@ -317,14 +412,20 @@ async fn recover_individual_stuck_job(
             running_duration.num_seconds(),
             stuck_threshold_seconds
         ))),
+        output: sea_orm::Set(None),
         created_at: sea_orm::Set(now),
     };
 
     job_execution_active_model.insert(db).await?;
 
-    // Reset the job to Pending status for retry
+    // Reset the job to Pending status for retry, unless it was being cancelled
+    let status = if stuck_job.cancel_requested {
+        JobStatus::Cancelled
+    } else {
+        JobStatus::Pending
+    };
     let mut active_job: job::ActiveModel = stuck_job.into();
-    active_job.status = sea_orm::Set(JobStatus::Pending);
+    active_job.status = sea_orm::Set(status);
     active_job.update(db).await?;
 
     Ok(())
@@ -383,10 +484,10 @@ async fn cleanup_old_jobs(config: &CleanupConfig, db: &DatabaseConnection) -> Re
                 .unwrap_or(172_800),
         );
 
-    // Clean up completed jobs
+    // Clean up completed and cancelled jobs
     cleanup_jobs_by_status(
         db,
-        &[JobStatus::Completed],
+        &[JobStatus::Completed, JobStatus::Cancelled],
         completed_cutoff,
         config.batch_size,
     )
@@ -436,3 +537,199 @@ async fn cleanup_jobs_by_status(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use sea_orm::{EntityTrait, Set};
+
+    use super::{filter_schedule, recover_stuck_jobs_for_pool, start_workers};
+    use crate::{
+        app::App,
+        config::{JobsConfig, ScheduleConfig, WorkerQueueConfig, WorkersConfig},
+        database::{
+            migrations::Migrator,
+            models::{job, job_status::JobStatus},
+        },
+        jobs::{job_registry::JobRegistry, scheduled_job::ScheduledJob, Job, JobError},
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+    };
+
+    struct StuckJob;
+
+    impl Job for StuckJob {
+        type Arguments = ();
+
+        fn name() -> &'static str {
+            "stuck_test_job"
+        }
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            Ok(())
+        }
+    }
+
+    /// Workers with a single pool that doesn't cover `StuckJob`.
+    fn uncovering_jobs_config(workers_enabled: bool) -> JobsConfig {
+        JobsConfig {
+            workers_enabled,
+            cleanup: Default::default(),
+            recovery: Default::default(),
+            workers: WorkersConfig {
+                workers: HashMap::from([(
+                    "default".to_string(),
+                    WorkerQueueConfig {
+                        jobs: vec!["other_job".to_string()],
+                        count: 1,
+                        concurrency: 1,
+                        job_timeout: 60,
+                        max_retries: 0,
+                        base_retry_delay_seconds: 60,
+                        retry_backoff_multiplier: 2,
+                        max_retry_delay_seconds: 3600,
+                        retry_jitter_fraction: 0.0,
+                        dead_letter: false,
+                        stuck_multiplier: 2,
+                        retry_age_penalty_seconds: 0,
+                    },
+                )]),
+            },
+            schedule: ScheduleConfig::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_uncovered_job_type_is_allowed_when_workers_are_disabled() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let mut registry = JobRegistry::new();
+        registry.register_job::<StuckJob>();
+
+        start_workers(&uncovering_jobs_config(false), &test.app(), &registry);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "No worker pool configured to handle job type 'stuck_test_job'")]
+    async fn test_uncovered_job_type_panics_when_workers_are_enabled() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let mut registry = JobRegistry::new();
+        registry.register_job::<StuckJob>();
+
+        start_workers(&uncovering_jobs_config(true), &test.app(), &registry);
+    }
+
+    struct OptionalJob;
+
+    impl Job for OptionalJob {
+        type Arguments = ();
+
+        fn name() -> &'static str {
+            "optional_test_job"
+        }
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_uncovered_optional_job_type_is_allowed_when_workers_are_enabled() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let mut registry = JobRegistry::new();
+        registry.register_optional_job::<OptionalJob>();
+
+        start_workers(&uncovering_jobs_config(true), &test.app(), &registry);
+    }
+
+    fn scheduled_job(name: &str) -> ScheduledJob {
+        ScheduledJob::new(name, "test_job", serde_json::Value::Null, "0 0 * * * *")
+    }
+
+    fn names(schedule: &[ScheduledJob]) -> Vec<&str> {
+        schedule.iter().map(|job| job.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_disabled_scheduled_job_is_not_scheduled() {
+        let schedule = vec![
+            scheduled_job("hourly_sync"),
+            scheduled_job("nightly_report"),
+        ];
+
+        let all = filter_schedule(&ScheduleConfig::default(), schedule.clone());
+        assert_eq!(names(&all), ["hourly_sync", "nightly_report"]);
+
+        let config = ScheduleConfig {
+            enabled: None,
+            disabled: vec!["nightly_report".to_string()],
+            maintenance: Vec::new(),
+        };
+        assert_eq!(
+            names(&filter_schedule(&config, schedule.clone())),
+            ["hourly_sync"]
+        );
+
+        let config = ScheduleConfig {
+            enabled: Some(vec!["nightly_report".to_string()]),
+            disabled: Vec::new(),
+            maintenance: Vec::new(),
+        };
+        assert_eq!(
+            names(&filter_schedule(&config, schedule)),
+            ["nightly_report"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stuck_multiplier_sets_recovery_threshold() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        // Running for 90s, with a 60s timeout. Inserted without
+        // `before_save`, which would reset `updated_at`.
+        let started = chrono::Utc::now().naive_utc() - chrono::Duration::seconds(90);
+        let id = uuid::Uuid::now_v7();
+        let stuck = job::ActiveModel {
+            id: Set(id),
+            created_at: Set(started),
+            updated_at: Set(started),
+            r#type: Set(StuckJob::name().to_string()),
+            arguments: Set(serde_json::Value::Null),
+            status: Set(JobStatus::Running),
+            retry_count: Set(0),
+            priority: Set(0),
+            cancel_requested: Set(false),
+            ..Default::default()
+        };
+        job::Entity::insert(stuck).exec(&test.db).await.unwrap();
+
+        let mut pool = WorkerQueueConfig {
+            jobs: vec![StuckJob::name().to_string()],
+            count: 1,
+            concurrency: 1,
+            job_timeout: 60,
+            max_retries: 4,
+            base_retry_delay_seconds: 60,
+            retry_backoff_multiplier: 5,
+            max_retry_delay_seconds: 86_400,
+            retry_jitter_fraction: 0.0,
+            dead_letter: false,
+            stuck_multiplier: 2,
+            retry_age_penalty_seconds: 0,
+        };
+        let recovered = recover_stuck_jobs_for_pool("default", &pool, &HashMap::new(), &test.db)
+            .await
+            .unwrap();
+        assert_eq!(recovered, 0);
+
+        pool.stuck_multiplier = 1;
+        let recovered = recover_stuck_jobs_for_pool("default", &pool, &HashMap::new(), &test.db)
+            .await
+            .unwrap();
+        assert_eq!(recovered, 1);
+        let job = job::Entity::find_by_id(id)
+            .one(&test.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(job.status, JobStatus::Pending);
+    }
+}
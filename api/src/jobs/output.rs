@@ -0,0 +1,58 @@
+//! Values a job hands back to whoever enqueued it, e.g. the URL of a file it
+//! generated. The worker stores the output of a completed run on its
+//! execution record, where [`get_job`](super::status::get_job) returns it.
+//!
+//! ```rust,ignore
+//! async fn execute(app: &App, args: ExportArgs) -> Result<(), JobError> {
+//!     let url = export_to_storage(app, &args).await?;
+//!     output::set_output(&json!({ "url": url }))
+//!         .map_err(|e| JobError::FailPermanently(e.to_string()))?;
+//!     Ok(())
+//! }
+//! ```
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+tokio::task_local! {
+    static CURRENT: JobOutput;
+}
+
+/// Slot for the output of the job running on the current task.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct JobOutput(Arc<Mutex<Option<Value>>>);
+
+impl JobOutput {
+    /// The slot of the current job, if called from inside one.
+    pub(crate) fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Remove and return the value the job set, if any.
+    pub(crate) fn take(&self) -> Option<Value> {
+        self.0.lock().unwrap().take()
+    }
+
+    /// Run `future` with `self` as the current slot.
+    pub(crate) async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+}
+
+/// Record `output` as the result of the current job. A later call replaces
+/// an earlier one. Kept only if the run completes; does nothing outside a
+/// job, e.g. when run with `App::run_job_now`.
+///
+/// # Errors
+/// Returns an error if `output` can't be serialized to JSON.
+pub fn set_output<T: Serialize>(output: &T) -> Result<(), serde_json::Error> {
+    let value = serde_json::to_value(output)?;
+    if let Some(current) = JobOutput::current() {
+        *current.0.lock().unwrap() = Some(value);
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! A job that does nothing but log, for checking that enqueued jobs reach a
+//! worker and complete. Run it with the `ping` CLI command.
+use std::{marker::PhantomData, time::Duration};
+
+use sea_orm::{ConnectionTrait, DbErr};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    app::App,
+    jobs::{
+        status::{get_job, JobStatus},
+        Job, JobError,
+    },
+};
+
+/// How often [`wait_for_job`] checks the job's status.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct PingJob<ExtraConfig = ()>(PhantomData<ExtraConfig>);
+
+impl<ExtraConfig: Clone + Send + Sync + 'static> Job<ExtraConfig> for PingJob<ExtraConfig> {
+    type Arguments = ();
+
+    fn name() -> &'static str {
+        "ping"
+    }
+
+    async fn execute(_app: &App<ExtraConfig>, _arguments: ()) -> Result<(), JobError> {
+        info!("🏓 Pong");
+        Ok(())
+    }
+}
+
+/// Poll the job with `id` until it reaches a terminal status or `timeout`
+/// passes. Returns the last status seen, or `None` if the job is gone.
+pub async fn wait_for_job<C: ConnectionTrait>(
+    db: &C,
+    id: Uuid,
+    timeout: Duration,
+) -> Result<Option<JobStatus>, DbErr> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let status = get_job(db, id).await?.map(|summary| summary.status);
+        if status.is_none_or(|status| status.is_terminal())
+            || tokio::time::Instant::now() >= deadline
+        {
+            return Ok(status);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
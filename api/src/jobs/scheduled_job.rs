@@ -1,8 +1,78 @@
+use std::{fmt, sync::Arc};
+
+/// Closure computing a scheduled job's arguments each time it is enqueued.
+pub type ArgumentsBuilder = Arc<dyn Fn() -> serde_json::Value + Send + Sync>;
+
 /// Scheduled job configuration
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ScheduledJob {
     pub name: String,
     pub job_name: &'static str,
+    /// Static arguments, used when no `arguments_builder` is set
     pub arguments: serde_json::Value,
+    /// Computes fresh arguments for every run (e.g. "yesterday's date")
+    pub arguments_builder: Option<ArgumentsBuilder>,
     pub cron_expression: String,
+    /// Enqueue a missed run at startup, see [`ScheduledJob::with_catch_up`]
+    pub catch_up: bool,
+}
+
+impl ScheduledJob {
+    pub fn new(
+        name: impl Into<String>,
+        job_name: &'static str,
+        arguments: serde_json::Value,
+        cron_expression: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            job_name,
+            arguments,
+            arguments_builder: None,
+            cron_expression: cron_expression.into(),
+            catch_up: false,
+        }
+    }
+
+    /// When the scheduler starts and the most recent slot passed without a
+    /// job of this type being created (e.g. the server was down at 2am),
+    /// enqueue one run right away. Only one run is made up, however many
+    /// slots were missed.
+    #[must_use]
+    pub const fn with_catch_up(mut self) -> Self {
+        self.catch_up = true;
+        self
+    }
+
+    /// Compute the arguments with `builder` at enqueue time instead of
+    /// reusing the static `arguments` value.
+    #[must_use]
+    pub fn with_arguments_builder<F>(mut self, builder: F) -> Self
+    where
+        F: Fn() -> serde_json::Value + Send + Sync + 'static,
+    {
+        self.arguments_builder = Some(Arc::new(builder));
+        self
+    }
+
+    /// Arguments for the next run.
+    pub fn build_arguments(&self) -> serde_json::Value {
+        match &self.arguments_builder {
+            Some(builder) => builder(),
+            None => self.arguments.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for ScheduledJob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScheduledJob")
+            .field("name", &self.name)
+            .field("job_name", &self.job_name)
+            .field("arguments", &self.arguments)
+            .field("arguments_builder", &self.arguments_builder.is_some())
+            .field("cron_expression", &self.cron_expression)
+            .field("catch_up", &self.catch_up)
+            .finish()
+    }
 }
@@ -1,20 +1,28 @@
-use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use sea_orm::{
+    sea_query::OnConflict, ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, Set,
+};
 use std::{error::Error, str::FromStr, time::Duration};
 use tokio::{
     task::JoinHandle,
     time::{sleep, sleep_until, Duration as TokioDuration, Instant},
 };
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
-    database::models::{job, job_status::JobStatus},
+    config::MaintenanceWindow,
+    database::models::{job, job_status::JobStatus, scheduled_run},
     jobs::scheduled_job::ScheduledJob,
 };
 
+/// Shortest time between two iterations of a scheduler task, so a schedule
+/// that keeps yielding due times can't spin the CPU
+const MIN_SLEEP: Duration = Duration::from_secs(1);
+
 /// Scheduler that spawns individual tasks for each scheduled job
 pub struct Scheduler {
     db: DatabaseConnection,
     schedule: Vec<ScheduledJob>,
+    maintenance_windows: Vec<MaintenanceWindow>,
     task_handles: Vec<JoinHandle<()>>,
 }
 
@@ -24,10 +32,18 @@ impl Scheduler {
         Self {
             db,
             schedule,
+            maintenance_windows: Vec::new(),
             task_handles: Vec::new(),
         }
     }
 
+    /// Skip scheduled runs that fall inside any of `windows`.
+    #[must_use]
+    pub fn with_maintenance_windows(mut self, windows: Vec<MaintenanceWindow>) -> Self {
+        self.maintenance_windows = windows;
+        self
+    }
+
     pub async fn run(&mut self) {
         info!(
             "📅 Scheduler started with {} scheduled jobs",
@@ -46,9 +62,10 @@ impl Scheduler {
         for scheduled_job in &self.schedule {
             let db = self.db.clone();
             let job = scheduled_job.clone();
+            let maintenance_windows = self.maintenance_windows.clone();
 
             let handle = tokio::spawn(async move {
-                run_scheduled_job(job, db).await;
+                run_scheduled_job(job, db, maintenance_windows).await;
             });
 
             self.task_handles.push(handle);
@@ -66,20 +83,52 @@ impl Scheduler {
 }
 
 /// Run a single scheduled job in its own loop
-async fn run_scheduled_job(scheduled_job: ScheduledJob, db: DatabaseConnection) {
+async fn run_scheduled_job(
+    scheduled_job: ScheduledJob,
+    db: DatabaseConnection,
+    maintenance_windows: Vec<MaintenanceWindow>,
+) {
     debug!("📅 Starting scheduler task for '{}'", scheduled_job.name);
 
     // Parse the cron expression once
     let schedule = parse_cron_schedule(&scheduled_job).expect("Failed to parse cron schedule");
 
+    if scheduled_job.catch_up {
+        if let Err(e) = catch_up_missed_run(
+            &scheduled_job,
+            &schedule,
+            &maintenance_windows,
+            &db,
+            chrono::Utc::now(),
+        )
+        .await
+        {
+            error!(
+                "❌ Failed to catch up scheduled job '{}': {}",
+                scheduled_job.name, e
+            );
+        }
+    }
+
+    let mut last_run = None;
     loop {
-        match execute_next_scheduled_run(&scheduled_job, &schedule, &db).await {
-            Ok(()) => {
+        let started = Instant::now();
+        match execute_next_scheduled_run(
+            &scheduled_job,
+            &schedule,
+            &mut last_run,
+            &maintenance_windows,
+            &db,
+        )
+        .await
+        {
+            Ok(true) => {
                 debug!(
                     "📅 Created scheduled job '{}' for execution",
                     scheduled_job.name
                 );
             }
+            Ok(false) => {}
             Err(e) => {
                 error!(
                     "❌ Failed to create scheduled job '{}': {}",
@@ -87,6 +136,7 @@ async fn run_scheduled_job(scheduled_job: ScheduledJob, db: DatabaseConnection)
                 );
             }
         }
+        sleep_until(started + MIN_SLEEP).await;
     }
 }
 
@@ -104,23 +154,25 @@ fn parse_cron_schedule(scheduled_job: &ScheduledJob) -> Result<cron::Schedule, (
     }
 }
 
-/// Execute the next scheduled run for a job
+/// Execute the next scheduled run for a job. Returns whether a job was created.
 async fn execute_next_scheduled_run(
     scheduled_job: &ScheduledJob,
     schedule: &cron::Schedule,
+    last_run: &mut Option<chrono::DateTime<chrono::Utc>>,
+    maintenance_windows: &[MaintenanceWindow],
     db: &DatabaseConnection,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<bool, Box<dyn Error>> {
     let now = chrono::Utc::now();
 
     // Get the next execution time
-    let Some(next_execution) = schedule.upcoming(chrono::Utc).take(1).next() else {
+    let Some(next_execution) = next_run_after(schedule, now, *last_run) else {
         error!(
             "❌ Could not determine next execution time for job '{}'",
             scheduled_job.name
         );
         // Sleep for a minute and try again
         sleep(TokioDuration::from_secs(60)).await;
-        return Ok(());
+        return Ok(false);
     };
 
     debug!(
@@ -132,8 +184,114 @@ async fn execute_next_scheduled_run(
     // Sleep until the next execution time
     wait_until_execution_time(next_execution, now).await;
 
-    // Create the job
-    create_scheduled_job(scheduled_job, db).await
+    // Recorded before enqueueing, so a failed insert moves on to the next
+    // run instead of retrying this one in a tight loop
+    *last_run = Some(next_execution);
+    enqueue_scheduled_run(scheduled_job, next_execution, maintenance_windows, db).await
+}
+
+/// The first run strictly after both `now` and the previous run. Never
+/// returns a time that already ran, even if the wall clock moved backwards.
+fn next_run_after(
+    schedule: &cron::Schedule,
+    now: chrono::DateTime<chrono::Utc>,
+    last_run: Option<chrono::DateTime<chrono::Utc>>,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    let from = last_run.map_or(now, |last_run| last_run.max(now));
+    let next = schedule.after(&from).next()?;
+    if next <= from {
+        warn!("📅 Schedule yielded {next}, which is not after {from}; ignoring it");
+        return None;
+    }
+    Some(next)
+}
+
+/// Enqueue the most recent slot before `now` if it comes after the last slot
+/// recorded for this scheduled job. Returns whether a job was created.
+async fn catch_up_missed_run(
+    scheduled_job: &ScheduledJob,
+    schedule: &cron::Schedule,
+    maintenance_windows: &[MaintenanceWindow],
+    db: &DatabaseConnection,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<bool, Box<dyn Error>> {
+    let last = scheduled_run::Entity::find_by_id(scheduled_job.name.clone())
+        .one(db)
+        .await?;
+    // Never ran before, so there is nothing to catch up on
+    let Some(last) = last else {
+        return Ok(false);
+    };
+    let Some(missed) = missed_slot(schedule, last.last_slot_at.and_utc(), now) else {
+        return Ok(false);
+    };
+
+    info!(
+        "⏪ Catching up scheduled job '{}' missed at {}",
+        scheduled_job.name,
+        missed.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    enqueue_scheduled_run(scheduled_job, missed, maintenance_windows, db).await
+}
+
+/// The most recent slot after `since` that is not after `now`.
+fn missed_slot(
+    schedule: &cron::Schedule,
+    since: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    schedule
+        .after(&since)
+        .take_while(|slot| *slot <= now)
+        .last()
+}
+
+/// Create the job for the run scheduled at `at`, unless `at` falls inside a
+/// maintenance window, and record `at` as the last handled slot. Returns
+/// whether a job was created.
+async fn enqueue_scheduled_run(
+    scheduled_job: &ScheduledJob,
+    at: chrono::DateTime<chrono::Utc>,
+    maintenance_windows: &[MaintenanceWindow],
+    db: &DatabaseConnection,
+) -> Result<bool, Box<dyn Error>> {
+    if let Some(window) = maintenance_windows
+        .iter()
+        .find(|window| window.contains(at))
+    {
+        info!(
+            "⏭️ Skipping scheduled job '{}' at {}: maintenance window until {}",
+            scheduled_job.name,
+            at.format("%Y-%m-%d %H:%M:%S UTC"),
+            window.end.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        record_slot(scheduled_job, at, db).await?;
+        return Ok(false);
+    }
+
+    create_scheduled_job(scheduled_job, db).await?;
+    record_slot(scheduled_job, at, db).await?;
+    Ok(true)
+}
+
+/// Store `at` as the last handled slot of `scheduled_job`.
+async fn record_slot(
+    scheduled_job: &ScheduledJob,
+    at: chrono::DateTime<chrono::Utc>,
+    db: &DatabaseConnection,
+) -> Result<(), DbErr> {
+    scheduled_run::Entity::insert(scheduled_run::ActiveModel {
+        name: Set(scheduled_job.name.clone()),
+        last_slot_at: Set(at.naive_utc()),
+    })
+    .on_conflict(
+        OnConflict::column(scheduled_run::Column::Name)
+            .update_column(scheduled_run::Column::LastSlotAt)
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    Ok(())
 }
 
 /// Wait until the specified execution time
@@ -157,7 +315,7 @@ async fn create_scheduled_job(
 
     let new_job = job::ActiveModel {
         r#type: Set(scheduled_job.job_name.to_string()),
-        arguments: Set(scheduled_job.arguments.clone()),
+        arguments: Set(scheduled_job.build_arguments()),
         status: Set(JobStatus::Pending),
         created_at: Set(now),
         updated_at: Set(now),
@@ -167,3 +325,215 @@ async fn create_scheduled_job(
     new_job.insert(db).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+    use serde_json::json;
+
+    use super::{
+        catch_up_missed_run, create_scheduled_job, enqueue_scheduled_run, missed_slot,
+        next_run_after, record_slot,
+    };
+    use crate::{
+        config::MaintenanceWindow,
+        database::{
+            migrations::Migrator,
+            models::{job, scheduled_run},
+        },
+        jobs::scheduled_job::ScheduledJob,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+    };
+
+    #[tokio::test]
+    async fn test_dynamic_arguments_are_computed_per_run() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+
+        let runs = AtomicUsize::new(0);
+        let scheduled_job = ScheduledJob::new(
+            "daily_report",
+            "daily_report_job",
+            serde_json::Value::Null,
+            "0 0 0 * * *",
+        )
+        .with_arguments_builder(move || json!({ "run": runs.fetch_add(1, Ordering::SeqCst) }));
+
+        create_scheduled_job(&scheduled_job, &test.db)
+            .await
+            .unwrap();
+        create_scheduled_job(&scheduled_job, &test.db)
+            .await
+            .unwrap();
+
+        let jobs = job::Entity::find()
+            .order_by_asc(job::Column::CreatedAt)
+            .all(&test.db)
+            .await
+            .unwrap();
+        let arguments: Vec<_> = jobs
+            .iter()
+            .filter(|job| job.r#type == "daily_report_job")
+            .map(|job| job.arguments.clone())
+            .collect();
+
+        assert_eq!(arguments.len(), 2);
+        assert_ne!(arguments[0], arguments[1]);
+    }
+
+    #[tokio::test]
+    async fn test_run_inside_maintenance_window_is_suppressed() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let scheduled_job = ScheduledJob::new(
+            "nightly_sync",
+            "maintenance_window_test_job",
+            serde_json::Value::Null,
+            "0 0 2 * * *",
+        );
+        let window = MaintenanceWindow {
+            start: "2026-10-18T01:00:00Z".parse().unwrap(),
+            end: "2026-10-18T05:00:00Z".parse().unwrap(),
+        };
+
+        let during = "2026-10-18T02:00:00Z".parse().unwrap();
+        let created = enqueue_scheduled_run(&scheduled_job, during, &[window], &test.db)
+            .await
+            .unwrap();
+        assert!(!created);
+
+        let count_jobs = || {
+            job::Entity::find()
+                .filter(job::Column::Type.eq("maintenance_window_test_job"))
+                .count(&test.db)
+        };
+        assert_eq!(count_jobs().await.unwrap(), 0);
+
+        // The end of the window is exclusive
+        let after = window.end;
+        let created = enqueue_scheduled_run(&scheduled_job, after, &[window], &test.db)
+            .await
+            .unwrap();
+        assert!(created);
+        assert_eq!(count_jobs().await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_next_run_is_after_the_last_run() {
+        let schedule: cron::Schedule = "* * * * * *".parse().unwrap();
+        let now: chrono::DateTime<chrono::Utc> = "2026-10-17T12:00:00.500Z".parse().unwrap();
+        assert_eq!(
+            next_run_after(&schedule, now, None),
+            Some("2026-10-17T12:00:01Z".parse().unwrap())
+        );
+
+        // The clock went back after the 12:00:05 run; that run must not repeat
+        let last_run = "2026-10-17T12:00:05Z".parse().unwrap();
+        assert_eq!(
+            next_run_after(&schedule, now, Some(last_run)),
+            Some("2026-10-17T12:00:06Z".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_missed_slot_is_the_latest_one_before_now() {
+        let schedule: cron::Schedule = "0 0 2 * * *".parse().unwrap();
+        let since = "2026-10-14T02:00:01Z".parse().unwrap();
+        let now = "2026-10-17T08:00:00Z".parse().unwrap();
+        assert_eq!(
+            missed_slot(&schedule, since, now),
+            Some("2026-10-17T02:00:00Z".parse().unwrap())
+        );
+
+        let since = "2026-10-17T02:00:01Z".parse().unwrap();
+        assert_eq!(missed_slot(&schedule, since, now), None);
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_enqueues_one_missed_run() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let scheduled_job = ScheduledJob::new(
+            "nightly_cleanup",
+            "catch_up_test_job",
+            serde_json::Value::Null,
+            "0 0 2 * * *",
+        )
+        .with_catch_up();
+        let schedule = scheduled_job.cron_expression.parse().unwrap();
+        let now = chrono::Utc::now();
+        let count_jobs = || {
+            job::Entity::find()
+                .filter(job::Column::Type.eq("catch_up_test_job"))
+                .count(&test.db)
+        };
+
+        // Nothing ran before, so there is nothing to make up
+        assert!(
+            !catch_up_missed_run(&scheduled_job, &schedule, &[], &test.db, now)
+                .await
+                .unwrap()
+        );
+
+        // Last ran three days ago, and cleanup has deleted that job since:
+        // only one of the missed runs is made up
+        let three_days_ago = now - chrono::Duration::days(3);
+        record_slot(&scheduled_job, three_days_ago, &test.db)
+            .await
+            .unwrap();
+        assert!(
+            catch_up_missed_run(&scheduled_job, &schedule, &[], &test.db, now)
+                .await
+                .unwrap()
+        );
+        assert_eq!(count_jobs().await.unwrap(), 1);
+
+        // The made-up run counts as the latest one
+        assert!(
+            !catch_up_missed_run(&scheduled_job, &schedule, &[], &test.db, now)
+                .await
+                .unwrap()
+        );
+        assert_eq!(count_jobs().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_schedules_of_one_job_type_catch_up_separately() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let nightly = ScheduledJob::new(
+            "nightly_export",
+            "shared_catch_up_test_job",
+            serde_json::Value::Null,
+            "0 0 2 * * *",
+        )
+        .with_catch_up();
+        let hourly = ScheduledJob::new(
+            "hourly_export",
+            "shared_catch_up_test_job",
+            serde_json::Value::Null,
+            "0 0 * * * *",
+        )
+        .with_catch_up();
+        // Whole seconds, as slots are
+        let now = chrono::SubsecRound::trunc_subsecs(chrono::Utc::now(), 0);
+
+        // The hourly schedule just ran; the nightly one missed yesterday's run
+        enqueue_scheduled_run(&hourly, now, &[], &test.db)
+            .await
+            .unwrap();
+        record_slot(&nightly, now - chrono::Duration::days(2), &test.db)
+            .await
+            .unwrap();
+
+        let schedule = nightly.cron_expression.parse().unwrap();
+        assert!(catch_up_missed_run(&nightly, &schedule, &[], &test.db, now)
+            .await
+            .unwrap());
+
+        let last = scheduled_run::Entity::find_by_id("hourly_export".to_string())
+            .one(&test.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(last.last_slot_at, now.naive_utc());
+    }
+}
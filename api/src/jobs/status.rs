@@ -0,0 +1,185 @@
+//! Read-only lookup of a job's status and execution history, e.g. to check
+//! on a job after enqueuing it or to back a job dashboard endpoint.
+
+use chrono::NaiveDateTime;
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::database::models::{job, job_execution};
+pub use crate::database::models::{
+    job_result::JobResult as ExecutionResult, job_status::JobStatus,
+};
+
+/// A job's current state and every attempt to run it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JobSummary {
+    pub id: Uuid,
+    pub job_type: String,
+    pub status: JobStatus,
+    pub retry_count: i32,
+    pub created_at: NaiveDateTime,
+    /// When a pending or retrying job becomes due; `None` if it's due now
+    pub next_execution_at: Option<NaiveDateTime>,
+    /// Oldest first
+    pub executions: Vec<ExecutionSummary>,
+}
+
+/// One attempt to run a job.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExecutionSummary {
+    pub result: ExecutionResult,
+    pub started_at: NaiveDateTime,
+    pub finished_at: NaiveDateTime,
+    pub execution_time_ms: i64,
+    pub failure_reason: Option<String>,
+    /// What the run passed to [`set_output`](super::output::set_output); only
+    /// kept for completed runs
+    pub output: Option<serde_json::Value>,
+}
+
+impl JobSummary {
+    /// Output of the most recent completed run, if it set one.
+    pub fn output(&self) -> Option<&serde_json::Value> {
+        self.executions
+            .iter()
+            .rev()
+            .find(|execution| execution.result == ExecutionResult::Completed)
+            .and_then(|execution| execution.output.as_ref())
+    }
+}
+
+/// Load the job with `id` and its executions. `None` if there is no such
+/// job, e.g. because it was deleted or cleaned up.
+pub async fn get_job<C: ConnectionTrait>(db: &C, id: Uuid) -> Result<Option<JobSummary>, DbErr> {
+    let Some(job) = job::Entity::find_by_id(id).one(db).await? else {
+        return Ok(None);
+    };
+
+    let executions = job_execution::Entity::find()
+        .filter(job_execution::Column::JobId.eq(id))
+        .order_by_asc(job_execution::Column::StartedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|execution| ExecutionSummary {
+            result: execution.result,
+            started_at: execution.started_at,
+            finished_at: execution.finished_at,
+            execution_time_ms: execution.execution_time_ms,
+            failure_reason: execution.failure_reason,
+            output: execution.output,
+        })
+        .collect();
+
+    Ok(Some(JobSummary {
+        id: job.id,
+        job_type: job.r#type,
+        status: job.status,
+        retry_count: job.retry_count,
+        created_at: job.created_at,
+        next_execution_at: job.next_execution_at,
+        executions,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use sea_orm::{ActiveModelTrait, Set};
+
+    use super::{get_job, ExecutionResult, JobStatus};
+    use crate::{
+        app::App,
+        database::{migrations::Migrator, models::job_execution},
+        job_queue::JobQueue,
+        jobs::{Job, JobError},
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+    };
+
+    struct ExportJob;
+
+    impl Job for ExportJob {
+        type Arguments = ();
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            Ok(())
+        }
+
+        fn name() -> &'static str {
+            "status_test_export"
+        }
+    }
+
+    async fn record_execution(
+        db: &sea_orm::DatabaseConnection,
+        job_id: uuid::Uuid,
+        result: ExecutionResult,
+        minutes_ago: i64,
+        failure_reason: Option<&str>,
+    ) {
+        let started_at = (Utc::now() - Duration::minutes(minutes_ago)).naive_utc();
+        job_execution::ActiveModel {
+            job_id: Set(job_id),
+            result: Set(result),
+            started_at: Set(started_at),
+            finished_at: Set(started_at + Duration::seconds(2)),
+            execution_time_ms: Set(2000),
+            failure_reason: Set(failure_reason.map(str::to_string)),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_job_returns_status_and_executions() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let db = &test.db;
+        let id = JobQueue::database()
+            .add::<ExportJob, ()>(db, ())
+            .await
+            .unwrap();
+
+        let summary = get_job(db, id).await.unwrap().unwrap();
+        assert_eq!(summary.job_type, ExportJob::name());
+        assert_eq!(summary.status, JobStatus::Pending);
+        assert!(summary.executions.is_empty());
+
+        record_execution(db, id, ExecutionResult::Completed, 1, None).await;
+        record_execution(
+            db,
+            id,
+            ExecutionResult::Failed,
+            10,
+            Some("smtp unavailable"),
+        )
+        .await;
+
+        let summary = get_job(db, id).await.unwrap().unwrap();
+        let results: Vec<_> = summary
+            .executions
+            .iter()
+            .map(|execution| execution.result)
+            .collect();
+        assert_eq!(
+            results,
+            [ExecutionResult::Failed, ExecutionResult::Completed]
+        );
+        assert_eq!(
+            summary.executions[0].failure_reason.as_deref(),
+            Some("smtp unavailable")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_job_returns_none_for_unknown_id() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+
+        assert!(get_job(&test.db, uuid::Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
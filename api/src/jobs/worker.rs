@@ -1,16 +1,23 @@
 use chrono::NaiveDateTime;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
-    QuerySelect, TransactionTrait,
+    sea_query::{Expr, SimpleExpr},
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, Order,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Select, TransactionTrait,
 };
 use sqlx::postgres::PgListener;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 use std::time::{Duration, Instant};
-use tokio::time::{timeout};
-use tracing::{debug, error, info, warn};
+use tokio::{sync::Semaphore, task::JoinSet, time::timeout};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
 use crate::app::App;
+use crate::log_context::LogContext;
 use crate::{
     database::models::{
+        dead_letter_job,
         job::{self, Entity as JobEntity},
         job_execution,
         job_result::JobResult as JobResultEnum,
@@ -18,14 +25,51 @@ use crate::{
     },
     {
         config::WorkerQueueConfig,
-        jobs::{job_result::JobResult, JobError},
+        jobs::{
+            deliver_job_callback_job::{
+                DeliverJobCallbackArgs, DeliverJobCallbackJob, JobCallbackPayload,
+            },
+            job_result::JobResult,
+            JobError,
+        },
     },
 };
 
+use super::advisory_lock::lock_job_type_for_transaction;
+use super::cancellation::{Cancellation, RunningJobs, POLL_INTERVAL as CANCELLATION_POLL_INTERVAL};
 use super::job_registry::JobRegistry;
+use super::output::JobOutput;
 
 const POLL_INTERVAL_SECS: u64 = 30;
 
+/// How often a worker with free slots looks for new jobs while others are
+/// still running, in case it misses a notification.
+const RECLAIM_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Jobs currently executing in this process, across all worker pools.
+static IN_FLIGHT_JOBS: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of jobs currently executing in this process.
+pub fn in_flight_jobs() -> usize {
+    IN_FLIGHT_JOBS.load(Ordering::Relaxed)
+}
+
+/// Keeps `IN_FLIGHT_JOBS` accurate even if the job future is dropped mid-run.
+struct InFlightGuard;
+
+impl InFlightGuard {
+    fn new() -> Self {
+        IN_FLIGHT_JOBS.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        IN_FLIGHT_JOBS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
 pub async fn worker<ExtraConfig>(
     worker_instance_name: &str,
     worker_config: &WorkerQueueConfig,
@@ -49,40 +93,19 @@ where
     );
 
     loop {
-        // Try to claim and execute all available jobs (drain the queue)
-        let mut jobs_processed = 0;
-        loop {
-            let job_option = claim_oldest_viable_job(worker_config, &app.db).await?;
-
-            let Some(job) = job_option else {
-                // No more jobs available
-                if jobs_processed > 0 {
-                    debug!(
-                        "Worker '{}' processed {} job(s), queue drained",
-                        worker_instance_name, jobs_processed
-                    );
-                }
-                break;
-            };
-
+        let jobs_processed = drain_queue(
+            worker_instance_name,
+            worker_config,
+            &app,
+            job_registry,
+            Some(&mut listener),
+        )
+        .await?;
+        if jobs_processed > 0 {
             debug!(
-                "🔧 Worker '{worker_instance_name}' claimed {status} {1}({0})",
-                job.id,
-                job.r#type,
-                status = job.status,
+                "Worker '{}' processed {} job(s), queue drained",
+                worker_instance_name, jobs_processed
             );
-
-            // Execute the job
-            execute_and_update_job(
-                &job,
-                worker_config,
-                &app,
-                job_registry,
-                worker_instance_name,
-            )
-            .await?;
-
-            jobs_processed += 1;
         }
 
         // Wait for NOTIFY or periodic timeout as a safety net
@@ -104,27 +127,223 @@ where
     }
 }
 
+/// Claim and execute jobs until none are left, running up to
+/// `worker_config.concurrency` of them at once. Returns how many were run.
+///
+/// Each job runs on its own task, with its own timeout and execution
+/// record. While jobs are running, free slots pick up jobs enqueued after the
+/// queue went empty, on a `listener` notification or every
+/// `RECLAIM_INTERVAL`. The running jobs are checked for cancellation
+/// together, with one query per `CANCELLATION_POLL_INTERVAL`. On an error,
+/// the jobs already running are finished before it is returned.
+async fn drain_queue<ExtraConfig>(
+    worker_instance_name: &str,
+    worker_config: &WorkerQueueConfig,
+    app: &App<ExtraConfig>,
+    job_registry: &JobRegistry<ExtraConfig>,
+    mut listener: Option<&mut PgListener>,
+) -> Result<usize, DbErr>
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    let slots = Arc::new(Semaphore::new(worker_config.concurrency.max(1)));
+    let shared = Arc::new((
+        worker_config.clone(),
+        job_registry.clone(),
+        worker_instance_name.to_string(),
+    ));
+    let mut running = JoinSet::new();
+    let running_jobs = RunningJobs::default();
+    // Aborted when dropped, once the queue has been drained
+    let mut cancellation_watch = JoinSet::new();
+    cancellation_watch
+        .spawn(watch_for_cancellation(app.db.clone(), running_jobs.clone()).in_current_span());
+    let mut jobs_processed = 0;
+    let mut first_error = None;
+
+    loop {
+        // Fill the free slots, stopping early once the queue is empty
+        let mut queue_empty = false;
+        while first_error.is_none() {
+            let Ok(permit) = slots.clone().try_acquire_owned() else {
+                break;
+            };
+            let job = match claim_oldest_viable_job(worker_config, job_registry, &app.db).await {
+                Ok(Some(job)) => job,
+                Ok(None) => {
+                    queue_empty = true;
+                    break;
+                }
+                Err(e) => {
+                    first_error = Some(e);
+                    break;
+                }
+            };
+
+            debug!(
+                "🔧 Worker '{worker_instance_name}' claimed {status} {1}({0})",
+                job.id,
+                job.r#type,
+                status = job.status,
+            );
+
+            let app = app.clone();
+            let shared = shared.clone();
+            let tracked = running_jobs.track(job.id);
+            running.spawn(
+                async move {
+                    let _permit = permit;
+                    let (worker_config, job_registry, worker_instance_name) = &*shared;
+                    let cancellation = tracked.cancellation.clone();
+                    execute_and_update_job(
+                        &job,
+                        worker_config,
+                        &app,
+                        job_registry,
+                        worker_instance_name,
+                        cancellation,
+                    )
+                    .await
+                }
+                .in_current_span(),
+            );
+            jobs_processed += 1;
+        }
+
+        // Free slots look for new jobs while the others run, instead of
+        // idling until a running job finishes
+        if queue_empty && !running.is_empty() && slots.available_permits() > 0 {
+            let notified = async {
+                match listener.as_deref_mut() {
+                    Some(listener) => listener.recv().await.map(drop),
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                finished = running.join_next() => {
+                    handle_finished(worker_instance_name, finished, &mut first_error);
+                }
+                notification = notified => {
+                    if let Err(e) = notification {
+                        error!("Worker '{}' PgListener error: {}", worker_instance_name, e);
+                        first_error.get_or_insert(DbErr::Custom(e.to_string()));
+                    }
+                }
+                () = tokio::time::sleep(RECLAIM_INTERVAL) => {}
+            }
+            continue;
+        }
+
+        // A finished job frees a slot; with none running, the queue is drained
+        let finished = running.join_next().await;
+        if finished.is_none() {
+            break;
+        }
+        handle_finished(worker_instance_name, finished, &mut first_error);
+    }
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(jobs_processed),
+    }
+}
+
+fn handle_finished(
+    worker_instance_name: &str,
+    finished: Option<Result<Result<(), DbErr>, tokio::task::JoinError>>,
+    first_error: &mut Option<DbErr>,
+) {
+    match finished {
+        Some(Ok(Ok(()))) | None => {}
+        Some(Ok(Err(e))) => {
+            first_error.get_or_insert(e);
+        }
+        Some(Err(e)) => error!("Worker '{}' lost a job task: {}", worker_instance_name, e),
+    }
+}
+
 async fn execute_and_update_job<ExtraConfig>(
     job_model: &job::Model,
     worker_config: &WorkerQueueConfig,
     app: &App<ExtraConfig>,
     job_registry: &JobRegistry<ExtraConfig>,
     worker_instance_name: &str,
+    cancellation: Cancellation,
+) -> Result<(), DbErr>
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    let _in_flight = InFlightGuard::new();
+
+    // Restore the context of the request that enqueued the job, so its logs
+    // (and any jobs it enqueues) can be traced back to that request
+    let log_context: LogContext = job_model
+        .log_context
+        .clone()
+        .and_then(|context| serde_json::from_value(context).ok())
+        .unwrap_or_default();
+    let span = info_span!(
+        "job",
+        job_id = %job_model.id,
+        job_type = %job_model.r#type,
+        request_id = log_context.request_id.as_deref(),
+        user_id = log_context.user_id.map(tracing::field::display),
+    );
+
+    let execution = run_and_record_job(
+        job_model,
+        worker_config,
+        app,
+        job_registry,
+        worker_instance_name,
+        cancellation,
+    );
+    log_context.scope(execution.instrument(span)).await
+}
+
+async fn run_and_record_job<ExtraConfig>(
+    job_model: &job::Model,
+    worker_config: &WorkerQueueConfig,
+    app: &App<ExtraConfig>,
+    job_registry: &JobRegistry<ExtraConfig>,
+    worker_instance_name: &str,
+    cancellation: Cancellation,
 ) -> Result<(), DbErr>
 where
     ExtraConfig: Clone + Send + Sync + 'static,
 {
     // Execute the job and measure execution time
     let start_time = Instant::now();
-    let timeout_duration = Duration::from_secs(u64::from(worker_config.job_timeout));
+    let timeout_duration = job_registry
+        .timeout(&job_model.r#type)
+        .unwrap_or_else(|| Duration::from_secs(u64::from(worker_config.job_timeout)));
+    // Retry decisions below use the job type's own limit when it declares one
+    let worker_config = &WorkerQueueConfig {
+        max_retries: job_registry
+            .max_retries(&job_model.r#type)
+            .unwrap_or(worker_config.max_retries),
+        ..worker_config.clone()
+    };
 
-    let result = (timeout(timeout_duration, async {
-        job_registry
-            .execute(app, &job_model.r#type, &job_model.arguments)
-            .await
-    })
-    .await)
-        .unwrap_or(JobResult::TimedOut);
+    let output = JobOutput::default();
+
+    let result = match job_model.decoded_arguments() {
+        Ok(arguments) => {
+            let execution = output.clone().scope(async {
+                (timeout(timeout_duration, async {
+                    job_registry
+                        .execute(app, &job_model.r#type, &arguments)
+                        .await
+                })
+                .await)
+                    .unwrap_or(JobResult::TimedOut)
+            });
+            cancellation.clone().scope(execution).await
+        }
+        Err(e) => JobResult::Failed(JobError::FailPermanently(format!(
+            "Failed to decompress job arguments: {e}"
+        ))),
+    };
 
     let execution_duration = start_time.elapsed();
 
@@ -145,9 +364,11 @@ where
     .record(execution_duration.as_secs_f64());
 
     // Update job status based on result
-    update_job_after_execution(
+    let status = update_job_after_execution(
         job_model,
         &result,
+        output.take(),
+        cancellation.is_cancelled(),
         execution_duration,
         worker_config,
         &app.db,
@@ -155,27 +376,200 @@ where
     )
     .await?;
 
+    if status == JobStatus::Failed {
+        let reason = failure_reason(&result).unwrap_or_default();
+        if let Ok(arguments) = job_model.decoded_arguments() {
+            job_registry
+                .on_permanent_failure(app, &job_model.r#type, arguments, reason.clone())
+                .await;
+        }
+        if worker_config.dead_letter {
+            move_to_dead_letter(job_model, reason, &app.db).await?;
+        }
+    }
+
+    if status.is_terminal() && status != JobStatus::Cancelled {
+        if let Some(callback_url) = &job_model.callback_url {
+            enqueue_callback(app, job_model, status, &result, callback_url).await;
+        }
+    }
+
     Ok(())
 }
 
-async fn claim_oldest_viable_job(
+/// Every `CANCELLATION_POLL_INTERVAL`, look up which of `running_jobs` have
+/// `cancel_requested` set and signal them. Runs until the caller aborts it.
+async fn watch_for_cancellation(db: DatabaseConnection, running_jobs: RunningJobs) {
+    loop {
+        tokio::time::sleep(CANCELLATION_POLL_INTERVAL).await;
+        let ids = running_jobs.ids();
+        if ids.is_empty() {
+            continue;
+        }
+
+        let requested = JobEntity::find()
+            .select_only()
+            .column(job::Column::Id)
+            .filter(job::Column::Id.is_in(ids))
+            .filter(job::Column::CancelRequested.eq(true))
+            .into_tuple::<uuid::Uuid>()
+            .all(&db)
+            .await;
+        match requested {
+            Ok(requested) => {
+                for job_id in requested {
+                    if running_jobs.cancel(job_id) {
+                        info!("🛑 Cancellation requested for running job {job_id}");
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to check running jobs for cancellation: {e}"),
+        }
+    }
+}
+
+/// Enqueue delivery of the job's outcome to its callback URL. A failure here
+/// is logged rather than returned so it doesn't take the worker down.
+async fn enqueue_callback<ExtraConfig>(
+    app: &App<ExtraConfig>,
+    job_model: &job::Model,
+    status: JobStatus,
+    result: &JobResult,
+    callback_url: &str,
+) where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    let args = DeliverJobCallbackArgs {
+        callback_url: callback_url.to_string(),
+        payload: JobCallbackPayload {
+            job_id: job_model.id,
+            job_type: job_model.r#type.clone(),
+            status: sea_orm::ActiveEnum::to_value(&status),
+            error: failure_reason(result),
+        },
+    };
+
+    if let Err(e) = app
+        .job_queue
+        .add::<DeliverJobCallbackJob<ExtraConfig>, ExtraConfig>(&app.db, args)
+        .await
+    {
+        error!(
+            "❌ Failed to enqueue callback for job {}({}): {}",
+            job_model.r#type, job_model.id, e
+        );
+    }
+}
+
+fn failure_reason(result: &JobResult) -> Option<String> {
+    match result {
+        JobResult::Failed(reason) => Some(reason.to_string()),
+        JobResult::TimedOut => Some("Job execution timed out".to_string()),
+        JobResult::Completed => None,
+    }
+}
+
+/// Jobs that haven't used up their retries, by the job type's own limit or
+/// else the pool's. A job waiting on its last retry has `retry_count ==
+/// max_retries` and still runs.
+fn retries_left<ExtraConfig>(
     worker_config: &WorkerQueueConfig,
-    db: &DatabaseConnection,
-) -> Result<Option<job::Model>, DbErr> {
-    let txn = db.begin().await?;
-    let now = chrono::Utc::now().naive_utc();
+    job_registry: &JobRegistry<ExtraConfig>,
+) -> Condition
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    worker_config
+        .jobs
+        .iter()
+        .fold(Condition::any(), |condition, job_type| {
+            let max_retries = job_registry
+                .max_retries(job_type)
+                .unwrap_or(worker_config.max_retries);
+            condition.add(
+                job::Column::Type
+                    .eq(job_type)
+                    .and(job::Column::RetryCount.lte(max_retries)),
+            )
+        })
+}
 
-    // Query for all viable jobs (pending jobs that are ready for execution)
-    let job_option = JobEntity::find()
+/// Pending jobs this worker could run now, most urgent first, then oldest.
+/// The status filter matches the `idx-job-claimable` partial index.
+fn viable_jobs<ExtraConfig>(
+    worker_config: &WorkerQueueConfig,
+    job_registry: &JobRegistry<ExtraConfig>,
+    saturated: &[&str],
+    now: NaiveDateTime,
+) -> Select<JobEntity>
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    JobEntity::find()
         .filter(job::Column::Type.is_in(worker_config.jobs.iter()))
+        .filter(job::Column::Type.is_not_in(saturated.iter().copied()))
         .filter(job::Column::Status.is_in([JobStatus::Pending, JobStatus::PendingRetry]))
-        .filter(job::Column::RetryCount.lt(worker_config.max_retries))
+        .filter(retries_left(worker_config, job_registry))
         .filter(
             job::Column::NextExecutionAt
                 .is_null()
                 .or(job::Column::NextExecutionAt.lte(now)),
         )
-        .order_by_asc(job::Column::CreatedAt) // Select oldest job first
+        .order_by_desc(job::Column::Priority) // Most urgent first,
+        .order_by(claim_age(worker_config), Order::Asc) // then oldest
+}
+
+/// Age used to order jobs of equal priority: `created_at`, pushed later by
+/// `retry_age_penalty_seconds` per retry so failing jobs don't hog the pool.
+fn claim_age(worker_config: &WorkerQueueConfig) -> SimpleExpr {
+    match worker_config.retry_age_penalty_seconds {
+        // Plain column, so the claimable index covers the ordering
+        0 => Expr::col((job::Entity, job::Column::CreatedAt)).into(),
+        penalty => Expr::cust(format!(
+            r#""job"."created_at" + "job"."retry_count" * interval '{penalty} seconds'"#
+        )),
+    }
+}
+
+async fn claim_oldest_viable_job<ExtraConfig>(
+    worker_config: &WorkerQueueConfig,
+    job_registry: &JobRegistry<ExtraConfig>,
+    db: &DatabaseConnection,
+) -> Result<Option<job::Model>, DbErr>
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    let txn = db.begin().await?;
+    let now = chrono::Utc::now().naive_utc();
+
+    // Skip types already running at their cap. Holding each capped type's lock
+    // until commit keeps two workers from both taking the last slot; sorting
+    // keeps the lock order the same everywhere so workers can't deadlock.
+    let mut capped: Vec<(&str, usize)> = worker_config
+        .jobs
+        .iter()
+        .filter_map(|job_type| Some((job_type.as_str(), job_registry.max_concurrency(job_type)?)))
+        .collect();
+    capped.sort_unstable();
+    let mut saturated = Vec::new();
+    for (job_type, max) in capped {
+        lock_job_type_for_transaction(&txn, job_type).await?;
+        let running = JobEntity::find()
+            .filter(job::Column::Type.eq(job_type))
+            .filter(job::Column::Status.eq(JobStatus::Running))
+            .count(&txn)
+            .await?;
+        if running >= max as u64 {
+            debug!(
+                job_type,
+                running, "Job type at max concurrency, leaving it pending"
+            );
+            saturated.push(job_type);
+        }
+    }
+
+    // Query for all viable jobs (pending jobs that are ready for execution)
+    let job_option = viable_jobs(worker_config, job_registry, &saturated, now)
         .limit(1)
         .lock_exclusive()
         .one(&txn)
@@ -198,11 +592,13 @@ async fn claim_oldest_viable_job(
 async fn update_job_after_execution(
     job_model: &job::Model,
     execution_result: &JobResult,
+    output: Option<serde_json::Value>,
+    cancelled: bool,
     execution_duration: Duration,
     worker_config: &WorkerQueueConfig,
     db: &DatabaseConnection,
     worker_instance_name: &str,
-) -> Result<(), DbErr> {
+) -> Result<JobStatus, DbErr> {
     let now = chrono::Utc::now().naive_utc();
     #[allow(clippy::cast_possible_truncation)]
     let execution_time_ms = execution_duration.as_millis() as i64;
@@ -219,17 +615,25 @@ async fn update_job_after_execution(
         started_at: sea_orm::Set(now - chrono::Duration::milliseconds(execution_time_ms)),
         finished_at: sea_orm::Set(now),
         execution_time_ms: sea_orm::Set(execution_time_ms),
-        failure_reason: sea_orm::Set(match execution_result {
-            JobResult::Failed(reason) => Some(reason.to_string()),
-            JobResult::TimedOut => Some("Job execution timed out".to_string()),
-            JobResult::Completed => None,
-        }),
+        failure_reason: sea_orm::Set(failure_reason(execution_result)),
+        output: sea_orm::Set(output.filter(|_| matches!(execution_result, JobResult::Completed))),
         created_at: sea_orm::Set(now),
     };
 
     job_execution_active_model.insert(db).await?;
 
     match execution_result {
+        // A job that finished anyway keeps its result; one that stopped isn't retried
+        JobResult::Failed(_) | JobResult::TimedOut if cancelled => {
+            info!(
+                "🛑 Worker '{worker_instance_name}' cancelled job {}({}) after {:?}",
+                job_model.r#type, job_model.id, execution_duration
+            );
+            let mut active_job: job::ActiveModel = job_model.clone().into();
+            active_job.status = sea_orm::Set(JobStatus::Cancelled);
+            active_job.update(db).await?;
+            Ok(JobStatus::Cancelled)
+        }
         JobResult::Completed => {
             // Job succeeded - mark as completed
             info!(
@@ -239,6 +643,7 @@ async fn update_job_after_execution(
             let mut active_job: job::ActiveModel = job_model.clone().into();
             active_job.status = sea_orm::Set(JobStatus::Completed);
             active_job.update(db).await?;
+            Ok(JobStatus::Completed)
         }
         result => {
             // Job failed - handle retry logic
@@ -252,11 +657,9 @@ async fn update_job_after_execution(
                 worker_instance_name,
                 execution_duration,
             )
-            .await?;
+            .await
         }
     }
-
-    Ok(())
 }
 
 async fn handle_job_failure(
@@ -267,7 +670,7 @@ async fn handle_job_failure(
     db: &DatabaseConnection,
     worker_instance_name: &str,
     execution_duration: Duration,
-) -> Result<(), DbErr> {
+) -> Result<JobStatus, DbErr> {
     let should_retry = match result {
         JobResult::Failed(JobError::FailPermanently(_)) => false,
         JobResult::Failed(JobError::TryAgainLater(_)) | JobResult::TimedOut => {
@@ -311,37 +714,1222 @@ async fn update_job_for_retry(
     next_execution_at: NaiveDateTime,
     retry_count: i32,
     db: &DatabaseConnection,
-) -> Result<(), DbErr> {
+) -> Result<JobStatus, DbErr> {
     let mut active_model: job::ActiveModel = job_model.clone().into();
     active_model.status = sea_orm::Set(JobStatus::PendingRetry);
     active_model.retry_count = sea_orm::Set(retry_count);
     active_model.next_execution_at = sea_orm::Set(Some(next_execution_at));
     active_model.update(db).await?;
-    Ok(())
+    Ok(JobStatus::PendingRetry)
 }
 
 async fn update_job_as_permanently_failed(
     job_model: &job::Model,
     result: &JobResult,
     db: &DatabaseConnection,
-) -> Result<(), DbErr> {
-    let mut active_model: job::ActiveModel = job_model.clone().into();
-    active_model.status = sea_orm::Set(match result {
+) -> Result<JobStatus, DbErr> {
+    let status = match result {
         JobResult::Failed(_) | JobResult::TimedOut => JobStatus::Failed,
         JobResult::Completed => JobStatus::Completed, // Should not happen in this context
-    });
+    };
+    let mut active_model: job::ActiveModel = job_model.clone().into();
+    active_model.status = sea_orm::Set(status);
     active_model.update(db).await?;
+    Ok(status)
+}
+
+/// Copy a permanently failed job to `dead_letter_job` and remove it, with
+/// its executions, from the queue.
+async fn move_to_dead_letter(
+    job_model: &job::Model,
+    failure_reason: String,
+    db: &DatabaseConnection,
+) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+    dead_letter_job::ActiveModel {
+        id: sea_orm::Set(job_model.id),
+        r#type: sea_orm::Set(job_model.r#type.clone()),
+        arguments: sea_orm::Set(job_model.arguments.clone()),
+        compressed_arguments: sea_orm::Set(job_model.compressed_arguments.clone()),
+        retry_count: sea_orm::Set(job_model.retry_count),
+        failure_reason: sea_orm::Set(Some(failure_reason)),
+        log_context: sea_orm::Set(job_model.log_context.clone()),
+        created_at: sea_orm::Set(job_model.created_at),
+        failed_at: sea_orm::Set(chrono::Utc::now().naive_utc()),
+    }
+    .insert(&txn)
+    .await?;
+    JobEntity::delete_by_id(job_model.id).exec(&txn).await?;
+    txn.commit().await?;
+
+    info!(
+        "🪦 Moved job {}({}) to the dead letter table",
+        job_model.r#type, job_model.id
+    );
     Ok(())
 }
 
 fn calculate_next_retry_time(retry_count: i32, worker_config: &WorkerQueueConfig) -> NaiveDateTime {
-    let delay_seconds = worker_config.base_retry_delay_seconds
-        * worker_config
-            .retry_backoff_multiplier
-            .pow(retry_count.try_into().unwrap_or(5));
+    let delay_seconds = with_jitter(
+        retry_delay_seconds(retry_count, worker_config),
+        worker_config.retry_jitter_fraction,
+    );
+
+    let delay = i64::try_from(delay_seconds)
+        .ok()
+        .and_then(chrono::Duration::try_seconds)
+        .unwrap_or(chrono::Duration::MAX);
+    let now = chrono::Utc::now().naive_utc();
+    now.checked_add_signed(delay).unwrap_or(NaiveDateTime::MAX)
+}
 
-    let delay_seconds_i64 = delay_seconds.try_into().unwrap_or(i64::MAX);
-    chrono::Utc::now().naive_utc() + chrono::Duration::seconds(delay_seconds_i64)
+/// `base * multiplier^retry_count`, saturating instead of overflowing and
+/// capped at `max_retry_delay_seconds`.
+fn retry_delay_seconds(retry_count: i32, worker_config: &WorkerQueueConfig) -> u64 {
+    let exponent = u32::try_from(retry_count).unwrap_or(0);
+    worker_config
+        .base_retry_delay_seconds
+        .saturating_mul(
+            worker_config
+                .retry_backoff_multiplier
+                .saturating_pow(exponent),
+        )
+        .min(worker_config.max_retry_delay_seconds)
+}
+
+/// Shorten `delay_seconds` by a random amount of up to `fraction` of it, so
+/// jobs that failed together don't all retry at the same moment. 1.0 is
+/// "full jitter", 0.5 "equal jitter" and 0.0 leaves the delay as is.
+fn with_jitter(delay_seconds: u64, fraction: f64) -> u64 {
+    let fraction = fraction.clamp(0.0, 1.0);
+    if fraction.is_nan() || fraction <= 0.0 {
+        return delay_seconds;
+    }
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    let window = (delay_seconds as f64 * fraction) as u64;
+    delay_seconds - fastrand::u64(0..=window.min(delay_seconds))
 }
 
 // Execution is provided by the application via the `executor` function parameter.
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    use sea_orm::{
+        ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, PaginatorTrait,
+        QueryFilter, QuerySelect, QueryTrait, Set,
+    };
+    use tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        Layer, Registry,
+    };
+
+    use super::{
+        calculate_next_retry_time, claim_oldest_viable_job, drain_queue, execute_and_update_job,
+        retry_delay_seconds, viable_jobs, with_jitter,
+    };
+    use crate::{
+        app::App,
+        config::WorkerQueueConfig,
+        database::{
+            migrations::Migrator,
+            models::{dead_letter_job, job, job_status::JobStatus},
+        },
+        job_queue::JobQueue,
+        jobs::{
+            self,
+            cancellation::{self, Cancellation},
+            job_registry::JobRegistry,
+            output,
+            ping_job::{wait_for_job, PingJob},
+            status::get_job,
+            Job, JobError,
+        },
+        log_context::LogContext,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+    };
+
+    /// Records the fields of every `job` span created while it is the default subscriber.
+    #[derive(Clone, Default)]
+    struct JobSpanFields(Arc<Mutex<Vec<(String, String)>>>);
+
+    impl tracing::field::Visit for JobSpanFields {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((field.name().to_string(), value.to_string()));
+        }
+
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((field.name().to_string(), format!("{value:?}")));
+        }
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for JobSpanFields {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _id: &tracing::span::Id,
+            _ctx: Context<'_, S>,
+        ) {
+            if attrs.metadata().name() == "job" {
+                attrs.record(&mut self.clone());
+            }
+        }
+    }
+
+    struct NoopJob;
+
+    impl Job for NoopJob {
+        type Arguments = ();
+
+        fn name() -> &'static str {
+            "callback_test_job"
+        }
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            Ok(())
+        }
+    }
+
+    /// Fails unless it receives exactly [`report_rows`].
+    struct CompressedReportJob;
+
+    fn report_rows() -> Vec<String> {
+        (0..200).map(|n| format!("row {n}: unchanged")).collect()
+    }
+
+    impl Job for CompressedReportJob {
+        type Arguments = Vec<String>;
+
+        fn name() -> &'static str {
+            "compressed_report_test_job"
+        }
+
+        fn compress_arguments() -> bool {
+            true
+        }
+
+        async fn execute(_app: &App, rows: Vec<String>) -> Result<(), JobError> {
+            if rows == report_rows() {
+                Ok(())
+            } else {
+                Err(JobError::FailPermanently(
+                    "arguments did not round-trip".to_string(),
+                ))
+            }
+        }
+    }
+
+    /// Never executed; its rows are only claimed. Claiming commits, so it
+    /// gets its own type to keep other tests from seeing its jobs.
+    struct PriorityJob;
+
+    impl Job for PriorityJob {
+        type Arguments = ();
+
+        fn name() -> &'static str {
+            "priority_test_job"
+        }
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            Ok(())
+        }
+    }
+
+    struct DelayedJob;
+
+    impl Job for DelayedJob {
+        type Arguments = u32;
+
+        fn name() -> &'static str {
+            "delayed_test_job"
+        }
+
+        async fn execute(_app: &App, _arguments: u32) -> Result<(), JobError> {
+            Ok(())
+        }
+    }
+
+    static CAPPED_RUNNING: AtomicUsize = AtomicUsize::new(0);
+    static CAPPED_PEAK: AtomicUsize = AtomicUsize::new(0);
+
+    /// Slow job that records how many copies of itself run at once.
+    struct CappedJob;
+
+    impl Job for CappedJob {
+        type Arguments = ();
+
+        fn name() -> &'static str {
+            "capped_test_job"
+        }
+
+        fn max_concurrency() -> Option<usize> {
+            Some(2)
+        }
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            let running = CAPPED_RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+            CAPPED_PEAK.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+            CAPPED_RUNNING.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    struct CancellableJob;
+
+    impl Job for CancellableJob {
+        type Arguments = ();
+
+        fn name() -> &'static str {
+            "cancellable_test_job"
+        }
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            tokio::time::timeout(
+                std::time::Duration::from_secs(10),
+                cancellation::cancelled(),
+            )
+            .await
+            .map_err(|_| JobError::FailPermanently("never cancelled".to_string()))?;
+            Err(JobError::TryAgainLater("stopped early".to_string()))
+        }
+    }
+
+    /// Outlasts its own timeout and gives up on the first failure, whatever
+    /// the pool's settings.
+    struct ImpatientJob;
+
+    impl Job for ImpatientJob {
+        type Arguments = ();
+
+        fn name() -> &'static str {
+            "impatient_test_job"
+        }
+
+        fn timeout() -> Option<std::time::Duration> {
+            Some(std::time::Duration::from_millis(50))
+        }
+
+        fn max_retries() -> Option<i32> {
+            Some(0)
+        }
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+            Ok(())
+        }
+    }
+
+    /// Retries more often than the pool allows.
+    struct PersistentJob;
+
+    impl Job for PersistentJob {
+        type Arguments = ();
+
+        fn name() -> &'static str {
+            "persistent_test_job"
+        }
+
+        fn max_retries() -> Option<i32> {
+            Some(10)
+        }
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            Ok(())
+        }
+    }
+
+    /// Reports how many rows it exported; fails after reporting when asked to
+    /// export a negative number.
+    struct ExportJob;
+
+    impl Job for ExportJob {
+        type Arguments = i64;
+
+        fn name() -> &'static str {
+            "export_output_test_job"
+        }
+
+        async fn execute(_app: &App, rows: i64) -> Result<(), JobError> {
+            output::set_output(&serde_json::json!({ "rows": rows })).unwrap();
+            if rows < 0 {
+                return Err(JobError::FailPermanently("negative row count".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    static OVERLAPPING_RUNNING: AtomicUsize = AtomicUsize::new(0);
+    static OVERLAPPING_PEAK: AtomicUsize = AtomicUsize::new(0);
+
+    /// Waits a while, recording how many of its kind run at the same time.
+    struct OverlappingJob;
+
+    impl Job for OverlappingJob {
+        type Arguments = ();
+
+        fn name() -> &'static str {
+            "overlapping_test_job"
+        }
+
+        async fn execute(_app: &App, _arguments: ()) -> Result<(), JobError> {
+            let running = OVERLAPPING_RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+            OVERLAPPING_PEAK.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            OVERLAPPING_RUNNING.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    /// Arguments of [`NappingJob`] runs, in the order they finished.
+    static NAPS_FINISHED: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+
+    /// Sleeps for the given number of milliseconds.
+    struct NappingJob;
+
+    impl Job for NappingJob {
+        type Arguments = u64;
+
+        fn name() -> &'static str {
+            "napping_test_job"
+        }
+
+        async fn execute(_app: &App, millis: u64) -> Result<(), JobError> {
+            tokio::time::sleep(std::time::Duration::from_millis(millis)).await;
+            NAPS_FINISHED.lock().unwrap().push(millis);
+            Ok(())
+        }
+    }
+
+    /// Reasons passed to [`FragileJob`]'s failure hook.
+    static FRAGILE_FAILURES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    /// Fails for good on its first run.
+    struct FragileJob;
+
+    impl Job for FragileJob {
+        type Arguments = String;
+
+        fn name() -> &'static str {
+            "fragile_test_job"
+        }
+
+        async fn execute(_app: &App, arguments: String) -> Result<(), JobError> {
+            Err(JobError::FailPermanently(format!("broke on {arguments}")))
+        }
+
+        async fn on_permanent_failure(_app: &App, arguments: String, reason: &str) {
+            FRAGILE_FAILURES
+                .lock()
+                .unwrap()
+                .push(format!("{arguments}: {reason}"));
+        }
+    }
+
+    fn retry_config(max_retry_delay_seconds: u64) -> WorkerQueueConfig {
+        WorkerQueueConfig {
+            jobs: vec![],
+            count: 1,
+            concurrency: 1,
+            job_timeout: 300,
+            max_retries: 4,
+            base_retry_delay_seconds: 60,
+            retry_backoff_multiplier: 5,
+            max_retry_delay_seconds,
+            retry_jitter_fraction: 0.0,
+            dead_letter: false,
+            stuck_multiplier: 2,
+            retry_age_penalty_seconds: 0,
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_grows_until_ceiling() {
+        let config = retry_config(3600);
+        assert_eq!(retry_delay_seconds(0, &config), 60);
+        assert_eq!(retry_delay_seconds(1, &config), 300);
+        assert_eq!(retry_delay_seconds(2, &config), 1500);
+        assert_eq!(retry_delay_seconds(3, &config), 3600);
+        assert_eq!(retry_delay_seconds(50, &config), 3600);
+    }
+
+    #[test]
+    fn test_retry_delay_never_overflows() {
+        // 5^i32::MAX overflows u64 many times over; with no ceiling it saturates
+        let config = retry_config(u64::MAX);
+        assert_eq!(retry_delay_seconds(i32::MAX, &config), u64::MAX);
+        assert_eq!(retry_delay_seconds(-1, &config), 60);
+
+        let far_future = calculate_next_retry_time(i32::MAX, &config);
+        assert!(far_future > chrono::Utc::now().naive_utc());
+    }
+
+    #[test]
+    fn test_retry_jitter_stays_within_window() {
+        assert_eq!(with_jitter(300, 0.0), 300);
+        assert_eq!(with_jitter(300, f64::NAN), 300);
+        for _ in 0..200 {
+            let equal = with_jitter(300, 0.5);
+            assert!((150..=300).contains(&equal), "{equal}");
+            let full = with_jitter(300, 1.0);
+            assert!(full <= 300, "{full}");
+            // Out-of-range fractions are clamped
+            assert!(with_jitter(300, 7.0) <= 300);
+            assert_eq!(with_jitter(300, -1.0), 300);
+        }
+        // The window of the longest delay doesn't overflow
+        with_jitter(u64::MAX, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_delayed_job_is_not_executed() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let db = &test.db;
+        let job_type = "cancel_delayed_test";
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![job_type.to_string()],
+            ..retry_config(86_400)
+        };
+
+        let delayed = job::ActiveModel {
+            r#type: Set(job_type.to_string()),
+            arguments: Set(serde_json::json!({})),
+            status: Set(JobStatus::Pending),
+            retry_count: Set(0),
+            next_execution_at: Set(Some(
+                chrono::Utc::now().naive_utc() + chrono::Duration::hours(1),
+            )),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap();
+
+        let queue = JobQueue::database();
+        assert!(queue.cancel(db, delayed.id).await.unwrap());
+        // Cancelling twice is a no-op
+        assert!(!queue.cancel(db, delayed.id).await.unwrap());
+
+        // Even once its scheduled time has passed, no worker claims it
+        let mut due: job::ActiveModel = job::Entity::find_by_id(delayed.id)
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap()
+            .into();
+        due.next_execution_at = Set(None);
+        due.update(db).await.unwrap();
+
+        assert!(
+            claim_oldest_viable_job(&worker_config, &JobRegistry::<()>::new(), db)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let cancelled = job::Entity::find_by_id(delayed.id)
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_completed_job_with_callback_enqueues_delivery() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![NoopJob::name().to_string()],
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<NoopJob>();
+
+        let job_model = job::ActiveModel {
+            r#type: Set(NoopJob::name().to_string()),
+            arguments: Set(serde_json::Value::Null),
+            status: Set(JobStatus::Running),
+            retry_count: Set(0),
+            callback_url: Set(Some("https://example.com/hooks/jobs".to_string())),
+            ..Default::default()
+        }
+        .insert(&test.db)
+        .await
+        .unwrap();
+
+        execute_and_update_job(
+            &job_model,
+            &worker_config,
+            &test.app(),
+            &registry,
+            "test",
+            Cancellation::default(),
+        )
+        .await
+        .unwrap();
+
+        let deliveries = test.enqueued_jobs_of_type("deliver_job_callback");
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(
+            deliveries[0].arguments,
+            serde_json::json!({
+                "callback_url": "https://example.com/hooks/jobs",
+                "payload": {
+                    "job_id": job_model.id,
+                    "job_type": "callback_test_job",
+                    "status": "completed",
+                    "error": null,
+                },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compressed_arguments_round_trip() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let db = &test.db;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![CompressedReportJob::name().to_string()],
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<CompressedReportJob>();
+
+        JobQueue::database()
+            .add::<CompressedReportJob, ()>(db, report_rows())
+            .await
+            .unwrap();
+
+        let job_model = claim_oldest_viable_job(&worker_config, &registry, db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(job_model.arguments, serde_json::Value::Null);
+        let compressed = job_model.compressed_arguments.as_ref().unwrap();
+        let raw_len = serde_json::to_string(&report_rows()).unwrap().len();
+        assert!(compressed.len() < raw_len);
+
+        execute_and_update_job(
+            &job_model,
+            &worker_config,
+            &test.app(),
+            &registry,
+            "test",
+            Cancellation::default(),
+        )
+        .await
+        .unwrap();
+
+        let finished = job::Entity::find_by_id(job_model.id)
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(finished.status, JobStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_pending_job_is_never_claimed() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let db = &test.db;
+        let job_type = "delete_pending_test";
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![job_type.to_string()],
+            ..retry_config(86_400)
+        };
+
+        let pending = job::ActiveModel {
+            r#type: Set(job_type.to_string()),
+            arguments: Set(serde_json::json!({})),
+            status: Set(JobStatus::Pending),
+            retry_count: Set(0),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap();
+
+        let queue = JobQueue::database();
+        assert!(queue.delete(db, pending.id).await.unwrap());
+        assert!(!queue.delete(db, pending.id).await.unwrap());
+
+        assert!(job::Entity::find_by_id(pending.id)
+            .one(db)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(
+            claim_oldest_viable_job(&worker_config, &JobRegistry::<()>::new(), db)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_id_from_enqueue_is_on_job_span() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![NoopJob::name().to_string()],
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<NoopJob>();
+
+        let request_context = LogContext {
+            request_id: Some("req-1749".to_string()),
+            user_id: Some(uuid::Uuid::nil()),
+        };
+        request_context
+            .scope(JobQueue::database().add::<NoopJob, ()>(&test.db, ()))
+            .await
+            .unwrap();
+
+        let job_model = job::Entity::find()
+            .filter(job::Column::Type.eq(NoopJob::name()))
+            .one(&test.db)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let fields = JobSpanFields::default();
+        let _guard = tracing::subscriber::set_default(Registry::default().with(fields.clone()));
+        execute_and_update_job(
+            &job_model,
+            &worker_config,
+            &test.app(),
+            &registry,
+            "test",
+            Cancellation::default(),
+        )
+        .await
+        .unwrap();
+
+        let fields = fields.0.lock().unwrap();
+        assert!(fields.contains(&("request_id".to_string(), "req-1749".to_string())));
+        assert!(fields.contains(&("user_id".to_string(), uuid::Uuid::nil().to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrency_is_never_exceeded() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![CappedJob::name().to_string()],
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<CappedJob>();
+
+        for _ in 0..6 {
+            JobQueue::database()
+                .add::<CappedJob, ()>(&test.db, ())
+                .await
+                .unwrap();
+        }
+
+        // More workers than the cap, each draining until nothing is left
+        let app = test.app();
+        let workers = (0..4).map(|_| async {
+            loop {
+                match claim_oldest_viable_job(&worker_config, &registry, &app.db)
+                    .await
+                    .unwrap()
+                {
+                    Some(job_model) => {
+                        execute_and_update_job(
+                            &job_model,
+                            &worker_config,
+                            &app,
+                            &registry,
+                            "test",
+                            Cancellation::default(),
+                        )
+                        .await
+                        .unwrap();
+                    }
+                    None => {
+                        let unfinished = job::Entity::find()
+                            .filter(job::Column::Type.eq(CappedJob::name()))
+                            .filter(job::Column::Status.ne(JobStatus::Completed))
+                            .count(&app.db)
+                            .await
+                            .unwrap();
+                        if unfinished == 0 {
+                            break;
+                        }
+                        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                    }
+                }
+            }
+        });
+        futures_util::future::join_all(workers).await;
+
+        assert_eq!(CAPPED_PEAK.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_jobs_are_claimed_first() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let db = &test.db;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![PriorityJob::name().to_string()],
+            ..retry_config(86_400)
+        };
+        let registry = JobRegistry::<()>::new();
+        let queue = JobQueue::database();
+
+        queue.add::<PriorityJob, ()>(db, ()).await.unwrap();
+        queue
+            .add_with_priority::<PriorityJob, ()>(db, (), -5)
+            .await
+            .unwrap();
+        queue
+            .add_with_priority::<PriorityJob, ()>(db, (), 10)
+            .await
+            .unwrap();
+        queue.add::<PriorityJob, ()>(db, ()).await.unwrap();
+
+        let mut claimed = Vec::new();
+        while let Some(job_model) = claim_oldest_viable_job(&worker_config, &registry, db)
+            .await
+            .unwrap()
+        {
+            claimed.push((job_model.priority, job_model.created_at));
+        }
+
+        let priorities: Vec<i32> = claimed.iter().map(|(priority, _)| *priority).collect();
+        assert_eq!(priorities, [10, 0, 0, -5]);
+        // Equal priorities keep oldest-first order
+        assert!(claimed[1].1 <= claimed[2].1);
+    }
+
+    #[tokio::test]
+    async fn test_delayed_jobs_are_not_claimed_early() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let db = &test.db;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![DelayedJob::name().to_string()],
+            ..retry_config(86_400)
+        };
+        let registry = JobRegistry::<()>::new();
+        let queue = JobQueue::database();
+
+        queue
+            .add_delayed::<DelayedJob, ()>(db, 1, std::time::Duration::from_secs(2 * 60 * 60))
+            .await
+            .unwrap();
+        queue
+            .add_at::<DelayedJob, ()>(
+                db,
+                2,
+                chrono::Utc::now().naive_utc() - chrono::Duration::minutes(1),
+            )
+            .await
+            .unwrap();
+
+        let claimed = claim_oldest_viable_job(&worker_config, &registry, db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.arguments, serde_json::json!(2));
+        assert!(claim_oldest_viable_job(&worker_config, &registry, db)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_running_job_stops_when_cancelled() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![CancellableJob::name().to_string()],
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<CancellableJob>();
+        let queue = JobQueue::database();
+
+        let id = queue.add::<CancellableJob, ()>(&test.db, ()).await.unwrap();
+
+        let app = test.app();
+        let (processed, ()) = tokio::join!(
+            drain_queue("test", &worker_config, &app, &registry, None),
+            async {
+                let mut status = JobStatus::Pending;
+                while status != JobStatus::Running {
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    status = job::Entity::find_by_id(id)
+                        .one(&test.db)
+                        .await
+                        .unwrap()
+                        .unwrap()
+                        .status;
+                }
+
+                // Running jobs only get the request, not the status change
+                assert!(jobs::cancel(&test.db, id).await.unwrap());
+                let requested = job::Entity::find_by_id(id)
+                    .one(&test.db)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(requested.status, JobStatus::Running);
+                assert!(requested.cancel_requested);
+            },
+        );
+        assert_eq!(processed.unwrap(), 1);
+
+        let cancelled = job::Entity::find_by_id(id)
+            .one(&test.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        assert_eq!(cancelled.retry_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_job_timeout_and_retries_override_the_pool() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![ImpatientJob::name().to_string()],
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<ImpatientJob>();
+
+        let id = JobQueue::database()
+            .add::<ImpatientJob, ()>(&test.db, ())
+            .await
+            .unwrap();
+        let job_model = claim_oldest_viable_job(&worker_config, &registry, &test.db)
+            .await
+            .unwrap()
+            .unwrap();
+        let started = std::time::Instant::now();
+        execute_and_update_job(
+            &job_model,
+            &worker_config,
+            &test.app(),
+            &registry,
+            "test",
+            Cancellation::default(),
+        )
+        .await
+        .unwrap();
+        assert!(
+            started.elapsed() < std::time::Duration::from_secs(5),
+            "the pool's 300s timeout was used"
+        );
+
+        // The pool would retry up to 4 times; the job allows none
+        let failed = job::Entity::find_by_id(id)
+            .one(&test.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(failed.status, JobStatus::Failed);
+        assert_eq!(failed.retry_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_job_with_more_retries_than_the_pool_is_still_claimed() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![PersistentJob::name().to_string()],
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<PersistentJob>();
+
+        let id = JobQueue::database()
+            .add::<PersistentJob, ()>(&test.db, ())
+            .await
+            .unwrap();
+        let mut retried: job::ActiveModel = job::Entity::find_by_id(id)
+            .one(&test.db)
+            .await
+            .unwrap()
+            .unwrap()
+            .into();
+        retried.status = sea_orm::Set(JobStatus::PendingRetry);
+        retried.retry_count = sea_orm::Set(worker_config.max_retries + 1);
+        retried.update(&test.db).await.unwrap();
+
+        let claimed = claim_oldest_viable_job(&worker_config, &registry, &test.db)
+            .await
+            .unwrap()
+            .expect("the job's own retry limit applies");
+        assert_eq!(claimed.id, id);
+    }
+
+    #[tokio::test]
+    async fn test_permanently_failed_job_runs_hook_and_moves_to_dead_letter() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![FragileJob::name().to_string()],
+            dead_letter: true,
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<FragileJob>();
+
+        let id = JobQueue::database()
+            .add::<FragileJob, ()>(&test.db, "glass".to_string())
+            .await
+            .unwrap();
+        let job_model = claim_oldest_viable_job(&worker_config, &registry, &test.db)
+            .await
+            .unwrap()
+            .unwrap();
+        execute_and_update_job(
+            &job_model,
+            &worker_config,
+            &test.app(),
+            &registry,
+            "test",
+            Cancellation::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(FRAGILE_FAILURES
+            .lock()
+            .unwrap()
+            .contains(&"glass: broke on glass".to_string()));
+        assert!(job::Entity::find_by_id(id)
+            .one(&test.db)
+            .await
+            .unwrap()
+            .is_none());
+        let dead = dead_letter_job::Entity::find_by_id(id)
+            .one(&test.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(dead.r#type, FragileJob::name());
+        assert_eq!(dead.arguments, serde_json::json!("glass"));
+        assert_eq!(dead.failure_reason.as_deref(), Some("broke on glass"));
+    }
+
+    #[tokio::test]
+    async fn test_worker_runs_up_to_its_concurrency_at_once() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![OverlappingJob::name().to_string()],
+            concurrency: 2,
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<OverlappingJob>();
+
+        let mut ids = Vec::new();
+        for _ in 0..4 {
+            ids.push(
+                JobQueue::database()
+                    .add::<OverlappingJob, ()>(&test.db, ())
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let processed = drain_queue("test", &worker_config, &test.app(), &registry, None)
+            .await
+            .unwrap();
+
+        assert_eq!(processed, 4);
+        assert_eq!(OVERLAPPING_PEAK.load(Ordering::SeqCst), 2);
+        for id in ids {
+            let job = job::Entity::find_by_id(id)
+                .one(&test.db)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(job.status, JobStatus::Completed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_free_slot_claims_job_enqueued_while_another_runs() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![NappingJob::name().to_string()],
+            concurrency: 2,
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<NappingJob>();
+        let queue = JobQueue::database();
+        queue.add::<NappingJob, ()>(&test.db, 4000).await.unwrap();
+
+        let app = test.app();
+        let (processed, ()) = tokio::join!(
+            drain_queue("test", &worker_config, &app, &registry, None),
+            async {
+                // Enqueued once the long job is running and the queue is empty
+                tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+                queue.add::<NappingJob, ()>(&test.db, 0).await.unwrap();
+            },
+        );
+
+        assert_eq!(processed.unwrap(), 2);
+        assert_eq!(*NAPS_FINISHED.lock().unwrap(), vec![0, 4000]);
+    }
+
+    #[tokio::test]
+    async fn test_output_of_completed_run_is_stored() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![ExportJob::name().to_string()],
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<ExportJob>();
+        let queue = JobQueue::database();
+        let completed = queue.add::<ExportJob, ()>(&test.db, 42).await.unwrap();
+        let failed = queue.add::<ExportJob, ()>(&test.db, -1).await.unwrap();
+
+        drain_queue("test", &worker_config, &test.app(), &registry, None)
+            .await
+            .unwrap();
+
+        let summary = get_job(&test.db, completed).await.unwrap().unwrap();
+        assert_eq!(summary.output(), Some(&serde_json::json!({ "rows": 42 })));
+        let summary = get_job(&test.db, failed).await.unwrap().unwrap();
+        assert_eq!(summary.status, JobStatus::Failed);
+        assert_eq!(summary.executions[0].output, None);
+        assert_eq!(summary.output(), None);
+    }
+
+    #[tokio::test]
+    async fn test_ping_job_completes() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec![PingJob::<()>::name().to_string()],
+            ..retry_config(86_400)
+        };
+        let mut registry = JobRegistry::new();
+        registry.register_job::<PingJob>();
+        let id = JobQueue::database()
+            .add::<PingJob, ()>(&test.db, ())
+            .await
+            .unwrap();
+
+        let app = test.app();
+        let (processed, status) = tokio::join!(
+            async {
+                // Give the waiter a pending status to see first
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                drain_queue("test", &worker_config, &app, &registry, None).await
+            },
+            wait_for_job(&test.db, id, std::time::Duration::from_secs(10)),
+        );
+
+        assert!(processed.unwrap() >= 1);
+        assert_eq!(status.unwrap(), Some(JobStatus::Completed));
+    }
+
+    #[tokio::test]
+    async fn test_retry_age_penalty_lets_fresh_jobs_go_first() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let now = chrono::Utc::now().naive_utc();
+        let job_at = |age_minutes, retry_count| job::ActiveModel {
+            id: Set(uuid::Uuid::now_v7()),
+            created_at: Set(now - chrono::Duration::minutes(age_minutes)),
+            updated_at: Set(now),
+            r#type: Set("retry_fairness_test".to_string()),
+            arguments: Set(serde_json::Value::Null),
+            status: Set(if retry_count > 0 {
+                JobStatus::PendingRetry
+            } else {
+                JobStatus::Pending
+            }),
+            retry_count: Set(retry_count),
+            next_execution_at: Set(Some(now - chrono::Duration::minutes(1))),
+            priority: Set(0),
+            cancel_requested: Set(false),
+            ..Default::default()
+        };
+        let failing = job::Entity::insert(job_at(10, 3))
+            .exec(&test.db)
+            .await
+            .unwrap()
+            .last_insert_id;
+        let fresh = job::Entity::insert(job_at(0, 0))
+            .exec(&test.db)
+            .await
+            .unwrap()
+            .last_insert_id;
+
+        let registry = JobRegistry::<()>::new();
+        let first_claimed = |retry_age_penalty_seconds| {
+            let worker_config = WorkerQueueConfig {
+                jobs: vec!["retry_fairness_test".to_string()],
+                retry_age_penalty_seconds,
+                ..retry_config(86_400)
+            };
+            let query = viable_jobs(&worker_config, &registry, &[], now);
+            async { query.one(&test.db).await.unwrap().unwrap().id }
+        };
+
+        // Strictly oldest first by default
+        assert_eq!(first_claimed(0).await, failing);
+        // Three retries at 5 minutes each make the failing job 5 minutes younger
+        assert_eq!(first_claimed(300).await, fresh);
+    }
+
+    #[tokio::test]
+    async fn test_claim_query_uses_claimable_index() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let worker_config = WorkerQueueConfig {
+            jobs: vec!["claim_index_test".to_string()],
+            ..retry_config(86_400)
+        };
+        let registry = JobRegistry::<()>::new();
+        let mut query = viable_jobs(
+            &worker_config,
+            &registry,
+            &[],
+            chrono::Utc::now().naive_utc(),
+        )
+        .limit(1)
+        .lock_exclusive()
+        .build(DbBackend::Postgres);
+        query.sql = format!("EXPLAIN {}", query.sql);
+
+        // The test table is tiny, so tell the planner a sequential scan is
+        // too expensive, as it would be with millions of finished jobs
+        test.db
+            .execute_unprepared("SET enable_seqscan = off")
+            .await
+            .unwrap();
+        let plan = test.db.query_all(query).await.unwrap();
+        test.db
+            .execute_unprepared("RESET enable_seqscan")
+            .await
+            .unwrap();
+
+        let plan: Vec<String> = plan
+            .iter()
+            .map(|row| row.try_get_by_index(0).unwrap())
+            .collect();
+        assert!(
+            plan.iter().any(|line| line.contains("idx-job-claimable")),
+            "claim query doesn't use the partial index:\n{}",
+            plan.join("\n")
+        );
+    }
+}
@@ -23,17 +23,20 @@ pub mod config;
 pub mod database;
 pub mod emails;
 pub mod environment;
+pub mod events;
 pub mod job_queue;
 pub mod jobs;
+pub mod log_context;
 pub mod mailer;
 pub mod metrics;
 pub mod password;
 pub mod policy;
 pub mod rate_limiting;
 pub mod router;
+pub mod setup_tracing;
+pub mod shutdown;
 pub mod storage;
 pub mod sync;
-pub mod setup_tracing;
 pub mod token;
 pub mod websocket;
 
@@ -0,0 +1,80 @@
+//! Docs: docs/src/content/docs/api/jobs.md
+use std::{cell::RefCell, future::Future};
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Header carrying the request id. Taken from the client if present,
+/// otherwise generated, and always echoed on the response.
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied request id that is accepted as is.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static CURRENT: RefCell<LogContext>;
+}
+
+/// Identifiers that tie log lines from background work back to the request
+/// that caused it.
+///
+/// Set for the duration of each HTTP request and each job execution.
+/// `JobQueue::add` stores the current context with the job, and the worker
+/// restores it and enters a `job` span carrying these fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogContext {
+    pub request_id: Option<String>,
+    pub user_id: Option<Uuid>,
+}
+
+impl LogContext {
+    /// The context of the current request or job, if any.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(|context| context.borrow().clone()).ok()
+    }
+
+    /// Run `future` with `self` as the current context.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(RefCell::new(self), future).await
+    }
+
+    /// Record the authenticated user on the current context. No-op outside a
+    /// request or job.
+    pub fn set_user_id(user_id: Uuid) {
+        let _ = CURRENT.try_with(|context| context.borrow_mut().user_id = Some(user_id));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.request_id.is_none() && self.user_id.is_none()
+    }
+}
+
+/// Assigns each request an id and makes it the current [`LogContext`].
+pub async fn request_context_middleware(mut req: Request, next: Next) -> Response {
+    let request_id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LEN)
+        .cloned()
+        .unwrap_or_else(|| {
+            HeaderValue::from_str(&Uuid::now_v7().to_string()).expect("UUID is a valid header")
+        });
+    req.headers_mut()
+        .insert(REQUEST_ID_HEADER.clone(), request_id.clone());
+
+    let context = LogContext {
+        request_id: request_id.to_str().ok().map(str::to_string),
+        user_id: None,
+    };
+    let mut response = context.scope(next.run(req)).await;
+    response
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER.clone(), request_id);
+    response
+}
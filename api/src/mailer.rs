@@ -1,13 +1,21 @@
 use std::{
     fmt::{self, Debug},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
 };
 
 use chrono::{DateTime, Utc};
-use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use lettre::{
+    transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport, Message,
+    Tokio1Executor,
+};
 use serde::Serialize;
 use uuid::Uuid;
 
+use crate::{config::EmailConfig, environment::Environment};
+
 #[derive(Clone, Debug, Serialize)]
 pub struct MockEmailRecord {
     pub id: Uuid,
@@ -22,6 +30,8 @@ pub struct MockEmailRecord {
 #[derive(Clone)]
 pub struct MockTransport {
     records: Arc<Mutex<Vec<MockEmailRecord>>>,
+    /// Mirrors the `emails_sent_total` metric for test assertions
+    sent_count: Arc<AtomicU64>,
 }
 
 impl Default for MockTransport {
@@ -34,9 +44,15 @@ impl MockTransport {
     pub fn new() -> Self {
         Self {
             records: Arc::new(Mutex::new(Vec::new())),
+            sent_count: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    /// Number of messages sent through this transport.
+    pub fn sent_count(&self) -> u64 {
+        self.sent_count.load(Ordering::Relaxed)
+    }
+
     pub fn store_record(&self, record: MockEmailRecord) {
         self.records.lock().unwrap().push(record);
     }
@@ -54,6 +70,7 @@ impl MockTransport {
 
     pub fn clear(&self) {
         self.records.lock().unwrap().clear();
+        self.sent_count.store(0, Ordering::Relaxed);
     }
 }
 
@@ -81,16 +98,103 @@ impl Mailer {
         Self::Smtp(transport)
     }
 
+    /// Build the transport described by `config`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the SMTP relay can't be set up for `host`.
+    pub fn from_config(config: &EmailConfig) -> Self {
+        match config {
+            EmailConfig::Mock => Self::mock(),
+            EmailConfig::Smtp {
+                host,
+                port,
+                username,
+                password,
+                use_tls,
+                ..
+            } => {
+                let mut mailer_builder = if *use_tls {
+                    AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+                        .expect("Failed to create mailer transport")
+                        .port(*port)
+                } else {
+                    AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host).port(*port)
+                };
+
+                if let (Some(username), Some(password)) = (username, password) {
+                    mailer_builder = mailer_builder
+                        .credentials(Credentials::new(username.clone(), password.clone()));
+                }
+
+                Self::smtp(mailer_builder.build())
+            }
+        }
+    }
+
+    /// Build the transport described by `config`, unless `environment`
+    /// always uses the mock mailer (see
+    /// [`Environment::should_use_mock_email`]). Used for the default mailer
+    /// and every named transport.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the SMTP relay can't be set up for `host`.
+    pub fn for_environment(config: &EmailConfig, environment: Environment) -> Self {
+        match config {
+            EmailConfig::Smtp { .. } if environment.should_use_mock_email() => {
+                tracing::warn!(
+                    "📧 SMTP is configured but {environment} always uses the mock mailer"
+                );
+                Self::mock()
+            }
+            config => Self::from_config(config),
+        }
+    }
+
+    /// Transport label used for the email metrics.
+    pub const fn transport_kind(&self) -> &'static str {
+        match self {
+            Self::Smtp(_) => "smtp",
+            Self::Mock(_) => "mock",
+        }
+    }
+
     pub async fn send(
         &self,
         message: Message,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let transport_kind = self.transport_kind();
+
         match self {
-            Self::Smtp(transport) => {
-                transport.send(message).await?;
+            Self::Smtp(transport) => match transport.send(message).await {
+                Ok(_) => {
+                    metrics::counter!("emails_sent_total", "transport" => transport_kind)
+                        .increment(1);
+                    Ok(())
+                }
+                Err(e) => {
+                    metrics::counter!("emails_failed_total",
+                        "transport" => transport_kind,
+                        "kind" => smtp_error_kind(&e),
+                    )
+                    .increment(1);
+                    Err(e.into())
+                }
+            },
+            Self::Mock(transport) => {
+                metrics::counter!("emails_sent_total", "transport" => transport_kind).increment(1);
+                transport.sent_count.fetch_add(1, Ordering::Relaxed);
                 Ok(())
             }
-            Self::Mock(_) => Ok(()),
+        }
+    }
+
+    /// Messages sent through the mock transport; `None` for SMTP.
+    pub fn sent_count(&self) -> Option<u64> {
+        match self {
+            Self::Mock(transport) => Some(transport.sent_count()),
+            Self::Smtp(_) => None,
         }
     }
 
@@ -120,3 +224,63 @@ impl Mailer {
         }
     }
 }
+
+fn smtp_error_kind(error: &lettre::transport::smtp::Error) -> &'static str {
+    if error.is_timeout() {
+        "timeout"
+    } else if error.is_tls() {
+        "tls"
+    } else if error.is_transient() {
+        "transient"
+    } else if error.is_permanent() {
+        "permanent"
+    } else if error.is_client() {
+        "client"
+    } else {
+        "other"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lettre::Message;
+
+    use super::Mailer;
+    use crate::{config::EmailConfig, environment::Environment};
+
+    #[tokio::test]
+    async fn test_mock_send_increments_sent_counter() {
+        let mailer = Mailer::mock();
+        let message = Message::builder()
+            .from("noreply@example.com".parse().unwrap())
+            .to("user@example.com".parse().unwrap())
+            .subject("Hello")
+            .body("Hi there".to_string())
+            .unwrap();
+
+        mailer.send(message).await.unwrap();
+
+        assert_eq!(mailer.sent_count(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_test_environment_always_gets_the_mock_mailer() {
+        let smtp = EmailConfig::Smtp {
+            host: "localhost".to_string(),
+            port: 2525,
+            sender: "noreply@example.com".parse().unwrap(),
+            username: None,
+            password: None,
+            use_tls: false,
+        };
+
+        assert_eq!(
+            Mailer::for_environment(&smtp, Environment::Test).transport_kind(),
+            "mock"
+        );
+        assert_eq!(
+            Mailer::for_environment(&smtp, Environment::Development).transport_kind(),
+            "smtp"
+        );
+    }
+}
@@ -6,6 +6,13 @@ use argon2::{
     },
     Argon2,
 };
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::collections::HashSet;
 
 /// Generates a cryptographically secure salt and hashes the password using Argon2
 pub fn hash_password(password: &str) -> Result<String, Error> {
@@ -32,6 +39,217 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool, Error> {
     }
 }
 
+/// A small built-in list of passwords that show up at the top of every breach corpus.
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456",
+    "123456789",
+    "12345678",
+    "password",
+    "qwerty123",
+    "qwerty",
+    "111111",
+    "12345",
+    "1234567890",
+    "1234567",
+    "password1",
+    "123123",
+    "abc123",
+    "iloveyou",
+    "000000",
+    "letmein",
+    "welcome",
+    "admin123",
+    "monkey",
+    "dragon",
+    "sunshine",
+    "football",
+    "baseball",
+    "princess",
+    "passw0rd",
+    "password123",
+    "qwertyuiop",
+];
+
+/// Passwords a policy rejects. Entries are lowercased as they are added, so
+/// they match a password in any case.
+#[derive(Debug, Clone, Default)]
+pub struct Denylist(HashSet<String>);
+
+impl Denylist {
+    /// Add `password`; returns `false` if it was already denied.
+    pub fn insert(&mut self, password: &str) -> bool {
+        self.0.insert(password.to_lowercase())
+    }
+
+    pub fn contains(&self, password: &str) -> bool {
+        self.0.contains(&password.to_lowercase())
+    }
+}
+
+impl<S: AsRef<str>> Extend<S> for Denylist {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, passwords: I) {
+        for password in passwords {
+            self.insert(password.as_ref());
+        }
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for Denylist {
+    fn from_iter<I: IntoIterator<Item = S>>(passwords: I) -> Self {
+        let mut denylist = Self::default();
+        denylist.extend(passwords);
+        denylist
+    }
+}
+
+/// Rules a password must satisfy before it is hashed and stored.
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    /// Minimum number of characters
+    pub min_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    /// Require a character that is neither a letter nor a digit
+    pub require_symbol: bool,
+    /// Rejected passwords, compared case-insensitively
+    pub denylist: Denylist,
+    /// Minimum estimated strength score from 0 (trivial) to 4 (strong)
+    pub min_score: Option<u8>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            denylist: Denylist::default(),
+            min_score: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Add the built-in list of common passwords to the denylist.
+    #[must_use]
+    pub fn with_common_passwords(self) -> Self {
+        self.with_denied_passwords(COMMON_PASSWORDS)
+    }
+
+    /// Add `passwords` to the denylist, e.g. the app's name or a list of
+    /// leaked passwords.
+    #[must_use]
+    pub fn with_denied_passwords<S: AsRef<str>>(
+        mut self,
+        passwords: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.denylist.extend(passwords);
+        self
+    }
+}
+
+/// A single reason a password was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum PasswordRejection {
+    TooShort { min_length: usize },
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSymbol,
+    Common,
+    TooWeak { score: u8, min_score: u8 },
+}
+
+/// Returned when a password violates a `PasswordPolicy`; lists every failed rule.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Password does not meet the password policy")]
+pub struct PasswordError {
+    pub reasons: Vec<PasswordRejection>,
+}
+
+impl IntoResponse for PasswordError {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({ "error": "weak_password", "reasons": self.reasons })),
+        )
+            .into_response()
+    }
+}
+
+/// Checks a password against `policy`, collecting every rule it breaks
+pub fn validate_password(password: &str, policy: &PasswordPolicy) -> Result<(), PasswordError> {
+    let mut reasons = Vec::new();
+
+    if password.chars().count() < policy.min_length {
+        reasons.push(PasswordRejection::TooShort {
+            min_length: policy.min_length,
+        });
+    }
+    if policy.require_lowercase && !password.chars().any(char::is_lowercase) {
+        reasons.push(PasswordRejection::MissingLowercase);
+    }
+    if policy.require_uppercase && !password.chars().any(char::is_uppercase) {
+        reasons.push(PasswordRejection::MissingUppercase);
+    }
+    if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        reasons.push(PasswordRejection::MissingDigit);
+    }
+    if policy.require_symbol && !password.chars().any(|c| !c.is_alphanumeric()) {
+        reasons.push(PasswordRejection::MissingSymbol);
+    }
+    if policy.denylist.contains(password) {
+        reasons.push(PasswordRejection::Common);
+    }
+    if let Some(min_score) = policy.min_score {
+        let score = strength_score(password);
+        if score < min_score {
+            reasons.push(PasswordRejection::TooWeak { score, min_score });
+        }
+    }
+
+    if reasons.is_empty() {
+        Ok(())
+    } else {
+        Err(PasswordError { reasons })
+    }
+}
+
+/// Rough strength estimate from 0 to 4, based on length and character variety.
+///
+/// This is a brute-force entropy estimate, not a pattern matcher like zxcvbn:
+/// combine it with the denylist to catch dictionary passwords.
+pub fn strength_score(password: &str) -> u8 {
+    let mut pool = 0u32;
+    if password.chars().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if password.chars().any(|c| !c.is_ascii_alphanumeric()) {
+        pool += 33;
+    }
+
+    let length = password.chars().count() as f64;
+    let entropy_bits = length * f64::from(pool.max(1)).log2();
+
+    match entropy_bits {
+        bits if bits < 28.0 => 0,
+        bits if bits < 36.0 => 1,
+        bits if bits < 60.0 => 2,
+        bits if bits < 128.0 => 3,
+        _ => 4,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,4 +282,74 @@ mod tests {
         assert!(verify_password(password, &hash1).expect("Failed to verify password"));
         assert!(verify_password(password, &hash2).expect("Failed to verify password"));
     }
+
+    #[test]
+    fn test_validate_password_too_short() {
+        let policy = PasswordPolicy::default();
+        let err = validate_password("short", &policy).unwrap_err();
+        assert_eq!(
+            err.reasons,
+            vec![PasswordRejection::TooShort { min_length: 8 }]
+        );
+    }
+
+    #[test]
+    fn test_validate_password_missing_classes() {
+        let policy = PasswordPolicy {
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            ..PasswordPolicy::default()
+        };
+        let err = validate_password("lowercaseonly", &policy).unwrap_err();
+        assert_eq!(
+            err.reasons,
+            vec![
+                PasswordRejection::MissingUppercase,
+                PasswordRejection::MissingDigit,
+                PasswordRejection::MissingSymbol,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_password_rejects_common_and_weak() {
+        let policy = PasswordPolicy {
+            min_score: Some(3),
+            ..PasswordPolicy::default()
+        }
+        .with_common_passwords();
+        let err = validate_password("Password123", &policy).unwrap_err();
+        assert!(err.reasons.contains(&PasswordRejection::Common));
+
+        let err = validate_password("aaaaaaaa", &policy).unwrap_err();
+        assert!(matches!(
+            err.reasons[..],
+            [PasswordRejection::TooWeak { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_denylist_entries_match_in_any_case() {
+        let policy = PasswordPolicy::default().with_denied_passwords(["Password1"]);
+        for password in ["Password1", "password1", "PASSWORD1"] {
+            let err = validate_password(password, &policy).unwrap_err();
+            assert_eq!(err.reasons, vec![PasswordRejection::Common]);
+        }
+    }
+
+    #[test]
+    fn test_validate_password_accepts_strong_password() {
+        let policy = PasswordPolicy {
+            min_length: 12,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            min_score: Some(3),
+            ..PasswordPolicy::default()
+        }
+        .with_common_passwords();
+        assert!(validate_password("correct-Horse-battery-9", &policy).is_ok());
+    }
 }
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+
+use axum::Json;
+use serde::Serialize;
+
+use super::Policy;
+use crate::auth::{CurrentUser, LoadForUser};
+
+/// Coarse capabilities of the current user, for frontends deciding what UI
+/// to render. Serialized as:
+///
+/// ```json
+/// {
+///   "roles": ["editor"],
+///   "entities": { "posts": { "create": true, "list": true } }
+/// }
+/// ```
+///
+/// Build one with [`abilities!`](crate::abilities) or by hand with
+/// [`Abilities::with_policy`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Abilities {
+    pub roles: Vec<String>,
+    pub entities: BTreeMap<String, EntityAbilities>,
+}
+
+/// Top-level checks of one entity's policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct EntityAbilities {
+    pub create: bool,
+    pub list: bool,
+}
+
+impl Abilities {
+    pub fn new<R: Into<String>>(roles: impl IntoIterator<Item = R>) -> Self {
+        Self {
+            roles: roles.into_iter().map(Into::into).collect(),
+            entities: BTreeMap::new(),
+        }
+    }
+
+    /// Record `policy`'s `can_create` and `can_list` under `name`.
+    #[must_use]
+    pub fn with_policy<E, P>(mut self, name: impl Into<String>, policy: &P) -> Self
+    where
+        E: sea_orm::EntityTrait,
+        P: Policy<E>,
+    {
+        self.entities.insert(
+            name.into(),
+            EntityAbilities {
+                create: policy.can_create(),
+                list: policy.can_list(),
+            },
+        );
+        self
+    }
+}
+
+/// App-supplied aggregation of the user's roles and policies, served by
+/// [`abilities_handler`].
+///
+/// Policies are per entity, so only the app knows which ones to report.
+///
+/// # Example
+/// ```rust,ignore
+/// struct AppAbilities;
+///
+/// impl UserAbilities<Profile> for AppAbilities {
+///     fn abilities(current_user: &CurrentUser<Profile>) -> Abilities {
+///         abilities!(current_user.user, roles: current_user.profile.roles.clone(), {
+///             "posts" => PostPolicy: post::Entity,
+///             "comments" => CommentPolicy: comment::Entity,
+///         })
+///     }
+/// }
+///
+/// Router::new().route("/me/abilities", get(abilities_handler::<AppAbilities, Profile>))
+/// ```
+pub trait UserAbilities<P: LoadForUser = ()>: Send + Sync + 'static {
+    fn abilities(current_user: &CurrentUser<P>) -> Abilities;
+}
+
+/// `GET /me/abilities`: the current user's [`Abilities`] as reported by `A`.
+pub async fn abilities_handler<A, P>(current_user: CurrentUser<P>) -> Json<Abilities>
+where
+    A: UserAbilities<P>,
+    P: LoadForUser,
+{
+    Json(A::abilities(&current_user))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::HeaderMap, routing::get, Router};
+    use sea_orm::{ActiveModelTrait, Select, Set};
+    use serde_json::json;
+
+    use super::{abilities_handler, Abilities, UserAbilities};
+    use crate::{
+        app::App,
+        auth::{jwt::generate_token, CurrentUser},
+        database::{migrations::Migrator, models::user},
+        policy::Policy,
+        sync::from_user::FromUser,
+        tests::setup_test::{no_fixtures, setup_test},
+    };
+
+    fn is_admin(user: &user::Model) -> bool {
+        user.email.ends_with("@admin.example.com")
+    }
+
+    struct UserDirectoryPolicy {
+        admin: bool,
+    }
+
+    impl FromUser for UserDirectoryPolicy {
+        fn from_user(user: &user::Model) -> Self {
+            Self {
+                admin: is_admin(user),
+            }
+        }
+    }
+
+    impl Policy<user::Entity> for UserDirectoryPolicy {
+        fn can_read(&self, _user: &user::Model) -> bool {
+            self.admin
+        }
+
+        fn readable(&self, query: Select<user::Entity>) -> Select<user::Entity> {
+            query
+        }
+
+        fn can_create(&self) -> bool {
+            self.admin
+        }
+
+        fn can_list(&self) -> bool {
+            self.admin
+        }
+    }
+
+    struct TestAbilities;
+
+    impl UserAbilities for TestAbilities {
+        fn abilities(current_user: &CurrentUser) -> Abilities {
+            let roles = if is_admin(current_user) {
+                vec!["admin"]
+            } else {
+                vec![]
+            };
+            crate::abilities!(current_user.user, roles: roles, {
+                "users" => UserDirectoryPolicy: user::Entity,
+            })
+        }
+    }
+
+    fn test_router(app: App) -> Router {
+        Router::new()
+            .route("/me/abilities", get(abilities_handler::<TestAbilities, ()>))
+            .with_state(app)
+    }
+
+    #[tokio::test]
+    async fn test_abilities_reflect_roles_and_create_permission() {
+        let test = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let mut abilities = Vec::new();
+
+        for email in ["root@admin.example.com", "reader@example.com"] {
+            let user = user::ActiveModel {
+                email: Set(email.to_string()),
+                password_hash: Set(String::new()),
+                email_verified_at: Set(Some(chrono::Utc::now().naive_utc())),
+                ..Default::default()
+            }
+            .insert(&test.db)
+            .await
+            .unwrap();
+            let token =
+                generate_token(&test.config, user.id, user.token_version, &HeaderMap::new())
+                    .unwrap();
+
+            let response = test
+                .server
+                .get("/api/me/abilities")
+                .add_header("Authorization", format!("Bearer {token}"))
+                .await;
+            response.assert_status_ok();
+            abilities.push(response.json::<serde_json::Value>());
+        }
+
+        assert_eq!(
+            abilities[0],
+            json!({ "roles": ["admin"], "entities": { "users": { "create": true, "list": true } } })
+        );
+        assert_eq!(
+            abilities[1],
+            json!({ "roles": [], "entities": { "users": { "create": false, "list": false } } })
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use sea_orm::{DbErr, EntityTrait, PrimaryKeyTrait, QuerySelect, TransactionTrait};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::Policy;
+
+/// What happened to one id passed to [`delete_authorized`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteOutcome {
+    Deleted,
+    Forbidden,
+    NotFound,
+}
+
+/// Per-id result of [`delete_authorized`], serialized as
+/// `{ "id": "...", "outcome": "deleted" }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DeleteResult {
+    pub id: Uuid,
+    pub outcome: DeleteOutcome,
+}
+
+/// Delete every entity in `ids` that `policy` allows deleting, in one
+/// transaction. Rows are locked while they are checked, so a concurrent
+/// update can't change the answer between the check and the delete.
+///
+/// Results are returned in the order of `ids`. An id listed twice is
+/// `NotFound` the second time.
+///
+/// # Example
+/// ```rust,ignore
+/// let results = delete_authorized::<post::Entity, _, _>(&app.db, &policy, &ids).await?;
+/// Ok(RequestSuccess::Ok(json!(results)))
+/// ```
+pub async fn delete_authorized<E, P, C>(
+    db: &C,
+    policy: &P,
+    ids: &[Uuid],
+) -> Result<Vec<DeleteResult>, DbErr>
+where
+    E: EntityTrait,
+    P: Policy<E>,
+    C: TransactionTrait,
+    <E::PrimaryKey as PrimaryKeyTrait>::ValueType: From<Uuid>,
+{
+    let txn = db.begin().await?;
+    let mut results = Vec::with_capacity(ids.len());
+
+    for &id in ids {
+        let outcome = match E::find_by_id(id).lock_exclusive().one(&txn).await? {
+            None => DeleteOutcome::NotFound,
+            Some(entity) if !policy.can_delete(&entity) => DeleteOutcome::Forbidden,
+            Some(_) => {
+                E::delete_by_id(id).exec(&txn).await?;
+                DeleteOutcome::Deleted
+            }
+        };
+        results.push(DeleteResult { id, outcome });
+    }
+
+    txn.commit().await?;
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::Router;
+    use sea_orm::{ActiveModelTrait, EntityTrait, Select, Set, TransactionTrait};
+    use uuid::Uuid;
+
+    use super::{delete_authorized, DeleteOutcome, DeleteResult};
+    use crate::{
+        app::App,
+        database::{migrations::Migrator, models::user},
+        policy::Policy,
+        tests::setup_test::{no_fixtures, setup_test},
+    };
+
+    /// Lets users on one domain be deleted
+    struct DomainPolicy;
+
+    impl Policy<user::Entity> for DomainPolicy {
+        fn can_read(&self, _user: &user::Model) -> bool {
+            true
+        }
+
+        fn readable(&self, query: Select<user::Entity>) -> Select<user::Entity> {
+            query
+        }
+
+        fn can_delete(&self, user: &user::Model) -> bool {
+            user.email.ends_with("@deletable.example.com")
+        }
+    }
+
+    fn test_router(app: App) -> Router {
+        Router::new().with_state(app)
+    }
+
+    #[tokio::test]
+    async fn test_only_authorized_entities_are_deleted() {
+        let t = setup_test::<Migrator>(test_router, no_fixtures).await;
+        // Rolled back when dropped, so nothing outlives the test
+        let txn = t.db.begin().await.unwrap();
+
+        let mut ids = Vec::new();
+        for email in [
+            "a@deletable.example.com",
+            "b@kept.example.com",
+            "c@deletable.example.com",
+        ] {
+            let user = user::ActiveModel {
+                email: Set(email.to_string()),
+                password_hash: Set("hash".to_string()),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await
+            .unwrap();
+            ids.push(user.id);
+        }
+        let missing = Uuid::new_v4();
+        ids.push(missing);
+
+        let results = delete_authorized::<user::Entity, _, _>(&txn, &DomainPolicy, &ids)
+            .await
+            .unwrap();
+
+        let deleted = |id| DeleteResult {
+            id,
+            outcome: DeleteOutcome::Deleted,
+        };
+        assert_eq!(
+            results,
+            vec![
+                deleted(ids[0]),
+                DeleteResult {
+                    id: ids[1],
+                    outcome: DeleteOutcome::Forbidden
+                },
+                deleted(ids[2]),
+                DeleteResult {
+                    id: missing,
+                    outcome: DeleteOutcome::NotFound
+                },
+            ]
+        );
+        let remaining: Vec<_> = user::Entity::find()
+            .all(&txn)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|user| user.id)
+            .collect();
+        assert!(!remaining.contains(&ids[0]));
+        assert!(remaining.contains(&ids[1]));
+        assert!(!remaining.contains(&ids[2]));
+    }
+}
@@ -0,0 +1,198 @@
+use axum::{extract::FromRequestParts, http::request::Parts};
+
+use crate::{
+    app::App,
+    auth::{AuthError, CurrentUser, LoadForUser},
+};
+
+/// Builds a policy for the current user, or for an anonymous request.
+///
+/// Implement it once per policy so handlers can take [`WithPolicy`] instead of
+/// constructing the policy from `CurrentUser` by hand.
+///
+/// # Example
+/// ```rust,ignore
+/// impl PolicyFactory for PostPolicy {
+///     fn for_user(current_user: Option<&CurrentUser>) -> Self {
+///         Self { user_id: current_user.map(|current_user| current_user.id) }
+///     }
+/// }
+/// ```
+pub trait PolicyFactory<P: LoadForUser = ()>: Sized + Send + Sync + 'static {
+    fn for_user(current_user: Option<&CurrentUser<P>>) -> Self;
+}
+
+/// Extracts the optional [`CurrentUser`] and the policy built from it by
+/// [`PolicyFactory`]. Derefs to the policy.
+///
+/// Requests without an `Authorization` header get the anonymous policy. A
+/// header with an invalid token is rejected with 401, like `CurrentUser`.
+///
+/// # Example
+/// ```rust,ignore
+/// async fn get_post(
+///     State(app): State<App>,
+///     policy: WithPolicy<PostPolicy>,
+///     Path(post_id): Path<Uuid>,
+/// ) -> RequestResult {
+///     let post = find_or_404::<post::Entity>(&app.db, post_id).await?;
+///     authorize!(policy, read, &post);
+///     Ok(RequestSuccess::Ok(serde_json::json!(post)))
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WithPolicy<Pol, P: LoadForUser = ()> {
+    pub policy: Pol,
+    pub current_user: Option<CurrentUser<P>>,
+}
+
+impl<Pol, P: LoadForUser> std::ops::Deref for WithPolicy<Pol, P> {
+    type Target = Pol;
+
+    fn deref(&self) -> &Self::Target {
+        &self.policy
+    }
+}
+
+impl<ExtraConfig, Pol, P> FromRequestParts<App<ExtraConfig>> for WithPolicy<Pol, P>
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+    Pol: PolicyFactory<P>,
+    P: LoadForUser,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &App<ExtraConfig>,
+    ) -> Result<Self, AuthError> {
+        let current_user = if parts.headers.contains_key("Authorization") {
+            Some(CurrentUser::<P>::from_request_parts(parts, state).await?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            policy: Pol::for_user(current_user.as_ref()),
+            current_user,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{extract::Path, http::HeaderMap, routing::get, Router};
+    use sea_orm::{ActiveModelTrait, Select, Set};
+    use uuid::Uuid;
+
+    use super::{PolicyFactory, WithPolicy};
+    use crate::{
+        api::{
+            find_or_404::find_or_404,
+            request_result::{RequestResult, RequestSuccess},
+        },
+        app::App,
+        auth::{jwt::generate_token, CurrentUser},
+        authorize,
+        database::{migrations::Migrator, models::user},
+        policy::Policy,
+        tests::setup_test::{no_fixtures, setup_test},
+    };
+
+    /// Users can read their own row and nobody else's
+    struct OwnUserPolicy {
+        user_id: Option<Uuid>,
+    }
+
+    impl PolicyFactory for OwnUserPolicy {
+        fn for_user(current_user: Option<&CurrentUser>) -> Self {
+            Self {
+                user_id: current_user.map(|current_user| current_user.id),
+            }
+        }
+    }
+
+    impl Policy<user::Entity> for OwnUserPolicy {
+        fn can_read(&self, user: &user::Model) -> bool {
+            self.user_id == Some(user.id)
+        }
+
+        fn readable(&self, query: Select<user::Entity>) -> Select<user::Entity> {
+            query
+        }
+    }
+
+    async fn get_user(
+        axum::extract::State(app): axum::extract::State<App>,
+        policy: WithPolicy<OwnUserPolicy>,
+        Path(user_id): Path<Uuid>,
+    ) -> RequestResult {
+        let user = find_or_404::<user::Entity>(&app.db, user_id).await?;
+        authorize!(policy, read, &user);
+        Ok(RequestSuccess::Ok(
+            serde_json::json!({ "email": user.email }),
+        ))
+    }
+
+    fn test_router(app: App) -> Router {
+        Router::new()
+            .route("/users/{id}", get(get_user))
+            .with_state(app)
+    }
+
+    #[tokio::test]
+    async fn test_handler_authorizes_with_extracted_policy() {
+        let test = setup_test::<Migrator>(test_router, no_fixtures).await;
+        let mut users = Vec::new();
+        for email in ["owner@policy.example.com", "other@policy.example.com"] {
+            let user = user::ActiveModel {
+                email: Set(email.to_string()),
+                password_hash: Set(String::new()),
+                email_verified_at: Set(Some(chrono::Utc::now().naive_utc())),
+                ..Default::default()
+            }
+            .insert(&test.db)
+            .await
+            .unwrap();
+            users.push(user);
+        }
+        let token = generate_token(
+            &test.config,
+            users[0].id,
+            users[0].token_version,
+            &HeaderMap::new(),
+        )
+        .unwrap();
+        let authorization = format!("Bearer {token}");
+
+        let own = test
+            .server
+            .get(&format!("/api/users/{}", users[0].id))
+            .add_header("Authorization", authorization.clone())
+            .await;
+        own.assert_status_ok();
+        assert_eq!(
+            own.json::<serde_json::Value>()["email"],
+            "owner@policy.example.com"
+        );
+
+        test.server
+            .get(&format!("/api/users/{}", users[1].id))
+            .add_header("Authorization", authorization)
+            .await
+            .assert_status_forbidden();
+
+        // Anonymous requests get the policy for no user
+        test.server
+            .get(&format!("/api/users/{}", users[0].id))
+            .await
+            .assert_status_forbidden();
+
+        // A bad token is still an authentication failure
+        test.server
+            .get(&format!("/api/users/{}", users[0].id))
+            .add_header("Authorization", "Bearer nope")
+            .await
+            .assert_status_unauthorized();
+    }
+}
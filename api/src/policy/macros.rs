@@ -64,3 +64,31 @@ macro_rules! authorize_view {
         }
     };
 }
+
+/// Collect roles and the top-level checks of several policies into an
+/// [`Abilities`](crate::policy::abilities::Abilities).
+///
+/// Each policy is built with [`FromUser`](crate::sync::from_user::FromUser)
+/// and reported under the given name.
+///
+/// # Usage
+///
+/// ```rust,ignore
+/// use erno::abilities;
+///
+/// let abilities = abilities!(user, roles: ["editor"], {
+///     "posts" => PostPolicy: post::Entity,
+///     "comments" => CommentPolicy: comment::Entity,
+/// });
+/// ```
+#[macro_export]
+macro_rules! abilities {
+    ($user:expr, roles: $roles:expr, { $($name:literal => $policy:ty : $entity:ty),* $(,)? }) => {{
+        let user = &$user;
+        $crate::policy::abilities::Abilities::new($roles)
+            $(.with_policy::<$entity, $policy>(
+                $name,
+                &<$policy as $crate::sync::from_user::FromUser>::from_user(user),
+            ))*
+    }};
+}
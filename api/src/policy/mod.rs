@@ -1,4 +1,7 @@
 //! Docs: docs/src/content/docs/api/authorization.md
+pub mod abilities;
+pub mod bulk_delete;
+pub mod extractor;
 pub mod macros;
 
 use sea_orm::Select;
@@ -63,6 +66,17 @@ where
     /// The filtered query with authorization conditions applied
     fn readable(&self, query: Select<E>) -> Select<E>;
 
+    /// Check if the current user can list entities of this type at all.
+    ///
+    /// Which records appear is still decided by `readable`; this is a coarse
+    /// switch for hiding a whole collection, e.g. in a frontend menu.
+    ///
+    /// # Returns
+    /// `true` if the user can list entities, `false` otherwise
+    fn can_list(&self) -> bool {
+        true
+    }
+
     /// Check if the current user can create an entity of this type.
     ///
     /// # Returns
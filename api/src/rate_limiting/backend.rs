@@ -5,48 +5,47 @@ use async_trait::async_trait;
 use dashmap::DashMap;
 use tracing::{trace, warn};
 
-use super::rate_limit_state::ActionRateLimit;
+use super::decision::RateLimitDecision;
+use super::rate_limit_state::{ActionRateLimit, RateLimitAlgorithm};
+use super::snapshot::{ClientSnapshot, Clock, RateLimitSnapshot};
 
 /// Pluggable storage backend for rate limiting.
 ///
 /// The default [`InMemoryBackend`] works correctly for single-replica deployments.
-/// For multi-replica setups, implement this trait against a shared store such as
-/// Redis or PostgreSQL and pass it to [`RateLimitState::with_backend`].
-///
-/// # Example (Redis backend sketch)
-///
-/// ```rust,ignore
-/// struct RedisBackend { pool: deadpool_redis::Pool }
-///
-/// #[async_trait]
-/// impl RateLimitBackend for RedisBackend {
-///     async fn check_rate_limit(&self, key: &str, limit: &ActionRateLimit, backoff_multiplier: f64) -> Result<(), Duration> {
-///         // sliding window via Redis ZADD + ZREMRANGEBYSCORE + ZCARD in a Lua script
-///         todo!()
-///     }
-/// }
-/// ```
+/// For multi-replica setups use [`RedisRateLimitBackend`](super::RedisRateLimitBackend),
+/// or implement this trait against another shared store and pass it to
+/// [`RateLimitState::with_backend`](super::RateLimitState::with_backend).
 #[async_trait]
 pub trait RateLimitBackend: Send + Sync {
-    /// Check whether the request identified by `key` is within limits.
+    /// Check whether the request identified by `key` is within limits, and
+    /// record it if so.
     ///
-    /// `key` is a composite string: `"{ip}/{action}"` (e.g. `"1.2.3.4/user_create"`).
-    /// Returns `Ok(())` if the request is allowed, or `Err(retry_after)` if it should
-    /// be rejected. The `retry_after` duration is suitable for the `Retry-After` header.
+    /// `key` is a composite string: `"{key}/{action}"` (e.g. `"1.2.3.4/user_create"`).
+    /// The returned decision's `retry_after` is set if the request should be
+    /// rejected, and is suitable for the `Retry-After` header. Penalties grow by
+    /// `backoff_multiplier` per repeated violation and never exceed `max_penalty`.
     async fn check_rate_limit(
         &self,
         key: &str,
         limit: &ActionRateLimit,
         backoff_multiplier: f64,
-    ) -> Result<(), Duration>;
+        max_penalty: Duration,
+    ) -> RateLimitDecision;
 }
 
-/// Per-client sliding-window state tracked by [`InMemoryBackend`].
+/// Violations beyond this no longer grow the penalty, so the multiplier can't
+/// overflow for a client that keeps hammering the API.
+pub(super) const MAX_COUNTED_VIOLATIONS: u32 = 32;
+
+/// Per-client state tracked by [`InMemoryBackend`]. Sliding windows use
+/// `requests`; token buckets only `tokens` and `last_refill`.
 #[derive(Debug, Clone)]
 pub(super) struct ClientState {
     requests: Vec<Instant>,
     violations: u32,
     blocked_until: Option<Instant>,
+    tokens: f64,
+    last_refill: Option<Instant>,
 }
 
 impl ClientState {
@@ -55,6 +54,32 @@ impl ClientState {
             requests: Vec::new(),
             violations: 0,
             blocked_until: None,
+            tokens: 0.0,
+            last_refill: None,
+        }
+    }
+
+    fn to_snapshot(&self, clock: Clock) -> ClientSnapshot {
+        ClientSnapshot {
+            requests: self.requests.iter().map(|&t| clock.to_wall(t)).collect(),
+            violations: self.violations,
+            blocked_until: self.blocked_until.map(|t| clock.to_wall(t)),
+            tokens: self.tokens,
+            last_refill: self.last_refill.map(|t| clock.to_wall(t)),
+        }
+    }
+
+    fn from_snapshot(snapshot: &ClientSnapshot, clock: Clock) -> Self {
+        Self {
+            requests: snapshot
+                .requests
+                .iter()
+                .filter_map(|&t| clock.to_instant(t))
+                .collect(),
+            violations: snapshot.violations,
+            blocked_until: snapshot.blocked_until.and_then(|t| clock.to_instant(t)),
+            tokens: snapshot.tokens,
+            last_refill: snapshot.last_refill.and_then(|t| clock.to_instant(t)),
         }
     }
 
@@ -73,11 +98,41 @@ impl ClientState {
         self.requests.retain(|&t| t > cutoff);
     }
 
+    /// Limit, remaining requests and reset time of the tier with the fewest
+    /// requests left.
+    fn tightest_tier(&self, limit: &ActionRateLimit, now: Instant) -> (u32, u32, Duration) {
+        limit
+            .tiers
+            .iter()
+            .map(|tier| {
+                let window = Duration::from_secs(tier.window_secs);
+                let cutoff = now - window;
+                let mut in_window = self.requests.iter().filter(|&&t| t > cutoff);
+                let oldest = in_window.next();
+                let count = oldest.map_or(0, |_| 1 + in_window.count());
+                let remaining = tier.max_requests.saturating_sub(count as u32);
+                let reset_after =
+                    oldest.map_or(window, |&t| (t + window).saturating_duration_since(now));
+                (tier.max_requests, remaining, reset_after)
+            })
+            .min_by_key(|&(_, remaining, _)| remaining)
+            .unwrap_or((0, 0, Duration::ZERO))
+    }
+
     pub(super) fn record_request(
         &mut self,
         limit: &ActionRateLimit,
         backoff_multiplier: f64,
-    ) -> Option<Duration> {
+        max_penalty: Duration,
+    ) -> RateLimitDecision {
+        if let RateLimitAlgorithm::TokenBucket {
+            refill_per_sec,
+            capacity,
+        } = limit.algorithm
+        {
+            return self.take_token(refill_per_sec, capacity, max_penalty);
+        }
+
         let now = Instant::now();
 
         // Reset violations once the block has fully expired so past incidents don't
@@ -102,9 +157,12 @@ impl ClientState {
             let count = self.requests.iter().filter(|&&t| t > cutoff).count();
 
             if count >= tier.max_requests as usize {
-                self.violations += 1;
-                let base = Duration::from_secs(tier.window_secs);
-                let penalty = base.mul_f64(backoff_multiplier.powi(self.violations as i32 - 1));
+                self.violations = self.violations.saturating_add(1);
+                let exponent = self.violations.min(MAX_COUNTED_VIOLATIONS) as i32 - 1;
+                let penalty_secs = tier.window_secs as f64 * backoff_multiplier.powi(exponent);
+                let penalty = Duration::try_from_secs_f64(penalty_secs)
+                    .unwrap_or(max_penalty)
+                    .min(max_penalty);
                 self.blocked_until = Some(now + penalty);
 
                 warn!(
@@ -115,13 +173,75 @@ impl ClientState {
                     "Rate limit tier exceeded with exponential backoff"
                 );
 
-                return Some(penalty);
+                return RateLimitDecision {
+                    limit: tier.max_requests,
+                    remaining: 0,
+                    reset_after: penalty,
+                    retry_after: Some(penalty),
+                };
             }
         }
 
         self.requests.push(now);
         trace!(total_requests = self.requests.len(), "Request recorded within all rate limit tiers");
-        None
+
+        let (tier_limit, remaining, reset_after) = self.tightest_tier(limit, now);
+        RateLimitDecision {
+            limit: tier_limit,
+            remaining,
+            reset_after,
+            retry_after: None,
+        }
+    }
+
+    /// Refill the bucket for the time since the last request and take one
+    /// token. There's no backoff: a rejected client just waits for the next
+    /// token, capped at `max_penalty` when the bucket never refills.
+    fn take_token(
+        &mut self,
+        refill_per_sec: f64,
+        capacity: u32,
+        max_penalty: Duration,
+    ) -> RateLimitDecision {
+        let now = Instant::now();
+        let capacity_f = f64::from(capacity);
+        let tokens = match self.last_refill {
+            Some(last) => (self.tokens + now.duration_since(last).as_secs_f64() * refill_per_sec)
+                .min(capacity_f),
+            None => capacity_f,
+        };
+        self.last_refill = Some(now);
+
+        // Time until `missing` more tokens have been refilled
+        let refill_time = |missing: f64| {
+            Duration::try_from_secs_f64(missing / refill_per_sec)
+                .unwrap_or(max_penalty)
+                .min(max_penalty)
+        };
+
+        if tokens >= 1.0 {
+            self.tokens = tokens - 1.0;
+            return RateLimitDecision {
+                limit: capacity,
+                remaining: self.tokens as u32,
+                reset_after: refill_time(capacity_f - self.tokens),
+                retry_after: None,
+            };
+        }
+
+        self.tokens = tokens;
+        let retry_after = refill_time(1.0 - tokens);
+        trace!(
+            tokens,
+            retry_after_ms = retry_after.as_millis() as u64,
+            "Token bucket empty"
+        );
+        RateLimitDecision {
+            limit: capacity,
+            remaining: 0,
+            reset_after: retry_after,
+            retry_after: Some(retry_after),
+        }
     }
 }
 
@@ -147,6 +267,19 @@ impl InMemoryBackend {
         }
     }
 
+    /// Number of keys with tracked state.
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Number of keys currently serving a penalty.
+    pub fn blocked_client_count(&self) -> usize {
+        self.clients
+            .iter()
+            .filter(|client| client.is_blocked().is_some())
+            .count()
+    }
+
     /// Remove entries for clients that haven't made a request in the last hour
     /// and are no longer blocked. Call this periodically to bound memory usage.
     pub fn cleanup_expired_entries(&self) {
@@ -157,9 +290,37 @@ impl InMemoryBackend {
                     return true;
                 }
             }
-            !client.requests.is_empty() && client.requests.last().is_some_and(|&t| t > cutoff)
+            client
+                .requests
+                .last()
+                .or(client.last_refill.as_ref())
+                .is_some_and(|&t| t > cutoff)
         });
     }
+
+    /// Every client's state, see [`RateLimitSnapshot`].
+    pub fn snapshot(&self) -> RateLimitSnapshot {
+        let clock = Clock::now();
+        RateLimitSnapshot {
+            taken_at: clock.wall(),
+            clients: self
+                .clients
+                .iter()
+                .map(|client| (client.key().clone(), client.to_snapshot(clock)))
+                .collect(),
+        }
+    }
+
+    /// Load the client states from `snapshot`, replacing any tracked for the
+    /// same keys. Time that passed since it was taken counts as elapsed, so
+    /// blocks that ran out meanwhile are over.
+    pub fn restore(&self, snapshot: &RateLimitSnapshot) {
+        let clock = Clock::now();
+        for (key, client) in &snapshot.clients {
+            self.clients
+                .insert(key.clone(), ClientState::from_snapshot(client, clock));
+        }
+    }
 }
 
 #[async_trait]
@@ -169,19 +330,22 @@ impl RateLimitBackend for InMemoryBackend {
         key: &str,
         limit: &ActionRateLimit,
         backoff_multiplier: f64,
-    ) -> Result<(), Duration> {
+        max_penalty: Duration,
+    ) -> RateLimitDecision {
         let mut entry = self.clients.entry(key.to_string()).or_insert_with(ClientState::new);
         let client = entry.value_mut();
 
-        if let Some(remaining) = client.is_blocked() {
-            return Err(remaining);
+        if let Some(blocked_for) = client.is_blocked() {
+            let (tier_limit, _, _) = client.tightest_tier(limit, Instant::now());
+            return RateLimitDecision {
+                limit: tier_limit,
+                remaining: 0,
+                reset_after: blocked_for,
+                retry_after: Some(blocked_for),
+            };
         }
 
-        if let Some(penalty) = client.record_request(limit, backoff_multiplier) {
-            return Err(penalty);
-        }
-
-        Ok(())
+        client.record_request(limit, backoff_multiplier, max_penalty)
     }
 }
 
@@ -190,16 +354,25 @@ mod tests {
     use super::*;
     use crate::rate_limiting::rate_limit_state::{ActionRateLimit, RateLimitTier};
 
+    const HOUR: Duration = Duration::from_secs(3600);
+
     fn make_limit(window_secs: u64, max_requests: u32) -> ActionRateLimit {
-        ActionRateLimit {
-            tiers: vec![RateLimitTier { window_secs, max_requests }],
-        }
+        ActionRateLimit::sliding_window(vec![RateLimitTier {
+            window_secs,
+            max_requests,
+        }])
     }
 
     fn make_multi_tier(tiers: Vec<(u64, u32)>) -> ActionRateLimit {
-        ActionRateLimit {
-            tiers: tiers.into_iter().map(|(w, m)| RateLimitTier { window_secs: w, max_requests: m }).collect(),
-        }
+        ActionRateLimit::sliding_window(
+            tiers
+                .into_iter()
+                .map(|(w, m)| RateLimitTier {
+                    window_secs: w,
+                    max_requests: m,
+                })
+                .collect(),
+        )
     }
 
     #[tokio::test]
@@ -207,7 +380,10 @@ mod tests {
         let backend = InMemoryBackend::new();
         let limit = make_limit(60, 5);
         for _ in 0..5 {
-            assert!(backend.check_rate_limit("ip/action", &limit, 2.0).await.is_ok());
+            assert!(backend
+                .check_rate_limit("ip/action", &limit, 2.0, HOUR)
+                .await
+                .is_allowed());
         }
     }
 
@@ -216,18 +392,33 @@ mod tests {
         let backend = InMemoryBackend::new();
         let limit = make_limit(60, 3);
         for _ in 0..3 {
-            assert!(backend.check_rate_limit("ip/action", &limit, 2.0).await.is_ok());
+            assert!(backend
+                .check_rate_limit("ip/action", &limit, 2.0, HOUR)
+                .await
+                .is_allowed());
         }
-        assert!(backend.check_rate_limit("ip/action", &limit, 2.0).await.is_err());
+        assert!(!backend
+            .check_rate_limit("ip/action", &limit, 2.0, HOUR)
+            .await
+            .is_allowed());
     }
 
     #[tokio::test]
     async fn test_multi_tier_catches_fast_burst() {
         let backend = InMemoryBackend::new();
         let limit = make_multi_tier(vec![(5, 2), (60, 100)]);
-        assert!(backend.check_rate_limit("ip/action", &limit, 2.0).await.is_ok());
-        assert!(backend.check_rate_limit("ip/action", &limit, 2.0).await.is_ok());
-        assert!(backend.check_rate_limit("ip/action", &limit, 2.0).await.is_err());
+        assert!(backend
+            .check_rate_limit("ip/action", &limit, 2.0, HOUR)
+            .await
+            .is_allowed());
+        assert!(backend
+            .check_rate_limit("ip/action", &limit, 2.0, HOUR)
+            .await
+            .is_allowed());
+        assert!(!backend
+            .check_rate_limit("ip/action", &limit, 2.0, HOUR)
+            .await
+            .is_allowed());
     }
 
     #[tokio::test]
@@ -235,7 +426,10 @@ mod tests {
         let backend = InMemoryBackend::new();
         let limit = make_multi_tier(vec![(5, 100), (60, 200)]);
         for _ in 0..50 {
-            assert!(backend.check_rate_limit("ip/action", &limit, 2.0).await.is_ok());
+            assert!(backend
+                .check_rate_limit("ip/action", &limit, 2.0, HOUR)
+                .await
+                .is_allowed());
         }
     }
 
@@ -247,20 +441,87 @@ mod tests {
         let limit = make_limit(1, 2); // 1s window, max 2
 
         // Hit the limit → violations = 1, penalty = 1s
-        assert!(backend.check_rate_limit("ip/test", &limit, 2.0).await.is_ok());
-        assert!(backend.check_rate_limit("ip/test", &limit, 2.0).await.is_ok());
-        assert!(backend.check_rate_limit("ip/test", &limit, 2.0).await.is_err());
+        assert!(backend
+            .check_rate_limit("ip/test", &limit, 2.0, HOUR)
+            .await
+            .is_allowed());
+        assert!(backend
+            .check_rate_limit("ip/test", &limit, 2.0, HOUR)
+            .await
+            .is_allowed());
+        assert!(!backend
+            .check_rate_limit("ip/test", &limit, 2.0, HOUR)
+            .await
+            .is_allowed());
 
         // Wait for block to expire
         thread::sleep(Duration::from_millis(1100));
 
         // First request after expiry should succeed and reset violations
-        assert!(backend.check_rate_limit("ip/test", &limit, 2.0).await.is_ok(), "First request after block should succeed");
+        assert!(
+            backend
+                .check_rate_limit("ip/test", &limit, 2.0, HOUR)
+                .await
+                .is_allowed(),
+            "First request after block should succeed"
+        );
 
         // Hit the limit again — penalty should be back to 1s (violations reset to 0)
-        assert!(backend.check_rate_limit("ip/test", &limit, 2.0).await.is_ok());
-        let err = backend.check_rate_limit("ip/test", &limit, 2.0).await;
-        assert!(err.is_err());
-        assert!(err.unwrap_err().as_secs() <= 1, "Penalty should be base window, not doubled");
+        assert!(backend
+            .check_rate_limit("ip/test", &limit, 2.0, HOUR)
+            .await
+            .is_allowed());
+        let decision = backend.check_rate_limit("ip/test", &limit, 2.0, HOUR).await;
+        assert!(!decision.is_allowed());
+        assert!(
+            decision.retry_after.unwrap().as_secs() <= 1,
+            "Penalty should be base window, not doubled"
+        );
+    }
+
+    #[test]
+    fn test_penalty_never_exceeds_cap() {
+        let mut client = ClientState::new();
+        let limit = make_limit(60, 1);
+        let cap = Duration::from_secs(600);
+
+        assert!(client.record_request(&limit, 10.0, cap).is_allowed());
+
+        // Recording directly skips the block check, so violations keep piling up
+        for _ in 0..200 {
+            let decision = client.record_request(&limit, 10.0, cap);
+            assert!(decision.retry_after.unwrap() <= cap);
+        }
+        assert_eq!(client.is_blocked().map(|d| d <= cap), Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_token_bucket_allows_burst_then_refills() {
+        let backend = InMemoryBackend::new();
+        let limit = ActionRateLimit::token_bucket(10.0, 3); // one token every 100ms
+
+        for remaining in [2, 1, 0] {
+            let decision = backend
+                .check_rate_limit("ip/search", &limit, 2.0, HOUR)
+                .await;
+            assert!(decision.is_allowed());
+            assert_eq!((decision.limit, decision.remaining), (3, remaining));
+        }
+
+        let decision = backend
+            .check_rate_limit("ip/search", &limit, 2.0, HOUR)
+            .await;
+        assert!(!decision.is_allowed());
+        assert!(decision.retry_after.unwrap() <= Duration::from_millis(100));
+
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert!(backend
+            .check_rate_limit("ip/search", &limit, 2.0, HOUR)
+            .await
+            .is_allowed());
+        assert!(!backend
+            .check_rate_limit("ip/search", &limit, 2.0, HOUR)
+            .await
+            .is_allowed());
     }
 }
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+/// Outcome of a rate limit check.
+///
+/// `limit`, `remaining` and `reset_after` describe the tightest tier — the one
+/// with the fewest requests left — and back the `X-RateLimit-*` headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    /// Requests allowed per window by the tightest tier
+    pub limit: u32,
+    /// Requests left in that tier's window, counting this one
+    pub remaining: u32,
+    /// Time until that tier has room for another request
+    pub reset_after: Duration,
+    /// Set if the request was rejected: how long the client must wait
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimitDecision {
+    /// A decision that allows the request without tracking any quota, used
+    /// when rate limiting is disabled or the backend is unavailable.
+    pub const fn unlimited() -> Self {
+        Self {
+            limit: u32::MAX,
+            remaining: u32::MAX,
+            reset_after: Duration::ZERO,
+            retry_after: None,
+        }
+    }
+
+    /// Whether the request may proceed.
+    pub const fn is_allowed(&self) -> bool {
+        self.retry_after.is_none()
+    }
+
+    /// Whether no quota applied to the request.
+    pub const fn is_unlimited(&self) -> bool {
+        self.limit == u32::MAX
+    }
+}
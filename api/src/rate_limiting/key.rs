@@ -0,0 +1,71 @@
+use std::fmt;
+use std::net::IpAddr;
+
+use uuid::Uuid;
+
+/// What a rate limit bucket is counted against.
+///
+/// The middleware buckets by client IP, or by user when
+/// [`UserKeyMode`](super::rate_limit_state::UserKeyMode) is enabled and the
+/// request carries a [`RateLimitUserExt`](super::RateLimitUserExt). Handlers can check their own keys
+/// through [`RateLimitState::check_rate_limit`](super::RateLimitState::check_rate_limit),
+/// e.g. to cap writes per target entity across all users.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // At most N comments per post per minute, whoever writes them
+/// let key = RateLimitKey::Custom(format!("comment_create:post:{post_id}"));
+/// let decision = app
+///     .rate_limit_state
+///     .check_rate_limit(key, &RateLimitAction::new("comment_create"))
+///     .await;
+/// if !decision.is_allowed() {
+///     return Err(RequestError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited"));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+    /// A client IP address
+    Ip(IpAddr),
+    /// An authenticated user, wherever they connect from
+    User(Uuid),
+    /// A user connecting from a specific IP address
+    Composite { ip: IpAddr, user: Uuid },
+    /// An application-defined key, e.g. `comment_create:post:{id}`
+    Custom(String),
+}
+
+impl RateLimitKey {
+    /// The client IP this key is tied to, if any.
+    pub fn ip(&self) -> Option<IpAddr> {
+        match self {
+            Self::Ip(ip) | Self::Composite { ip, .. } => Some(*ip),
+            Self::User(_) | Self::Custom(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for RateLimitKey {
+    /// Custom keys are prefixed so they can never collide with an IP bucket.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ip(ip) => write!(f, "{ip}"),
+            Self::User(user) => write!(f, "user:{user}"),
+            Self::Composite { ip, user } => write!(f, "{ip}+user:{user}"),
+            Self::Custom(key) => write!(f, "custom:{key}"),
+        }
+    }
+}
+
+impl From<IpAddr> for RateLimitKey {
+    fn from(ip: IpAddr) -> Self {
+        Self::Ip(ip)
+    }
+}
+
+impl From<Uuid> for RateLimitKey {
+    fn from(user: Uuid) -> Self {
+        Self::User(user)
+    }
+}
@@ -1,15 +1,27 @@
-use std::net::{IpAddr, SocketAddr};
-
 use axum::{
     body::Body,
     extract::{Request, State},
-    http::{header, StatusCode},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
     middleware::Next,
     response::Response,
 };
 use tracing::{debug, instrument, warn};
+use uuid::Uuid;
+
+use std::time::Duration;
+
+use chrono::{DateTime, SubsecRound, Timelike, Utc};
+
+use super::{
+    action::RateLimitAction,
+    decision::RateLimitDecision,
+    rate_limit_state::{RateLimitState, RetryAfterFormat},
+};
+use crate::api::client_ip::resolve_client_ip;
 
-use super::{action::RateLimitAction, rate_limit_state::RateLimitState};
+const X_RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+const X_RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+const X_RATELIMIT_RESET: HeaderName = HeaderName::from_static("x-ratelimit-reset");
 
 /// Extension key for storing the rate limit action in request extensions.
 ///
@@ -18,78 +30,63 @@ use super::{action::RateLimitAction, rate_limit_state::RateLimitState};
 #[derive(Debug, Clone)]
 pub struct RateLimitActionExt(pub RateLimitAction);
 
-/// Extract client IP from proxy headers, falling back to the socket address.
+/// Extension carrying the authenticated user of a request, for
+/// [`UserKeyMode`](super::rate_limit_state::UserKeyMode) bucketing.
 ///
-/// Only reads proxy headers when `trust_proxy` is enabled — otherwise an attacker
-/// could spoof `X-Forwarded-For` to bypass rate limiting entirely.
-fn extract_client_ip(req: &Request, trust_proxy: bool) -> Option<IpAddr> {
-    if trust_proxy {
-        // X-Forwarded-For: client, proxy1, proxy2 — leftmost is the real client
-        if let Some(ip) = req
-            .headers()
-            .get("X-Forwarded-For")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.split(',').next())
-            .and_then(|s| s.trim().parse::<IpAddr>().ok())
-        {
-            return Some(ip);
-        }
-
-        if let Some(ip) = req
-            .headers()
-            .get("X-Real-IP")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.trim().parse::<IpAddr>().ok())
-        {
-            return Some(ip);
-        }
-    }
-
-    req.extensions()
-        .get::<axum::extract::ConnectInfo<SocketAddr>>()
-        .map(|ci| ci.0.ip())
-}
+/// The router inserts it from a valid bearer token before rate limiting runs.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitUserExt(pub Uuid);
 
 /// Middleware function that enforces rate limits.
 ///
-/// Extracts the client IP address and rate limit action, then checks
-/// if the request should be allowed. Returns 429 Too Many Requests
-/// with a Retry-After header if the rate limit is exceeded.
-#[instrument(skip(state, req, next), fields(ip, action))]
+/// Extracts the client IP address, the user (if any) and the rate limit
+/// action, then checks if the request should be allowed. Returns 429 Too Many
+/// Requests with a Retry-After header if the rate limit is exceeded. Every
+/// response carries `X-RateLimit-*` headers describing the tightest tier.
+#[instrument(skip(state, req, next), fields(key, action))]
 pub async fn rate_limit_middleware(
     State(state): State<RateLimitState>,
     req: Request,
     next: Next,
 ) -> Response {
-    let ip = match extract_client_ip(&req, state.trust_proxy()) {
-        Some(ip) => ip,
+    let ip = resolve_client_ip(
+        req.headers(),
+        req.extensions(),
+        state.trust_proxy(),
+        state.trusted_proxies(),
+    );
+    // Checked here as well because a user-only key no longer carries the IP
+    if ip.is_some_and(|ip| state.is_allowlisted(ip)) {
+        return next.run(req).await;
+    }
+    let user = req.extensions().get::<RateLimitUserExt>().map(|ext| ext.0);
+
+    let key = match state.key_for(ip, user) {
+        Some(key) => key,
         None => {
             warn!("No client IP found in request, allowing request");
             return next.run(req).await;
         }
     };
 
-    tracing::Span::current().record("ip", tracing::field::display(&ip));
+    tracing::Span::current().record("key", tracing::field::display(&key));
 
-    // Get the action from request extensions, or use a default
+    // Get the action from request extensions, or use the configured default
     let action = req
         .extensions()
         .get::<RateLimitActionExt>()
         .map(|ext| ext.0.clone())
-        .unwrap_or_else(|| RateLimitAction::new("default"));
+        .unwrap_or_else(|| state.default_action());
 
     tracing::Span::current().record("action", action.as_str());
 
-    // Check rate limit
-    match state.check_rate_limit(ip, &action).await {
-        Ok(()) => {
-            // Request allowed
-            next.run(req).await
-        }
-        Err(retry_after) => {
-            // Rate limit exceeded
+    let decision = state.check_rate_limit(key.clone(), &action).await;
+
+    let mut response = match decision.retry_after {
+        None => next.run(req).await,
+        Some(retry_after) => {
             debug!(
-                ip = %ip,
+                key = %key,
                 action = action.as_str(),
                 retry_after_secs = retry_after.as_secs(),
                 "Rate limit exceeded, returning 429"
@@ -97,13 +94,52 @@ pub async fn rate_limit_middleware(
 
             Response::builder()
                 .status(StatusCode::TOO_MANY_REQUESTS)
-                .header(header::RETRY_AFTER, retry_after.as_secs().to_string())
+                .header(
+                    header::RETRY_AFTER,
+                    retry_after_value(retry_after, state.retry_after_format(), Utc::now()),
+                )
                 .body(Body::from("Rate limit exceeded. Please try again later."))
                 .unwrap()
         }
+    };
+
+    if !decision.is_unlimited() {
+        insert_rate_limit_headers(response.headers_mut(), &decision);
+    }
+    response
+}
+
+/// The `Retry-After` value for a client blocked for `retry_after`. The
+/// HTTP-date is rounded up to the next whole second, so a client that waits
+/// until then is never retrying early.
+fn retry_after_value(
+    retry_after: Duration,
+    format: RetryAfterFormat,
+    now: DateTime<Utc>,
+) -> String {
+    match format {
+        RetryAfterFormat::Seconds => retry_after.as_secs().to_string(),
+        RetryAfterFormat::HttpDate => {
+            let retry_at = now + retry_after;
+            let retry_at = if retry_at.nanosecond() > 0 {
+                retry_at.trunc_subsecs(0) + chrono::Duration::seconds(1)
+            } else {
+                retry_at
+            };
+            retry_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+        }
     }
 }
 
+/// Set `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset`
+/// (whole seconds until the tightest tier has room, rounded up).
+fn insert_rate_limit_headers(headers: &mut HeaderMap, decision: &RateLimitDecision) {
+    let reset = decision.reset_after.as_secs() + u64::from(decision.reset_after.subsec_nanos() > 0);
+    headers.insert(X_RATELIMIT_LIMIT, HeaderValue::from(decision.limit));
+    headers.insert(X_RATELIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(X_RATELIMIT_RESET, HeaderValue::from(reset));
+}
+
 /// Helper function to create request extensions with a rate limit action.
 ///
 /// This can be used in route-specific middleware to set the action name
@@ -111,3 +147,201 @@ pub async fn rate_limit_middleware(
 pub fn with_rate_limit_action(action: impl Into<RateLimitAction>) -> RateLimitActionExt {
     RateLimitActionExt(action.into())
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        http::{HeaderMap, StatusCode},
+        routing::get,
+        Router,
+    };
+
+    use std::time::Duration;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::retry_after_value;
+    use crate::{
+        app::App,
+        auth::jwt::generate_token,
+        database::migrations::Migrator,
+        rate_limiting::rate_limit_state::{RateLimitConfig, RetryAfterFormat, UserKeyMode},
+        tests::setup_test::{no_fixtures, setup_test_with_rate_limit},
+    };
+
+    fn test_router(_app: App) -> Router {
+        Router::new().route("/ping", get(|| async { "pong" }))
+    }
+
+    #[tokio::test]
+    async fn test_tightened_limit_returns_429() {
+        let config = RateLimitConfig::builder()
+            .trust_proxy(true)
+            .action("default")
+            .tier(60, 2)
+            .build();
+        let test = setup_test_with_rate_limit::<Migrator>(test_router, no_fixtures, config).await;
+
+        for _ in 0..2 {
+            test.server
+                .get("/api/ping")
+                .add_header("X-Forwarded-For", "203.0.113.7")
+                .await
+                .assert_status_ok();
+        }
+
+        let response = test
+            .server
+            .get("/api/ping")
+            .add_header("X-Forwarded-For", "203.0.113.7")
+            .await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key("retry-after"));
+    }
+
+    #[tokio::test]
+    async fn test_allowed_responses_carry_quota_headers() {
+        let config = RateLimitConfig::builder()
+            .trust_proxy(true)
+            .action("default")
+            .tier(5, 3)
+            .tier(60, 2)
+            .build();
+        let test = setup_test_with_rate_limit::<Migrator>(test_router, no_fixtures, config).await;
+        let ping = || {
+            test.server
+                .get("/api/ping")
+                .add_header("X-Forwarded-For", "203.0.113.10")
+        };
+
+        // The 60s tier has fewer requests left, so it is the one reported
+        let first = ping().await;
+        first.assert_status_ok();
+        first.assert_header("x-ratelimit-limit", "2");
+        first.assert_header("x-ratelimit-remaining", "1");
+        first.assert_header("x-ratelimit-reset", "60");
+
+        ping().await.assert_header("x-ratelimit-remaining", "0");
+
+        let blocked = ping().await;
+        blocked.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        blocked.assert_header("x-ratelimit-limit", "2");
+        blocked.assert_header("x-ratelimit-remaining", "0");
+        blocked.assert_header("retry-after", "60");
+    }
+
+    #[test]
+    fn test_retry_after_formats() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 21, 7, 26, 0).unwrap();
+        let wait = Duration::from_secs(120);
+        assert_eq!(
+            retry_after_value(wait, RetryAfterFormat::Seconds, now),
+            "120"
+        );
+        assert_eq!(
+            retry_after_value(wait, RetryAfterFormat::HttpDate, now),
+            "Wed, 21 Oct 2026 07:28:00 GMT"
+        );
+
+        // A fraction of a second rounds up, never down
+        let now = now + chrono::Duration::milliseconds(250);
+        assert_eq!(
+            retry_after_value(wait, RetryAfterFormat::HttpDate, now),
+            "Wed, 21 Oct 2026 07:28:01 GMT"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_as_http_date() {
+        let config = RateLimitConfig::builder()
+            .trust_proxy(true)
+            .retry_after_format(RetryAfterFormat::HttpDate)
+            .action("default")
+            .tier(60, 1)
+            .build();
+        let test = setup_test_with_rate_limit::<Migrator>(test_router, no_fixtures, config).await;
+        let ping = || {
+            test.server
+                .get("/api/ping")
+                .add_header("X-Forwarded-For", "203.0.113.11")
+        };
+
+        ping().await.assert_status_ok();
+        let before = Utc::now();
+        let blocked = ping().await;
+        blocked.assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+        let header = blocked.headers()["retry-after"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(header.ends_with(" GMT"), "not an HTTP-date: {header}");
+        let retry_at =
+            chrono::DateTime::parse_from_rfc2822(&header.replace("GMT", "+0000")).unwrap();
+        let wait = retry_at.signed_duration_since(before).num_seconds();
+        assert!((59..=61).contains(&wait), "retry in {wait}s");
+    }
+
+    #[tokio::test]
+    async fn test_untagged_route_uses_configured_default_action() {
+        let config = RateLimitConfig::builder()
+            .trust_proxy(true)
+            .default_action("catch_all")
+            .action("catch_all")
+            .tier(60, 1)
+            .build();
+        let test = setup_test_with_rate_limit::<Migrator>(test_router, no_fixtures, config).await;
+
+        test.server
+            .get("/api/ping")
+            .add_header("X-Forwarded-For", "203.0.113.8")
+            .await
+            .assert_status_ok();
+
+        test.server
+            .get("/api/ping")
+            .add_header("X-Forwarded-For", "203.0.113.8")
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_users_behind_one_ip_have_separate_buckets() {
+        let config = RateLimitConfig::builder()
+            .trust_proxy(true)
+            .user_key(UserKeyMode::ReplaceIp)
+            .action("default")
+            .tier(60, 1)
+            .build();
+        let test = setup_test_with_rate_limit::<Migrator>(test_router, no_fixtures, config).await;
+        let token = |user_id| {
+            let token = generate_token(&test.config, user_id, 0, &HeaderMap::new()).unwrap();
+            format!("Bearer {token}")
+        };
+        let alice = token(uuid::Uuid::new_v4());
+        let bob = token(uuid::Uuid::new_v4());
+
+        for authorization in [&alice, &bob] {
+            test.server
+                .get("/api/ping")
+                .add_header("X-Forwarded-For", "203.0.113.9")
+                .add_header("Authorization", authorization)
+                .await
+                .assert_status_ok();
+        }
+
+        test.server
+            .get("/api/ping")
+            .add_header("X-Forwarded-For", "203.0.113.9")
+            .add_header("Authorization", &alice)
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+        // Anonymous requests still fall back to the IP bucket
+        test.server
+            .get("/api/ping")
+            .add_header("X-Forwarded-For", "203.0.113.9")
+            .await
+            .assert_status_ok();
+    }
+}
@@ -1,10 +1,22 @@
 //! Docs: docs/src/content/docs/api/rate-limiting.md
 pub mod action;
 pub mod backend;
+pub mod decision;
+pub mod key;
 pub mod middleware;
 pub mod rate_limit_state;
+pub mod redis_backend;
+pub mod snapshot;
+pub mod stats;
 
 pub use action::RateLimitAction;
 pub use backend::{InMemoryBackend, RateLimitBackend};
-pub use middleware::{rate_limit_middleware, with_rate_limit_action, RateLimitActionExt};
-pub use rate_limit_state::RateLimitState;
+pub use decision::RateLimitDecision;
+pub use key::RateLimitKey;
+pub use middleware::{
+    rate_limit_middleware, with_rate_limit_action, RateLimitActionExt, RateLimitUserExt,
+};
+pub use rate_limit_state::{RateLimitAlgorithm, RateLimitState, RetryAfterFormat, UserKeyMode};
+pub use redis_backend::RedisRateLimitBackend;
+pub use snapshot::RateLimitSnapshot;
+pub use stats::RateLimitStats;
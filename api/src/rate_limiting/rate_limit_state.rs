@@ -1,19 +1,28 @@
 use std::collections::HashMap;
 use std::fmt;
 use std::net::IpAddr;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
+use ipnetwork::IpNetwork;
 use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
 
 use super::action::RateLimitAction;
 use super::backend::{InMemoryBackend, RateLimitBackend};
+use super::decision::RateLimitDecision;
+use super::key::RateLimitKey;
+use super::redis_backend::RedisRateLimitBackend;
+use super::snapshot::RateLimitSnapshot;
+use super::stats::{RateLimitCounters, RateLimitStats};
 
 /// A single tier in a multi-tier rate limit.
 ///
 /// Represents a constraint: no more than `max_requests` in a `window_secs` window.
 /// Multiple tiers catch attacks at different speeds.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RateLimitTier {
     /// Duration of the rate limit window in seconds
     pub window_secs: u64,
@@ -21,24 +30,111 @@ pub struct RateLimitTier {
     pub max_requests: u32,
 }
 
+/// How an action's requests are counted.
+///
+/// ```toml
+/// [rate_limiting.actions.search]
+/// algorithm = { type = "token_bucket", refill_per_sec = 5.0, capacity = 20 }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RateLimitAlgorithm {
+    /// Keep a timestamp per request and enforce every tier.
+    #[default]
+    SlidingWindow,
+    /// Hold up to `capacity` tokens, refilled at `refill_per_sec`; each
+    /// request takes one. Stores two numbers per client instead of a request
+    /// log, so it's cheaper for high-traffic actions. Tiers are ignored.
+    TokenBucket { refill_per_sec: f64, capacity: u32 },
+}
+
 /// Configuration for a specific action's rate limit.
 ///
 /// Uses multiple tiers to catch attacks at different speeds:
 /// - Tier 1: fast burst detection (e.g., 2 requests in 5 seconds)
 /// - Tier 2: moderate rate (e.g., 5 requests per minute)
 /// - Tier 3: sustained rate (e.g., 20 requests per hour)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ActionRateLimit {
     /// Multiple rate limit tiers, checked in order.
     /// If any tier is exceeded, the request is rate-limited.
+    #[serde(default)]
     pub tiers: Vec<RateLimitTier>,
+    /// Sliding windows over `tiers` unless set to a token bucket.
+    #[serde(default)]
+    pub algorithm: RateLimitAlgorithm,
+}
+
+impl ActionRateLimit {
+    /// Limit requests by sliding windows over `tiers`.
+    pub fn sliding_window(tiers: Vec<RateLimitTier>) -> Self {
+        Self {
+            tiers,
+            algorithm: RateLimitAlgorithm::SlidingWindow,
+        }
+    }
+
+    /// Limit requests by a token bucket holding up to `capacity` tokens.
+    pub fn token_bucket(refill_per_sec: f64, capacity: u32) -> Self {
+        Self {
+            tiers: Vec::new(),
+            algorithm: RateLimitAlgorithm::TokenBucket {
+                refill_per_sec,
+                capacity,
+            },
+        }
+    }
+}
+
+/// Where rate limiting state is stored.
+///
+/// ```toml
+/// [rate_limiting.backend]
+/// type = "redis"
+/// url = "redis://127.0.0.1:6379"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RateLimitBackendConfig {
+    /// Process-local counters. Each replica enforces its own limits.
+    #[default]
+    Memory,
+    /// Counters shared by all replicas through Redis.
+    Redis { url: String },
+}
+
+/// Whether requests from authenticated users are bucketed by user.
+///
+/// Useful when many legitimate users share one IP, e.g. behind a corporate NAT.
+/// Anonymous requests are always bucketed by IP.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserKeyMode {
+    /// Bucket every request by IP
+    #[default]
+    Off,
+    /// Bucket authenticated requests by user id alone
+    ReplaceIp,
+    /// Bucket authenticated requests by user id and IP together
+    WithIp,
+}
+
+/// How the `Retry-After` header of a 429 response is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryAfterFormat {
+    /// Seconds to wait, e.g. `120`
+    #[default]
+    Seconds,
+    /// The time the client may retry, e.g. `Wed, 21 Oct 2026 07:28:00 GMT`
+    HttpDate,
 }
 
 /// Global rate limiting configuration.
 ///
 /// Contains default settings and per-action overrides. When an action
 /// is not found in the overrides, the default settings are used.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RateLimitConfig {
     /// Whether rate limiting is enabled
     #[serde(default = "default_enabled")]
@@ -50,6 +146,25 @@ pub struct RateLimitConfig {
     #[serde(default)]
     pub trust_proxy: bool,
 
+    /// Networks of reverse proxies whose forwarding headers are honoured.
+    /// When the peer is in one of these, the client is the right-most address
+    /// in `Forwarded` / `X-Forwarded-For` outside them. Safer than
+    /// `trust_proxy`, which trusts headers from any peer.
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpNetwork>,
+
+    /// Networks that are never rate limited, e.g. monitoring or internal services.
+    #[serde(default)]
+    pub allowlist: Vec<IpNetwork>,
+
+    /// Bucket authenticated requests by user instead of, or together with, their IP.
+    #[serde(default)]
+    pub user_key: UserKeyMode,
+
+    /// Storage for request counters. Use Redis when running several replicas.
+    #[serde(default)]
+    pub backend: RateLimitBackendConfig,
+
     /// Default time window in seconds
     #[serde(default = "default_window_secs")]
     pub default_window_secs: u64,
@@ -62,9 +177,28 @@ pub struct RateLimitConfig {
     #[serde(default = "default_backoff_multiplier")]
     pub backoff_multiplier: f64,
 
+    /// Upper bound on a single penalty in seconds, however many violations
+    /// a client has racked up.
+    #[serde(default = "default_max_penalty_secs")]
+    pub max_penalty_secs: u64,
+
+    /// Action name applied to requests that carry no action tag.
+    #[serde(default = "default_action")]
+    pub default_action: String,
+
+    /// Format of the `Retry-After` header on 429 responses.
+    #[serde(default)]
+    pub retry_after_format: RetryAfterFormat,
+
     /// Per-action rate limit overrides. Keys are action names (e.g. `"user_create"`).
     #[serde(default)]
     pub actions: HashMap<String, ActionRateLimit>,
+
+    /// File the in-memory backend's state is saved to on shutdown and
+    /// restored from at boot, so penalties survive a restart. Only for
+    /// single-replica deployments; Redis keeps its own state.
+    #[serde(default)]
+    pub snapshot_path: Option<PathBuf>,
 }
 
 fn default_enabled() -> bool {
@@ -83,92 +217,175 @@ fn default_backoff_multiplier() -> f64 {
     2.0
 }
 
+fn default_max_penalty_secs() -> u64 {
+    3600
+}
+
+fn default_action() -> String {
+    "default".to_string()
+}
+
 impl Default for RateLimitConfig {
     fn default() -> Self {
         Self {
             enabled: default_enabled(),
             trust_proxy: false,
+            trusted_proxies: Vec::new(),
+            allowlist: Vec::new(),
+            user_key: UserKeyMode::default(),
+            backend: RateLimitBackendConfig::default(),
             default_window_secs: default_window_secs(),
             default_max_requests: default_max_requests(),
             backoff_multiplier: default_backoff_multiplier(),
+            max_penalty_secs: default_max_penalty_secs(),
+            default_action: default_action(),
+            retry_after_format: RetryAfterFormat::default(),
             actions: Self::default_actions(),
+            snapshot_path: None,
         }
     }
 }
 
 impl RateLimitConfig {
-    /// Pre-configured limits for sensitive auth endpoints.
+    /// Build a config fluently, starting from [`RateLimitConfig::default`].
+    pub fn builder() -> RateLimitConfigBuilder {
+        RateLimitConfigBuilder {
+            config: Self::default(),
+        }
+    }
+
+    /// Pre-configured limits for sensitive auth endpoints, plus the
+    /// `"default"` action used for untagged routes.
     ///
     /// These match the action names emitted by the route-tagging middleware in
     /// `router.rs`. Any action not listed here falls back to the default tier.
     fn default_actions() -> HashMap<String, ActionRateLimit> {
         let mut actions = HashMap::new();
 
+        actions.insert(
+            default_action(),
+            ActionRateLimit::sliding_window(vec![
+                RateLimitTier {
+                    window_secs: 5,
+                    max_requests: 10,
+                },
+                RateLimitTier {
+                    window_secs: 60,
+                    max_requests: 100,
+                },
+            ]),
+        );
+
         actions.insert(
             "user_create".to_string(),
-            ActionRateLimit {
-                tiers: vec![
-                    RateLimitTier { window_secs: 5, max_requests: 2 },
-                    RateLimitTier { window_secs: 60, max_requests: 5 },
-                    RateLimitTier { window_secs: 3600, max_requests: 20 },
-                ],
-            },
+            ActionRateLimit::sliding_window(vec![
+                RateLimitTier {
+                    window_secs: 5,
+                    max_requests: 2,
+                },
+                RateLimitTier {
+                    window_secs: 60,
+                    max_requests: 5,
+                },
+                RateLimitTier {
+                    window_secs: 3600,
+                    max_requests: 20,
+                },
+            ]),
         );
 
         actions.insert(
             "user_verify".to_string(),
-            ActionRateLimit {
-                tiers: vec![
-                    RateLimitTier { window_secs: 5, max_requests: 15 },
-                    RateLimitTier { window_secs: 20, max_requests: 30 },
-                    RateLimitTier { window_secs: 60, max_requests: 60 },
-                    RateLimitTier { window_secs: 300, max_requests: 150 },
-                ],
-            },
+            ActionRateLimit::sliding_window(vec![
+                RateLimitTier {
+                    window_secs: 5,
+                    max_requests: 15,
+                },
+                RateLimitTier {
+                    window_secs: 20,
+                    max_requests: 30,
+                },
+                RateLimitTier {
+                    window_secs: 60,
+                    max_requests: 60,
+                },
+                RateLimitTier {
+                    window_secs: 300,
+                    max_requests: 150,
+                },
+            ]),
         );
 
         actions.insert(
             "user_login".to_string(),
-            ActionRateLimit {
-                tiers: vec![
-                    RateLimitTier { window_secs: 5, max_requests: 5 },
-                    RateLimitTier { window_secs: 60, max_requests: 10 },
-                    RateLimitTier { window_secs: 3600, max_requests: 30 },
-                ],
-            },
+            ActionRateLimit::sliding_window(vec![
+                RateLimitTier {
+                    window_secs: 5,
+                    max_requests: 5,
+                },
+                RateLimitTier {
+                    window_secs: 60,
+                    max_requests: 10,
+                },
+                RateLimitTier {
+                    window_secs: 3600,
+                    max_requests: 30,
+                },
+            ]),
         );
 
         actions.insert(
             "password_reset_request".to_string(),
-            ActionRateLimit {
-                tiers: vec![
-                    RateLimitTier { window_secs: 5, max_requests: 2 },
-                    RateLimitTier { window_secs: 60, max_requests: 5 },
-                    RateLimitTier { window_secs: 3600, max_requests: 10 },
-                ],
-            },
+            ActionRateLimit::sliding_window(vec![
+                RateLimitTier {
+                    window_secs: 5,
+                    max_requests: 2,
+                },
+                RateLimitTier {
+                    window_secs: 60,
+                    max_requests: 5,
+                },
+                RateLimitTier {
+                    window_secs: 3600,
+                    max_requests: 10,
+                },
+            ]),
         );
 
         actions.insert(
             "password_reset_confirm".to_string(),
-            ActionRateLimit {
-                tiers: vec![
-                    RateLimitTier { window_secs: 5, max_requests: 5 },
-                    RateLimitTier { window_secs: 60, max_requests: 10 },
-                    RateLimitTier { window_secs: 3600, max_requests: 20 },
-                ],
-            },
+            ActionRateLimit::sliding_window(vec![
+                RateLimitTier {
+                    window_secs: 5,
+                    max_requests: 5,
+                },
+                RateLimitTier {
+                    window_secs: 60,
+                    max_requests: 10,
+                },
+                RateLimitTier {
+                    window_secs: 3600,
+                    max_requests: 20,
+                },
+            ]),
         );
 
         actions.insert(
             "resend_verification".to_string(),
-            ActionRateLimit {
-                tiers: vec![
-                    RateLimitTier { window_secs: 5, max_requests: 2 },
-                    RateLimitTier { window_secs: 60, max_requests: 5 },
-                    RateLimitTier { window_secs: 3600, max_requests: 10 },
-                ],
-            },
+            ActionRateLimit::sliding_window(vec![
+                RateLimitTier {
+                    window_secs: 5,
+                    max_requests: 2,
+                },
+                RateLimitTier {
+                    window_secs: 60,
+                    max_requests: 5,
+                },
+                RateLimitTier {
+                    window_secs: 3600,
+                    max_requests: 10,
+                },
+            ]),
         );
 
         actions
@@ -185,62 +402,239 @@ impl RateLimitConfig {
             .unwrap_or_else(|| {
                 // Short burst window (1/12 of the main window) + full window.
                 // .max(1) prevents a zero window if default_window_secs < 12.
-                ActionRateLimit {
-                    tiers: vec![
-                        RateLimitTier {
-                            window_secs: (self.default_window_secs / 12).max(1),
-                            max_requests: (self.default_max_requests / 10).max(1),
-                        },
-                        RateLimitTier {
-                            window_secs: self.default_window_secs,
-                            max_requests: self.default_max_requests,
-                        },
-                    ],
-                }
+                ActionRateLimit::sliding_window(vec![
+                    RateLimitTier {
+                        window_secs: (self.default_window_secs / 12).max(1),
+                        max_requests: (self.default_max_requests / 10).max(1),
+                    },
+                    RateLimitTier {
+                        window_secs: self.default_window_secs,
+                        max_requests: self.default_max_requests,
+                    },
+                ])
             })
     }
 }
 
+/// Fluent builder for [`RateLimitConfig`], starting from the defaults.
+///
+/// ```rust,ignore
+/// let config = RateLimitConfig::builder()
+///     .trust_proxy(true)
+///     .action("user_create")
+///     .tier(5, 2)
+///     .tier(60, 5)
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimitConfigBuilder {
+    config: RateLimitConfig,
+}
+
+impl RateLimitConfigBuilder {
+    #[must_use]
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.config.enabled = enabled;
+        self
+    }
+
+    #[must_use]
+    pub fn trust_proxy(mut self, trust_proxy: bool) -> Self {
+        self.config.trust_proxy = trust_proxy;
+        self
+    }
+
+    /// Limit applied to actions without their own tiers.
+    #[must_use]
+    pub fn default_limit(mut self, window_secs: u64, max_requests: u32) -> Self {
+        self.config.default_window_secs = window_secs;
+        self.config.default_max_requests = max_requests;
+        self
+    }
+
+    #[must_use]
+    pub fn trusted_proxies(mut self, trusted_proxies: impl IntoIterator<Item = IpNetwork>) -> Self {
+        self.config.trusted_proxies = trusted_proxies.into_iter().collect();
+        self
+    }
+
+    #[must_use]
+    pub fn allowlist(mut self, allowlist: impl IntoIterator<Item = IpNetwork>) -> Self {
+        self.config.allowlist = allowlist.into_iter().collect();
+        self
+    }
+
+    #[must_use]
+    pub fn user_key(mut self, user_key: UserKeyMode) -> Self {
+        self.config.user_key = user_key;
+        self
+    }
+
+    #[must_use]
+    pub fn backend(mut self, backend: RateLimitBackendConfig) -> Self {
+        self.config.backend = backend;
+        self
+    }
+
+    #[must_use]
+    pub fn backoff_multiplier(mut self, backoff_multiplier: f64) -> Self {
+        self.config.backoff_multiplier = backoff_multiplier;
+        self
+    }
+
+    #[must_use]
+    pub fn max_penalty_secs(mut self, max_penalty_secs: u64) -> Self {
+        self.config.max_penalty_secs = max_penalty_secs;
+        self
+    }
+
+    #[must_use]
+    pub fn default_action(mut self, action: impl Into<String>) -> Self {
+        self.config.default_action = action.into();
+        self
+    }
+
+    #[must_use]
+    pub fn retry_after_format(mut self, retry_after_format: RetryAfterFormat) -> Self {
+        self.config.retry_after_format = retry_after_format;
+        self
+    }
+
+    /// Start configuring `action`. Its tiers replace any existing ones,
+    /// including the built-in defaults.
+    #[must_use]
+    pub fn action(self, action: impl Into<String>) -> ActionRateLimitBuilder {
+        ActionRateLimitBuilder {
+            parent: self,
+            action: action.into(),
+            tiers: Vec::new(),
+            algorithm: RateLimitAlgorithm::SlidingWindow,
+        }
+    }
+
+    pub fn build(self) -> RateLimitConfig {
+        self.config
+    }
+}
+
+/// Collects the tiers of one action; returned by [`RateLimitConfigBuilder::action`].
+#[derive(Debug, Clone)]
+pub struct ActionRateLimitBuilder {
+    parent: RateLimitConfigBuilder,
+    action: String,
+    tiers: Vec<RateLimitTier>,
+    algorithm: RateLimitAlgorithm,
+}
+
+impl ActionRateLimitBuilder {
+    /// Allow at most `max_requests` per `window_secs`.
+    #[must_use]
+    pub fn tier(mut self, window_secs: u64, max_requests: u32) -> Self {
+        self.tiers.push(RateLimitTier {
+            window_secs,
+            max_requests,
+        });
+        self
+    }
+
+    /// Use a token bucket instead of tiers: up to `capacity` requests at
+    /// once, refilled at `refill_per_sec`.
+    #[must_use]
+    pub fn token_bucket(mut self, refill_per_sec: f64, capacity: u32) -> Self {
+        self.algorithm = RateLimitAlgorithm::TokenBucket {
+            refill_per_sec,
+            capacity,
+        };
+        self
+    }
+
+    /// Finish this action and start configuring another.
+    #[must_use]
+    pub fn action(self, action: impl Into<String>) -> Self {
+        self.finish().action(action)
+    }
+
+    pub fn build(self) -> RateLimitConfig {
+        self.finish().build()
+    }
+
+    fn finish(mut self) -> RateLimitConfigBuilder {
+        self.parent.config.actions.insert(
+            self.action,
+            ActionRateLimit {
+                tiers: self.tiers,
+                algorithm: self.algorithm,
+            },
+        );
+        self.parent
+    }
+}
+
 /// Rate limiting state — config plus a pluggable storage backend.
 ///
 /// The default constructor uses [`InMemoryBackend`], which is correct for
-/// single-replica deployments. For multi-replica use [`RateLimitState::with_backend`]
-/// and supply a shared-store backend (Redis, Postgres, etc.).
+/// single-replica deployments. [`RateLimitState::from_config`] honours the
+/// configured `backend`, and [`RateLimitState::with_backend`] accepts any other
+/// shared-store backend.
 #[derive(Clone)]
 pub struct RateLimitState {
+    /// Config at startup. Client IP resolution, keying and the backend are
+    /// read from here and never change.
     config: Arc<RateLimitConfig>,
+    /// Config the limits are checked against, replaced by [`Self::reload_config`]
+    live: Arc<RwLock<Arc<RateLimitConfig>>>,
     backend: Arc<dyn RateLimitBackend>,
     /// Kept as a concrete reference so the cleanup task can call
     /// `cleanup_expired_entries` without needing a trait method or downcasting.
     in_memory: Option<Arc<InMemoryBackend>>,
+    counters: Arc<RateLimitCounters>,
 }
 
 impl fmt::Debug for RateLimitState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RateLimitState")
-            .field("config", &self.config)
+            .field("config", &self.live_config())
             .field("backend", &"<dyn RateLimitBackend>")
             .finish()
     }
 }
 
 impl RateLimitState {
-    /// Create a new state with the default in-memory backend.
+    /// Create a new state with the backend selected by `config.backend`.
+    ///
+    /// Fails only if the Redis URL is invalid; the connection itself is opened lazily.
+    pub fn from_config(config: RateLimitConfig) -> Result<Self, redis::RedisError> {
+        match &config.backend {
+            RateLimitBackendConfig::Memory => Ok(Self::new(config)),
+            RateLimitBackendConfig::Redis { url } => {
+                let backend = RedisRateLimitBackend::new(url)?;
+                Ok(Self::with_backend(config, Arc::new(backend)))
+            }
+        }
+    }
+
+    /// Create a new state with the in-memory backend, ignoring `config.backend`.
     pub fn new(config: RateLimitConfig) -> Self {
         let backend = Arc::new(InMemoryBackend::new());
+        let config = Arc::new(config);
         Self {
-            config: Arc::new(config),
+            live: Arc::new(RwLock::new(config.clone())),
+            config,
             in_memory: Some(backend.clone()),
             backend,
+            counters: Arc::default(),
         }
     }
 
     /// Create a new state with a custom backend (e.g. Redis for multi-replica).
     pub fn with_backend(config: RateLimitConfig, backend: Arc<dyn RateLimitBackend>) -> Self {
+        let config = Arc::new(config);
         Self {
-            config: Arc::new(config),
+            live: Arc::new(RwLock::new(config.clone())),
+            config,
             backend,
             in_memory: None,
+            counters: Arc::default(),
         }
     }
 
@@ -249,16 +643,154 @@ impl RateLimitState {
         self.config.trust_proxy
     }
 
-    /// Check if a request from `ip` for `action` is within the rate limit.
+    /// Proxies whose forwarding headers are honoured, see [`RateLimitConfig::trusted_proxies`].
+    pub fn trusted_proxies(&self) -> &[IpNetwork] {
+        &self.config.trusted_proxies
+    }
+
+    /// How authenticated requests are bucketed.
+    pub fn user_key(&self) -> UserKeyMode {
+        self.config.user_key
+    }
+
+    /// The bucket for a request from `ip` made by `user`, following [`UserKeyMode`].
     ///
-    /// Returns `Ok(())` if allowed, or `Err(retry_after)` if blocked.
-    pub async fn check_rate_limit(&self, ip: IpAddr, action: &RateLimitAction) -> Result<(), Duration> {
-        if !self.config.enabled {
-            return Ok(());
+    /// `None` if there is nothing to key on, i.e. no IP and no usable user.
+    pub fn key_for(&self, ip: Option<IpAddr>, user: Option<Uuid>) -> Option<RateLimitKey> {
+        match (self.config.user_key, ip, user) {
+            (UserKeyMode::ReplaceIp, _, Some(user)) => Some(RateLimitKey::User(user)),
+            (UserKeyMode::WithIp, Some(ip), Some(user)) => {
+                Some(RateLimitKey::Composite { ip, user })
+            }
+            (_, ip, _) => ip.map(RateLimitKey::Ip),
         }
-        let limit = self.config.get_limit(action);
-        let key = format!("{}/{}", ip, action.as_str());
-        self.backend.check_rate_limit(&key, &limit, self.config.backoff_multiplier).await
+    }
+
+    /// Whether `ip` is in one of the [`RateLimitConfig::allowlist`] networks.
+    pub fn is_allowlisted(&self, ip: IpAddr) -> bool {
+        self.live_config()
+            .allowlist
+            .iter()
+            .any(|net| net.contains(ip))
+    }
+
+    /// Action applied to requests without a [`RateLimitActionExt`](super::RateLimitActionExt).
+    pub fn default_action(&self) -> RateLimitAction {
+        RateLimitAction::new(&self.live_config().default_action)
+    }
+
+    /// How the `Retry-After` header of a 429 response is written.
+    pub fn retry_after_format(&self) -> RetryAfterFormat {
+        self.live_config().retry_after_format
+    }
+
+    /// Check if a request counted against `key` for `action` is within the rate limit.
+    ///
+    /// `key` is usually the client IP; pass a [`RateLimitKey::Custom`] to bucket
+    /// on something else. Each key and action pair has its own bucket.
+    ///
+    /// The decision's `retry_after` is set if the request is blocked. Keys
+    /// carrying an allowlisted IP are always allowed without being counted.
+    pub async fn check_rate_limit(
+        &self,
+        key: impl Into<RateLimitKey>,
+        action: &RateLimitAction,
+    ) -> RateLimitDecision {
+        let key = key.into();
+        let config = self.live_config();
+        if !config.enabled
+            || key
+                .ip()
+                .is_some_and(|ip| config.allowlist.iter().any(|net| net.contains(ip)))
+        {
+            return RateLimitDecision::unlimited();
+        }
+        let limit = config.get_limit(action);
+        let key = format!("{}/{}", key, action.as_str());
+        let max_penalty = Duration::from_secs(config.max_penalty_secs);
+        let decision = self
+            .backend
+            .check_rate_limit(&key, &limit, config.backoff_multiplier, max_penalty)
+            .await;
+        if !decision.is_allowed() {
+            self.counters.record_block(action.as_str());
+        }
+        decision
+    }
+
+    /// The config limits are currently checked against.
+    pub fn live_config(&self) -> Arc<RateLimitConfig> {
+        self.live.read().unwrap().clone()
+    }
+
+    /// Swap in new limits without a restart, e.g. after the config file
+    /// changed. Counters and penalties already recorded are kept.
+    ///
+    /// `trust_proxy`, `trusted_proxies`, `user_key`, `backend` and
+    /// `snapshot_path` are fixed at startup, as is turning rate limiting on
+    /// when it started disabled (the middleware isn't installed then). Changes
+    /// to those are logged and ignored. Returns whether anything was applied.
+    pub fn reload_config(&self, mut config: RateLimitConfig) -> bool {
+        let startup = &self.config;
+        let fixed = [
+            ("trust_proxy", config.trust_proxy != startup.trust_proxy),
+            (
+                "trusted_proxies",
+                config.trusted_proxies != startup.trusted_proxies,
+            ),
+            ("user_key", config.user_key != startup.user_key),
+            ("backend", config.backend != startup.backend),
+            (
+                "snapshot_path",
+                config.snapshot_path != startup.snapshot_path,
+            ),
+            ("enabled", config.enabled && !startup.enabled),
+        ];
+        for (field, _) in fixed.iter().filter(|(_, changed)| *changed) {
+            warn!("🔄 rate_limiting.{field} can't be changed without a restart, ignoring it");
+        }
+        config.trust_proxy = startup.trust_proxy;
+        config.trusted_proxies.clone_from(&startup.trusted_proxies);
+        config.user_key = startup.user_key;
+        config.backend = startup.backend.clone();
+        config.snapshot_path.clone_from(&startup.snapshot_path);
+        config.enabled &= startup.enabled;
+
+        let mut live = self.live.write().unwrap();
+        if **live == config {
+            return false;
+        }
+        *live = Arc::new(config);
+        info!("🔄 Rate limits reloaded");
+        true
+    }
+
+    /// Current client counts and cumulative block counters, e.g. for a
+    /// metrics endpoint. Client counts are only known for the in-memory backend.
+    pub fn stats(&self) -> RateLimitStats {
+        RateLimitStats {
+            tracked_clients: self.in_memory.as_ref().map(|mem| mem.client_count()),
+            blocked_clients: self
+                .in_memory
+                .as_ref()
+                .map(|mem| mem.blocked_client_count()),
+            blocked_requests: self.counters.blocked_requests(),
+            blocked_requests_by_action: self.counters.blocked_requests_by_action(),
+        }
+    }
+
+    /// The in-memory backend's client states, or `None` for other backends.
+    pub fn snapshot(&self) -> Option<RateLimitSnapshot> {
+        self.in_memory.as_ref().map(|mem| mem.snapshot())
+    }
+
+    /// Restore client states saved by [`Self::snapshot`]. Returns `false`,
+    /// ignoring the snapshot, for non-in-memory backends.
+    pub fn restore(&self, snapshot: &RateLimitSnapshot) -> bool {
+        self.in_memory
+            .as_ref()
+            .inspect(|mem| mem.restore(snapshot))
+            .is_some()
     }
 
     /// Remove stale in-memory entries. No-op for non-in-memory backends.
@@ -279,15 +811,26 @@ mod tests {
         RateLimitState::new(RateLimitConfig {
             enabled,
             trust_proxy: false,
+            trusted_proxies: Vec::new(),
+            allowlist: Vec::new(),
+            user_key: UserKeyMode::Off,
+            backend: RateLimitBackendConfig::Memory,
             default_window_secs: 60,
             default_max_requests: default_max,
             backoff_multiplier: 2.0,
+            max_penalty_secs: 3600,
+            default_action: "default".to_string(),
+            retry_after_format: RetryAfterFormat::Seconds,
             actions,
+            snapshot_path: None,
         })
     }
 
     fn action_limit(window_secs: u64, max_requests: u32) -> ActionRateLimit {
-        ActionRateLimit { tiers: vec![RateLimitTier { window_secs, max_requests }] }
+        ActionRateLimit::sliding_window(vec![RateLimitTier {
+            window_secs,
+            max_requests,
+        }])
     }
 
     #[tokio::test]
@@ -295,10 +838,10 @@ mod tests {
         let mut actions = HashMap::new();
         actions.insert("test".to_string(), action_limit(60, 5));
         let state = make_state(true, actions, 5);
-        let ip = "127.0.0.1".parse().unwrap();
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
         let action = RateLimitAction::new("test");
         for _ in 0..5 {
-            assert!(state.check_rate_limit(ip, &action).await.is_ok());
+            assert!(state.check_rate_limit(ip, &action).await.is_allowed());
         }
     }
 
@@ -307,55 +850,114 @@ mod tests {
         let mut actions = HashMap::new();
         actions.insert("test".to_string(), action_limit(60, 3));
         let state = make_state(true, actions, 10);
-        let ip = "127.0.0.1".parse().unwrap();
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
         let action = RateLimitAction::new("test");
         for _ in 0..3 {
-            assert!(state.check_rate_limit(ip, &action).await.is_ok());
+            assert!(state.check_rate_limit(ip, &action).await.is_allowed());
+        }
+        assert!(!state.check_rate_limit(ip, &action).await.is_allowed());
+    }
+
+    #[tokio::test]
+    async fn test_blocked_client_stays_blocked_after_restoring_snapshot() {
+        let actions = HashMap::from([("test".to_string(), action_limit(60, 2))]);
+        let state = make_state(true, actions.clone(), 10);
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let action = RateLimitAction::new("test");
+        for _ in 0..2 {
+            assert!(state.check_rate_limit(ip, &action).await.is_allowed());
         }
-        assert!(state.check_rate_limit(ip, &action).await.is_err());
+        let blocked = state.check_rate_limit(ip, &action).await;
+        assert!(!blocked.is_allowed());
+
+        let path = std::env::temp_dir().join(format!("erno-rate-limits-{}.json", Uuid::new_v4()));
+        let snapshot = state.snapshot().unwrap();
+        snapshot.write(&path).await.unwrap();
+        let read = RateLimitSnapshot::read(&path).await.unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read, snapshot);
+        assert_eq!(read.clients["127.0.0.1/test"].violations, 1);
+
+        // A fresh process restoring the snapshot keeps the block and its history
+        let restarted = make_state(true, actions, 10);
+        assert!(restarted.restore(&read));
+        let decision = restarted.check_rate_limit(ip, &action).await;
+        assert!(!decision.is_allowed());
+        assert!(decision.retry_after.unwrap() <= blocked.retry_after.unwrap());
+        assert!(decision.retry_after.unwrap() > Duration::from_secs(50));
+        assert_eq!(
+            restarted.snapshot().unwrap().clients["127.0.0.1/test"]
+                .requests
+                .len(),
+            2
+        );
+
+        // Other clients aren't affected
+        assert!(restarted
+            .check_rate_limit("10.0.0.1".parse::<IpAddr>().unwrap(), &action)
+            .await
+            .is_allowed());
+        assert_eq!(RateLimitSnapshot::read(&path).await.unwrap(), None);
     }
 
     #[tokio::test]
     async fn test_multi_tier_catches_fast_burst() {
         let mut actions = HashMap::new();
-        actions.insert("test".to_string(), ActionRateLimit {
-            tiers: vec![
-                RateLimitTier { window_secs: 5, max_requests: 2 },
-                RateLimitTier { window_secs: 60, max_requests: 100 },
-            ],
-        });
+        actions.insert(
+            "test".to_string(),
+            ActionRateLimit::sliding_window(vec![
+                RateLimitTier {
+                    window_secs: 5,
+                    max_requests: 2,
+                },
+                RateLimitTier {
+                    window_secs: 60,
+                    max_requests: 100,
+                },
+            ]),
+        );
         let state = make_state(true, actions, 100);
-        let ip = "127.0.0.1".parse().unwrap();
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
         let action = RateLimitAction::new("test");
-        assert!(state.check_rate_limit(ip, &action).await.is_ok());
-        assert!(state.check_rate_limit(ip, &action).await.is_ok());
-        assert!(state.check_rate_limit(ip, &action).await.is_err());
+        assert!(state.check_rate_limit(ip, &action).await.is_allowed());
+        assert!(state.check_rate_limit(ip, &action).await.is_allowed());
+        assert!(!state.check_rate_limit(ip, &action).await.is_allowed());
     }
 
     #[tokio::test]
     async fn test_multi_tier_allows_normal_rate() {
         let mut actions = HashMap::new();
-        actions.insert("test".to_string(), ActionRateLimit {
-            tiers: vec![
-                RateLimitTier { window_secs: 5, max_requests: 100 },
-                RateLimitTier { window_secs: 60, max_requests: 200 },
-            ],
-        });
+        actions.insert(
+            "test".to_string(),
+            ActionRateLimit::sliding_window(vec![
+                RateLimitTier {
+                    window_secs: 5,
+                    max_requests: 100,
+                },
+                RateLimitTier {
+                    window_secs: 60,
+                    max_requests: 200,
+                },
+            ]),
+        );
         let state = make_state(true, actions, 100);
-        let ip = "127.0.0.1".parse().unwrap();
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
         let action = RateLimitAction::new("test");
         for _ in 0..50 {
-            assert!(state.check_rate_limit(ip, &action).await.is_ok(), "Request should succeed");
+            assert!(
+                state.check_rate_limit(ip, &action).await.is_allowed(),
+                "Request should succeed"
+            );
         }
     }
 
     #[tokio::test]
     async fn test_disabled_rate_limiting() {
         let state = make_state(false, HashMap::new(), 1);
-        let ip = "127.0.0.1".parse().unwrap();
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
         let action = RateLimitAction::new("test");
         for _ in 0..100 {
-            assert!(state.check_rate_limit(ip, &action).await.is_ok());
+            assert!(state.check_rate_limit(ip, &action).await.is_allowed());
         }
     }
 
@@ -364,19 +966,19 @@ mod tests {
         let mut actions = HashMap::new();
         actions.insert("strict".to_string(), action_limit(60, 2));
         let state = make_state(true, actions, 100);
-        let ip = "127.0.0.1".parse().unwrap();
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
         let strict = RateLimitAction::new("strict");
         let normal = RateLimitAction::new("normal");
 
-        assert!(state.check_rate_limit(ip, &strict).await.is_ok());
-        assert!(state.check_rate_limit(ip, &strict).await.is_ok());
-        assert!(state.check_rate_limit(ip, &strict).await.is_err());
+        assert!(state.check_rate_limit(ip, &strict).await.is_allowed());
+        assert!(state.check_rate_limit(ip, &strict).await.is_allowed());
+        assert!(!state.check_rate_limit(ip, &strict).await.is_allowed());
 
-        let ip2 = "127.0.0.2".parse().unwrap();
+        let ip2: IpAddr = "127.0.0.2".parse().unwrap();
         for _ in 0..10 {
-            assert!(state.check_rate_limit(ip2, &normal).await.is_ok());
+            assert!(state.check_rate_limit(ip2, &normal).await.is_allowed());
         }
-        assert!(state.check_rate_limit(ip2, &normal).await.is_err());
+        assert!(!state.check_rate_limit(ip2, &normal).await.is_allowed());
     }
 
     #[tokio::test]
@@ -387,8 +989,14 @@ mod tests {
 
         #[async_trait]
         impl RateLimitBackend for AlwaysAllow {
-            async fn check_rate_limit(&self, _key: &str, _limit: &ActionRateLimit, _backoff: f64) -> Result<(), Duration> {
-                Ok(())
+            async fn check_rate_limit(
+                &self,
+                _key: &str,
+                _limit: &ActionRateLimit,
+                _backoff: f64,
+                _max_penalty: Duration,
+            ) -> RateLimitDecision {
+                RateLimitDecision::unlimited()
             }
         }
 
@@ -396,11 +1004,180 @@ mod tests {
             RateLimitConfig { enabled: true, default_max_requests: 1, ..Default::default() },
             Arc::new(AlwaysAllow),
         );
-        let ip = "127.0.0.1".parse().unwrap();
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
         let action = RateLimitAction::new("test");
         // AlwaysAllow never blocks, even past the config limit
         for _ in 0..200 {
-            assert!(state.check_rate_limit(ip, &action).await.is_ok());
+            assert!(state.check_rate_limit(ip, &action).await.is_allowed());
+        }
+    }
+
+    #[test]
+    fn test_builder_sets_action_tiers() {
+        let config = RateLimitConfig::builder()
+            .trust_proxy(true)
+            .action("user_create")
+            .tier(5, 2)
+            .tier(60, 5)
+            .action("report_export")
+            .tier(3600, 1)
+            .action("search")
+            .token_bucket(5.0, 20)
+            .build();
+
+        assert!(config.trust_proxy);
+        let tiers: Vec<_> = config.actions["user_create"]
+            .tiers
+            .iter()
+            .map(|tier| (tier.window_secs, tier.max_requests))
+            .collect();
+        assert_eq!(tiers, [(5, 2), (60, 5)]);
+        assert_eq!(config.actions["report_export"].tiers.len(), 1);
+        assert_eq!(config.actions["report_export"].tiers[0].max_requests, 1);
+        assert_eq!(
+            config.actions["search"].algorithm,
+            RateLimitAlgorithm::TokenBucket {
+                refill_per_sec: 5.0,
+                capacity: 20
+            }
+        );
+        assert_eq!(
+            config.actions["user_create"].algorithm,
+            RateLimitAlgorithm::SlidingWindow
+        );
+        // Actions the builder didn't touch keep their defaults
+        assert_eq!(config.actions["user_login"].tiers.len(), 3);
+    }
+
+    #[test]
+    fn test_from_config_selects_backend() {
+        let config: RateLimitConfig = serde_json::from_value(serde_json::json!({
+            "backend": { "type": "redis", "url": "redis://127.0.0.1:6379" }
+        }))
+        .unwrap();
+        assert_eq!(
+            config.backend,
+            RateLimitBackendConfig::Redis {
+                url: "redis://127.0.0.1:6379".to_string()
+            }
+        );
+        let state = RateLimitState::from_config(config).unwrap();
+        assert!(state.in_memory.is_none());
+
+        let memory = RateLimitState::from_config(RateLimitConfig::default()).unwrap();
+        assert!(memory.in_memory.is_some());
+
+        let invalid = RateLimitConfig::builder()
+            .backend(RateLimitBackendConfig::Redis {
+                url: "not a url".to_string(),
+            })
+            .build();
+        assert!(RateLimitState::from_config(invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_ips_are_never_limited() {
+        let state = RateLimitState::new(
+            RateLimitConfig::builder()
+                .allowlist([
+                    "10.0.0.0/8".parse().unwrap(),
+                    "2001:db8::/32".parse().unwrap(),
+                ])
+                .action("status")
+                .tier(60, 1)
+                .build(),
+        );
+        let action = RateLimitAction::new("status");
+
+        for _ in 0..10 {
+            let ipv4: IpAddr = "10.1.2.3".parse().unwrap();
+            let ipv6: IpAddr = "2001:db8::42".parse().unwrap();
+            assert!(state.check_rate_limit(ipv4, &action).await.is_unlimited());
+            assert!(state.check_rate_limit(ipv6, &action).await.is_unlimited());
         }
+
+        let outsider: IpAddr = "192.0.2.1".parse().unwrap();
+        assert!(state.check_rate_limit(outsider, &action).await.is_allowed());
+        assert!(!state.check_rate_limit(outsider, &action).await.is_allowed());
+    }
+
+    #[tokio::test]
+    async fn test_stats_count_clients_and_blocks() {
+        let mut actions = HashMap::new();
+        actions.insert("login".to_string(), action_limit(60, 1));
+        actions.insert("search".to_string(), action_limit(60, 2));
+        let state = make_state(true, actions, 100);
+        let login = RateLimitAction::new("login");
+        let search = RateLimitAction::new("search");
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let ip2: IpAddr = "127.0.0.2".parse().unwrap();
+
+        assert!(state.check_rate_limit(ip, &login).await.is_allowed());
+        assert!(!state.check_rate_limit(ip, &login).await.is_allowed());
+        assert!(!state.check_rate_limit(ip, &login).await.is_allowed());
+        assert!(state.check_rate_limit(ip2, &search).await.is_allowed());
+
+        let stats = state.stats();
+        assert_eq!(stats.tracked_clients, Some(2));
+        assert_eq!(stats.blocked_clients, Some(1));
+        assert_eq!(stats.blocked_requests, 2);
+        assert_eq!(
+            stats.blocked_requests_by_action,
+            [("login".to_string(), 2)].into()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_keys_have_independent_buckets() {
+        let mut actions = HashMap::new();
+        actions.insert("comment_create".to_string(), action_limit(60, 2));
+        let state = make_state(true, actions, 100);
+        let action = RateLimitAction::new("comment_create");
+        let post_a = RateLimitKey::Custom("comment_create:post:a".to_string());
+        let post_b = RateLimitKey::Custom("comment_create:post:b".to_string());
+
+        assert!(state
+            .check_rate_limit(post_a.clone(), &action)
+            .await
+            .is_allowed());
+        assert!(state
+            .check_rate_limit(post_a.clone(), &action)
+            .await
+            .is_allowed());
+        assert!(!state.check_rate_limit(post_a, &action).await.is_allowed());
+
+        // Post B is untouched by the writes to post A
+        assert!(state
+            .check_rate_limit(post_b.clone(), &action)
+            .await
+            .is_allowed());
+        assert!(state.check_rate_limit(post_b, &action).await.is_allowed());
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_applies_new_limits_and_keeps_fixed_fields() {
+        let mut actions = HashMap::new();
+        actions.insert("login".to_string(), action_limit(60, 1));
+        let state = make_state(true, actions, 100);
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let login = RateLimitAction::new("login");
+        assert!(state.check_rate_limit(ip, &login).await.is_allowed());
+
+        let mut config = (*state.live_config()).clone();
+        config
+            .actions
+            .insert("login".to_string(), action_limit(60, 3));
+        config.trust_proxy = true;
+        assert!(state.reload_config(config.clone()));
+        assert!(
+            !state.reload_config(config),
+            "reloading the same config is a no-op"
+        );
+
+        assert!(!state.trust_proxy());
+        // The request counted before the reload still counts against the new limit
+        assert!(state.check_rate_limit(ip, &login).await.is_allowed());
+        assert!(state.check_rate_limit(ip, &login).await.is_allowed());
+        assert!(!state.check_rate_limit(ip, &login).await.is_allowed());
     }
 }
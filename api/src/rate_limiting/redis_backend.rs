@@ -0,0 +1,236 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::{aio::ConnectionManager, Client, RedisResult, Script};
+use tokio::sync::OnceCell;
+use tracing::warn;
+
+use super::backend::RateLimitBackend;
+use super::decision::RateLimitDecision;
+use super::rate_limit_state::{ActionRateLimit, RateLimitAlgorithm};
+
+/// Sliding-window check run atomically inside Redis.
+///
+/// KEYS[1] is a sorted set of request timestamps, KEYS[2] a hash holding the
+/// violation count and block deadline. ARGV is the backoff multiplier, the
+/// maximum penalty in milliseconds, a unique member for this request, then
+/// `window_ms, max_requests` pairs per tier.
+///
+/// Returns `{retry_after_ms, limit, remaining, reset_ms}` for the tightest tier,
+/// with `retry_after_ms` set to `-1` if the request is allowed. The state hash
+/// expires together with the block, which resets violations the same way
+/// [`InMemoryBackend`](super::InMemoryBackend) does.
+const CHECK_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local multiplier = tonumber(ARGV[1])
+local max_penalty = tonumber(ARGV[2])
+local member = ARGV[3]
+
+local max_window = 0
+for i = 4, #ARGV, 2 do
+    max_window = math.max(max_window, tonumber(ARGV[i]))
+end
+if max_window == 0 then
+    max_window = 60000
+end
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - max_window)
+
+local function tightest_tier()
+    local limit, remaining, reset = 0, nil, 0
+    for i = 4, #ARGV, 2 do
+        local window = tonumber(ARGV[i])
+        local max_requests = tonumber(ARGV[i + 1])
+        local since = '(' .. (now - window)
+        local tier_remaining = math.max(max_requests - redis.call('ZCOUNT', KEYS[1], since, '+inf'), 0)
+        if remaining == nil or tier_remaining < remaining then
+            local oldest = redis.call('ZRANGEBYSCORE', KEYS[1], since, '+inf', 'WITHSCORES', 'LIMIT', 0, 1)
+            limit, remaining, reset = max_requests, tier_remaining, window
+            if oldest[2] then
+                reset = math.max(tonumber(oldest[2]) + window - now, 0)
+            end
+        end
+    end
+    return limit, remaining or 0, reset
+end
+
+local blocked_until = tonumber(redis.call('HGET', KEYS[2], 'blocked_until') or '0')
+if blocked_until > now then
+    local limit = tightest_tier()
+    return {blocked_until - now, limit, 0, blocked_until - now}
+end
+local violations = tonumber(redis.call('HGET', KEYS[2], 'violations') or '0')
+
+for i = 4, #ARGV, 2 do
+    local window = tonumber(ARGV[i])
+    local max_requests = tonumber(ARGV[i + 1])
+    local count = redis.call('ZCOUNT', KEYS[1], '(' .. (now - window), '+inf')
+    if count >= max_requests then
+        violations = violations + 1
+        -- Same cap as MAX_COUNTED_VIOLATIONS in the in-memory backend
+        local exponent = math.min(violations, 32) - 1
+        local penalty = math.floor(math.min(window * multiplier ^ exponent, max_penalty))
+        redis.call('HSET', KEYS[2], 'violations', violations, 'blocked_until', now + penalty)
+        redis.call('PEXPIRE', KEYS[2], math.max(penalty, 1))
+        return {penalty, max_requests, 0, penalty}
+    end
+end
+
+redis.call('ZADD', KEYS[1], now, member)
+redis.call('PEXPIRE', KEYS[1], max_window)
+local limit, remaining, reset = tightest_tier()
+return {-1, limit, remaining, reset}
+";
+
+/// Token bucket run atomically inside Redis.
+///
+/// KEYS[1] is a hash holding the token count and the time it was last
+/// refilled. ARGV is the refill rate per second, the capacity and the
+/// maximum wait in milliseconds. Returns the same shape as [`CHECK_SCRIPT`].
+/// The hash expires once the bucket would be full again.
+const TOKEN_BUCKET_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local refill_per_ms = tonumber(ARGV[1]) / 1000
+local capacity = tonumber(ARGV[2])
+local max_wait = tonumber(ARGV[3])
+
+local function refill_time(missing)
+    if refill_per_ms <= 0 then
+        return max_wait
+    end
+    return math.min(math.ceil(missing / refill_per_ms), max_wait)
+end
+
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'refilled_at')
+local tokens = capacity
+if state[1] and state[2] then
+    local elapsed = math.max(now - tonumber(state[2]), 0)
+    tokens = math.min(tonumber(state[1]) + elapsed * refill_per_ms, capacity)
+end
+
+local retry_after = -1
+if tokens >= 1 then
+    tokens = tokens - 1
+else
+    retry_after = refill_time(1 - tokens)
+end
+
+local reset = refill_time(capacity - tokens)
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'refilled_at', now)
+redis.call('PEXPIRE', KEYS[1], math.max(reset, 1000))
+if retry_after >= 0 then
+    return {retry_after, capacity, 0, retry_after}
+end
+return {-1, capacity, math.floor(tokens), reset}
+";
+
+/// Rate limiting backend that keeps per-client sliding windows in Redis, so
+/// every replica behind a load balancer shares the same allowance.
+///
+/// Tiers, token buckets and exponential backoff behave like [`InMemoryBackend`](super::InMemoryBackend).
+/// Timestamps come from the Redis server clock, so replicas with skewed clocks
+/// still agree. The connection is opened on first use and reconnects on its own.
+///
+/// If Redis is unreachable the request is allowed and a warning is logged —
+/// an outage of the limiter should not take the API down with it.
+pub struct RedisRateLimitBackend {
+    client: Client,
+    connection: OnceCell<ConnectionManager>,
+    script: Script,
+    token_bucket_script: Script,
+}
+
+impl RedisRateLimitBackend {
+    /// Create a backend for the Redis server at `url`, e.g. `redis://127.0.0.1:6379`.
+    ///
+    /// Only the URL is validated here; no connection is made until the first check.
+    pub fn new(url: &str) -> RedisResult<Self> {
+        Ok(Self {
+            client: Client::open(url)?,
+            connection: OnceCell::new(),
+            script: Script::new(CHECK_SCRIPT),
+            token_bucket_script: Script::new(TOKEN_BUCKET_SCRIPT),
+        })
+    }
+
+    async fn connection(&self) -> RedisResult<ConnectionManager> {
+        self.connection
+            .get_or_try_init(|| self.client.get_connection_manager())
+            .await
+            .cloned()
+    }
+
+    async fn run_check(
+        &self,
+        key: &str,
+        limit: &ActionRateLimit,
+        backoff_multiplier: f64,
+        max_penalty: Duration,
+    ) -> RedisResult<(i64, u32, u32, u64)> {
+        let mut connection = self.connection().await?;
+
+        if let RateLimitAlgorithm::TokenBucket {
+            refill_per_sec,
+            capacity,
+        } = limit.algorithm
+        {
+            return self
+                .token_bucket_script
+                .key(format!("erno:rate_limit:{{{key}}}:bucket"))
+                .arg(refill_per_sec)
+                .arg(capacity)
+                .arg(max_penalty.as_millis() as u64)
+                .invoke_async(&mut connection)
+                .await;
+        }
+
+        // The hash tag keeps both keys in the same cluster slot
+        let mut invocation = self.script.prepare_invoke();
+        invocation
+            .key(format!("erno:rate_limit:{{{key}}}:requests"))
+            .key(format!("erno:rate_limit:{{{key}}}:state"))
+            .arg(backoff_multiplier)
+            .arg(max_penalty.as_millis() as u64)
+            .arg(uuid::Uuid::new_v4().to_string());
+        for tier in &limit.tiers {
+            invocation
+                .arg(tier.window_secs * 1000)
+                .arg(tier.max_requests);
+        }
+
+        invocation.invoke_async(&mut connection).await
+    }
+}
+
+#[async_trait]
+impl RateLimitBackend for RedisRateLimitBackend {
+    async fn check_rate_limit(
+        &self,
+        key: &str,
+        limit: &ActionRateLimit,
+        backoff_multiplier: f64,
+        max_penalty: Duration,
+    ) -> RateLimitDecision {
+        match self
+            .run_check(key, limit, backoff_multiplier, max_penalty)
+            .await
+        {
+            Ok((retry_after_ms, limit, remaining, reset_ms)) => RateLimitDecision {
+                limit,
+                remaining,
+                reset_after: Duration::from_millis(reset_ms),
+                retry_after: u64::try_from(retry_after_ms)
+                    .ok()
+                    .map(Duration::from_millis),
+            },
+            Err(e) => {
+                warn!(
+                    key,
+                    "Rate limit check against Redis failed, allowing request: {}", e
+                );
+                RateLimitDecision::unlimited()
+            }
+        }
+    }
+}
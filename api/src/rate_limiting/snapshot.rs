@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The in-memory backend's client states at one point in time, with wall-clock
+/// timestamps so it stays meaningful after a restart.
+///
+/// Written on shutdown and restored at boot when `rate_limiting.snapshot_path`
+/// is set, so counters, violations and blocks survive a deploy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitSnapshot {
+    pub taken_at: DateTime<Utc>,
+    /// By backend key, `"{key}/{action}"`
+    pub clients: HashMap<String, ClientSnapshot>,
+}
+
+/// One client's state in a [`RateLimitSnapshot`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientSnapshot {
+    pub requests: Vec<DateTime<Utc>>,
+    pub violations: u32,
+    pub blocked_until: Option<DateTime<Utc>>,
+    pub tokens: f64,
+    pub last_refill: Option<DateTime<Utc>>,
+}
+
+impl RateLimitSnapshot {
+    /// Read a snapshot written by [`Self::write`]. `None` if there is no file.
+    pub async fn read(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the snapshot as JSON, replacing the file in one step so a crash
+    /// mid-write can't leave a truncated snapshot behind.
+    pub async fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let bytes = serde_json::to_vec(self).map_err(io::Error::other)?;
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, bytes).await?;
+        tokio::fs::rename(&tmp, path).await
+    }
+}
+
+/// Maps between `Instant`s and wall-clock times using a single reading of
+/// both clocks.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Clock {
+    instant: Instant,
+    wall: DateTime<Utc>,
+}
+
+impl Clock {
+    pub(super) fn now() -> Self {
+        Self {
+            instant: Instant::now(),
+            wall: Utc::now(),
+        }
+    }
+
+    pub(super) const fn wall(&self) -> DateTime<Utc> {
+        self.wall
+    }
+
+    pub(super) fn to_wall(self, instant: Instant) -> DateTime<Utc> {
+        let offset = |d| chrono::Duration::from_std(d).unwrap_or(chrono::Duration::MAX);
+        if instant >= self.instant {
+            self.wall + offset(instant - self.instant)
+        } else {
+            self.wall - offset(self.instant - instant)
+        }
+    }
+
+    /// `None` for times before the monotonic clock's start, e.g. requests
+    /// made long before a reboot; they're outside any window anyway.
+    pub(super) fn to_instant(self, wall: DateTime<Utc>) -> Option<Instant> {
+        let offset = (wall - self.wall).abs().to_std().ok()?;
+        if wall >= self.wall {
+            self.instant.checked_add(offset)
+        } else {
+            self.instant.checked_sub(offset)
+        }
+    }
+}
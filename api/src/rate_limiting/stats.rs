@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dashmap::DashMap;
+use serde::Serialize;
+
+/// Snapshot of rate limiting activity, returned by
+/// [`RateLimitState::stats`](super::RateLimitState::stats).
+///
+/// Counters are per process and start at zero on boot.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RateLimitStats {
+    /// Keys with in-memory state. `None` for shared-store backends, which
+    /// keep their state elsewhere.
+    pub tracked_clients: Option<usize>,
+    /// Keys currently serving a penalty. `None` for shared-store backends.
+    pub blocked_clients: Option<usize>,
+    /// Requests rejected with 429 since startup
+    pub blocked_requests: u64,
+    /// `blocked_requests` broken down by action name
+    pub blocked_requests_by_action: BTreeMap<String, u64>,
+}
+
+/// Cumulative counters behind [`RateLimitStats`]. Kept apart from the
+/// backend's client map so recording and reading never wait on it.
+#[derive(Debug, Default)]
+pub(super) struct RateLimitCounters {
+    blocked_requests: AtomicU64,
+    blocked_by_action: DashMap<String, AtomicU64>,
+}
+
+impl RateLimitCounters {
+    pub(super) fn record_block(&self, action: &str) {
+        self.blocked_requests.fetch_add(1, Ordering::Relaxed);
+        // Only the first block of an action takes the shard's write lock
+        if let Some(count) = self.blocked_by_action.get(action) {
+            count.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.blocked_by_action
+            .entry(action.to_string())
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn blocked_requests(&self) -> u64 {
+        self.blocked_requests.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn blocked_requests_by_action(&self) -> BTreeMap<String, u64> {
+        self.blocked_by_action
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().load(Ordering::Relaxed)))
+            .collect()
+    }
+}
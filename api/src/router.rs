@@ -1,16 +1,28 @@
-use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response, routing::get, Router};
+use axum::{
+    error_handling::HandleErrorLayer,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    BoxError, Router,
+};
+use tower::{limit::GlobalConcurrencyLimitLayer, load_shed::LoadShedLayer, ServiceBuilder};
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
+use tracing::warn;
+use uuid::Uuid;
 
 use crate::{
     api, app::App,
-    auth::router::auth_router,
+    auth::{jwt, router::auth_router},
     config::EmailConfig,
     dev,
-    environment::Environment,
-    metrics::{self, MetricsEndpointState, http::metrics_middleware},
-    rate_limiting::middleware::{rate_limit_middleware, RateLimitActionExt},
+    log_context::{request_context_middleware, REQUEST_ID_HEADER},
+    metrics::{self, http::metrics_middleware, MetricsEndpointState},
     rate_limiting::action::RateLimitAction,
+    rate_limiting::middleware::{rate_limit_middleware, RateLimitActionExt, RateLimitUserExt},
+    rate_limiting::rate_limit_state::UserKeyMode,
     websocket::auth::authenticated_ws_handler,
 };
 
@@ -19,6 +31,7 @@ use crate::{
 /// outermost layer (before rate limiting) so the extension is available when
 /// `rate_limit_middleware` inspects it.
 async fn tag_rate_limit_action(mut req: Request, next: Next) -> Response {
+    // Untagged requests fall through to the configured default action
     let action = match req.uri().path() {
         "/api/auth/login" => "user_login",
         "/api/auth/register" => "user_create",
@@ -26,13 +39,51 @@ async fn tag_rate_limit_action(mut req: Request, next: Next) -> Response {
         "/api/auth/email/resend-verification" => "resend_verification",
         "/api/auth/password-reset/request" => "password_reset_request",
         "/api/auth/password-reset/confirm" => "password_reset_confirm",
-        _ => "default",
+        _ => return next.run(req).await,
     };
     req.extensions_mut()
         .insert(RateLimitActionExt(RateLimitAction::new(action)));
     next.run(req).await
 }
 
+/// Tags requests carrying a valid bearer token with their user id so the
+/// rate-limit middleware can bucket by user. Only the token is checked, not
+/// the database; a revoked but unexpired token still buckets as its user.
+async fn tag_rate_limit_user<ExtraConfig>(
+    State(app): State<App<ExtraConfig>>,
+    mut req: Request,
+    next: Next,
+) -> Response
+where
+    ExtraConfig: Clone + Send + Sync + 'static,
+{
+    let user_id = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .and_then(|token| jwt::verify_token(&app.config, token).ok())
+        .and_then(|claims| Uuid::parse_str(&claims.sub).ok());
+    if let Some(user_id) = user_id {
+        req.extensions_mut().insert(RateLimitUserExt(user_id));
+    }
+    next.run(req).await
+}
+
+/// Seconds a client is asked to wait when a request is shed for overload.
+const OVERLOAD_RETRY_AFTER_SECS: u64 = 1;
+
+/// Turns a request rejected by the load-shed layer into a 503.
+async fn shed_overload(_err: BoxError) -> Response {
+    warn!("Shedding request, max_concurrent_requests reached");
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(header::RETRY_AFTER, OVERLOAD_RETRY_AFTER_SECS.to_string())],
+        "Server is overloaded. Please try again later.",
+    )
+        .into_response()
+}
+
 pub fn router<ExtraConfig>(
     app: App<ExtraConfig>,
     app_router: fn(App<ExtraConfig>) -> Router,
@@ -42,7 +93,11 @@ where
 {
     let rate_limit_state = app.rate_limit_state.clone();
     let rate_limiting_enabled = app.config.rate_limiting.enabled;
+    let rate_limit_by_user = app.config.rate_limiting.user_key != UserKeyMode::Off;
+    let app_for_rate_limit = app.clone();
     let metrics_enabled = app.config.metrics.enabled;
+    let websocket_enabled = app.config.websocket.enabled;
+    let max_concurrent_requests = app.config.server.max_concurrent_requests;
     let cors_origins: Vec<HeaderValue> = app.config.cors.allowed_origins.iter()
         .filter_map(|o| o.parse().ok())
         .collect();
@@ -51,19 +106,28 @@ where
         auth_token: app.config.metrics.auth_token.clone(),
     };
     let metrics_path = app.config.metrics.path.clone();
-    let is_dev_mock = app.environment == Environment::Development
-        && matches!(&app.config.email, EmailConfig::Mock);
+    let liveness_path = app.config.server.liveness_path.clone();
+    let readiness_path = app.config.server.readiness_path.clone();
+    let is_dev_mock =
+        app.environment.exposes_dev_routes() && matches!(&app.config.email, EmailConfig::Mock);
 
-    // WebSocket route needs App state resolved before merging into the rate-limited group
-    let ws_router = Router::new()
-        .route("/ws", get(authenticated_ws_handler))
-        .with_state(app.clone());
+    let app_for_health = app.clone();
 
     let app_for_dev = app.clone();
     // Auth routes are auto-mounted alongside user routes under /api.
-    let mut rate_limited = Router::new()
-        .nest("/api", auth_router(app.clone()).merge(app_router(app)))
-        .merge(ws_router);
+    let mut rate_limited = Router::new().nest(
+        "/api",
+        auth_router(app.clone()).merge(app_router(app.clone())),
+    );
+
+    if websocket_enabled {
+        // WebSocket route needs App state resolved before merging into the rate-limited group
+        rate_limited = rate_limited.merge(
+            Router::new()
+                .route("/ws", get(authenticated_ws_handler))
+                .with_state(app),
+        );
+    }
 
     if metrics_enabled {
         rate_limited = rate_limited
@@ -80,14 +144,49 @@ where
                 rate_limit_middleware,
             ))
             .layer(axum::middleware::from_fn(tag_rate_limit_action));
+
+        if rate_limit_by_user {
+            rate_limited = rate_limited.layer(axum::middleware::from_fn_with_state(
+                app_for_rate_limit,
+                tag_rate_limit_user::<ExtraConfig>,
+            ));
+        }
+    }
+
+    // Outermost, so a saturated server sheds requests before doing any other work.
+    // The limit is global: every route shares one semaphore.
+    if let Some(max) = max_concurrent_requests {
+        rate_limited = rate_limited.layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(shed_overload))
+                .layer(LoadShedLayer::new())
+                .layer(GlobalConcurrencyLimitLayer::new(max)),
+        );
     }
 
     // Health check and metrics endpoints are excluded from rate limiting intentionally
     let mut base = Router::new()
-        .route("/liveness", get(api::health_checks::ok))
-        .route("/readiness", get(api::health_checks::ok))
+        .route(&liveness_path, get(api::health_checks::ok))
+        .route(
+            &readiness_path,
+            get(api::health_checks::readiness).with_state(app_for_health),
+        )
         .merge(rate_limited)
-        .layer(TraceLayer::new_for_http());
+        .layer(TraceLayer::new_for_http().make_span_with(|req: &Request| {
+            let request_id = req
+                .headers()
+                .get(&REQUEST_ID_HEADER)
+                .and_then(|v| v.to_str().ok());
+            tracing::debug_span!(
+                "request",
+                method = %req.method(),
+                uri = %req.uri(),
+                version = ?req.version(),
+                request_id,
+            )
+        }))
+        // Outside the trace layer so the request id is set before the span is made
+        .layer(axum::middleware::from_fn(request_context_middleware));
 
     if metrics_enabled {
         base = base.route(
@@ -112,3 +211,95 @@ where
 
     base
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::LazyLock;
+
+    use axum::{http::StatusCode, routing::get, Router};
+    use tokio::sync::Notify;
+
+    use crate::{
+        app::App,
+        database::migrations::Migrator,
+        tests::setup_test::{no_fixtures, setup_test_with_config},
+        websocket::listener::spawn_listener,
+    };
+
+    static SLOW_ENTERED: LazyLock<Notify> = LazyLock::new(Notify::new);
+    static SLOW_RELEASE: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+    /// Holds its concurrency slot until the test releases it.
+    async fn slow() -> &'static str {
+        SLOW_ENTERED.notify_one();
+        SLOW_RELEASE.notified().await;
+        "done"
+    }
+
+    fn test_router(_app: App) -> Router {
+        Router::new()
+            .route("/slow", get(slow))
+            .route("/ping", get(|| async { "pong" }))
+    }
+
+    #[tokio::test]
+    async fn test_custom_health_check_paths() {
+        let test = setup_test_with_config::<Migrator>(test_router, no_fixtures, |config| {
+            config.server.liveness_path = "/healthz".to_string();
+            config.server.readiness_path = "/readyz".to_string();
+        })
+        .await;
+
+        test.server.get("/healthz").await.assert_status_ok();
+        test.server.get("/readyz").await.assert_status_ok();
+        test.server.get("/liveness").await.assert_status_not_found();
+        test.server
+            .get("/readiness")
+            .await
+            .assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_requests_beyond_concurrency_limit_are_shed() {
+        let test = setup_test_with_config::<Migrator>(test_router, no_fixtures, |config| {
+            config.server.max_concurrent_requests = Some(1);
+        })
+        .await;
+
+        let saturated = async {
+            SLOW_ENTERED.notified().await;
+            let shed = test.server.get("/api/ping").await;
+            // Health checks are outside the limit
+            test.server.get("/liveness").await.assert_status_ok();
+            SLOW_RELEASE.notify_one();
+            shed
+        };
+        let (slow, shed) = tokio::join!(test.server.get("/api/slow"), saturated);
+
+        slow.assert_status_ok();
+        shed.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        shed.assert_header("retry-after", "1");
+
+        // Once the slot is free, requests go through again
+        test.server.get("/api/ping").await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_disabled_websockets_skip_listener_and_route() {
+        let test = setup_test_with_config::<Migrator>(test_router, no_fixtures, |config| {
+            config.websocket.enabled = false;
+        })
+        .await;
+
+        test.server.get("/ws").await.assert_status_not_found();
+        test.server.get("/api/ping").await.assert_status_ok();
+
+        let app = test.app();
+        let listener = spawn_listener(
+            &app.config.websocket,
+            app.db.clone(),
+            app.websocket_connections.clone(),
+        );
+        assert!(listener.is_none());
+    }
+}
@@ -1,8 +1,16 @@
+use std::sync::OnceLock;
+
 use time::format_description::parse;
-use tracing_subscriber::fmt::time::OffsetTime;
+use tracing_subscriber::{
+    fmt::time::OffsetTime, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter,
+    Registry,
+};
 
 use crate::cli::Commands;
 
+/// Handle on the installed filter, used to change the log level at runtime
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
 pub fn setup_tracing_for_command(command: &Option<Commands>, server_log_level: &str) {
     // Set appropriate default tracing level based on command type:
     // - CLI commands (migrate, version) use 'warn'/'error' to reduce noise
@@ -10,8 +18,14 @@ pub fn setup_tracing_for_command(command: &Option<Commands>, server_log_level: &
     // - Users can override with RUST_LOG environment variable (e.g., RUST_LOG=debug)
     let default_level = match command {
         // CLI commands should have minimal log output for clean UX
-        Some(Commands::Migrate { .. } | Commands::Db { .. }) => "warn",
-        Some(Commands::Version | Commands::GenerateJwtSecret | Commands::Routes) => "error", // Version, GenerateJwtSecret, and Routes should be very quiet
+        Some(Commands::Migrate { .. } | Commands::Db { .. } | Commands::Ping { .. }) => "warn",
+        Some(
+            Commands::Version
+            | Commands::Generate { .. }
+            | Commands::GenerateJwtSecret
+            | Commands::Routes
+            | Commands::Cron { .. },
+        ) => "error", // Version, Generate, GenerateJwtSecret, Routes and Cron should be very quiet
         // Admin TUI runs interactively — suppress log output
         #[cfg(feature = "admin")]
         Some(Commands::Admin) => "error",
@@ -19,14 +33,14 @@ pub fn setup_tracing_for_command(command: &Option<Commands>, server_log_level: &
         Some(Commands::Serve) | None => server_log_level,
     };
 
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(default_level))
-        // Filter out noisy third-party logs
-        .add_directive("sqlx::postgres::notice=warn".parse().unwrap())
-        .add_directive("sea_orm_migration::migrator=warn".parse().unwrap());
+    let env_filter = EnvFilter::try_from_default_env()
+        .map(with_quiet_dependencies)
+        .or_else(|_| log_filter(default_level))
+        .unwrap_or_else(|_| log_filter("info").unwrap());
+    let (env_filter, handle) = reload::Layer::new(env_filter);
+    let _ = LOG_FILTER.set(handle);
 
-    tracing_subscriber::fmt()
-        .with_env_filter(env_filter)
+    let fmt_layer = tracing_subscriber::fmt::layer()
         .with_target(false) // Remove module paths for cleaner output
         .with_thread_ids(false) // Remove thread IDs for cleaner output
         .with_thread_names(false) // Remove thread names for cleaner output
@@ -36,6 +50,69 @@ pub fn setup_tracing_for_command(command: &Option<Commands>, server_log_level: &
             time::UtcOffset::current_local_offset().unwrap_or(time::UtcOffset::UTC),
             parse("[hour]:[minute]:[second].[subsecond digits:2]").unwrap(),
         ))
-        .compact() // Use compact format
+        .compact(); // Use compact format
+
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(fmt_layer)
         .init();
 }
+
+/// Change the log level of the running process, e.g. after a config reload.
+///
+/// Does nothing when `RUST_LOG` is set, since that overrides the configured level.
+pub fn reload_log_level(level: &str) -> Result<(), String> {
+    if std::env::var_os("RUST_LOG").is_some() {
+        return Ok(());
+    }
+    match LOG_FILTER.get() {
+        Some(handle) => apply_log_level(handle, level),
+        None => Err("tracing isn't set up".to_string()),
+    }
+}
+
+fn apply_log_level<S>(handle: &reload::Handle<EnvFilter, S>, level: &str) -> Result<(), String> {
+    let filter = log_filter(level)?;
+    handle.reload(filter).map_err(|e| e.to_string())
+}
+
+fn log_filter(level: &str) -> Result<EnvFilter, String> {
+    EnvFilter::try_new(level)
+        .map(with_quiet_dependencies)
+        .map_err(|e| format!("invalid log level {level:?}: {e}"))
+}
+
+/// Filter out noisy third-party logs
+fn with_quiet_dependencies(filter: EnvFilter) -> EnvFilter {
+    filter
+        .add_directive("sqlx::postgres::notice=warn".parse().unwrap())
+        .add_directive("sea_orm_migration::migrator=warn".parse().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing::Level;
+
+    use super::*;
+
+    #[test]
+    fn test_reloading_the_filter_changes_the_active_level() {
+        let (filter, handle) = reload::Layer::new(log_filter("info").unwrap());
+        let subscriber = tracing_subscriber::registry().with(filter);
+
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(!tracing::enabled!(Level::DEBUG));
+
+            apply_log_level(&handle, "debug").unwrap();
+            assert!(tracing::enabled!(Level::DEBUG));
+
+            apply_log_level(&handle, "warn").unwrap();
+            assert!(!tracing::enabled!(Level::INFO));
+        });
+    }
+
+    #[test]
+    fn test_invalid_level_is_rejected() {
+        assert!(log_filter("loud=[").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Docs: docs/src/content/docs/api/boot.md
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter};
+use tracing::{info, warn};
+
+use crate::{
+    database::models::{job, job_status::JobStatus},
+    jobs::in_flight_jobs,
+    websocket::connections::Connections,
+};
+
+/// Resolves when the process receives Ctrl+C or, on Unix, SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
+    }
+}
+
+/// Work left unfinished when the server shut down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Jobs executing in this process when it stopped; they stay `running`
+    /// until stuck-job recovery picks them up.
+    pub in_flight_jobs: usize,
+    /// Jobs still waiting in the queue, including those waiting to be
+    /// retried. `None` if the database could not be queried.
+    pub pending_jobs: Option<u64>,
+    /// WebSocket connections closed by the shutdown.
+    pub websocket_connections_closed: usize,
+}
+
+impl ShutdownReport {
+    /// Close all WebSocket connections and gather what was left behind.
+    pub async fn collect(db: &DatabaseConnection, connections: &Connections) -> Self {
+        let pending_jobs = match count_pending_jobs(db).await {
+            Ok(count) => Some(count),
+            Err(e) => {
+                warn!("🛑 Failed to count pending jobs during shutdown: {}", e);
+                None
+            }
+        };
+
+        Self {
+            in_flight_jobs: in_flight_jobs(),
+            pending_jobs,
+            websocket_connections_closed: connections.close_all().await,
+        }
+    }
+
+    /// Log the report as a single structured event.
+    pub fn log(&self) {
+        info!(
+            in_flight_jobs = self.in_flight_jobs,
+            pending_jobs = self.pending_jobs,
+            websocket_connections_closed = self.websocket_connections_closed,
+            "🛑 Shutdown report"
+        );
+    }
+}
+
+async fn count_pending_jobs(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    job::Entity::find()
+        .filter(job::Column::Status.is_in([JobStatus::Pending, JobStatus::PendingRetry]))
+        .count(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{sea_query::Expr, ColumnTrait, EntityTrait, QueryFilter};
+
+    use super::{count_pending_jobs, ShutdownReport};
+    use crate::{
+        database::{
+            migrations::Migrator,
+            models::{job, job_status::JobStatus},
+        },
+        job_queue::JobQueue,
+        jobs::ping_job::PingJob,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+        websocket::connections::Connections,
+    };
+
+    #[tokio::test]
+    async fn test_shutdown_report_counts_closed_connections() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let connections = Connections::new();
+        let user_a = uuid::Uuid::new_v4();
+        let user_b = uuid::Uuid::new_v4();
+
+        let mut rx_a1 = connections
+            .register(user_a, uuid::Uuid::new_v4())
+            .await
+            .unwrap();
+        let _rx_a2 = connections
+            .register(user_a, uuid::Uuid::new_v4())
+            .await
+            .unwrap();
+        let _rx_b = connections
+            .register(user_b, uuid::Uuid::new_v4())
+            .await
+            .unwrap();
+
+        let report = ShutdownReport::collect(&test.db, &connections).await;
+
+        assert_eq!(report.websocket_connections_closed, 3);
+        assert!(report.pending_jobs.is_some());
+        assert_eq!(connections.connection_count().await, 0);
+        // Dropping the sender ends the connection's outgoing stream
+        assert!(rx_a1.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pending_jobs_include_those_waiting_for_a_retry() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let before = count_pending_jobs(&test.db).await.unwrap();
+
+        let queue = JobQueue::database();
+        queue.add::<PingJob, ()>(&test.db, ()).await.unwrap();
+        let retrying = queue.add::<PingJob, ()>(&test.db, ()).await.unwrap();
+        job::Entity::update_many()
+            .col_expr(job::Column::Status, Expr::value(JobStatus::PendingRetry))
+            .filter(job::Column::Id.eq(retrying))
+            .exec(&test.db)
+            .await
+            .unwrap();
+
+        assert_eq!(count_pending_jobs(&test.db).await.unwrap(), before + 2);
+    }
+}
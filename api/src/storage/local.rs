@@ -47,3 +47,43 @@ impl StorageService for LocalStorage {
         Ok(format!("/storage/{}", key))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use super::LocalStorage;
+    use crate::storage::service::StorageService;
+
+    #[tokio::test]
+    async fn test_local_roundtrip_and_url() {
+        let root = std::env::temp_dir().join(format!("erno-storage-{}", uuid::Uuid::new_v4()));
+        let storage = LocalStorage::new(&root);
+        let key = "reports/2026-10/export.csv";
+
+        storage
+            .upload(
+                key,
+                Bytes::from_static(b"id,total\n1,42\n"),
+                Some("text/csv"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage.download(key).await.unwrap(),
+            Bytes::from_static(b"id,total\n1,42\n")
+        );
+        assert!(root.join(key).is_file());
+        assert_eq!(
+            storage.url(key, Duration::from_secs(60)).await.unwrap(),
+            "/storage/reports/2026-10/export.csv"
+        );
+
+        storage.delete(key).await.unwrap();
+        assert!(storage.download(key).await.is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -220,29 +220,19 @@ impl FileStorage {
 
 #[cfg(test)]
 mod tests {
-    use axum::Router;
     use bytes::Bytes;
     use sea_orm::EntityTrait;
     use uuid::Uuid;
 
     use super::{file, file_attachment, FileStorage};
-    use crate::{app::App, database::migrations::Migrator, tests::setup_test::setup_test};
-
-    fn no_router(app: App) -> Router {
-        Router::new()
-    }
-
-    fn no_fixtures(
-        db: &sea_orm::DatabaseConnection,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
-        Box::pin(async move {
-            let _ = db;
-        })
-    }
+    use crate::{
+        database::migrations::Migrator,
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+    };
 
     #[tokio::test]
     async fn test_store_creates_file_record() {
-        let t = setup_test::<Migrator>(no_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         let storage = FileStorage::mock();
 
         let file = storage
@@ -261,7 +251,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_attach_creates_attachment_record() {
-        let t = setup_test::<Migrator>(no_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         let storage = FileStorage::mock();
 
         let file = storage
@@ -283,7 +273,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_detach_removes_attachment_and_orphaned_file() {
-        let t = setup_test::<Migrator>(no_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         let storage = FileStorage::mock();
 
         let file = storage
@@ -314,7 +304,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_detach_keeps_file_when_other_attachments_exist() {
-        let t = setup_test::<Migrator>(no_router, no_fixtures).await;
+        let t = setup_test::<Migrator>(empty_router, no_fixtures).await;
         let storage = FileStorage::mock();
 
         let file = storage
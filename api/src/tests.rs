@@ -1 +1,2 @@
+pub mod migration_round_trip;
 pub mod setup_test;
@@ -0,0 +1,97 @@
+use sea_orm::{ConnectionTrait, DatabaseConnection, Statement};
+use sea_orm_migration::MigratorTrait;
+
+use crate::{
+    boot::read_config,
+    database::{create_schema, setup_database_connection},
+    environment::Environment,
+};
+
+/// Check that every migration of `AppMigrator` can be reverted.
+///
+/// Runs all migrations up, all the way down and up again in a fresh,
+/// throwaway schema on the test database, and panics if any step fails or if
+/// a table is left behind once everything is down. The schema is dropped
+/// afterwards.
+///
+/// # Example
+/// ```rust,ignore
+/// #[tokio::test]
+/// async fn test_migrations_round_trip() {
+///     assert_migrations_round_trip::<Migrator>().await;
+/// }
+/// ```
+///
+/// # Panics
+/// Panics with the failing step and its error.
+pub async fn assert_migrations_round_trip<AppMigrator: MigratorTrait>() {
+    let mut db_config = read_config::<()>(&Environment::Test).database;
+    let schema = format!("migration_round_trip_{}", uuid::Uuid::now_v7().simple());
+    db_config.schema = Some(schema.clone());
+    // One connection, so the search path set on it applies to every step
+    db_config.pool_size = 1;
+
+    let db = setup_database_connection(&db_config).await;
+    create_schema(&db, Some(&schema))
+        .await
+        .expect("Failed to create the round-trip schema");
+
+    let result = round_trip::<AppMigrator>(&db, &schema).await;
+
+    db.execute_unprepared(&format!("DROP SCHEMA \"{schema}\" CASCADE"))
+        .await
+        .expect("Failed to drop the round-trip schema");
+
+    if let Err(failure) = result {
+        panic!("Migrations don't round-trip: {failure}");
+    }
+}
+
+async fn round_trip<AppMigrator: MigratorTrait>(
+    db: &DatabaseConnection,
+    schema: &str,
+) -> Result<(), String> {
+    AppMigrator::up(db, None)
+        .await
+        .map_err(|e| format!("first up failed: {e}"))?;
+    AppMigrator::down(db, None)
+        .await
+        .map_err(|e| format!("down failed: {e}"))?;
+
+    let leftover = leftover_tables(db, schema).await?;
+    if !leftover.is_empty() {
+        return Err(format!("tables left after down: {}", leftover.join(", ")));
+    }
+
+    AppMigrator::up(db, None)
+        .await
+        .map_err(|e| format!("second up failed: {e}"))
+}
+
+/// Tables in `schema` other than the migration bookkeeping table.
+async fn leftover_tables(db: &DatabaseConnection, schema: &str) -> Result<Vec<String>, String> {
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "SELECT table_name::text FROM information_schema.tables \
+             WHERE table_schema = $1 AND table_name <> 'seaql_migrations' ORDER BY table_name",
+            [schema.into()],
+        ))
+        .await
+        .map_err(|e| format!("listing tables failed: {e}"))?;
+
+    rows.iter()
+        .map(|row| row.try_get_by_index::<String>(0).map_err(|e| e.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::assert_migrations_round_trip;
+    use crate::database::migrations::Migrator;
+
+    #[tokio::test]
+    async fn test_crate_migrations_round_trip() {
+        assert_migrations_round_trip::<Migrator>().await;
+    }
+}
@@ -1,9 +1,14 @@
 use crate::{
-    app::App, boot::read_config, environment::Environment, mailer::Mailer,
-    rate_limiting::RateLimitState, router::router, websocket::connections::Connections,
+    app::App,
+    boot::read_config,
+    database::{DatabaseSetupStatus, DatabaseStatus},
+    environment::Environment,
+    mailer::Mailer,
+    rate_limiting::{rate_limit_state::RateLimitConfig, RateLimitState},
+    router::router,
+    websocket::connections::Connections,
 };
 use axum::Router;
-use lettre::{transport::smtp::authentication::Credentials, AsyncSmtpTransport, Tokio1Executor};
 use sea_orm::{ConnectOptions, ConnectionTrait, Statement};
 use sea_orm_migration::MigratorTrait;
 use tokio::sync::OnceCell;
@@ -33,13 +38,32 @@ pub type FixtureLoader =
         &'a sea_orm::DatabaseConnection,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + 'a>>;
 
+/// A [`FixtureLoader`] that loads nothing, for tests that insert their own
+/// rows.
+pub fn no_fixtures(
+    db: &sea_orm::DatabaseConnection,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
+    Box::pin(async move {
+        let _ = db;
+    })
+}
+
+/// A router without routes, for tests that don't send requests to the app.
+pub fn empty_router(_app: App) -> Router {
+    Router::new()
+}
+
 /// Initialize the database schema once for all tests.
 ///
 /// Drops and recreates the schema, runs migrations, and loads fixtures once.
 /// This ensures a completely clean database state before any tests run.
 /// Each test will get its own connection to this initialized database.
 async fn initialize_database_schema<AppMigrator: MigratorTrait>(fixture_loader: FixtureLoader) {
-    use crate::{boot::read_config, database::setup_database_connection, environment::Environment};
+    use crate::{
+        boot::read_config,
+        database::{create_schema, setup_database_connection},
+        environment::Environment,
+    };
     use tracing::{debug, error, info, trace};
 
     info!("Initializing test database schema (one-time setup)");
@@ -52,6 +76,9 @@ async fn initialize_database_schema<AppMigrator: MigratorTrait>(fixture_loader:
     debug!("Connecting to test database for schema setup");
     let db = setup_database_connection(&app_config.database).await;
     debug!("Database connection established");
+    create_schema(&db, app_config.database.schema.as_deref())
+        .await
+        .expect("Failed to create the database schema");
 
     // Reset and reapply migrations for a clean slate
     debug!("Refreshing database migrations");
@@ -94,6 +121,72 @@ async fn initialize_database_schema<AppMigrator: MigratorTrait>(fixture_loader:
 pub async fn setup_test<AppMigrator: MigratorTrait>(
     app_router: fn(App) -> Router,
     fixture_loader: FixtureLoader,
+) -> TestUtils {
+    setup_test_with_config::<AppMigrator>(app_router, fixture_loader, |_| {}).await
+}
+
+/// Same as [`setup_test`], but lets the test adjust the loaded configuration
+/// before the application and router are built.
+///
+/// # Example
+///
+/// ```ignore
+/// let test = setup_test_with_config::<Migrator>(router, fixtures, |config| {
+///     config.server.liveness_path = "/healthz".to_string();
+/// })
+/// .await;
+/// ```
+///
+/// # Panics
+///
+/// Panics if database setup or migrations fail.
+pub async fn setup_test_with_config<AppMigrator: MigratorTrait>(
+    app_router: fn(App) -> Router,
+    fixture_loader: FixtureLoader,
+    configure: fn(&mut crate::config::Config),
+) -> TestUtils {
+    build_test::<AppMigrator>(app_router, fixture_loader, configure).await
+}
+
+/// Same as [`setup_test`], but with rate limiting enabled using `rate_limit_config`
+/// instead of the (disabled) limits from `config/test.toml`.
+///
+/// Requests go through the real rate-limit middleware. There is no socket
+/// address in tests, so set `trust_proxy` and send an `X-Forwarded-For` header
+/// to give requests a client IP.
+///
+/// # Example
+///
+/// ```ignore
+/// let config = RateLimitConfig::builder()
+///     .trust_proxy(true)
+///     .action("default")
+///     .tier(60, 2)
+///     .build();
+/// let test = setup_test_with_rate_limit::<Migrator>(router, fixtures, config).await;
+/// ```
+///
+/// # Panics
+///
+/// Panics if database setup or migrations fail.
+pub async fn setup_test_with_rate_limit<AppMigrator: MigratorTrait>(
+    app_router: fn(App) -> Router,
+    fixture_loader: FixtureLoader,
+    rate_limit_config: RateLimitConfig,
+) -> TestUtils {
+    build_test::<AppMigrator>(app_router, fixture_loader, move |config| {
+        config.rate_limiting = RateLimitConfig {
+            enabled: true,
+            ..rate_limit_config
+        };
+    })
+    .await
+}
+
+async fn build_test<AppMigrator: MigratorTrait>(
+    app_router: fn(App) -> Router,
+    fixture_loader: FixtureLoader,
+    configure: impl FnOnce(&mut crate::config::Config),
 ) -> TestUtils {
     // Initialize tracing for test output
     init_tracing();
@@ -112,7 +205,8 @@ pub async fn setup_test<AppMigrator: MigratorTrait>(
     // Create a NEW connection for this specific test with a SINGLE connection pool
     // This ensures all queries go through the same connection, enabling transaction isolation
     let environment = Environment::Test;
-    let app_config = read_config(&environment);
+    let mut app_config = read_config(&environment);
+    configure(&mut app_config);
 
     debug!("Creating single-connection pool for test isolation");
     let db = {
@@ -121,6 +215,9 @@ pub async fn setup_test<AppMigrator: MigratorTrait>(
         // Use exactly 1 connection so all queries go through the same connection
         options.max_connections(1);
         options.min_connections(1);
+        if let Some(schema) = &app_config.database.schema {
+            options.set_schema_search_path(schema);
+        }
 
         sea_orm::Database::connect(options)
             .await
@@ -134,47 +231,45 @@ pub async fn setup_test<AppMigrator: MigratorTrait>(
         .await
         .expect("Failed to begin transaction");
 
-    // Create mailer based on config (mock or real SMTP)
-    let mailer = match &app_config.email {
-        crate::config::EmailConfig::Mock => crate::mailer::Mailer::mock(),
-        crate::config::EmailConfig::Smtp {
-            host,
-            port,
-            username,
-            password,
-            ..
-        } => {
-            let mut mailer_builder = AsyncSmtpTransport::<Tokio1Executor>::relay(host)
-                .expect("Failed to create mailer transport")
-                .port(*port);
-
-            if let (Some(username), Some(password)) = (username, password) {
-                mailer_builder = mailer_builder
-                    .credentials(Credentials::new(username.clone(), password.clone()));
-            }
-
-            crate::mailer::Mailer::smtp(mailer_builder.build())
-        }
-    };
+    // The test environment always gets the mock mailer, whatever the config says
+    let mailer = Mailer::for_environment(&app_config.email, environment);
+
+    let email_transports = std::sync::Arc::new(
+        app_config
+            .email_transports
+            .iter()
+            .map(|(name, email_config)| {
+                (
+                    name.clone(),
+                    Mailer::for_environment(email_config, environment),
+                )
+            })
+            .collect::<std::collections::HashMap<_, _>>(),
+    );
 
     // Use mock job queue for tests
     let job_queue = crate::job_queue::JobQueue::mock();
 
-    // Initialize rate limiting with default config for tests
-    let rate_limit_state = crate::rate_limiting::RateLimitState::new(
-        crate::rate_limiting::rate_limit_state::RateLimitConfig::default(),
-    );
+    // Rate limiting follows the test config (disabled unless overridden)
+    let rate_limit_state = RateLimitState::new(app_config.rate_limiting.clone());
+
+    let database_status = DatabaseStatus::new(DatabaseSetupStatus::Completed);
 
     let app = App {
         config: app_config.clone(),
         environment,
         db: db.clone(),
+        database_status: database_status.clone(),
         mailer: mailer.clone(),
+        email_transports: email_transports.clone(),
         job_queue: job_queue.clone(),
+        job_registry: std::sync::Arc::new(crate::jobs::job_registry::JobRegistry::new()),
         sync_queue: crate::sync::queue::SyncQueue::mock(),
         sync_registry: std::sync::Arc::new(crate::sync::registry::SyncRegistry::new()),
+        user_loader: std::sync::Arc::new(crate::auth::DatabaseUserLoader),
         rate_limit_state,
         websocket_connections: Connections::new(),
+        event_bus: crate::events::EventBus::new(),
         storage: crate::storage::FileStorage::mock(),
         prometheus_handle: crate::metrics::setup_metrics(),
         metrics_collectors: std::sync::Arc::new(
@@ -190,7 +285,9 @@ pub async fn setup_test<AppMigrator: MigratorTrait>(
     TestUtils {
         server,
         db,
+        database_status,
         mailer,
+        email_transports,
         job_queue,
         config: app_config,
         environment,
@@ -211,7 +308,11 @@ pub async fn setup_test<AppMigrator: MigratorTrait>(
 pub struct TestUtils {
     pub server: axum_test::TestServer,
     pub db: sea_orm::DatabaseConnection,
+    /// Status reported by the readiness probe; starts out as `Completed`.
+    pub database_status: DatabaseStatus,
     pub mailer: Mailer,
+    /// Transports from `[email_transports]`, shared with every `App`
+    pub email_transports: std::sync::Arc<std::collections::HashMap<String, Mailer>>,
     pub job_queue: crate::job_queue::JobQueue,
     pub config: crate::config::Config,
     pub environment: crate::environment::Environment,
@@ -263,6 +364,29 @@ impl TestUtils {
         self.job_queue.clear_scheduled_jobs();
     }
 
+    /// Assert that the mock job queue is empty.
+    ///
+    /// Panics with the type and arguments of every enqueued job otherwise.
+    #[track_caller]
+    pub fn assert_no_jobs_enqueued(&self) {
+        self.assert_jobs_enqueued_count(0);
+    }
+
+    /// Assert that exactly `expected` jobs were enqueued on the mock job queue.
+    ///
+    /// Panics with the type and arguments of every enqueued job otherwise.
+    #[track_caller]
+    pub fn assert_jobs_enqueued_count(&self, expected: usize) {
+        let jobs = self.enqueued_jobs();
+        if jobs.len() != expected {
+            panic!(
+                "expected {expected} enqueued job(s), found {}:{}",
+                jobs.len(),
+                describe_jobs(&jobs)
+            );
+        }
+    }
+
     /// Execute a job directly in tests.
     ///
     /// This creates an App instance from the test context and executes the job.
@@ -292,26 +416,34 @@ impl TestUtils {
     where
         J::Arguments: serde::Serialize + serde::de::DeserializeOwned,
     {
-        // Create an App instance using the test database connection
+        J::execute(&self.app(), args).await
+    }
+
+    /// An `App` backed by the test database, mailer and job queue, for calling
+    /// code that takes an `App` outside of a request.
+    pub fn app(&self) -> App {
         // Jobs use the transaction implicitly through test queries
-        let app = App {
+        App {
             config: self.config.clone(),
             environment: self.environment,
             db: self.db.clone(),
+            database_status: self.database_status.clone(),
             mailer: self.mailer.clone(),
+            email_transports: self.email_transports.clone(),
             job_queue: self.job_queue.clone(),
+            job_registry: std::sync::Arc::new(crate::jobs::job_registry::JobRegistry::new()),
             sync_queue: crate::sync::queue::SyncQueue::mock(),
             sync_registry: std::sync::Arc::new(crate::sync::registry::SyncRegistry::new()),
+            user_loader: std::sync::Arc::new(crate::auth::DatabaseUserLoader),
             rate_limit_state: RateLimitState::new(self.config.rate_limiting.clone()),
             websocket_connections: Connections::new(),
+            event_bus: crate::events::EventBus::new(),
             storage: crate::storage::FileStorage::mock(),
             prometheus_handle: crate::metrics::setup_metrics(),
             metrics_collectors: std::sync::Arc::new(
                 crate::metrics::collector::CollectorRegistry::default(),
             ),
-        };
-
-        J::execute(&app, args).await
+        }
     }
 }
 
@@ -337,3 +469,84 @@ impl Drop for TestUtils {
         }
     }
 }
+
+fn describe_jobs(jobs: &[crate::job_queue::EnqueuedJob]) -> String {
+    if jobs.is_empty() {
+        return " none".to_string();
+    }
+    jobs.iter()
+        .map(|job| format!("\n  - {} {}", job.job_type, job.arguments))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::Router;
+
+    use super::setup_test;
+    use crate::{
+        app::App,
+        database::migrations::Migrator,
+        jobs::{Job, JobError},
+    };
+
+    struct WelcomeEmailJob;
+
+    impl Job for WelcomeEmailJob {
+        type Arguments = String;
+
+        async fn execute(_app: &App, _arguments: String) -> Result<(), JobError> {
+            Ok(())
+        }
+
+        fn name() -> &'static str {
+            "welcome_email_assertion_test"
+        }
+    }
+
+    fn test_router(_app: App) -> Router {
+        Router::new()
+    }
+
+    fn no_fixtures(
+        db: &sea_orm::DatabaseConnection,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
+        Box::pin(async move {
+            let _ = db;
+        })
+    }
+
+    #[tokio::test]
+    async fn test_job_count_assertions_pass() {
+        let test = setup_test::<Migrator>(test_router, no_fixtures).await;
+        test.assert_no_jobs_enqueued();
+
+        test.job_queue
+            .add::<WelcomeEmailJob, ()>(&test.db, "ada@example.com".to_string())
+            .await
+            .unwrap();
+        test.assert_jobs_enqueued_count(1);
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "expected 0 enqueued job(s), found 1:\n  - welcome_email_assertion_test \"ada@example.com\""
+    )]
+    async fn test_assert_no_jobs_enqueued_lists_unexpected_jobs() {
+        let test = setup_test::<Migrator>(test_router, no_fixtures).await;
+        test.job_queue
+            .add::<WelcomeEmailJob, ()>(&test.db, "ada@example.com".to_string())
+            .await
+            .unwrap();
+
+        test.assert_no_jobs_enqueued();
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "expected 2 enqueued job(s), found 0: none")]
+    async fn test_assert_jobs_enqueued_count_reports_mismatch() {
+        let test = setup_test::<Migrator>(test_router, no_fixtures).await;
+
+        test.assert_jobs_enqueued_count(2);
+    }
+}
@@ -1,19 +1,94 @@
 use rand::Rng;
 use rand::rngs::OsRng;
 use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// Length used for tokens that guard an account, such as password reset or
+/// refresh tokens. 32 alphanumeric characters carry about 190 bits of entropy.
+pub const RECOMMENDED_TOKEN_LENGTH: usize = 32;
+
+/// Shortest token considered safe by default: 22 alphanumeric characters
+/// carry about 131 bits, just over the 128 bits a guess should have to beat.
+pub const MIN_TOKEN_LENGTH: usize = 22;
+
+/// Rules applied by [`generate_token_with_policy`] to the requested length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenPolicy {
+    pub min_length: usize,
+    /// Return an error instead of logging a warning for shorter tokens
+    pub reject_short: bool,
+}
+
+impl Default for TokenPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: MIN_TOKEN_LENGTH,
+            reject_short: false,
+        }
+    }
+}
+
+/// Returned by [`generate_token_with_policy`] when a token shorter than the
+/// policy's minimum is requested and short tokens are rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Token length {length} is below the minimum of {min_length}")]
+pub struct TokenTooShort {
+    pub length: usize,
+    pub min_length: usize,
+}
 
 /// Generate a cryptographically secure random token.
 ///
 /// Creates a random alphanumeric string of the specified length suitable for
 /// use as verification tokens, password reset tokens, or other security-sensitive
-/// identifiers.
+/// identifiers. Lengths below [`MIN_TOKEN_LENGTH`] are logged as a warning; use
+/// [`generate_token_with_policy`] to reject them instead.
 ///
 /// # Arguments
-/// * `length` - The desired length of the token (typically 32 or 64 characters)
+/// * `length` - The desired length of the token (typically
+///   [`RECOMMENDED_TOKEN_LENGTH`] or 64 characters)
 ///
 /// # Returns
 /// A string containing random alphanumeric characters (A-Z, a-z, 0-9)
 pub fn generate_secure_token(length: usize) -> String {
+    if length < MIN_TOKEN_LENGTH {
+        warn_short_token(length, MIN_TOKEN_LENGTH);
+    }
+    random_alphanumeric(length)
+}
+
+/// Generate a token like [`generate_secure_token`], checking `length`
+/// against `policy` first.
+///
+/// # Example
+/// ```rust,ignore
+/// let strict = TokenPolicy { reject_short: true, ..Default::default() };
+/// let code = generate_token_with_policy(RECOMMENDED_TOKEN_LENGTH, &strict)?;
+/// ```
+pub fn generate_token_with_policy(
+    length: usize,
+    policy: &TokenPolicy,
+) -> Result<String, TokenTooShort> {
+    if length < policy.min_length {
+        if policy.reject_short {
+            return Err(TokenTooShort {
+                length,
+                min_length: policy.min_length,
+            });
+        }
+        warn_short_token(length, policy.min_length);
+    }
+    Ok(random_alphanumeric(length))
+}
+
+fn warn_short_token(length: usize, min_length: usize) {
+    warn!(
+        "Generating a {}-character token, below the minimum of {} for security-sensitive tokens",
+        length, min_length
+    );
+}
+
+fn random_alphanumeric(length: usize) -> String {
     const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
     (0..length)
         .map(|_| {
@@ -53,6 +128,29 @@ mod tests {
         assert_ne!(token1, token2);
     }
 
+    #[test]
+    fn test_short_token_is_rejected_or_allowed_per_policy() {
+        let strict = TokenPolicy {
+            reject_short: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            generate_token_with_policy(4, &strict),
+            Err(TokenTooShort {
+                length: 4,
+                min_length: MIN_TOKEN_LENGTH
+            })
+        );
+        assert_eq!(
+            generate_token_with_policy(RECOMMENDED_TOKEN_LENGTH, &strict).map(|token| token.len()),
+            Ok(RECOMMENDED_TOKEN_LENGTH)
+        );
+
+        // The default policy only warns
+        let token = generate_token_with_policy(4, &TokenPolicy::default()).unwrap();
+        assert_eq!(token.len(), 4);
+    }
+
     #[test]
     fn test_hash_token_is_deterministic() {
         let token = "abc123";
@@ -1,5 +1,9 @@
 //! Docs: docs/src/content/docs/api/websocket.md
 pub mod auth;
 pub mod connections;
+pub mod ip_limit;
 pub mod listener;
 pub mod message;
+pub mod outbox;
+pub mod retention;
+pub mod stats;
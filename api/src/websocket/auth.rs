@@ -1,13 +1,16 @@
 use axum::{
     extract::{Query, State, WebSocketUpgrade},
-    http::{header, HeaderMap, StatusCode},
+    http::{header, Extensions, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
 use serde::Deserialize;
+use tracing::warn;
 use uuid::Uuid;
 
+use crate::api::client_ip::resolve_client_ip;
 use crate::app::App;
 use crate::auth::jwt;
+use crate::websocket::stats::ConnectionCounters;
 
 /// Query parameters for WebSocket authentication
 #[derive(Debug, Deserialize)]
@@ -39,12 +42,39 @@ fn extract_token(query: &WsAuthQuery, headers: &HeaderMap) -> Option<String> {
 ///
 /// Authenticates the user via JWT token (from query param or header),
 /// then upgrades the connection and passes the user_id to the connection handler.
+///
+/// With an IP connection limit set, a client IP already at the limit gets
+/// `429` before its token is looked at.
 pub async fn authenticated_ws_handler<ExtraConfig>(
     ws: WebSocketUpgrade,
     Query(query): Query<WsAuthQuery>,
     headers: HeaderMap,
+    extensions: Extensions,
     State(app): State<App<ExtraConfig>>,
 ) -> Response {
+    // Held until the socket closes
+    let ip_permit = match app.websocket_connections.ip_connection_limit() {
+        Some(limit) => {
+            let ip = resolve_client_ip(
+                &headers,
+                &extensions,
+                app.rate_limit_state.trust_proxy(),
+                app.rate_limit_state.trusted_proxies(),
+            );
+            match ip.map(|ip| (ip, limit.try_acquire(ip))) {
+                Some((_, Some(permit))) => Some(permit),
+                Some((ip, None)) => {
+                    warn!(%ip, "Rejecting WebSocket upgrade, too many connections from this IP");
+                    ConnectionCounters::record_rejected();
+                    return (StatusCode::TOO_MANY_REQUESTS, "Too many connections").into_response();
+                }
+                // Without connect info there is no IP to count against
+                None => None,
+            }
+        }
+        None => None,
+    };
+
     // Extract token from query or header
     let Some(token) = extract_token(&query, &headers) else {
         return (StatusCode::UNAUTHORIZED, "Missing token").into_response();
@@ -58,6 +88,11 @@ pub async fn authenticated_ws_handler<ExtraConfig>(
         }
     };
 
+    // Tokens bound to a client are only good from that client, like for `CurrentUser`
+    if !jwt::matches_client(&app.config, &claims, &headers) {
+        return (StatusCode::UNAUTHORIZED, "Invalid token").into_response();
+    }
+
     // Parse user_id from claims
     let user_id = match Uuid::parse_str(&claims.sub) {
         Ok(id) => id,
@@ -70,5 +105,115 @@ pub async fn authenticated_ws_handler<ExtraConfig>(
     let connections = app.websocket_connections.clone();
 
     // Upgrade to WebSocket with the authenticated user_id
-    ws.on_upgrade(move |socket| async move { connections.handle_socket(user_id, socket).await })
+    ws.on_upgrade(move |socket| async move {
+        connections.handle_socket(user_id, socket).await;
+        drop(ip_permit);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::time::Duration;
+
+    use axum::{http::HeaderMap, routing::get, Router};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use uuid::Uuid;
+
+    use super::authenticated_ws_handler;
+    use crate::{
+        app::App,
+        auth::jwt::generate_token,
+        database::migrations::Migrator,
+        tests::setup_test::{empty_router, no_fixtures, setup_test, setup_test_with_config},
+        websocket::{connections::Connections, ip_limit::IpConnectionLimit},
+    };
+
+    /// Serve `/ws` on a local port, with connect info like the real server.
+    async fn serve(app: App) -> SocketAddr {
+        let router = Router::new()
+            .route("/ws", get(authenticated_ws_handler::<()>))
+            .with_state(app);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+        addr
+    }
+
+    /// Send an upgrade request and return the open stream with the status code.
+    async fn upgrade(addr: SocketAddr, token: &str) -> (TcpStream, u16) {
+        upgrade_as(addr, token, "test-client").await
+    }
+
+    async fn upgrade_as(addr: SocketAddr, token: &str, user_agent: &str) -> (TcpStream, u16) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "GET /ws?token={token} HTTP/1.1\r\nHost: {addr}\r\nUser-Agent: {user_agent}\r\n\
+             Connection: Upgrade\r\nUpgrade: websocket\r\n\
+             Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n"
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut buf = [0u8; 1024];
+        let n = stream.read(&mut buf).await.unwrap();
+        let response = String::from_utf8_lossy(&buf[..n]);
+        let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+        (stream, status)
+    }
+
+    #[tokio::test]
+    async fn test_excess_connections_from_one_ip_are_rejected() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let limit = IpConnectionLimit::new(2);
+        let app = App {
+            websocket_connections: Connections::new().with_ip_connection_limit(Some(limit.clone())),
+            ..test.app()
+        };
+        let token = generate_token(&test.config, Uuid::new_v4(), 0, &HeaderMap::new()).unwrap();
+
+        let addr = serve(app).await;
+
+        let (first, status) = upgrade(addr, &token).await;
+        assert_eq!(status, 101);
+        let (_second, status) = upgrade(addr, &token).await;
+        assert_eq!(status, 101);
+        assert_eq!(upgrade(addr, &token).await.1, 429);
+        // Checked before the token, so unauthenticated floods are capped too
+        assert_eq!(upgrade(addr, "not-a-token").await.1, 429);
+
+        // Closing a socket frees its slot
+        drop(first);
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while limit.open_connections(localhost) > 1 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(upgrade(addr, &token).await.1, 101);
+    }
+
+    #[tokio::test]
+    async fn test_bound_token_is_refused_from_another_client() {
+        let test = setup_test_with_config::<Migrator>(empty_router, no_fixtures, |config| {
+            config.auth.bind_tokens_to_client = true;
+        })
+        .await;
+        let mut headers = HeaderMap::new();
+        headers.insert("User-Agent", "ExampleApp/1.0".parse().unwrap());
+        let token = generate_token(&test.config, Uuid::new_v4(), 0, &headers).unwrap();
+        let addr = serve(test.app()).await;
+
+        let (_socket, status) = upgrade_as(addr, &token, "ExampleApp/1.0").await;
+        assert_eq!(status, 101);
+        assert_eq!(upgrade_as(addr, &token, "StolenElsewhere/2.0").await.1, 401);
+    }
 }
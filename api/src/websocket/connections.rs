@@ -1,29 +1,129 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use axum::extract::ws::{Message, WebSocket};
-use futures_util::{SinkExt, StreamExt};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket};
+use futures_util::{Sink, SinkExt, StreamExt};
 use serde_json::Value;
-use tokio::sync::{mpsc, Mutex};
-use tracing::{error, info, warn};
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use crate::websocket::message::{Message as WsMessage, Request, Response};
+use crate::config::{ConnectionLimitPolicy, WebSocketConfig};
+use crate::websocket::{
+    ip_limit::IpConnectionLimit,
+    message::{Message as WsMessage, Request, Response},
+    outbox::{self, OutboxReceiver, OutboxSender, SendBuffer, SendOutcome},
+    retention::OfflineRetention,
+    stats::{ConnectionCounters, ConnectionStats},
+};
 
 pub type ConnectionId = Uuid;
 pub type UserId = Uuid;
-pub type ConnectionSender = mpsc::UnboundedSender<String>;
+pub type ConnectionSender = OutboxSender;
 pub type UserConnections = Vec<(ConnectionId, ConnectionSender)>;
 pub type ConnectionStore = Arc<Mutex<HashMap<UserId, UserConnections>>>;
 pub type AppRequestHandler = Arc<dyn Fn(Value) -> Response + Send + Sync>;
+pub type MessageId = Uuid;
+pub type UnackedStore = Arc<Mutex<HashMap<UserId, HashMap<MessageId, UnackedMessage>>>>;
+pub type RoomStore = Arc<Mutex<HashMap<String, HashSet<UserId>>>>;
+
+/// Upper bound of unacknowledged messages kept per user; the oldest are dropped.
+const MAX_UNACKED_PER_USER: usize = 1000;
+
+/// When unacknowledged reliable messages are resent, and when they are given
+/// up on for users who don't come back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReliableDelivery {
+    pub ack_timeout: Duration,
+    /// Age after which a message is dropped if its user has no connection
+    pub unacked_ttl: Duration,
+}
+
+impl ReliableDelivery {
+    #[must_use]
+    pub const fn from_config(config: &WebSocketConfig) -> Self {
+        Self {
+            ack_timeout: Duration::from_secs(config.ack_timeout_seconds),
+            unacked_ttl: Duration::from_secs(config.unacked_ttl_seconds),
+        }
+    }
+}
+
+/// How often connections are pinged, and how long a client may take to
+/// answer before its connection is closed as dead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Heartbeat {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
+impl Heartbeat {
+    /// `None` if `heartbeat_interval_seconds` is 0.
+    #[must_use]
+    pub const fn from_config(config: &WebSocketConfig) -> Option<Self> {
+        if config.heartbeat_interval_seconds == 0 {
+            return None;
+        }
+        Some(Self {
+            interval: Duration::from_secs(config.heartbeat_interval_seconds),
+            timeout: Duration::from_secs(config.heartbeat_timeout_seconds),
+        })
+    }
+}
+
+/// How many connections one user may hold open, and what happens to one
+/// more.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionLimit {
+    pub max_per_user: usize,
+    pub policy: ConnectionLimitPolicy,
+}
+
+impl ConnectionLimit {
+    /// `None` if `max_connections_per_user` is 0.
+    #[must_use]
+    pub const fn from_config(config: &WebSocketConfig) -> Option<Self> {
+        if config.max_connections_per_user == 0 {
+            return None;
+        }
+        Some(Self {
+            max_per_user: config.max_connections_per_user,
+            policy: config.connection_limit_policy,
+        })
+    }
+}
+
+/// A reliable message awaiting the client's `Request::Ack`.
+#[derive(Debug, Clone)]
+pub struct UnackedMessage {
+    payload: String,
+    sent_at: Instant,
+    /// First sent; resends don't change it
+    queued_at: Instant,
+}
 
 #[derive(Clone)]
 pub struct Connections {
     // Track multiple connections per user: UserId -> Vec<(ConnectionId, Sender)>
     connections: ConnectionStore,
+    // Reliable messages not yet acknowledged, per user so they survive reconnects
+    unacked: UnackedStore,
+    // Room name -> connected users who joined it. Locked after `connections`.
+    rooms: RoomStore,
     // Optional application-specific request handler
     app_handler: Option<AppRequestHandler>,
+    // Pings sent to detect half-open connections; none if unset
+    heartbeat: Option<Heartbeat>,
+    // Per-connection queue size and what to do when a client falls behind
+    send_buffer: SendBuffer,
+    // Cap on open connections per user; none if unset
+    connection_limit: Option<ConnectionLimit>,
+    // Cap on open connections per client IP, checked on upgrade; none if unset
+    ip_connection_limit: Option<IpConnectionLimit>,
+    // Outbox messages kept for users who weren't connected; none if unset
+    offline_retention: Option<OfflineRetention>,
+    counters: Arc<ConnectionCounters>,
 }
 
 impl Default for Connections {
@@ -37,7 +137,15 @@ impl Connections {
     pub fn new() -> Self {
         Self {
             connections: Arc::new(Mutex::new(HashMap::new())),
+            unacked: Arc::new(Mutex::new(HashMap::new())),
+            rooms: Arc::new(Mutex::new(HashMap::new())),
             app_handler: None,
+            heartbeat: None,
+            send_buffer: SendBuffer::default(),
+            connection_limit: None,
+            ip_connection_limit: None,
+            offline_retention: None,
+            counters: Arc::default(),
         }
     }
 
@@ -49,38 +157,290 @@ impl Connections {
     {
         Self {
             connections: Arc::new(Mutex::new(HashMap::new())),
+            unacked: Arc::new(Mutex::new(HashMap::new())),
+            rooms: Arc::new(Mutex::new(HashMap::new())),
             app_handler: Some(Arc::new(handler)),
+            heartbeat: None,
+            send_buffer: SendBuffer::default(),
+            connection_limit: None,
+            ip_connection_limit: None,
+            offline_retention: None,
+            counters: Arc::default(),
+        }
+    }
+
+    /// Ping every connection on `heartbeat`'s interval and close those that
+    /// stop answering, so clients lost to a dead network don't linger.
+    #[must_use]
+    pub const fn with_heartbeat(mut self, heartbeat: Option<Heartbeat>) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Bound each connection's queue of unsent messages, so a client that
+    /// reads slower than it is sent to can't grow memory without limit.
+    #[must_use]
+    pub const fn with_send_buffer(mut self, send_buffer: SendBuffer) -> Self {
+        self.send_buffer = send_buffer;
+        self
+    }
+
+    /// Limit how many connections one user can hold open, so a misbehaving
+    /// client can't exhaust memory by reconnecting in a loop.
+    #[must_use]
+    pub const fn with_connection_limit(
+        mut self,
+        connection_limit: Option<ConnectionLimit>,
+    ) -> Self {
+        self.connection_limit = connection_limit;
+        self
+    }
+
+    /// Limit how many connections one client IP can hold open, checked
+    /// before authentication by
+    /// [`authenticated_ws_handler`](super::auth::authenticated_ws_handler).
+    #[must_use]
+    pub fn with_ip_connection_limit(
+        mut self,
+        ip_connection_limit: Option<IpConnectionLimit>,
+    ) -> Self {
+        self.ip_connection_limit = ip_connection_limit;
+        self
+    }
+
+    /// Keep `user`-targeted outbox messages for users who aren't connected
+    /// and deliver them when they next connect.
+    #[must_use]
+    pub fn with_offline_retention(mut self, offline_retention: Option<OfflineRetention>) -> Self {
+        self.offline_retention = offline_retention;
+        self
+    }
+
+    pub(crate) const fn offline_retention(&self) -> Option<&OfflineRetention> {
+        self.offline_retention.as_ref()
+    }
+
+    pub(crate) const fn ip_connection_limit(&self) -> Option<&IpConnectionLimit> {
+        self.ip_connection_limit.as_ref()
+    }
+
+    /// Send a message to all connections for a specific user, returning how
+    /// many connections it was sent to.
+    ///
+    /// Connections whose socket task has already gone away are pruned, so the
+    /// store heals itself before `handle_socket` gets around to unregistering.
+    pub async fn send_to_user(&self, user_id: UserId, message: String) -> usize {
+        let mut connections = self.connections.lock().await;
+        let Some(user_connections) = connections.get_mut(&user_id) else {
+            return 0;
+        };
+        send_or_prune(user_id, user_connections, &message, &self.counters);
+        let reached = user_connections.len();
+        if reached == 0 {
+            connections.remove(&user_id);
+            ConnectionCounters::record_connected_users(connections.len());
+            leave_all_rooms(&mut *self.rooms.lock().await, &[user_id]);
         }
+        reached
     }
 
-    /// Send a message to all connections for a specific user
-    pub async fn send_to_user(&self, user_id: UserId, message: String) {
+    /// Send a message to all connected users, pruning dead connections like
+    /// [`Connections::send_to_user`].
+    pub async fn send_to_all(&self, message: String) {
+        let mut connections = self.connections.lock().await;
+        let mut gone = Vec::new();
+        connections.retain(|user_id, user_connections| {
+            send_or_prune(*user_id, user_connections, &message, &self.counters);
+            if user_connections.is_empty() {
+                gone.push(*user_id);
+            }
+            !user_connections.is_empty()
+        });
+        ConnectionCounters::record_connected_users(connections.len());
+        if !gone.is_empty() {
+            leave_all_rooms(&mut *self.rooms.lock().await, &gone);
+        }
+    }
+
+    /// Add a connected user to `room`, so messages sent to the room reach all
+    /// of their connections. Returns `false` if the user has no open
+    /// connection. Users leave every room when their last connection closes.
+    pub async fn join_room(&self, user_id: UserId, room: impl Into<String>) -> bool {
         let connections = self.connections.lock().await;
-        if let Some(user_connections) = connections.get(&user_id) {
-            for (connection_id, tx) in user_connections {
-                if let Err(e) = tx.send(message.clone()) {
-                    error!(
-                        "Failed to send message to user {} connection {}: {:?}",
-                        user_id, connection_id, e
-                    );
+        if !connections.contains_key(&user_id) {
+            return false;
+        }
+        self.rooms
+            .lock()
+            .await
+            .entry(room.into())
+            .or_default()
+            .insert(user_id);
+        true
+    }
+
+    /// Remove a user from `room`. Returns `false` if they weren't in it.
+    pub async fn leave_room(&self, user_id: UserId, room: &str) -> bool {
+        let mut rooms = self.rooms.lock().await;
+        let Some(members) = rooms.get_mut(room) else {
+            return false;
+        };
+        let removed = members.remove(&user_id);
+        if members.is_empty() {
+            rooms.remove(room);
+        }
+        removed
+    }
+
+    /// Users currently in `room`.
+    pub async fn room_members(&self, room: &str) -> Vec<UserId> {
+        self.rooms
+            .lock()
+            .await
+            .get(room)
+            .map(|members| members.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Send a message to every connection of every user in `room`, pruning
+    /// dead connections like [`Connections::send_to_user`].
+    pub async fn send_to_room(&self, room: &str, message: String) {
+        let mut connections = self.connections.lock().await;
+        let mut rooms = self.rooms.lock().await;
+        let Some(members) = rooms.get(room) else {
+            return;
+        };
+        let mut gone = Vec::new();
+        for user_id in members {
+            if let Some(user_connections) = connections.get_mut(user_id) {
+                send_or_prune(*user_id, user_connections, &message, &self.counters);
+                if user_connections.is_empty() {
+                    connections.remove(user_id);
+                    gone.push(*user_id);
                 }
             }
         }
+        if !gone.is_empty() {
+            ConnectionCounters::record_connected_users(connections.len());
+            leave_all_rooms(&mut rooms, &gone);
+        }
     }
 
-    /// Send a message to all connected users
-    pub async fn send_to_all(&self, message: String) {
-        let connections = self.connections.lock().await;
-        for (_user_id, user_connections) in connections.iter() {
-            for (connection_id, tx) in user_connections {
-                if let Err(e) = tx.send(message.clone()) {
-                    error!(
-                        "Failed to send message to connection {}: {:?}",
-                        connection_id, e
+    /// Send a message to a user and keep redelivering it — on reconnect or
+    /// after the ack timeout — until one of the user's clients acknowledges
+    /// it, or it expires while the user is away.
+    pub async fn send_reliable_to_user(
+        &self,
+        user_id: UserId,
+        message_id: MessageId,
+        payload: Value,
+    ) {
+        let envelope = WsMessage::Delivery {
+            message_id,
+            payload,
+        };
+        let message = match serde_json::to_string(&envelope) {
+            Ok(message) => message,
+            Err(e) => {
+                error!("Failed to serialize delivery {}: {:?}", message_id, e);
+                return;
+            }
+        };
+
+        {
+            let mut unacked = self.unacked.lock().await;
+            let user_unacked = unacked.entry(user_id).or_default();
+            if user_unacked.len() >= MAX_UNACKED_PER_USER {
+                if let Some(oldest) = user_unacked
+                    .iter()
+                    .min_by_key(|(_, m)| m.sent_at)
+                    .map(|(id, _)| *id)
+                {
+                    warn!(
+                        "Too many unacknowledged messages for user {}, dropping {}",
+                        user_id, oldest
                     );
+                    user_unacked.remove(&oldest);
                 }
             }
+            let now = Instant::now();
+            user_unacked.insert(
+                message_id,
+                UnackedMessage {
+                    payload: message.clone(),
+                    sent_at: now,
+                    queued_at: now,
+                },
+            );
         }
+
+        self.send_to_user(user_id, message).await;
+    }
+
+    /// Send a reliable message to every user in `room`.
+    pub async fn send_reliable_to_room(&self, room: &str, message_id: MessageId, payload: Value) {
+        for user_id in self.room_members(room).await {
+            self.send_reliable_to_user(user_id, message_id, payload.clone())
+                .await;
+        }
+    }
+
+    /// Send a reliable message to every connected user.
+    pub async fn send_reliable_to_all(&self, message_id: MessageId, payload: Value) {
+        for user_id in self.connected_user_ids().await {
+            self.send_reliable_to_user(user_id, message_id, payload.clone())
+                .await;
+        }
+    }
+
+    /// Mark a reliable message as received. Returns `false` if it was not pending.
+    pub async fn acknowledge(&self, user_id: UserId, message_id: MessageId) -> bool {
+        acknowledge(&self.unacked, user_id, message_id).await
+    }
+
+    /// Resend reliable messages that have been unacknowledged for longer than
+    /// `older_than` to the users' open connections.
+    pub async fn redeliver_unacked(&self, older_than: Duration) {
+        let now = Instant::now();
+        let mut due: Vec<(UserId, String)> = Vec::new();
+        {
+            let mut unacked = self.unacked.lock().await;
+            for (user_id, messages) in unacked.iter_mut() {
+                for message in messages.values_mut() {
+                    if now.duration_since(message.sent_at) >= older_than {
+                        message.sent_at = now;
+                        due.push((*user_id, message.payload.clone()));
+                    }
+                }
+            }
+        }
+
+        for (user_id, payload) in due {
+            self.send_to_user(user_id, payload).await;
+        }
+    }
+
+    /// Drop unacknowledged messages first sent more than `older_than` ago to
+    /// users with no open connection, and return how many were dropped.
+    pub async fn expire_unacked(&self, older_than: Duration) -> usize {
+        let now = Instant::now();
+        // Same lock order as everywhere else: unacked, then connections
+        let mut unacked = self.unacked.lock().await;
+        let connections = self.connections.lock().await;
+        let mut expired = 0;
+        unacked.retain(|user_id, messages| {
+            if connections.contains_key(user_id) {
+                return true;
+            }
+            let before = messages.len();
+            messages.retain(|_, message| now.duration_since(message.queued_at) < older_than);
+            expired += before - messages.len();
+            !messages.is_empty()
+        });
+        if expired > 0 {
+            debug!("Dropped {expired} unacknowledged message(s) of users who didn't reconnect");
+        }
+        expired
     }
 
     /// Get the IDs of all currently connected users.
@@ -88,6 +448,17 @@ impl Connections {
         self.connections.lock().await.keys().copied().collect()
     }
 
+    /// IDs of a user's open connections, oldest first, e.g. to pick one for
+    /// [`Connections::disconnect_connection`].
+    pub async fn user_connection_ids(&self, user_id: UserId) -> Vec<ConnectionId> {
+        self.connections
+            .lock()
+            .await
+            .get(&user_id)
+            .map(|user_connections| user_connections.iter().map(|(cid, _)| *cid).collect())
+            .unwrap_or_default()
+    }
+
     /// Get count of connected users
     pub async fn user_count(&self) -> usize {
         self.connections.lock().await.len()
@@ -103,6 +474,78 @@ impl Connections {
             .sum()
     }
 
+    /// Connection churn and message counters, e.g. to spot flapping clients.
+    pub async fn stats(&self) -> ConnectionStats {
+        let connections = self.connections.lock().await;
+        let open_connections = connections.values().map(Vec::len).sum();
+        self.counters.snapshot(open_connections, connections.len())
+    }
+
+    /// Close every connection of a user, e.g. after banning them or a
+    /// password change, and remove them from their rooms.
+    ///
+    /// Dropping a connection's sender ends its socket task once queued
+    /// messages are written. Returns the number of connections closed.
+    /// Unacknowledged messages are kept for the user's next connection.
+    pub async fn disconnect_user(&self, user_id: UserId) -> usize {
+        let mut connections = self.connections.lock().await;
+        let Some(user_connections) = connections.remove(&user_id) else {
+            return 0;
+        };
+        leave_all_rooms(&mut *self.rooms.lock().await, &[user_id]);
+        self.counters.record_closed(user_connections.len());
+        ConnectionCounters::record_connected_users(connections.len());
+        info!(
+            "🔌 Disconnected {} WebSocket connection(s) of user {}",
+            user_connections.len(),
+            user_id
+        );
+        user_connections.len()
+    }
+
+    /// Close one connection of a user. Returns `false` if it isn't open.
+    pub async fn disconnect_connection(
+        &self,
+        user_id: UserId,
+        connection_id: ConnectionId,
+    ) -> bool {
+        let mut connections = self.connections.lock().await;
+        let Some(user_connections) = connections.get_mut(&user_id) else {
+            return false;
+        };
+        let before = user_connections.len();
+        user_connections.retain(|(cid, _)| *cid != connection_id);
+        if user_connections.len() == before {
+            return false;
+        }
+        if user_connections.is_empty() {
+            connections.remove(&user_id);
+            leave_all_rooms(&mut *self.rooms.lock().await, &[user_id]);
+        }
+        self.counters.record_closed(1);
+        ConnectionCounters::record_connected_users(connections.len());
+        info!(
+            "🔌 Disconnected WebSocket connection {} of user {}",
+            connection_id, user_id
+        );
+        true
+    }
+
+    /// Drop every open connection so its socket task ends, e.g. on shutdown.
+    ///
+    /// Returns the number of connections closed. Unacknowledged messages are
+    /// kept; they are lost with the process unless it is only the server that
+    /// restarts.
+    pub async fn close_all(&self) -> usize {
+        let mut connections = self.connections.lock().await;
+        let closed = connections.values().map(Vec::len).sum();
+        connections.clear();
+        self.rooms.lock().await.clear();
+        self.counters.record_closed(closed);
+        ConnectionCounters::record_connected_users(0);
+        closed
+    }
+
     pub async fn handle_socket(&self, user_id: UserId, socket: WebSocket) {
         let connection_id = Uuid::new_v4();
         info!(
@@ -111,31 +554,47 @@ impl Connections {
         );
 
         let (mut sender, mut receiver) = socket.split();
-        let (tx, mut rx) = mpsc::unbounded_channel();
+        let Some(rx) = self.register(user_id, connection_id).await else {
+            warn!(
+                user_id = %user_id,
+                connection_id = %connection_id,
+                "Too many WebSocket connections for user, rejecting new connection"
+            );
+            let close = CloseFrame {
+                code: close_code::POLICY,
+                reason: "Too many connections".into(),
+            };
+            if let Err(e) = sender.send(Message::Close(Some(close))).await {
+                error!("Failed to close rejected WebSocket connection: {:?}", e);
+            }
+            return;
+        };
 
-        // Add connection to manager
-        {
-            let mut connections = self.connections.lock().await;
-            connections
-                .entry(user_id)
-                .or_insert_with(Vec::new)
-                .push((connection_id, tx));
+        if let Some(retention) = &self.offline_retention {
+            if let Err(e) = retention.flush(self, user_id).await {
+                error!(
+                    "Failed to deliver retained messages to user {}: {:?}",
+                    user_id, e
+                );
+            }
         }
 
+        let last_pong = Arc::new(std::sync::Mutex::new(Instant::now()));
+
         // Handle outgoing messages
-        let outgoing_task = tokio::spawn(async move {
-            while let Some(msg) = rx.recv().await {
-                if let Err(e) = sender.send(Message::Text(msg.into())).await {
-                    error!("Failed to send WebSocket message: {:?}", e);
-                    break;
-                }
-            }
-        });
+        let mut outgoing_task = tokio::spawn(forward_outgoing(
+            sender,
+            rx,
+            self.heartbeat,
+            last_pong.clone(),
+        ));
 
         // Handle incoming messages
         let connections = self.connections.clone();
+        let unacked = self.unacked.clone();
         let app_handler = self.app_handler.clone();
-        let incoming_task = tokio::spawn(async move {
+        let counters = self.counters.clone();
+        let mut incoming_task = tokio::spawn(async move {
             // Sliding-window message rate limiter: max 20 messages per second per connection.
             // Exceeding this disconnects the client to prevent message-flood DDoS.
             const MAX_MSGS_PER_WINDOW: usize = 20;
@@ -161,7 +620,13 @@ impl Connections {
                         if let Ok(WsMessage::Request { request, id }) =
                             serde_json::from_str::<WsMessage>(&text)
                         {
-                            let response = handle_request(request, &app_handler);
+                            let response = match request {
+                                Request::Ack { message_id } => {
+                                    acknowledge(&unacked, user_id, message_id).await;
+                                    Response::Ok
+                                }
+                                request => handle_request(request, &app_handler),
+                            };
                             let response_msg = WsMessage::Response { response, id };
 
                             if let Ok(serialized) = serde_json::to_string(&response_msg) {
@@ -172,12 +637,22 @@ impl Connections {
                                         .iter()
                                         .find(|(cid, _)| *cid == connection_id)
                                     {
-                                        let _ = tx.send(serialized);
+                                        let outcome = tx.send(serialized);
+                                        counters.record_outcomes(&[outcome]);
+                                        if outcome == SendOutcome::Overflowed {
+                                            warn!(
+                                                user_id = %user_id,
+                                                connection_id = %connection_id,
+                                                "WebSocket send queue full, closing connection"
+                                            );
+                                            break;
+                                        }
                                     }
                                 }
                             }
                         }
                     }
+                    Ok(Message::Pong(_)) => *last_pong.lock().unwrap() = Instant::now(),
                     Ok(Message::Close(_)) => break,
                     Err(e) => {
                         error!("WebSocket error: {:?}", e);
@@ -188,28 +663,217 @@ impl Connections {
             }
         });
 
-        // Wait for either task to complete
+        // Wait for either task to complete, then stop the other so the
+        // socket is dropped and closed
         tokio::select! {
-            _ = outgoing_task => {},
-            _ = incoming_task => {},
+            _ = &mut outgoing_task => incoming_task.abort(),
+            _ = &mut incoming_task => outgoing_task.abort(),
         }
 
-        // Clean up connection
-        {
-            let mut connections = self.connections.lock().await;
-            if let Some(user_connections) = connections.get_mut(&user_id) {
-                user_connections.retain(|(cid, _)| *cid != connection_id);
-                // Remove user entry if no more connections
-                if user_connections.is_empty() {
-                    connections.remove(&user_id);
-                }
-            }
-        }
+        self.unregister(user_id, connection_id).await;
         info!(
             "🔌 WebSocket connection closed: {} for user: {}",
             connection_id, user_id
         );
     }
+
+    /// Add a connection to the manager and replay the user's unacknowledged
+    /// messages to it.
+    ///
+    /// Returns `None` if the user is at the connection limit and the policy
+    /// is to reject new connections. The limit is checked under the
+    /// connection store lock, so concurrent connects can't overshoot it.
+    pub(crate) async fn register(
+        &self,
+        user_id: UserId,
+        connection_id: ConnectionId,
+    ) -> Option<OutboxReceiver> {
+        // Same lock order as everywhere else: unacked, then connections
+        let mut unacked = self.unacked.lock().await;
+        let mut connections = self.connections.lock().await;
+        let user_connections = connections.entry(user_id).or_default();
+
+        if let Some(limit) = self.connection_limit {
+            if user_connections.len() >= limit.max_per_user {
+                match limit.policy {
+                    ConnectionLimitPolicy::RejectNew => {
+                        if user_connections.is_empty() {
+                            connections.remove(&user_id);
+                        }
+                        ConnectionCounters::record_rejected();
+                        return None;
+                    }
+                    ConnectionLimitPolicy::EvictOldest => {
+                        // Dropping the sender ends that connection's socket task
+                        let evicted = user_connections.len() + 1 - limit.max_per_user;
+                        for (evicted_id, _) in user_connections.drain(..evicted) {
+                            warn!(
+                                "Too many WebSocket connections for user {}, closing oldest connection {}",
+                                user_id, evicted_id
+                            );
+                        }
+                        self.counters.record_closed(evicted);
+                    }
+                }
+            }
+        }
+
+        let (tx, rx) = outbox::channel(self.send_buffer);
+        if let Some(messages) = unacked.get_mut(&user_id) {
+            let now = Instant::now();
+            // Whatever doesn't fit is resent after the ack timeout
+            let outcomes: Vec<_> = messages
+                .values_mut()
+                .map(|message| {
+                    message.sent_at = now;
+                    tx.send(message.payload.clone())
+                })
+                .take_while(|outcome| *outcome != SendOutcome::Overflowed)
+                .collect();
+            self.counters.record_outcomes(&outcomes);
+        }
+        drop(unacked);
+
+        user_connections.push((connection_id, tx));
+        self.counters.record_opened();
+        ConnectionCounters::record_connected_users(connections.len());
+
+        Some(rx)
+    }
+
+    async fn unregister(&self, user_id: UserId, connection_id: ConnectionId) {
+        let mut connections = self.connections.lock().await;
+        if let Some(user_connections) = connections.get_mut(&user_id) {
+            let before = user_connections.len();
+            user_connections.retain(|(cid, _)| *cid != connection_id);
+            // Already gone if a send pruned it first
+            self.counters.record_closed(before - user_connections.len());
+            // Remove user entry if no more connections
+            if user_connections.is_empty() {
+                connections.remove(&user_id);
+                leave_all_rooms(&mut *self.rooms.lock().await, &[user_id]);
+            }
+        }
+        ConnectionCounters::record_connected_users(connections.len());
+    }
+}
+
+/// Write queued messages to the socket, pinging it on `heartbeat`'s
+/// interval. Returns when the connection is dropped from the store, a write
+/// fails, or no pong has arrived within the heartbeat timeout.
+async fn forward_outgoing<S>(
+    mut sender: S,
+    mut rx: OutboxReceiver,
+    heartbeat: Option<Heartbeat>,
+    last_pong: Arc<std::sync::Mutex<Instant>>,
+) where
+    S: Sink<Message> + Unpin,
+    S::Error: std::fmt::Debug,
+{
+    let mut heartbeat =
+        heartbeat.map(|heartbeat| (tokio::time::interval(heartbeat.interval), heartbeat.timeout));
+    loop {
+        tokio::select! {
+            msg = rx.recv() => {
+                let Some(msg) = msg else { break };
+                if let Err(e) = sender.send(Message::Text(msg.into())).await {
+                    error!("Failed to send WebSocket message: {:?}", e);
+                    break;
+                }
+            }
+            timeout = next_ping(&mut heartbeat) => {
+                if last_pong.lock().unwrap().elapsed() > timeout {
+                    warn!("No pong from WebSocket client within {:?}, closing connection", timeout);
+                    break;
+                }
+                if let Err(e) = sender.send(Message::Ping(Vec::new().into())).await {
+                    error!("Failed to ping WebSocket client: {:?}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Resolves with the pong timeout when the next ping is due; never without a
+/// heartbeat.
+async fn next_ping(heartbeat: &mut Option<(tokio::time::Interval, Duration)>) -> Duration {
+    match heartbeat {
+        Some((interval, timeout)) => {
+            interval.tick().await;
+            *timeout
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Send `message` on each of a user's connections and drop those whose
+/// receiver is gone, or whose queue is full under
+/// [`OverflowPolicy::Disconnect`](crate::config::OverflowPolicy::Disconnect).
+///
+/// Runs under the connection store lock and takes no other lock, so it can't
+/// deadlock against `register`, which locks the unacked store first.
+fn send_or_prune(
+    user_id: UserId,
+    user_connections: &mut UserConnections,
+    message: &str,
+    counters: &ConnectionCounters,
+) {
+    let before = user_connections.len();
+    let mut outcomes = Vec::with_capacity(before);
+    user_connections.retain(|(connection_id, tx)| {
+        let outcome = tx.send(message.to_string());
+        outcomes.push(outcome);
+        match outcome {
+            SendOutcome::Closed => {
+                warn!(
+                    "Pruning closed connection {} of user {}",
+                    connection_id, user_id
+                );
+                false
+            }
+            SendOutcome::Overflowed => {
+                warn!(
+                    "Closing connection {} of user {}, its send queue is full",
+                    connection_id, user_id
+                );
+                false
+            }
+            outcome => {
+                if outcome.dropped_message() {
+                    debug!(
+                        "Send queue of connection {} of user {} is full, dropped a message",
+                        connection_id, user_id
+                    );
+                }
+                true
+            }
+        }
+    });
+    counters.record_outcomes(&outcomes);
+    counters.record_closed(before - user_connections.len());
+}
+
+/// Remove `users` from every room, dropping rooms left empty.
+fn leave_all_rooms(rooms: &mut HashMap<String, HashSet<UserId>>, users: &[UserId]) {
+    rooms.retain(|_, members| {
+        for user_id in users {
+            members.remove(user_id);
+        }
+        !members.is_empty()
+    });
+}
+
+async fn acknowledge(unacked: &UnackedStore, user_id: UserId, message_id: MessageId) -> bool {
+    let mut unacked = unacked.lock().await;
+    let Some(messages) = unacked.get_mut(&user_id) else {
+        return false;
+    };
+    let removed = messages.remove(&message_id).is_some();
+    if messages.is_empty() {
+        unacked.remove(&user_id);
+    }
+    removed
 }
 
 fn handle_request(request: Request, app_handler: &Option<AppRequestHandler>) -> Response {
@@ -217,6 +881,8 @@ fn handle_request(request: Request, app_handler: &Option<AppRequestHandler>) ->
         Request::Version => Response::Version {
             version: env!("CARGO_PKG_VERSION").to_string(),
         },
+        // Acks are handled by the connection loop, which owns the unacked store
+        Request::Ack { .. } => Response::Ok,
         Request::Application(value) => {
             if let Some(handler) = app_handler {
                 handler(value)
@@ -228,3 +894,357 @@ fn handle_request(request: Request, app_handler: &Option<AppRequestHandler>) ->
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use axum::extract::ws::Message as WsFrame;
+
+    use super::{forward_outgoing, ConnectionLimit, Connections, Heartbeat};
+    use crate::websocket::message::Message;
+    use crate::{
+        config::{ConnectionLimitPolicy, OverflowPolicy},
+        websocket::outbox::{self, SendBuffer},
+    };
+
+    /// A sink recording every frame written to it.
+    fn recording_sink(
+        frames: Arc<Mutex<Vec<WsFrame>>>,
+    ) -> impl futures_util::Sink<WsFrame, Error = std::convert::Infallible> + Unpin {
+        Box::pin(futures_util::sink::unfold((), move |(), frame| {
+            frames.lock().unwrap().push(frame);
+            async { Ok(()) }
+        }))
+    }
+
+    const HEARTBEAT: Heartbeat = Heartbeat {
+        interval: Duration::from_millis(20),
+        timeout: Duration::from_millis(100),
+    };
+
+    #[tokio::test]
+    async fn test_connection_without_pongs_is_closed() {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let (_tx, rx) = outbox::channel(SendBuffer::default());
+        let last_pong = Arc::new(Mutex::new(Instant::now()));
+
+        let outgoing = forward_outgoing(
+            recording_sink(frames.clone()),
+            rx,
+            Some(HEARTBEAT),
+            last_pong,
+        );
+        tokio::time::timeout(Duration::from_secs(2), outgoing)
+            .await
+            .expect("a silent client should be dropped after the timeout");
+
+        let frames = frames.lock().unwrap();
+        assert!(frames.len() >= 2);
+        assert!(frames.iter().all(|frame| matches!(frame, WsFrame::Ping(_))));
+    }
+
+    #[tokio::test]
+    async fn test_connection_answering_pings_stays_open() {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = outbox::channel(SendBuffer::default());
+        let last_pong = Arc::new(Mutex::new(Instant::now()));
+
+        let outgoing = forward_outgoing(
+            recording_sink(frames.clone()),
+            rx,
+            Some(HEARTBEAT),
+            last_pong.clone(),
+        );
+        let client = async {
+            for _ in 0..15 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                *last_pong.lock().unwrap() = Instant::now();
+            }
+            tx.send("hello".to_string());
+            drop(tx);
+        };
+        let (_, ()) = tokio::join!(outgoing, client);
+
+        // Still open after three timeouts' worth of time; it ended only when
+        // the connection's channel closed
+        let frames = frames.lock().unwrap();
+        assert!(matches!(frames.last(), Some(WsFrame::Text(text)) if text.as_str() == "hello"));
+    }
+
+    #[tokio::test]
+    async fn test_unacked_messages_of_users_who_stay_away_expire() {
+        let connections = Connections::new();
+        let (away, online) = (Uuid::new_v4(), Uuid::new_v4());
+        let _online_rx = connections.register(online, Uuid::new_v4()).await.unwrap();
+        for user_id in [away, online] {
+            connections
+                .send_reliable_to_user(user_id, Uuid::new_v4(), json!({ "hello": "world" }))
+                .await;
+        }
+
+        // Not old enough yet
+        assert_eq!(connections.expire_unacked(Duration::from_secs(60)).await, 0);
+
+        // Only the message of the user with no connection is dropped
+        assert_eq!(connections.expire_unacked(Duration::ZERO).await, 1);
+        let mut rx = connections.register(away, Uuid::new_v4()).await.unwrap();
+        assert!(rx.try_recv().is_none());
+        assert_eq!(connections.expire_unacked(Duration::ZERO).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_unacked_message_is_redelivered_on_reconnect() {
+        let connections = Connections::new();
+        let user_id = Uuid::new_v4();
+        let message_id = Uuid::new_v4();
+
+        let first_connection = Uuid::new_v4();
+        let mut rx = connections
+            .register(user_id, first_connection)
+            .await
+            .unwrap();
+        connections
+            .send_reliable_to_user(user_id, message_id, json!({ "hello": "world" }))
+            .await;
+        assert!(rx.recv().await.is_some());
+
+        // Client disconnects without acknowledging
+        connections.unregister(user_id, first_connection).await;
+        drop(rx);
+
+        let second_connection = Uuid::new_v4();
+        let mut rx = connections
+            .register(user_id, second_connection)
+            .await
+            .unwrap();
+        let redelivered = rx
+            .try_recv()
+            .expect("unacked message should be redelivered");
+        match serde_json::from_str::<Message>(&redelivered).unwrap() {
+            Message::Delivery {
+                message_id: id,
+                payload,
+            } => {
+                assert_eq!(id, message_id);
+                assert_eq!(payload, json!({ "hello": "world" }));
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+
+        // Once acknowledged, the message is no longer redelivered
+        assert!(connections.acknowledge(user_id, message_id).await);
+        connections.unregister(user_id, second_connection).await;
+        let mut rx = connections.register(user_id, Uuid::new_v4()).await.unwrap();
+        assert!(rx.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_closed_connection_is_pruned_after_failed_send() {
+        let connections = Connections::new();
+        let user_id = Uuid::new_v4();
+
+        let mut open = connections.register(user_id, Uuid::new_v4()).await.unwrap();
+        let closed = connections.register(user_id, Uuid::new_v4()).await.unwrap();
+        let only_closed = connections
+            .register(Uuid::new_v4(), Uuid::new_v4())
+            .await
+            .unwrap();
+        // The socket tasks ended without unregistering yet
+        drop(closed);
+        drop(only_closed);
+        assert_eq!(connections.connection_count().await, 3);
+
+        connections.send_to_user(user_id, "hello".to_string()).await;
+        assert_eq!(open.recv().await.as_deref(), Some("hello"));
+        assert_eq!(connections.connection_count().await, 2);
+
+        connections.send_to_all("everyone".to_string()).await;
+        assert_eq!(open.recv().await.as_deref(), Some("everyone"));
+        assert_eq!(connections.connection_count().await, 1);
+        assert_eq!(connections.connected_user_ids().await, vec![user_id]);
+    }
+
+    #[tokio::test]
+    async fn test_room_messages_reach_members_until_they_disconnect() {
+        let connections = Connections::new();
+        let alice = Uuid::new_v4();
+        let bob = Uuid::new_v4();
+        let carol = Uuid::new_v4();
+
+        let alice_connection = Uuid::new_v4();
+        let mut alice_rx = connections.register(alice, alice_connection).await.unwrap();
+        let mut alice_phone = connections.register(alice, Uuid::new_v4()).await.unwrap();
+        let mut bob_rx = connections.register(bob, Uuid::new_v4()).await.unwrap();
+        let mut carol_rx = connections.register(carol, Uuid::new_v4()).await.unwrap();
+        assert!(connections.join_room(alice, "lobby").await);
+        assert!(connections.join_room(bob, "lobby").await);
+        assert!(connections.join_room(carol, "kitchen").await);
+        // Only connected users can join
+        assert!(!connections.join_room(Uuid::new_v4(), "lobby").await);
+
+        connections.send_to_room("lobby", "hi".to_string()).await;
+        assert_eq!(alice_rx.try_recv().as_deref(), Some("hi"));
+        assert_eq!(alice_phone.try_recv().as_deref(), Some("hi"));
+        assert_eq!(bob_rx.try_recv().as_deref(), Some("hi"));
+        assert!(carol_rx.try_recv().is_none());
+
+        assert!(connections.leave_room(bob, "lobby").await);
+        assert!(!connections.leave_room(bob, "lobby").await);
+        connections
+            .send_to_room("lobby", "still here?".to_string())
+            .await;
+        assert!(bob_rx.try_recv().is_none());
+
+        // Alice keeps her rooms until her last connection closes
+        connections.unregister(alice, alice_connection).await;
+        assert_eq!(connections.room_members("lobby").await, vec![alice]);
+        drop(alice_phone);
+        connections.send_to_room("lobby", "bye".to_string()).await;
+        assert!(connections.room_members("lobby").await.is_empty());
+        assert_eq!(connections.room_members("kitchen").await, vec![carol]);
+    }
+
+    #[tokio::test]
+    async fn test_slow_clients_are_handled_by_the_overflow_policy() {
+        let buffer = |policy| SendBuffer {
+            capacity: 1,
+            policy,
+        };
+        let user_id = Uuid::new_v4();
+
+        let connections = Connections::new().with_send_buffer(buffer(OverflowPolicy::DropNewest));
+        let mut rx = connections.register(user_id, Uuid::new_v4()).await.unwrap();
+        connections.send_to_user(user_id, "first".to_string()).await;
+        connections.send_to_all("second".to_string()).await;
+        assert_eq!(rx.try_recv().as_deref(), Some("first"));
+        assert!(rx.try_recv().is_none());
+        assert_eq!(connections.stats().await.messages_dropped, 1);
+
+        let connections = Connections::new().with_send_buffer(buffer(OverflowPolicy::Disconnect));
+        let mut stalled = connections.register(user_id, Uuid::new_v4()).await.unwrap();
+        let mut reading = connections.register(user_id, Uuid::new_v4()).await.unwrap();
+        connections.send_to_user(user_id, "first".to_string()).await;
+        assert_eq!(reading.try_recv().as_deref(), Some("first"));
+        connections
+            .send_to_user(user_id, "second".to_string())
+            .await;
+
+        // The stalled client is dropped; it still gets what was queued before
+        assert_eq!(reading.try_recv().as_deref(), Some("second"));
+        assert_eq!(connections.connection_count().await, 1);
+        assert_eq!(stalled.recv().await.as_deref(), Some("first"));
+        assert_eq!(stalled.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_connections_over_the_per_user_limit_follow_the_policy() {
+        let limit = |policy| {
+            Some(ConnectionLimit {
+                max_per_user: 3,
+                policy,
+            })
+        };
+        let user_id = Uuid::new_v4();
+
+        let connections =
+            Connections::new().with_connection_limit(limit(ConnectionLimitPolicy::RejectNew));
+        let mut open = Vec::new();
+        for _ in 0..3 {
+            open.push(connections.register(user_id, Uuid::new_v4()).await.unwrap());
+        }
+        assert!(connections
+            .register(user_id, Uuid::new_v4())
+            .await
+            .is_none());
+        assert_eq!(connections.connection_count().await, 3);
+        // Other users have their own allowance
+        assert!(connections
+            .register(Uuid::new_v4(), Uuid::new_v4())
+            .await
+            .is_some());
+
+        let connections =
+            Connections::new().with_connection_limit(limit(ConnectionLimitPolicy::EvictOldest));
+        let mut oldest = connections.register(user_id, Uuid::new_v4()).await.unwrap();
+        let mut middle = Vec::new();
+        for _ in 0..2 {
+            middle.push(connections.register(user_id, Uuid::new_v4()).await.unwrap());
+        }
+        let mut newest = connections.register(user_id, Uuid::new_v4()).await.unwrap();
+        assert_eq!(connections.connection_count().await, 3);
+        assert_eq!(oldest.recv().await, None);
+
+        connections.send_to_user(user_id, "hello".to_string()).await;
+        assert_eq!(newest.try_recv().as_deref(), Some("hello"));
+        assert_eq!(connections.stats().await.connections_closed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_disconnecting_a_user_or_one_connection() {
+        let connections = Connections::new();
+        let user_id = Uuid::new_v4();
+        let other = Uuid::new_v4();
+
+        let laptop = Uuid::new_v4();
+        let mut laptop_rx = connections.register(user_id, laptop).await.unwrap();
+        let mut phone_rx = connections.register(user_id, Uuid::new_v4()).await.unwrap();
+        let mut other_rx = connections.register(other, Uuid::new_v4()).await.unwrap();
+        assert!(connections.join_room(user_id, "lobby").await);
+        assert_eq!(connections.user_connection_ids(user_id).await[0], laptop);
+
+        assert!(connections.disconnect_connection(user_id, laptop).await);
+        assert!(!connections.disconnect_connection(user_id, laptop).await);
+        assert_eq!(laptop_rx.recv().await, None);
+        connections
+            .send_to_user(user_id, "still here".to_string())
+            .await;
+        assert_eq!(phone_rx.try_recv().as_deref(), Some("still here"));
+
+        assert_eq!(connections.disconnect_user(user_id).await, 1);
+        assert_eq!(phone_rx.recv().await, None);
+        assert!(connections.room_members("lobby").await.is_empty());
+        assert_eq!(connections.disconnect_user(user_id).await, 0);
+
+        // Other users are unaffected
+        connections.send_to_all("hello".to_string()).await;
+        assert_eq!(other_rx.try_recv().as_deref(), Some("hello"));
+        assert_eq!(connections.stats().await.connections_closed, 2);
+    }
+
+    #[tokio::test]
+    async fn test_opening_and_closing_connections_updates_stats() {
+        let connections = Connections::new();
+        let user_id = Uuid::new_v4();
+
+        let first = Uuid::new_v4();
+        let mut rx = connections.register(user_id, first).await.unwrap();
+        let second = connections.register(user_id, Uuid::new_v4()).await.unwrap();
+        connections.send_to_user(user_id, "hello".to_string()).await;
+        assert_eq!(rx.recv().await.as_deref(), Some("hello"));
+
+        let stats = connections.stats().await;
+        assert_eq!(stats.connections_opened, 2);
+        assert_eq!(stats.connections_closed, 0);
+        assert_eq!(stats.open_connections, 2);
+        assert_eq!(stats.connected_users, 1);
+        assert_eq!(stats.messages_sent, 2);
+
+        // One connection closes normally, the other is pruned on the next send
+        connections.unregister(user_id, first).await;
+        drop(second);
+        connections.send_to_user(user_id, "again".to_string()).await;
+
+        let stats = connections.stats().await;
+        assert_eq!(stats.connections_opened, 2);
+        assert_eq!(stats.connections_closed, 2);
+        assert_eq!(stats.open_connections, 0);
+        assert_eq!(stats.connected_users, 0);
+    }
+}
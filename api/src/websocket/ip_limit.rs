@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use crate::config::WebSocketConfig;
+
+/// Cap on concurrent WebSocket connections from one client IP, checked before
+/// the token is, so a flood of upgrade attempts can't pile up sockets.
+#[derive(Debug, Clone)]
+pub struct IpConnectionLimit {
+    max_per_ip: usize,
+    open: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl IpConnectionLimit {
+    #[must_use]
+    pub fn new(max_per_ip: usize) -> Self {
+        Self {
+            max_per_ip,
+            open: Arc::default(),
+        }
+    }
+
+    /// `None` if `max_connections_per_ip` is 0.
+    #[must_use]
+    pub fn from_config(config: &WebSocketConfig) -> Option<Self> {
+        (config.max_connections_per_ip > 0).then(|| Self::new(config.max_connections_per_ip))
+    }
+
+    /// Take one of `ip`'s slots for the lifetime of the returned permit, or
+    /// `None` if it has none left.
+    #[must_use]
+    pub fn try_acquire(&self, ip: IpAddr) -> Option<IpConnectionPermit> {
+        let mut open = self.open.lock().unwrap();
+        let count = open.get(&ip).copied().unwrap_or(0);
+        if count >= self.max_per_ip {
+            return None;
+        }
+        open.insert(ip, count + 1);
+        Some(IpConnectionPermit {
+            ip,
+            open: self.open.clone(),
+        })
+    }
+
+    /// Connections currently held open from `ip`.
+    #[must_use]
+    pub fn open_connections(&self, ip: IpAddr) -> usize {
+        self.open.lock().unwrap().get(&ip).copied().unwrap_or(0)
+    }
+}
+
+/// A slot taken by [`IpConnectionLimit::try_acquire`], given back on drop.
+#[derive(Debug)]
+pub struct IpConnectionPermit {
+    ip: IpAddr,
+    open: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl Drop for IpConnectionPermit {
+    fn drop(&mut self) {
+        let mut open = self.open.lock().unwrap();
+        if let Some(count) = open.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                open.remove(&self.ip);
+            }
+        }
+    }
+}
@@ -1,10 +1,12 @@
-use sea_orm::{DatabaseConnection, EntityTrait, QueryOrder};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder};
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgListener;
+use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
 use tracing::{debug, error, info, warn};
 
-use crate::database::models::websocket_message::Entity as WebsocketMessage;
+use crate::config::WebSocketConfig;
+use crate::database::models::websocket_message::{self, Entity as WebsocketMessage};
 use crate::websocket::connections::{Connections, UserId};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,26 +14,99 @@ use crate::websocket::connections::{Connections, UserId};
 pub enum RecipientCriteria {
     /// Send to a specific user
     User { user_id: UserId },
+    /// Send to each of several users, e.g. a document's collaborators
+    Users { user_ids: Vec<UserId> },
     /// Send to all connected users
     All,
+    /// Send to the users in a room, see [`Connections::join_room`]
+    Room { room: String },
+}
+
+/// Spawn [`start_listener`] in the background, unless WebSockets are disabled
+/// in the config.
+pub fn spawn_listener(
+    config: &WebSocketConfig,
+    db: DatabaseConnection,
+    connections: Connections,
+) -> Option<JoinHandle<()>> {
+    if !config.enabled {
+        info!("WebSockets disabled, not starting the listener");
+        return None;
+    }
+    let backoff = ReconnectBackoff::from_config(config);
+    Some(tokio::spawn(start_listener(db, connections, backoff)))
+}
+
+/// Exponential delay between listener reconnect attempts, so a database
+/// that stays down doesn't get hammered.
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    base: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+impl ReconnectBackoff {
+    #[must_use]
+    pub const fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max,
+            attempt: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_config(config: &WebSocketConfig) -> Self {
+        Self::new(
+            Duration::from_secs(config.listener_retry_base_seconds),
+            Duration::from_secs(config.listener_retry_max_seconds),
+        )
+    }
+
+    /// Delay before the next attempt: `base * 2^attempt`, capped at `max`.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self
+            .base
+            .checked_mul(2u32.saturating_pow(self.attempt))
+            .map_or(self.max, |delay| delay.min(self.max));
+        self.attempt = self.attempt.saturating_add(1);
+        delay
+    }
+
+    /// Start over from `base`, after a connection succeeded.
+    pub const fn reset(&mut self) {
+        self.attempt = 0;
+    }
 }
 
 /// Start listening for PostgreSQL NOTIFY events and broadcast messages to WebSocket connections
-pub async fn start_listener(db: DatabaseConnection, connections: Connections) {
+pub async fn start_listener(
+    db: DatabaseConnection,
+    connections: Connections,
+    mut backoff: ReconnectBackoff,
+) {
     loop {
-        if let Err(e) = listen_loop(&db, &connections).await {
-            error!("WebSocket listener error: {}, restarting in 5s...", e);
-        } else {
-            warn!("WebSocket listener exited normally, restarting...");
+        match connect(&db).await {
+            Ok(listener) => {
+                backoff.reset();
+                if let Err(e) = listen_loop(listener, &db, &connections).await {
+                    error!("WebSocket listener error: {}", e);
+                } else {
+                    warn!("WebSocket listener exited normally");
+                }
+            }
+            Err(e) => error!("WebSocket listener failed to connect: {}", e),
         }
-        sleep(Duration::from_secs(5)).await;
+
+        let delay = backoff.next_delay();
+        metrics::counter!("websocket_listener_reconnects_total").increment(1);
+        warn!("WebSocket listener reconnecting in {:?}", delay);
+        sleep(delay).await;
     }
 }
 
-async fn listen_loop(
-    db: &DatabaseConnection,
-    connections: &Connections,
-) -> Result<(), Box<dyn std::error::Error>> {
+async fn connect(db: &DatabaseConnection) -> Result<PgListener, sqlx::Error> {
     // Get the underlying sqlx pool from SeaORM
     let sqlx_pool = db.get_postgres_connection_pool();
 
@@ -39,7 +114,14 @@ async fn listen_loop(
     listener.listen("websocket_new_message").await?;
 
     info!("WebSocket listener started, listening on channel 'websocket_new_message'");
+    Ok(listener)
+}
 
+async fn listen_loop(
+    mut listener: PgListener,
+    db: &DatabaseConnection,
+    connections: &Connections,
+) -> Result<(), Box<dyn std::error::Error>> {
     loop {
         // Wait for notification (payload is ignored - just a wake-up signal)
         listener.recv().await?;
@@ -50,11 +132,7 @@ async fn listen_loop(
         let mut processed_count = 0;
         loop {
             // Fetch oldest unprocessed message
-            let message = match WebsocketMessage::find()
-                .order_by_asc(crate::database::models::websocket_message::Column::CreatedAt)
-                .one(db)
-                .await
-            {
+            let message = match next_message(db).await {
                 Ok(Some(msg)) => msg,
                 Ok(None) => {
                     // No more messages, wait for next notification
@@ -72,55 +150,336 @@ async fn listen_loop(
                 }
             };
 
-            let message_id = message.id;
+            process_message(db, connections, message).await;
+            processed_count += 1;
+        }
 
-            // Parse recipient criteria
-            let criteria: RecipientCriteria =
-                match serde_json::from_value(message.recipient_criteria) {
-                    Ok(c) => c,
-                    Err(e) => {
-                        error!(
-                            "Failed to parse recipient_criteria for message {}: {:?}",
-                            message_id, e
-                        );
-                        // Delete invalid message to prevent infinite loop
-                        let _ = WebsocketMessage::delete_by_id(message_id).exec(db).await;
-                        continue;
-                    }
-                };
+        if let Some(retention) = connections.offline_retention() {
+            match retention.delete_expired().await {
+                Ok(0) => {}
+                Ok(expired) => debug!("Dropped {} expired retained message(s)", expired),
+                Err(e) => error!("Failed to drop expired retained messages: {:?}", e),
+            }
+        }
+    }
+}
 
-            // Convert payload to string for sending
-            let payload = match serde_json::to_string(&message.payload) {
-                Ok(p) => p,
-                Err(e) => {
-                    error!(
-                        "Failed to serialize payload for message {}: {:?}",
-                        message_id, e
-                    );
-                    // Delete invalid message to prevent infinite loop
-                    let _ = WebsocketMessage::delete_by_id(message_id).exec(db).await;
-                    continue;
-                }
-            };
+/// Oldest outbox message not yet processed. Messages retained for offline
+/// users wait for them to connect instead.
+async fn next_message(db: &DatabaseConnection) -> Result<Option<websocket_message::Model>, DbErr> {
+    WebsocketMessage::find()
+        .filter(websocket_message::Column::RetainedUntil.is_null())
+        .order_by_asc(websocket_message::Column::CreatedAt)
+        .one(db)
+        .await
+}
+
+/// Deliver one outbox message and delete it, or keep it for an offline user
+/// when retention is on.
+async fn process_message(
+    db: &DatabaseConnection,
+    connections: &Connections,
+    message: websocket_message::Model,
+) {
+    let message_id = message.id;
+
+    // Parse recipient criteria
+    let criteria: RecipientCriteria = match serde_json::from_value(message.recipient_criteria) {
+        Ok(c) => c,
+        Err(e) => {
+            error!(
+                "Failed to parse recipient_criteria for message {}: {:?}",
+                message_id, e
+            );
+            // Delete invalid message to prevent infinite loop
+            let _ = WebsocketMessage::delete_by_id(message_id).exec(db).await;
+            return;
+        }
+    };
+
+    // Convert payload to string for sending
+    let payload = match serde_json::to_string(&message.payload) {
+        Ok(p) => p,
+        Err(e) => {
+            error!(
+                "Failed to serialize payload for message {}: {:?}",
+                message_id, e
+            );
+            // Delete invalid message to prevent infinite loop
+            let _ = WebsocketMessage::delete_by_id(message_id).exec(db).await;
+            return;
+        }
+    };
 
-            // Broadcast based on criteria
-            match criteria {
-                RecipientCriteria::User { user_id } => {
-                    debug!("Sending message {} to user {}", message_id, user_id);
-                    connections.send_to_user(user_id, payload).await;
+    // Broadcast based on criteria
+    if message.requires_ack {
+        match criteria {
+            RecipientCriteria::User { user_id } => {
+                debug!("Delivering message {} to user {}", message_id, user_id);
+                connections
+                    .send_reliable_to_user(user_id, message_id, message.payload)
+                    .await;
+            }
+            RecipientCriteria::Users { user_ids } => {
+                debug!(
+                    "Delivering message {} to {} users",
+                    message_id,
+                    user_ids.len()
+                );
+                for user_id in user_ids {
+                    connections
+                        .send_reliable_to_user(user_id, message_id, message.payload.clone())
+                        .await;
+                }
+            }
+            RecipientCriteria::All => {
+                debug!("Delivering message {} to all users", message_id);
+                connections
+                    .send_reliable_to_all(message_id, message.payload)
+                    .await;
+            }
+            RecipientCriteria::Room { room } => {
+                debug!("Delivering message {} to room {}", message_id, room);
+                connections
+                    .send_reliable_to_room(&room, message_id, message.payload)
+                    .await;
+            }
+        }
+    } else {
+        match criteria {
+            RecipientCriteria::User { user_id } => {
+                debug!("Sending message {} to user {}", message_id, user_id);
+                if connections.send_to_user(user_id, payload).await == 0 {
+                    if let Some(retention) = connections.offline_retention() {
+                        debug!(
+                            "User {} is offline, keeping message {}",
+                            user_id, message_id
+                        );
+                        match retention.retain(message_id).await {
+                            Ok(()) => return,
+                            Err(e) => error!("Failed to retain message {}: {:?}", message_id, e),
+                        }
+                    }
                 }
-                RecipientCriteria::All => {
-                    debug!("Broadcasting message {} to all users", message_id);
-                    connections.send_to_all(payload).await;
+            }
+            RecipientCriteria::Users { user_ids } => {
+                debug!("Sending message {} to {} users", message_id, user_ids.len());
+                for user_id in user_ids {
+                    connections.send_to_user(user_id, payload.clone()).await;
                 }
             }
+            RecipientCriteria::All => {
+                debug!("Broadcasting message {} to all users", message_id);
+                connections.send_to_all(payload).await;
+            }
+            RecipientCriteria::Room { room } => {
+                debug!("Sending message {} to room {}", message_id, room);
+                connections.send_to_room(&room, payload).await;
+            }
+        }
+    }
+
+    // Delete the message after processing
+    if let Err(e) = WebsocketMessage::delete_by_id(message_id).exec(db).await {
+        error!("Failed to delete message {}: {:?}", message_id, e);
+    }
+}
 
-            // Delete the message after processing
-            if let Err(e) = WebsocketMessage::delete_by_id(message_id).exec(db).await {
-                error!("Failed to delete message {}: {:?}", message_id, e);
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use sea_orm::{ActiveModelTrait, ConnectOptions, Database, EntityTrait, Set};
+    use serde_json::json;
+    use tokio::time::Duration;
+    use tracing_subscriber::{layer::SubscriberExt, Layer, Registry};
+    use uuid::Uuid;
+
+    use super::{
+        next_message, process_message, start_listener, RecipientCriteria, ReconnectBackoff,
+    };
+    use crate::{
+        database::{
+            migrations::Migrator,
+            models::websocket_message::{self, Entity as WebsocketMessage},
+        },
+        tests::setup_test::{empty_router, no_fixtures, setup_test},
+        websocket::{connections::Connections, retention::OfflineRetention},
+    };
+
+    #[test]
+    fn test_users_criteria_round_trips() {
+        let user_ids = vec![Uuid::new_v4(), Uuid::new_v4()];
+        let value = json!({ "type": "users", "user_ids": user_ids });
+
+        let criteria: RecipientCriteria = serde_json::from_value(value.clone()).unwrap();
+        assert!(
+            matches!(&criteria, RecipientCriteria::Users { user_ids: ids } if *ids == user_ids)
+        );
+        assert_eq!(serde_json::to_value(&criteria).unwrap(), value);
+    }
+
+    /// Messages of the listener's "reconnecting in" warnings.
+    #[derive(Clone, Default)]
+    struct ReconnectWarnings(Arc<Mutex<Vec<String>>>);
+
+    impl tracing::field::Visit for ReconnectWarnings {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            let message = format!("{value:?}");
+            if field.name() == "message"
+                && message.starts_with("WebSocket listener reconnecting in")
+            {
+                self.0.lock().unwrap().push(message);
             }
+        }
+    }
 
-            processed_count += 1;
+    impl<S: tracing::Subscriber> Layer<S> for ReconnectWarnings {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            event.record(&mut self.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backoff_grows_across_failed_connects_up_to_the_cap() {
+        // Nothing listens on port 1, so every connect fails
+        let mut options = ConnectOptions::new("postgres://erno@127.0.0.1:1/erno");
+        options
+            .connect_lazy(true)
+            .acquire_timeout(Duration::from_millis(100));
+        let db = Database::connect(options).await.unwrap();
+
+        let warnings = ReconnectWarnings::default();
+        let _guard = tracing::subscriber::set_default(Registry::default().with(warnings.clone()));
+
+        let backoff = ReconnectBackoff::new(Duration::from_millis(10), Duration::from_millis(80));
+        let listener = tokio::spawn(start_listener(db, Connections::new(), backoff));
+        for _ in 0..200 {
+            if warnings.0.lock().unwrap().len() >= 6 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        listener.abort();
+
+        let warnings = warnings.0.lock().unwrap();
+        let delays: Vec<_> = warnings
+            .iter()
+            .take(6)
+            .map(|message| message.rsplit(' ').next().unwrap())
+            .collect();
+        assert_eq!(delays, ["10ms", "20ms", "40ms", "80ms", "80ms", "80ms"]);
+    }
+
+    #[test]
+    fn test_backoff_starts_over_after_a_reset() {
+        let mut backoff = ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(8));
+        backoff.next_delay();
+        backoff.next_delay();
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_user_message_waits_for_offline_user_to_connect() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let retention = OfflineRetention::new(test.db.clone(), Duration::from_secs(60));
+        let connections = Connections::new().with_offline_retention(Some(retention.clone()));
+        let user_id = Uuid::new_v4();
+
+        let message = websocket_message::ActiveModel {
+            recipient_criteria: Set(json!({ "type": "user", "user_id": user_id })),
+            payload: Set(json!({ "text": "while you were away" })),
+            requires_ack: Set(false),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(&test.db)
+        .await
+        .unwrap();
+
+        // Nobody is connected, so the message is kept instead of deleted
+        process_message(&test.db, &connections, message.clone()).await;
+        let kept = WebsocketMessage::find_by_id(message.id)
+            .one(&test.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(kept.retained_until.is_some());
+        assert!(next_message(&test.db).await.unwrap().is_none());
+
+        // Connecting delivers it once
+        let mut receiver = connections.register(user_id, Uuid::new_v4()).await.unwrap();
+        assert_eq!(retention.flush(&connections, user_id).await.unwrap(), 1);
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            r#"{"text":"while you were away"}"#
+        );
+        assert!(WebsocketMessage::find_by_id(message.id)
+            .one(&test.db)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(retention.flush(&connections, user_id).await.unwrap(), 0);
+
+        // An expired message isn't delivered and gets cleaned up
+        let expired = websocket_message::ActiveModel {
+            recipient_criteria: Set(json!({ "type": "user", "user_id": user_id })),
+            payload: Set(json!({ "text": "too late" })),
+            requires_ack: Set(false),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            retained_until: Set(Some(
+                chrono::Utc::now().naive_utc() - chrono::Duration::seconds(1),
+            )),
+            ..Default::default()
+        }
+        .insert(&test.db)
+        .await
+        .unwrap();
+        assert_eq!(retention.flush(&connections, user_id).await.unwrap(), 0);
+        assert_eq!(retention.delete_expired().await.unwrap(), 1);
+        assert!(WebsocketMessage::find_by_id(expired.id)
+            .one(&test.db)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_endless_retention_does_not_overflow() {
+        let test = setup_test::<Migrator>(empty_router, no_fixtures).await;
+        let retention = OfflineRetention::new(test.db.clone(), Duration::MAX);
+        let message = websocket_message::ActiveModel {
+            recipient_criteria: Set(json!({ "type": "user", "user_id": Uuid::new_v4() })),
+            payload: Set(json!({})),
+            requires_ack: Set(false),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(&test.db)
+        .await
+        .unwrap();
+
+        retention.retain(message.id).await.unwrap();
+
+        let kept = WebsocketMessage::find_by_id(message.id)
+            .one(&test.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(kept.retained_until.unwrap() > chrono::Utc::now().naive_utc());
+    }
+
+    #[test]
+    fn test_backoff_never_overflows() {
+        let mut backoff = ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(60));
+        for _ in 0..100 {
+            assert!(backoff.next_delay() <= Duration::from_secs(60));
         }
     }
 }
@@ -1,10 +1,15 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum Request {
     Version,
+    /// Acknowledge receipt of a `Delivery`, stopping its redelivery
+    Ack {
+        message_id: Uuid,
+    },
     /// Application-specific requests
     /// The Value should be an object with a "type" field for routing
     Application(Value),
@@ -36,8 +41,23 @@ pub enum Broadcast {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum Message {
-    Request { request: Request, id: String },
-    Response { response: Response, id: String },
-    Broadcast { broadcast: Broadcast },
-    Error { message: String },
+    Request {
+        request: Request,
+        id: String,
+    },
+    Response {
+        response: Response,
+        id: String,
+    },
+    Broadcast {
+        broadcast: Broadcast,
+    },
+    /// A message that is redelivered until the client sends `Request::Ack`
+    Delivery {
+        message_id: Uuid,
+        payload: Value,
+    },
+    Error {
+        message: String,
+    },
 }
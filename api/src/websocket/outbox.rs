@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+
+use crate::config::{OverflowPolicy, WebSocketConfig};
+
+/// Size of each connection's send queue and what happens when it fills up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendBuffer {
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+}
+
+impl SendBuffer {
+    #[must_use]
+    pub const fn from_config(config: &WebSocketConfig) -> Self {
+        Self {
+            capacity: config.send_buffer_size,
+            policy: config.overflow_policy,
+        }
+    }
+}
+
+impl Default for SendBuffer {
+    fn default() -> Self {
+        Self::from_config(&WebSocketConfig::default())
+    }
+}
+
+/// What [`OutboxSender::send`] did with a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendOutcome {
+    Queued,
+    /// Queued after dropping the oldest queued message
+    QueuedDroppingOldest,
+    /// The queue was full and the message was dropped
+    Dropped,
+    /// The queue was full and the policy is to disconnect
+    Overflowed,
+    /// The connection's socket task has gone away
+    Closed,
+}
+
+impl SendOutcome {
+    /// Whether the message will reach the socket task.
+    #[must_use]
+    pub const fn is_queued(self) -> bool {
+        matches!(self, Self::Queued | Self::QueuedDroppingOldest)
+    }
+
+    /// Whether a message, this one or an older one, was dropped.
+    #[must_use]
+    pub const fn dropped_message(self) -> bool {
+        matches!(self, Self::QueuedDroppingOldest | Self::Dropped)
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    queue: VecDeque<String>,
+    sender_dropped: bool,
+    receiver_dropped: bool,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    // Wakes the receiver when a message is queued or the sender is dropped
+    notify: Notify,
+    buffer: SendBuffer,
+}
+
+/// A bounded queue of messages for one connection.
+///
+/// Unlike a bounded `mpsc` channel, sending never waits: a full queue is
+/// handled by the [`OverflowPolicy`], so one stalled client can't hold up a
+/// broadcast to everyone else.
+#[must_use]
+pub fn channel(buffer: SendBuffer) -> (OutboxSender, OutboxReceiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State::default()),
+        notify: Notify::new(),
+        buffer,
+    });
+    (
+        OutboxSender {
+            shared: shared.clone(),
+        },
+        OutboxReceiver { shared },
+    )
+}
+
+/// Sending half of [`channel`], kept in the connection store.
+#[derive(Debug)]
+pub struct OutboxSender {
+    shared: Arc<Shared>,
+}
+
+impl OutboxSender {
+    pub fn send(&self, message: String) -> SendOutcome {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.receiver_dropped {
+            return SendOutcome::Closed;
+        }
+
+        let outcome = if state.queue.len() < self.shared.buffer.capacity.max(1) {
+            SendOutcome::Queued
+        } else {
+            match self.shared.buffer.policy {
+                OverflowPolicy::DropOldest => {
+                    state.queue.pop_front();
+                    SendOutcome::QueuedDroppingOldest
+                }
+                OverflowPolicy::DropNewest => return SendOutcome::Dropped,
+                OverflowPolicy::Disconnect => return SendOutcome::Overflowed,
+            }
+        };
+        state.queue.push_back(message);
+        drop(state);
+        self.shared.notify.notify_one();
+        outcome
+    }
+}
+
+impl Drop for OutboxSender {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().sender_dropped = true;
+        self.shared.notify.notify_one();
+    }
+}
+
+/// Receiving half of [`channel`], owned by the connection's socket task.
+#[derive(Debug)]
+pub struct OutboxReceiver {
+    shared: Arc<Shared>,
+}
+
+impl OutboxReceiver {
+    /// The next queued message, or `None` once the queue is drained and the
+    /// sender has been dropped. Cancel safe.
+    pub async fn recv(&mut self) -> Option<String> {
+        loop {
+            if let Some(message) = self.try_recv() {
+                return Some(message);
+            }
+            if self.shared.state.lock().unwrap().sender_dropped {
+                return None;
+            }
+            // A notification sent since the check above is stored, not lost
+            self.shared.notify.notified().await;
+        }
+    }
+
+    /// The next queued message, if there is one.
+    pub fn try_recv(&mut self) -> Option<String> {
+        self.shared.state.lock().unwrap().queue.pop_front()
+    }
+}
+
+impl Drop for OutboxReceiver {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().receiver_dropped = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{channel, SendBuffer, SendOutcome};
+    use crate::config::OverflowPolicy;
+
+    fn full_outbox(policy: OverflowPolicy) -> (super::OutboxSender, super::OutboxReceiver) {
+        let (tx, rx) = channel(SendBuffer {
+            capacity: 2,
+            policy,
+        });
+        assert_eq!(tx.send("1".to_string()), SendOutcome::Queued);
+        assert_eq!(tx.send("2".to_string()), SendOutcome::Queued);
+        (tx, rx)
+    }
+
+    #[tokio::test]
+    async fn test_full_outbox_follows_overflow_policy() {
+        let (tx, mut rx) = full_outbox(OverflowPolicy::DropOldest);
+        assert_eq!(tx.send("3".to_string()), SendOutcome::QueuedDroppingOldest);
+        drop(tx);
+        assert_eq!(rx.recv().await.as_deref(), Some("2"));
+        assert_eq!(rx.recv().await.as_deref(), Some("3"));
+        assert_eq!(rx.recv().await, None);
+
+        let (tx, mut rx) = full_outbox(OverflowPolicy::DropNewest);
+        assert_eq!(tx.send("3".to_string()), SendOutcome::Dropped);
+        assert_eq!(rx.try_recv().as_deref(), Some("1"));
+        assert_eq!(tx.send("4".to_string()), SendOutcome::Queued);
+        assert_eq!(rx.try_recv().as_deref(), Some("2"));
+        assert_eq!(rx.try_recv().as_deref(), Some("4"));
+
+        let (tx, rx) = full_outbox(OverflowPolicy::Disconnect);
+        assert_eq!(tx.send("3".to_string()), SendOutcome::Overflowed);
+        drop(rx);
+        assert_eq!(tx.send("4".to_string()), SendOutcome::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_receiver_wakes_for_messages_sent_later() {
+        let (tx, mut rx) = channel(SendBuffer::default());
+        let sender = tokio::spawn(async move {
+            tokio::task::yield_now().await;
+            tx.send("hello".to_string());
+        });
+        assert_eq!(rx.recv().await.as_deref(), Some("hello"));
+        sender.await.unwrap();
+        assert_eq!(rx.recv().await, None);
+    }
+}
@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use sea_orm::{sea_query::Expr, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use tracing::{debug, error};
+
+use crate::config::WebSocketConfig;
+use crate::database::models::websocket_message::{Column, Entity as WebsocketMessage};
+use crate::websocket::connections::{Connections, MessageId, UserId};
+
+/// Keeps `user`-targeted outbox messages for users who aren't connected, so
+/// a page reload doesn't lose the notifications sent meanwhile.
+///
+/// Only for a single instance: "not connected" means not connected to this
+/// process, so with several replicas a message can be retained by one while
+/// the user stays connected to another, and isn't delivered until they
+/// reconnect.
+#[derive(Debug, Clone)]
+pub struct OfflineRetention {
+    db: DatabaseConnection,
+    ttl: Duration,
+}
+
+impl OfflineRetention {
+    #[must_use]
+    pub const fn new(db: DatabaseConnection, ttl: Duration) -> Self {
+        Self { db, ttl }
+    }
+
+    /// `None` if `offline_retention_seconds` is 0.
+    #[must_use]
+    pub fn from_config(config: &WebSocketConfig, db: DatabaseConnection) -> Option<Self> {
+        (config.offline_retention_seconds > 0)
+            .then(|| Self::new(db, Duration::from_secs(config.offline_retention_seconds)))
+    }
+
+    /// Keep an undeliverable message until the retention period runs out. A
+    /// period too long to represent keeps it for as long as possible.
+    pub(crate) async fn retain(&self, message_id: MessageId) -> Result<(), DbErr> {
+        let retained_until = chrono::Duration::from_std(self.ttl)
+            .ok()
+            .and_then(|ttl| chrono::Utc::now().naive_utc().checked_add_signed(ttl))
+            .unwrap_or(chrono::NaiveDateTime::MAX);
+        WebsocketMessage::update_many()
+            .col_expr(Column::RetainedUntil, Expr::value(retained_until))
+            .filter(Column::Id.eq(message_id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Send a user's unexpired retained messages, oldest first, and return
+    /// how many there were.
+    ///
+    /// Rows are deleted as they are claimed, so two connections opening at
+    /// once don't both get them.
+    pub(crate) async fn flush(
+        &self,
+        connections: &Connections,
+        user_id: UserId,
+    ) -> Result<usize, DbErr> {
+        let mut messages = WebsocketMessage::delete_many()
+            .filter(Column::RetainedUntil.gt(chrono::Utc::now().naive_utc()))
+            .filter(Expr::cust_with_values(
+                "recipient_criteria->>'type' = 'user' AND recipient_criteria->>'user_id' = $1",
+                [user_id.to_string()],
+            ))
+            .exec_with_returning(&self.db)
+            .await?;
+        messages.sort_by_key(|message| message.created_at);
+
+        for message in &messages {
+            match serde_json::to_string(&message.payload) {
+                Ok(payload) => {
+                    connections.send_to_user(user_id, payload).await;
+                }
+                Err(e) => error!(
+                    "Failed to serialize payload for message {}: {:?}",
+                    message.id, e
+                ),
+            }
+        }
+        if !messages.is_empty() {
+            debug!(
+                "Delivered {} retained message(s) to user {}",
+                messages.len(),
+                user_id
+            );
+        }
+        Ok(messages.len())
+    }
+
+    /// Drop retained messages whose user didn't connect in time.
+    pub(crate) async fn delete_expired(&self) -> Result<u64, DbErr> {
+        let result = WebsocketMessage::delete_many()
+            .filter(Column::RetainedUntil.lte(chrono::Utc::now().naive_utc()))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
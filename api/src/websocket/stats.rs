@@ -0,0 +1,108 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+
+use super::outbox::SendOutcome;
+
+/// Snapshot of WebSocket activity, returned by
+/// [`Connections::stats`](super::connections::Connections::stats).
+///
+/// Counters are per process and start at zero on boot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ConnectionStats {
+    /// Connections registered since startup
+    pub connections_opened: u64,
+    /// Connections closed or pruned since startup
+    pub connections_closed: u64,
+    /// Connections currently open
+    pub open_connections: usize,
+    /// Users with at least one open connection
+    pub connected_users: usize,
+    /// Messages queued to clients since startup, including redeliveries and
+    /// responses to requests
+    pub messages_sent: u64,
+    /// Messages dropped since startup because a client's send queue was full
+    pub messages_dropped: u64,
+}
+
+/// Cumulative counters behind [`ConnectionStats`]. Each update is also
+/// reported to the metrics recorder.
+#[derive(Debug, Default)]
+pub(super) struct ConnectionCounters {
+    opened: AtomicU64,
+    closed: AtomicU64,
+    messages_sent: AtomicU64,
+    messages_dropped: AtomicU64,
+}
+
+impl ConnectionCounters {
+    pub(super) fn record_opened(&self) {
+        self.opened.fetch_add(1, Ordering::Relaxed);
+        metrics::counter!("websocket_connections_opened_total").increment(1);
+    }
+
+    pub(super) fn record_rejected() {
+        metrics::counter!("websocket_connections_rejected_total").increment(1);
+    }
+
+    pub(super) fn record_closed(&self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        self.closed.fetch_add(count as u64, Ordering::Relaxed);
+        metrics::counter!("websocket_connections_closed_total").increment(count as u64);
+    }
+
+    fn record_sent(&self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        self.messages_sent
+            .fetch_add(count as u64, Ordering::Relaxed);
+        metrics::counter!("websocket_messages_sent_total").increment(count as u64);
+    }
+
+    fn record_dropped(&self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        self.messages_dropped
+            .fetch_add(count as u64, Ordering::Relaxed);
+        metrics::counter!("websocket_messages_dropped_total").increment(count as u64);
+    }
+
+    /// Record the results of sending to one or more connections.
+    pub(super) fn record_outcomes(&self, outcomes: &[SendOutcome]) {
+        self.record_sent(
+            outcomes
+                .iter()
+                .filter(|outcome| outcome.is_queued())
+                .count(),
+        );
+        self.record_dropped(
+            outcomes
+                .iter()
+                .filter(|outcome| outcome.dropped_message())
+                .count(),
+        );
+    }
+
+    pub(super) fn record_connected_users(users: usize) {
+        metrics::gauge!("websocket_connected_users").set(users as f64);
+    }
+
+    pub(super) fn snapshot(
+        &self,
+        open_connections: usize,
+        connected_users: usize,
+    ) -> ConnectionStats {
+        ConnectionStats {
+            connections_opened: self.opened.load(Ordering::Relaxed),
+            connections_closed: self.closed.load(Ordering::Relaxed),
+            open_connections,
+            connected_users,
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            messages_dropped: self.messages_dropped.load(Ordering::Relaxed),
+        }
+    }
+}